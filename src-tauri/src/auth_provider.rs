@@ -0,0 +1,399 @@
+// Provedores de autenticação do ScoreApp
+// Local (senha em users_table) e LDAP/Active Directory (bind no diretório corporativo)
+
+use ldap3::{ldap_escape, LdapConn, LdapConnSettings, Scope, SearchEntry};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Provedor que autentica pela senha armazenada em users_table
+pub const AUTH_PROVIDER_LOCAL: &str = "local";
+/// Provedor que autentica por bind no servidor LDAP/AD
+pub const AUTH_PROVIDER_LDAP: &str = "ldap";
+
+/// Código LDAP de credenciais inválidas (invalidCredentials)
+const LDAP_INVALID_CREDENTIALS: u32 = 49;
+
+/// Papéis do ScoreApp em ordem crescente de privilégio
+const ROLE_PRIORITY: [&str; 3] = ["User", "Admin", "Super Admin"];
+
+/// Resultado de uma tentativa de autenticação
+#[derive(Debug)]
+pub enum AuthOutcome {
+    /// Credenciais aceitas pelo provedor
+    Authenticated(AuthenticatedIdentity),
+    /// Usuário não existe no provedor
+    UnknownUser,
+    /// Usuário existe, mas a senha não confere
+    InvalidCredentials,
+}
+
+/// Dados do usuário autenticado retornados pelo provedor
+#[derive(Debug, Clone, Serialize, Default)]
+pub struct AuthenticatedIdentity {
+    /// Nome de exibição vindo do diretório (None no provedor local)
+    pub display_name: Option<String>,
+    /// DN do usuário no diretório
+    pub user_dn: Option<String>,
+    /// Grupos do usuário no diretório
+    pub groups: Vec<String>,
+    /// Papel do ScoreApp resolvido pelos grupos (None = manter o papel cadastrado)
+    pub role: Option<String>,
+}
+
+/// Interface comum dos provedores de autenticação.
+/// Erros (`Err`) indicam falha de infraestrutura (ex.: servidor fora do ar),
+/// nunca credenciais inválidas.
+pub trait AuthProvider {
+    /// Nome do provedor (gravado no log de login)
+    fn name(&self) -> &'static str;
+
+    /// Valida as credenciais do WWID informado
+    fn authenticate(&self, wwid: &str, password: &str) -> Result<AuthOutcome, String>;
+
+    /// Indica se a política de senhas/expiração do ScoreApp se aplica a este provedor
+    fn enforces_password_policy(&self) -> bool;
+}
+
+/// Mapeamento de um grupo do diretório para um papel do ScoreApp
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroupRoleMapping {
+    /// DN completo ou apenas o CN do grupo
+    pub group: String,
+    /// "User", "Admin" ou "Super Admin"
+    pub role: String,
+}
+
+/// Configuração de conexão e busca no LDAP/AD
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LdapConfig {
+    /// Ex.: ldap://localhost:389 ou ldaps://ad.empresa.com:636
+    pub url: String,
+    pub use_starttls: bool,
+    pub timeout_seconds: u64,
+    /// Conta de serviço usada para localizar o usuário (vazio = bind anônimo)
+    pub bind_dn: String,
+    pub bind_password: String,
+    pub user_base_dn: String,
+    /// Filtro de busca do usuário; `{wwid}` é substituído pelo WWID (escapado)
+    pub user_filter: String,
+    pub name_attribute: String,
+    /// Atributo do usuário com seus grupos (memberOf no AD)
+    pub group_attribute: String,
+    /// Base de busca de grupos para servidores sem memberOf (vazio = não buscar)
+    pub group_base_dn: String,
+    /// Filtro de grupos; `{dn}` é substituído pelo DN do usuário (escapado)
+    pub group_filter: String,
+    pub group_role_mappings: Vec<GroupRoleMapping>,
+}
+
+impl Default for LdapConfig {
+    fn default() -> Self {
+        LdapConfig {
+            url: "ldap://localhost:389".to_string(),
+            use_starttls: false,
+            timeout_seconds: 10,
+            bind_dn: String::new(),
+            bind_password: String::new(),
+            user_base_dn: String::new(),
+            user_filter: "(sAMAccountName={wwid})".to_string(),
+            name_attribute: "displayName".to_string(),
+            group_attribute: "memberOf".to_string(),
+            group_base_dn: String::new(),
+            group_filter: "(|(member={dn})(uniqueMember={dn}))".to_string(),
+            group_role_mappings: Vec::new(),
+        }
+    }
+}
+
+/// Configuração de autenticação (salva na app_settings_table)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthConfig {
+    /// "local" ou "ldap"
+    pub provider: String,
+    pub ldap: LdapConfig,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        AuthConfig {
+            provider: AUTH_PROVIDER_LOCAL.to_string(),
+            ldap: LdapConfig::default(),
+        }
+    }
+}
+
+impl AuthConfig {
+    /// Valida a configuração antes de salvar
+    pub fn validate(&self) -> Result<(), String> {
+        match self.provider.as_str() {
+            AUTH_PROVIDER_LOCAL => Ok(()),
+            AUTH_PROVIDER_LDAP => self.ldap.validate(),
+            other => Err(format!("Provedor de autenticação desconhecido: {}", other)),
+        }
+    }
+}
+
+impl LdapConfig {
+    fn validate(&self) -> Result<(), String> {
+        if !(self.url.starts_with("ldap://") || self.url.starts_with("ldaps://")) {
+            return Err("A URL do LDAP deve começar com ldap:// ou ldaps://".to_string());
+        }
+        if self.user_base_dn.trim().is_empty() {
+            return Err("Informe a base DN de usuários do LDAP".to_string());
+        }
+        if !self.user_filter.contains("{wwid}") {
+            return Err("O filtro de usuários deve conter {wwid}".to_string());
+        }
+        if !self.group_base_dn.trim().is_empty() && !self.group_filter.contains("{dn}") {
+            return Err("O filtro de grupos deve conter {dn}".to_string());
+        }
+        if self.group_role_mappings.is_empty() {
+            return Err("Mapeie ao menos um grupo do diretório para um papel do ScoreApp".to_string());
+        }
+        if let Some(mapping) = self
+            .group_role_mappings
+            .iter()
+            .find(|m| !ROLE_PRIORITY.contains(&m.role.as_str()))
+        {
+            return Err(format!("Papel inválido no mapeamento do grupo '{}': {}", mapping.group, mapping.role));
+        }
+        Ok(())
+    }
+}
+
+/// Provedor local: compara com a senha de users_table (comportamento original)
+pub struct LocalAuthProvider<'a> {
+    conn: &'a Connection,
+}
+
+impl<'a> LocalAuthProvider<'a> {
+    pub fn new(conn: &'a Connection) -> Self {
+        LocalAuthProvider { conn }
+    }
+}
+
+impl AuthProvider for LocalAuthProvider<'_> {
+    fn name(&self) -> &'static str {
+        AUTH_PROVIDER_LOCAL
+    }
+
+    fn authenticate(&self, wwid: &str, password: &str) -> Result<AuthOutcome, String> {
+        let stored_password: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT user_password FROM users_table WHERE user_wwid = ?1",
+                [wwid],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
+
+        match stored_password {
+            None => Ok(AuthOutcome::UnknownUser),
+            // Usuários provisionados pelo diretório não têm senha local
+            Some(stored) if stored.is_empty() || stored != password => Ok(AuthOutcome::InvalidCredentials),
+            Some(_) => Ok(AuthOutcome::Authenticated(AuthenticatedIdentity::default())),
+        }
+    }
+
+    fn enforces_password_policy(&self) -> bool {
+        true
+    }
+}
+
+/// Usuário localizado no diretório pela conta de serviço
+struct DirectoryEntry {
+    dn: String,
+    name: Option<String>,
+    groups: Vec<String>,
+}
+
+/// Provedor LDAP/AD: localiza o usuário, faz bind com a senha dele e resolve o papel pelos grupos
+pub struct LdapAuthProvider {
+    config: LdapConfig,
+}
+
+impl LdapAuthProvider {
+    pub fn new(config: LdapConfig) -> Self {
+        LdapAuthProvider { config }
+    }
+
+    fn connect(&self) -> Result<LdapConn, String> {
+        let settings = LdapConnSettings::new()
+            .set_conn_timeout(Duration::from_secs(self.config.timeout_seconds.max(1)))
+            .set_starttls(self.config.use_starttls);
+
+        LdapConn::with_settings(settings, &self.config.url)
+            .map_err(|e| format!("Erro ao conectar ao LDAP ({}): {}", self.config.url, e))
+    }
+
+    /// Busca o usuário com a conta de serviço, retornando DN, nome e grupos do atributo memberOf
+    fn find_user(&self, ldap: &mut LdapConn, wwid: &str) -> Result<Option<DirectoryEntry>, String> {
+        ldap.simple_bind(&self.config.bind_dn, &self.config.bind_password)
+            .and_then(|result| result.success())
+            .map_err(|e| format!("Erro no bind da conta de serviço LDAP: {}", e))?;
+
+        let filter = self.config.user_filter.replace("{wwid}", &ldap_escape(wwid));
+        let (entries, _) = ldap
+            .search(
+                &self.config.user_base_dn,
+                Scope::Subtree,
+                &filter,
+                vec![self.config.name_attribute.as_str(), self.config.group_attribute.as_str()],
+            )
+            .and_then(|result| result.success())
+            .map_err(|e| format!("Erro ao buscar usuário no LDAP: {}", e))?;
+
+        if entries.len() > 1 {
+            return Err(format!("Filtro LDAP retornou {} usuários para o WWID {}", entries.len(), wwid));
+        }
+
+        Ok(entries.into_iter().next().map(|entry| {
+            let entry = SearchEntry::construct(entry);
+            let name = entry
+                .attrs
+                .get(&self.config.name_attribute)
+                .and_then(|values| values.first().cloned());
+            let groups = entry
+                .attrs
+                .get(&self.config.group_attribute)
+                .cloned()
+                .unwrap_or_default();
+            DirectoryEntry { dn: entry.dn, name, groups }
+        }))
+    }
+
+    /// Busca grupos que têm o usuário como membro (OpenLDAP sem overlay memberOf)
+    fn find_groups_by_member(&self, ldap: &mut LdapConn, user_dn: &str) -> Result<Vec<String>, String> {
+        if self.config.group_base_dn.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let filter = self.config.group_filter.replace("{dn}", &ldap_escape(user_dn));
+        let (entries, _) = ldap
+            .search(&self.config.group_base_dn, Scope::Subtree, &filter, vec!["cn"])
+            .and_then(|result| result.success())
+            .map_err(|e| format!("Erro ao buscar grupos no LDAP: {}", e))?;
+
+        Ok(entries.into_iter().map(|entry| SearchEntry::construct(entry).dn).collect())
+    }
+
+    /// Resolve o papel de maior privilégio entre os grupos mapeados
+    pub fn resolve_role(&self, groups: &[String]) -> Option<String> {
+        self.config
+            .group_role_mappings
+            .iter()
+            .filter(|mapping| groups.iter().any(|group| group_matches(group, &mapping.group)))
+            .filter_map(|mapping| ROLE_PRIORITY.iter().position(|role| *role == mapping.role))
+            .max()
+            .map(|index| ROLE_PRIORITY[index].to_string())
+    }
+}
+
+impl AuthProvider for LdapAuthProvider {
+    fn name(&self) -> &'static str {
+        AUTH_PROVIDER_LDAP
+    }
+
+    fn authenticate(&self, wwid: &str, password: &str) -> Result<AuthOutcome, String> {
+        // Bind com senha vazia é um bind anônimo e seria aceito pelo servidor
+        if password.is_empty() {
+            return Ok(AuthOutcome::InvalidCredentials);
+        }
+
+        let mut ldap = self.connect()?;
+
+        let DirectoryEntry { dn: user_dn, name: display_name, mut groups } = match self.find_user(&mut ldap, wwid)? {
+            Some(user) => user,
+            None => {
+                let _ = ldap.unbind();
+                return Ok(AuthOutcome::UnknownUser);
+            }
+        };
+
+        let bind_result = ldap
+            .simple_bind(&user_dn, password)
+            .map_err(|e| format!("Erro no bind do usuário LDAP: {}", e))?;
+
+        if bind_result.rc == LDAP_INVALID_CREDENTIALS {
+            let _ = ldap.unbind();
+            return Ok(AuthOutcome::InvalidCredentials);
+        }
+        bind_result
+            .success()
+            .map_err(|e| format!("Erro no bind do usuário LDAP: {}", e))?;
+
+        // Os grupos são buscados com a conta de serviço, que tem permissão de leitura garantida
+        ldap.simple_bind(&self.config.bind_dn, &self.config.bind_password)
+            .and_then(|result| result.success())
+            .map_err(|e| format!("Erro no bind da conta de serviço LDAP: {}", e))?;
+
+        for group in self.find_groups_by_member(&mut ldap, &user_dn)? {
+            if !groups.iter().any(|existing| existing.eq_ignore_ascii_case(&group)) {
+                groups.push(group);
+            }
+        }
+
+        let _ = ldap.unbind();
+
+        let role = self.resolve_role(&groups);
+
+        Ok(AuthOutcome::Authenticated(AuthenticatedIdentity {
+            display_name,
+            user_dn: Some(user_dn),
+            groups,
+            role,
+        }))
+    }
+
+    fn enforces_password_policy(&self) -> bool {
+        false
+    }
+}
+
+/// Compara um grupo do usuário (DN) com o grupo do mapeamento (DN completo ou CN)
+fn group_matches(user_group_dn: &str, mapped_group: &str) -> bool {
+    let mapped_group = mapped_group.trim();
+    if user_group_dn.eq_ignore_ascii_case(mapped_group) {
+        return true;
+    }
+
+    let first_rdn = user_group_dn.split(',').next().unwrap_or_default();
+    match first_rdn.split_once('=') {
+        Some((attr, value)) if attr.trim().eq_ignore_ascii_case("cn") => {
+            value.trim().eq_ignore_ascii_case(mapped_group)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn group_matches_full_dn_ignoring_case() {
+        let dn = "CN=SCR Admins,OU=Groups,DC=empresa,DC=com";
+        assert!(group_matches(dn, "cn=scr admins,ou=groups,dc=empresa,dc=com"));
+        assert!(group_matches(dn, "  CN=SCR Admins,OU=Groups,DC=empresa,DC=com  "));
+        assert!(!group_matches(dn, "CN=SCR Admins,OU=Other,DC=empresa,DC=com"));
+    }
+
+    #[test]
+    fn group_matches_common_name_only() {
+        let dn = "CN=SCR Admins,OU=Groups,DC=empresa,DC=com";
+        assert!(group_matches(dn, "SCR Admins"));
+        assert!(group_matches(dn, "scr admins"));
+        assert!(group_matches("cn = SCR Admins ,OU=Groups", "SCR Admins"));
+        assert!(!group_matches(dn, "SCR"));
+        assert!(!group_matches(dn, "Groups"));
+    }
+
+    #[test]
+    fn group_matches_requires_cn_as_first_component() {
+        assert!(!group_matches("OU=SCR Admins,DC=empresa,DC=com", "SCR Admins"));
+        assert!(!group_matches("SCR Admins", "Other"));
+        assert!(group_matches("SCR Admins", "scr admins"));
+    }
+}
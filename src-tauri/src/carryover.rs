@@ -0,0 +1,99 @@
+// Notas copiadas de outro mês (ponto de partida para fornecedores estáveis)
+// Cada critério copiado fica marcado aqui até ser confirmado ou alterado: a fila de
+// pendências continua mostrando o critério para revisão enquanto a marca existir.
+// Gravar um valor diferente (formulário, importação, cálculo de OTIF/NIL/inspeção)
+// ou salvar o critério individualmente remove a marca.
+
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Origem de um critério copiado
+#[derive(Debug, Serialize, Clone)]
+pub struct CarriedOverScore {
+    pub criterion_key: String,
+    pub source_record_id: i64,
+    /// AAAA-MM de origem
+    pub source_period: String,
+    pub copied_at: String,
+    pub copied_by: String,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS carried_over_score_table (
+            record_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            source_record_id INTEGER NOT NULL,
+            source_period TEXT NOT NULL,
+            copied_at TEXT NOT NULL,
+            copied_by TEXT NOT NULL,
+            PRIMARY KEY (record_id, criterion_key)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela carried_over_score_table: {}", e))?;
+    Ok(())
+}
+
+/// Marca o critério do registro como copiado
+pub fn mark(conn: &Connection, record_id: i64, criterion_key: &str, source_record_id: i64, source_period: &str, copied_by: &str) -> Result<(), String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT OR REPLACE INTO carried_over_score_table
+         (record_id, criterion_key, source_record_id, source_period, copied_at, copied_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![record_id, criterion_key, source_record_id, source_period, now, copied_by],
+    )
+    .map_err(|e| format!("Erro ao marcar nota copiada {}: {}", criterion_key, e))?;
+    Ok(())
+}
+
+/// Remove a marca (critério confirmado ou alterado); devolve se havia marca
+pub fn clear(conn: &Connection, record_id: i64, criterion_key: &str) -> Result<bool, String> {
+    let cleared = conn
+        .execute(
+            "DELETE FROM carried_over_score_table WHERE record_id = ?1 AND criterion_key = ?2",
+            rusqlite::params![record_id, criterion_key],
+        )
+        .map_err(|e| format!("Erro ao confirmar nota copiada {}: {}", criterion_key, e))?;
+    Ok(cleared > 0)
+}
+
+/// Compara a nota gravada com a nova (tolerância de arredondamento)
+pub fn value_changed(old: Option<f64>, new: Option<f64>) -> bool {
+    match (old, new) {
+        (Some(old), Some(new)) => (old - new).abs() > 0.001,
+        (old, new) => old.is_some() != new.is_some(),
+    }
+}
+
+/// Critérios copiados e ainda não confirmados do registro
+pub fn read(conn: &Connection, record_id: i64) -> Result<BTreeMap<String, CarriedOverScore>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT criterion_key, source_record_id, source_period, copied_at, copied_by
+             FROM carried_over_score_table WHERE record_id = ?1",
+        )
+        .map_err(|e| format!("Erro ao buscar notas copiadas: {}", e))?;
+    let rows = stmt
+        .query_map([record_id], |row| {
+            Ok(CarriedOverScore {
+                criterion_key: row.get(0)?,
+                source_record_id: row.get(1)?,
+                source_period: row.get(2)?,
+                copied_at: row.get(3)?,
+                copied_by: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar notas copiadas: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar notas copiadas: {}", e))?;
+    Ok(rows.into_iter().map(|c| (c.criterion_key.clone(), c)).collect())
+}
+
+pub fn delete_for_record(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM carried_over_score_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao remover notas copiadas do registro {}: {}", record_id, e))?;
+    Ok(())
+}
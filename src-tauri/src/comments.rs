@@ -0,0 +1,178 @@
+// Comentários das notas: thread por registro, com autor e critério opcional
+// Substitui a coluna única `comment` de supplier_score_records_table, que era
+// sobrescrita a cada gravação. A coluna continua sendo preenchida com o último
+// comentário geral para as telas antigas; o histórico fica aqui.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Comentário de uma nota (criterion_key vazio = comentário geral do registro)
+#[derive(Debug, Serialize, Clone)]
+pub struct ScoreComment {
+    pub comment_id: i64,
+    pub record_id: i64,
+    pub supplier_id: String,
+    pub month: i32,
+    pub year: i32,
+    pub criterion_key: Option<String>,
+    pub body: String,
+    pub author_name: String,
+    pub author_wwid: Option<String>,
+    pub created_at: String,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS score_comment_table (
+            comment_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            record_id INTEGER NOT NULL,
+            criterion_key TEXT,
+            body TEXT NOT NULL,
+            author_name TEXT NOT NULL,
+            author_wwid TEXT,
+            created_at TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela score_comment_table: {}", e))?;
+
+    // Comentários antigos (coluna única) viram o primeiro item geral da thread
+    let migrated = conn
+        .execute(
+            "INSERT INTO score_comment_table (record_id, criterion_key, body, author_name, author_wwid, created_at)
+             SELECT r.id, NULL, r.comment,
+                    COALESCE(NULLIF(r.changed_by, ''), NULLIF(r.registered_by, ''), 'Sistema'), NULL,
+                    COALESCE(r.change_date, r.register_date, '')
+             FROM supplier_score_records_table r
+             WHERE trim(COALESCE(r.comment, '')) != ''
+               AND NOT EXISTS (SELECT 1 FROM score_comment_table c WHERE c.record_id = r.id)",
+            [],
+        )
+        .map_err(|e| format!("Erro ao migrar comentários: {}", e))?;
+    if migrated > 0 {
+        println!("💬 {} comentários migrados para score_comment_table", migrated);
+    }
+    Ok(())
+}
+
+fn row_to_comment(row: &rusqlite::Row) -> rusqlite::Result<ScoreComment> {
+    Ok(ScoreComment {
+        comment_id: row.get(0)?,
+        record_id: row.get(1)?,
+        supplier_id: row.get(2)?,
+        month: row.get::<_, String>(3)?.trim().parse().unwrap_or(0),
+        year: row.get::<_, String>(4)?.trim().parse().unwrap_or(0),
+        criterion_key: row.get(5)?,
+        body: row.get(6)?,
+        author_name: row.get(7)?,
+        author_wwid: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+const SELECT_COMMENTS: &str = "SELECT c.comment_id, c.record_id, r.supplier_id, r.month, r.year, c.criterion_key, c.body,
+            c.author_name, c.author_wwid, c.created_at
+     FROM score_comment_table c
+     JOIN supplier_score_records_table r ON r.id = c.record_id";
+
+/// Acrescenta um comentário à thread do registro; devolve o id
+pub fn add(
+    conn: &Connection,
+    record_id: i64,
+    criterion_key: Option<&str>,
+    body: &str,
+    author_name: &str,
+    author_wwid: Option<&str>,
+) -> Result<i64, String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return Err("O comentário não pode ser vazio".to_string());
+    }
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO score_comment_table (record_id, criterion_key, body, author_name, author_wwid, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![record_id, criterion_key, body, author_name, author_wwid, now],
+    )
+    .map_err(|e| format!("Erro ao gravar comentário: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Acrescenta o comentário só se for diferente do último do mesmo critério (evita duplicar em regravações)
+pub fn add_if_changed(
+    conn: &Connection,
+    record_id: i64,
+    criterion_key: Option<&str>,
+    body: &str,
+    author_name: &str,
+    author_wwid: Option<&str>,
+) -> Result<bool, String> {
+    if body.trim().is_empty() || latest(conn, record_id, criterion_key)?.as_deref() == Some(body.trim()) {
+        return Ok(false);
+    }
+    add(conn, record_id, criterion_key, body, author_name, author_wwid)?;
+    Ok(true)
+}
+
+/// Texto do último comentário do critério (None = geral)
+pub fn latest(conn: &Connection, record_id: i64, criterion_key: Option<&str>) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT body FROM score_comment_table
+         WHERE record_id = ?1 AND criterion_key IS ?2
+         ORDER BY comment_id DESC LIMIT 1",
+        rusqlite::params![record_id, criterion_key],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar comentário: {}", e))
+}
+
+/// Último comentário de cada critério do registro (usado pela regra "comentário abaixo do target")
+pub fn latest_by_criterion(conn: &Connection, record_id: i64) -> Result<HashMap<String, String>, String> {
+    let mut latest = HashMap::new();
+    for comment in load_thread(conn, record_id)? {
+        if let Some(key) = comment.criterion_key {
+            latest.insert(key, comment.body);
+        }
+    }
+    Ok(latest)
+}
+
+/// Thread do registro em ordem cronológica
+pub fn load_thread(conn: &Connection, record_id: i64) -> Result<Vec<ScoreComment>, String> {
+    let query = format!("{} WHERE c.record_id = ?1 ORDER BY c.comment_id", SELECT_COMMENTS);
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| format!("Erro ao buscar comentários: {}", e))?;
+    let comments = stmt
+        .query_map([record_id], row_to_comment)
+        .map_err(|e| format!("Erro ao processar comentários: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar comentários: {}", e))?;
+    Ok(comments)
+}
+
+/// Comentários de um fornecedor no ano (mês opcional), por período e ordem de criação
+pub fn load_for_supplier(conn: &Connection, supplier_id: &str, year: i32, month: Option<i32>) -> Result<Vec<ScoreComment>, String> {
+    let query = format!(
+        "{} WHERE lower(trim(r.supplier_id)) = lower(trim(?1)) AND r.year = ?2 AND (?3 IS NULL OR r.month = ?3)
+         ORDER BY CAST(r.month AS INTEGER), c.comment_id",
+        SELECT_COMMENTS
+    );
+    let mut stmt = conn
+        .prepare(&query)
+        .map_err(|e| format!("Erro ao buscar comentários: {}", e))?;
+    let comments = stmt
+        .query_map(rusqlite::params![supplier_id, year.to_string(), month.map(|m| m.to_string())], row_to_comment)
+        .map_err(|e| format!("Erro ao processar comentários: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar comentários: {}", e))?;
+    Ok(comments)
+}
+
+pub fn delete_for_record(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM score_comment_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao remover comentários do registro {}: {}", record_id, e))?;
+    Ok(())
+}
@@ -0,0 +1,603 @@
+// Critérios de avaliação configuráveis
+// As notas ficam em formato longo (registro, critério) em supplier_score_values_table.
+// Os quatro critérios originais continuam espelhados nas colunas legadas de
+// supplier_score_records_table e users_table para as telas e consultas antigas.
+// Pesos, target e ativação são versionados por período de vigência (criteria_version_table):
+// cada registro é calculado com a versão vigente no seu mês/ano.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::score_engine::parse_score_text;
+
+/// Critério de avaliação configurado em criteria_definition_table
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CriterionDefinition {
+    #[serde(default)]
+    pub criterion_key: String,
+    pub display_name: String,
+    pub weight: f64,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+/// Versão de peso/target de um critério, vigente a partir de `effective_from` (AAAA-MM)
+#[derive(Debug, Serialize, Clone)]
+pub struct CriteriaVersion {
+    pub version_id: i64,
+    pub criterion_key: String,
+    pub display_name: String,
+    pub weight: Option<f64>,
+    pub target: Option<f64>,
+    /// Ativação/desativação do critério a partir da vigência (None = sem mudança)
+    pub is_active: Option<bool>,
+    pub effective_from: String,
+    pub created_at: Option<String>,
+    pub created_by: Option<String>,
+}
+
+/// Chave usada no versionamento do target geral (aplicado ao total_score)
+pub const TOTAL_TARGET_KEY: &str = "total";
+
+/// Vigência da versão inicial: cobre todo o histórico anterior ao versionamento
+pub const BASELINE_PERIOD: &str = "1900-01";
+
+/// Critério original com suas colunas legadas
+pub struct BuiltinCriterion {
+    pub key: &'static str,
+    pub display_name: &'static str,
+    /// Coluna em supplier_score_records_table
+    pub score_column: &'static str,
+    /// true quando a coluna legada é TEXT (quality_pickup / quality_package)
+    pub text_column: bool,
+    /// Coluna de permissão em users_table
+    pub permission_column: &'static str,
+}
+
+pub const BUILTIN_CRITERIA: [BuiltinCriterion; 4] = [
+    BuiltinCriterion { key: "otif", display_name: "OTIF", score_column: "otif", text_column: false, permission_column: "otif" },
+    BuiltinCriterion { key: "nil", display_name: "NIL", score_column: "nil", text_column: false, permission_column: "nil" },
+    BuiltinCriterion { key: "pickup", display_name: "Pickup", score_column: "quality_pickup", text_column: true, permission_column: "pickup" },
+    BuiltinCriterion { key: "package", display_name: "Package", score_column: "quality_package", text_column: true, permission_column: "package" },
+];
+
+pub fn builtin(key: &str) -> Option<&'static BuiltinCriterion> {
+    BUILTIN_CRITERIA.iter().find(|c| c.key == key)
+}
+
+/// Identifica o critério original a partir do nome usado em criteria_table
+pub fn builtin_key_for_category(category: &str) -> Option<&'static str> {
+    let name = category.to_lowercase();
+    if name.contains("target") {
+        None
+    } else if name.contains("otif") {
+        Some("otif")
+    } else if name.contains("nil") {
+        Some("nil")
+    } else if name.contains("pickup") || name.contains("pick up") {
+        Some("pickup")
+    } else if name.contains("package") {
+        Some("package")
+    } else {
+        None
+    }
+}
+
+/// Gera a chave de um critério a partir do nome exibido ("Sustentabilidade Ambiental" -> "sustentabilidade_ambiental")
+pub fn normalize_key(name: &str) -> String {
+    let mut key = String::new();
+    for ch in name.trim().to_lowercase().chars() {
+        if ch.is_ascii_alphanumeric() {
+            key.push(ch);
+        } else if !key.ends_with('_') {
+            key.push('_');
+        }
+    }
+    key.trim_matches('_').to_string()
+}
+
+fn table_exists(conn: &Connection, table_name: &str) -> Result<bool, String> {
+    conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        [table_name],
+        |row| row.get::<_, i32>(0),
+    )
+    .map(|count| count > 0)
+    .map_err(|e| format!("Erro ao verificar tabela {}: {}", table_name, e))
+}
+
+/// Cria as tabelas de critérios e migra os dados das colunas legadas (apenas na primeira vez)
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    let values_existed = table_exists(conn, "supplier_score_values_table")?;
+    let permissions_existed = table_exists(conn, "user_criteria_permissions_table")?;
+    let versions_existed = table_exists(conn, "criteria_version_table")?;
+
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS criteria_definition_table (
+            criterion_key TEXT PRIMARY KEY,
+            display_name TEXT NOT NULL,
+            weight REAL NOT NULL DEFAULT 0,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            created_at TEXT
+        );
+        CREATE TABLE IF NOT EXISTS supplier_score_values_table (
+            record_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            value REAL NOT NULL,
+            PRIMARY KEY (record_id, criterion_key)
+        );
+        CREATE INDEX IF NOT EXISTS idx_score_values_criterion ON supplier_score_values_table (criterion_key);
+        CREATE TABLE IF NOT EXISTS user_criteria_permissions_table (
+            user_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            can_edit INTEGER NOT NULL DEFAULT 0,
+            PRIMARY KEY (user_id, criterion_key)
+        );
+        CREATE TABLE IF NOT EXISTS criteria_version_table (
+            version_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            criterion_key TEXT NOT NULL,
+            weight REAL,
+            target REAL,
+            is_active INTEGER,
+            effective_from TEXT NOT NULL,
+            created_at TEXT,
+            created_by TEXT,
+            UNIQUE (criterion_key, effective_from)
+        );",
+    )
+    .map_err(|e| format!("Erro ao criar tabelas de critérios: {}", e))?;
+
+    seed_builtin_definitions(conn)?;
+
+    if !values_existed {
+        migrate_legacy_scores(conn)?;
+    }
+    if !permissions_existed {
+        migrate_legacy_permissions(conn)?;
+    }
+    if !versions_existed {
+        seed_baseline_versions(conn)?;
+    } else {
+        let has_active_column: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('criteria_version_table') WHERE lower(name) = 'is_active'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao verificar colunas de criteria_version_table: {}", e))?;
+        if has_active_column == 0 {
+            conn.execute("ALTER TABLE criteria_version_table ADD COLUMN is_active INTEGER", [])
+                .map_err(|e| format!("Erro ao adicionar coluna is_active: {}", e))?;
+            seed_inactive_versions(conn)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn seed_builtin_definitions(conn: &Connection) -> Result<(), String> {
+    // Pesos iniciais vêm de criteria_table (mesma identificação por nome usada no cálculo)
+    let mut weights: HashMap<&'static str, f64> = HashMap::new();
+    if table_exists(conn, "criteria_table")? {
+        let mut stmt = conn
+            .prepare("SELECT criteria_category, value FROM criteria_table")
+            .map_err(|e| format!("Erro ao buscar pesos: {}", e))?;
+        let rows = stmt
+            .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+            .map_err(|e| format!("Erro ao processar pesos: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar pesos: {}", e))?;
+
+        for (category, value) in rows {
+            if let Some(key) = builtin_key_for_category(&category) {
+                let weight = value.as_deref().and_then(parse_score_text).unwrap_or(0.0).max(0.0);
+                weights.insert(key, weight);
+            }
+        }
+    }
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    for (idx, c) in BUILTIN_CRITERIA.iter().enumerate() {
+        conn.execute(
+            "INSERT OR IGNORE INTO criteria_definition_table (criterion_key, display_name, weight, sort_order, is_active, created_at)
+             VALUES (?1, ?2, ?3, ?4, 1, ?5)",
+            rusqlite::params![c.key, c.display_name, weights.get(c.key).copied().unwrap_or(0.25), idx as i32, now],
+        )
+        .map_err(|e| format!("Erro ao cadastrar critério {}: {}", c.key, e))?;
+    }
+
+    Ok(())
+}
+
+fn migrate_legacy_scores(conn: &Connection) -> Result<(), String> {
+    let mut stmt = conn
+        .prepare("SELECT id, otif, nil, quality_pickup, quality_package FROM supplier_score_records_table")
+        .map_err(|e| format!("Erro ao ler notas legadas: {}", e))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            let as_text = |idx: usize| -> rusqlite::Result<Option<String>> {
+                Ok(match row.get_ref(idx)? {
+                    rusqlite::types::ValueRef::Null => None,
+                    rusqlite::types::ValueRef::Integer(v) => Some(v.to_string()),
+                    rusqlite::types::ValueRef::Real(v) => Some(v.to_string()),
+                    rusqlite::types::ValueRef::Text(t) => Some(String::from_utf8_lossy(t).to_string()),
+                    rusqlite::types::ValueRef::Blob(_) => None,
+                })
+            };
+            Ok((row.get::<_, i64>(0)?, [as_text(1)?, as_text(2)?, as_text(3)?, as_text(4)?]))
+        })
+        .map_err(|e| format!("Erro ao processar notas legadas: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar notas legadas: {}", e))?;
+
+    let mut migrated = 0;
+    for (record_id, values) in rows {
+        for (c, value) in BUILTIN_CRITERIA.iter().zip(values.iter()) {
+            if let Some(v) = value.as_deref().and_then(parse_score_text) {
+                conn.execute(
+                    "INSERT OR IGNORE INTO supplier_score_values_table (record_id, criterion_key, value) VALUES (?1, ?2, ?3)",
+                    rusqlite::params![record_id, c.key, v],
+                )
+                .map_err(|e| format!("Erro ao migrar nota {} do registro {}: {}", c.key, record_id, e))?;
+                migrated += 1;
+            }
+        }
+    }
+
+    println!("✅ {} notas migradas para supplier_score_values_table", migrated);
+    Ok(())
+}
+
+fn migrate_legacy_permissions(conn: &Connection) -> Result<(), String> {
+    for c in BUILTIN_CRITERIA.iter() {
+        conn.execute(
+            &format!(
+                "INSERT OR IGNORE INTO user_criteria_permissions_table (user_id, criterion_key, can_edit)
+                 SELECT user_id, ?1, CASE WHEN CAST({} AS INTEGER) = 1 THEN 1 ELSE 0 END FROM users_table",
+                c.permission_column
+            ),
+            [c.key],
+        )
+        .map_err(|e| format!("Erro ao migrar permissões de {}: {}", c.key, e))?;
+    }
+    Ok(())
+}
+
+/// Versão inicial: pesos atuais e o target de criteria_table valem para todo o histórico
+fn seed_baseline_versions(conn: &Connection) -> Result<(), String> {
+    for definition in load_definitions(conn, true)? {
+        record_version(conn, &definition.criterion_key, Some(definition.weight), None, BASELINE_PERIOD, "System")?;
+    }
+    seed_inactive_versions(conn)?;
+
+    let target: Option<f64> = if table_exists(conn, "criteria_table")? {
+        conn.query_row(
+            "SELECT value FROM criteria_table WHERE LOWER(criteria_category) LIKE '%target%' LIMIT 1",
+            [],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar target: {}", e))?
+        .flatten()
+        .as_deref()
+        .and_then(parse_score_text)
+    } else {
+        None
+    };
+    if let Some(target) = target {
+        record_version(conn, TOTAL_TARGET_KEY, None, Some(target), BASELINE_PERIOD, "System")?;
+    }
+
+    Ok(())
+}
+
+/// Critérios já inativos antes do versionamento da ativação continuam fora de todo o histórico
+fn seed_inactive_versions(conn: &Connection) -> Result<(), String> {
+    for definition in load_definitions(conn, true)?.into_iter().filter(|d| !d.is_active) {
+        record_activation(conn, &definition.criterion_key, false, BASELINE_PERIOD, "System")?;
+    }
+    Ok(())
+}
+
+fn row_to_definition(row: &rusqlite::Row) -> rusqlite::Result<CriterionDefinition> {
+    Ok(CriterionDefinition {
+        criterion_key: row.get(0)?,
+        display_name: row.get(1)?,
+        weight: row.get(2)?,
+        sort_order: row.get(3)?,
+        is_active: row.get::<_, i32>(4)? == 1,
+    })
+}
+
+/// Lista os critérios configurados na ordem de exibição
+pub fn load_definitions(conn: &Connection, include_inactive: bool) -> Result<Vec<CriterionDefinition>, String> {
+    let query = if include_inactive {
+        "SELECT criterion_key, display_name, weight, sort_order, is_active FROM criteria_definition_table
+         ORDER BY sort_order, criterion_key"
+    } else {
+        "SELECT criterion_key, display_name, weight, sort_order, is_active FROM criteria_definition_table
+         WHERE is_active = 1 ORDER BY sort_order, criterion_key"
+    };
+
+    let mut stmt = conn
+        .prepare(query)
+        .map_err(|e| format!("Erro ao buscar critérios: {}", e))?;
+    let definitions = stmt
+        .query_map([], row_to_definition)
+        .map_err(|e| format!("Erro ao processar critérios: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar critérios: {}", e))?;
+    Ok(definitions)
+}
+
+/// Busca um critério pela chave (aceita também o nome exibido, sem diferenciar maiúsculas)
+pub fn find_definition(conn: &Connection, key_or_name: &str) -> Result<CriterionDefinition, String> {
+    let trimmed = key_or_name.trim();
+    conn.query_row(
+        "SELECT criterion_key, display_name, weight, sort_order, is_active FROM criteria_definition_table
+         WHERE criterion_key = lower(?1) OR lower(display_name) = lower(?1)
+         ORDER BY CASE WHEN criterion_key = lower(?1) THEN 0 ELSE 1 END
+         LIMIT 1",
+        [trimmed],
+        row_to_definition,
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar critério: {}", e))?
+    .ok_or_else(|| format!("Critério inválido: {}", trimmed))
+}
+
+/// Grava a nota de um critério (vazio remove a nota) e espelha na coluna legada quando houver
+pub fn write_value(conn: &Connection, record_id: i64, criterion_key: &str, raw_value: &str) -> Result<(), String> {
+    let trimmed = raw_value.trim();
+    let numeric = parse_score_text(trimmed);
+
+    // As colunas legadas TEXT (pickup/package) também só aceitam números
+    if !trimmed.is_empty() && numeric.is_none() {
+        return Err(format!("Valor inválido para {}: {}", criterion_key, raw_value));
+    }
+
+    match numeric {
+        Some(value) => conn.execute(
+            "INSERT OR REPLACE INTO supplier_score_values_table (record_id, criterion_key, value) VALUES (?1, ?2, ?3)",
+            rusqlite::params![record_id, criterion_key, value],
+        ),
+        None => conn.execute(
+            "DELETE FROM supplier_score_values_table WHERE record_id = ?1 AND criterion_key = ?2",
+            rusqlite::params![record_id, criterion_key],
+        ),
+    }
+    .map_err(|e| format!("Erro ao gravar nota {}: {}", criterion_key, e))?;
+
+    if let Some(c) = builtin(criterion_key) {
+        let query = format!("UPDATE supplier_score_records_table SET {} = ?1 WHERE id = ?2", c.score_column);
+        if c.text_column {
+            let text = if trimmed.is_empty() { None } else { Some(trimmed.to_string()) };
+            conn.execute(&query, rusqlite::params![text, record_id])
+        } else {
+            conn.execute(&query, rusqlite::params![numeric, record_id])
+        }
+        .map_err(|e| format!("Erro ao atualizar coluna {}: {}", c.score_column, e))?;
+    }
+
+    Ok(())
+}
+
+/// Notas gravadas de um registro, por critério
+pub fn read_values(conn: &Connection, record_id: i64) -> Result<HashMap<String, f64>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT criterion_key, value FROM supplier_score_values_table WHERE record_id = ?1")
+        .map_err(|e| format!("Erro ao buscar notas do registro: {}", e))?;
+    let values = stmt
+        .query_map([record_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, f64>(1)?)))
+        .map_err(|e| format!("Erro ao processar notas do registro: {}", e))?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| format!("Erro ao coletar notas do registro: {}", e))?;
+    Ok(values)
+}
+
+/// Mesmo que `read_values`, ordenado pela chave para as respostas da API
+pub fn read_values_sorted(conn: &Connection, record_id: i64) -> Result<BTreeMap<String, f64>, String> {
+    Ok(read_values(conn, record_id)?.into_iter().collect())
+}
+
+/// Remove as notas de um registro excluído
+pub fn delete_values(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM supplier_score_values_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao excluir notas do registro {}: {}", record_id, e))?;
+    Ok(())
+}
+
+/// Permissões de edição do usuário para cada critério ativo
+pub fn load_user_permissions(conn: &Connection, user_id: i64) -> Result<BTreeMap<String, bool>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT d.criterion_key, COALESCE(p.can_edit, 0)
+             FROM criteria_definition_table d
+             LEFT JOIN user_criteria_permissions_table p ON p.criterion_key = d.criterion_key AND p.user_id = ?1
+             WHERE d.is_active = 1",
+        )
+        .map_err(|e| format!("Erro ao buscar permissões: {}", e))?;
+    let permissions = stmt
+        .query_map([user_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)? == 1)))
+        .map_err(|e| format!("Erro ao processar permissões: {}", e))?
+        .collect::<Result<BTreeMap<_, _>, _>>()
+        .map_err(|e| format!("Erro ao coletar permissões: {}", e))?;
+    Ok(permissions)
+}
+
+/// Grava a permissão de um critério, espelhando na coluna legada de users_table
+pub fn set_user_permission(conn: &Connection, user_id: i64, criterion_key: &str, can_edit: bool) -> Result<(), String> {
+    conn.execute(
+        "INSERT OR REPLACE INTO user_criteria_permissions_table (user_id, criterion_key, can_edit) VALUES (?1, ?2, ?3)",
+        rusqlite::params![user_id, criterion_key, can_edit as i32],
+    )
+    .map_err(|e| format!("Erro ao gravar permissão {}: {}", criterion_key, e))?;
+
+    if let Some(c) = builtin(criterion_key) {
+        conn.execute(
+            &format!("UPDATE users_table SET {} = ?1 WHERE user_id = ?2", c.permission_column),
+            rusqlite::params![(can_edit as i32).to_string(), user_id],
+        )
+        .map_err(|e| format!("Erro ao atualizar permissão legada {}: {}", criterion_key, e))?;
+    }
+
+    Ok(())
+}
+
+/// Sincroniza as permissões dos critérios originais gravadas pelas telas antigas
+pub fn sync_builtin_permissions(conn: &Connection, user_id: i64, otif: bool, nil: bool, pickup: bool, package: bool) -> Result<(), String> {
+    for (key, can_edit) in [("otif", otif), ("nil", nil), ("pickup", pickup), ("package", package)] {
+        set_user_permission(conn, user_id, key, can_edit)?;
+    }
+    Ok(())
+}
+
+/// Chave de período usada nas vigências ("2025-03")
+pub fn period_key(month: i32, year: i32) -> String {
+    format!("{:04}-{:02}", year, month)
+}
+
+/// Período corrente (vigência padrão das alterações)
+pub fn current_period() -> String {
+    chrono::Local::now().format("%Y-%m").to_string()
+}
+
+/// Valida uma vigência informada como AAAA-MM (vazio = período corrente)
+pub fn parse_effective_from(value: Option<&str>) -> Result<String, String> {
+    let trimmed = value.map(str::trim).unwrap_or("");
+    if trimmed.is_empty() {
+        return Ok(current_period());
+    }
+
+    let (year, month) = trimmed
+        .split_once('-')
+        .and_then(|(y, m)| Some((y.parse::<i32>().ok()?, m.parse::<i32>().ok()?)))
+        .ok_or_else(|| format!("Vigência inválida: {} (use AAAA-MM)", trimmed))?;
+    if !(1..=12).contains(&month) || !(1900..=9999).contains(&year) {
+        return Err(format!("Vigência inválida: {} (use AAAA-MM)", trimmed));
+    }
+    Ok(period_key(month, year))
+}
+
+/// Registra uma versão de peso e/ou target; a mesma vigência é sobrescrita
+pub fn record_version(
+    conn: &Connection,
+    criterion_key: &str,
+    weight: Option<f64>,
+    target: Option<f64>,
+    effective_from: &str,
+    created_by: &str,
+) -> Result<(), String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO criteria_version_table (criterion_key, weight, target, effective_from, created_at, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+         ON CONFLICT (criterion_key, effective_from) DO UPDATE SET
+            weight = COALESCE(excluded.weight, weight),
+            target = COALESCE(excluded.target, target),
+            created_at = excluded.created_at,
+            created_by = excluded.created_by",
+        rusqlite::params![criterion_key, weight, target, effective_from, now, created_by],
+    )
+    .map_err(|e| format!("Erro ao registrar versão de {}: {}", criterion_key, e))?;
+    Ok(())
+}
+
+/// Registra a ativação/desativação de um critério a partir da vigência
+pub fn record_activation(conn: &Connection, criterion_key: &str, is_active: bool, effective_from: &str, created_by: &str) -> Result<(), String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO criteria_version_table (criterion_key, is_active, effective_from, created_at, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5)
+         ON CONFLICT (criterion_key, effective_from) DO UPDATE SET
+            is_active = excluded.is_active,
+            created_at = excluded.created_at,
+            created_by = excluded.created_by",
+        rusqlite::params![criterion_key, is_active as i32, effective_from, now, created_by],
+    )
+    .map_err(|e| format!("Erro ao registrar ativação de {}: {}", criterion_key, e))?;
+    Ok(())
+}
+
+/// Critério ativo no período (sem versão de ativação, vale como ativo desde o início)
+pub fn active_for_period(conn: &Connection, criterion_key: &str, period: &str) -> Result<bool, String> {
+    let active: Option<i32> = conn
+        .query_row(
+            "SELECT is_active FROM criteria_version_table
+             WHERE criterion_key = ?1 AND effective_from <= ?2 AND is_active IS NOT NULL
+             ORDER BY effective_from DESC LIMIT 1",
+            rusqlite::params![criterion_key, period],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar ativação vigente de {}: {}", criterion_key, e))?;
+    Ok(active.map(|a| a != 0).unwrap_or(true))
+}
+
+/// Peso vigente de um critério no período (None = critério ainda não existia)
+pub fn weight_for_period(conn: &Connection, criterion_key: &str, period: &str) -> Result<Option<f64>, String> {
+    conn.query_row(
+        "SELECT weight FROM criteria_version_table
+         WHERE criterion_key = ?1 AND effective_from <= ?2 AND weight IS NOT NULL
+         ORDER BY effective_from DESC LIMIT 1",
+        rusqlite::params![criterion_key, period],
+        |row| row.get::<_, f64>(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar peso vigente de {}: {}", criterion_key, e))
+}
+
+/// Target vigente no período para um critério (ou `TOTAL_TARGET_KEY` para o target geral)
+pub fn target_for_period(conn: &Connection, criterion_key: &str, period: &str) -> Result<Option<f64>, String> {
+    conn.query_row(
+        "SELECT target FROM criteria_version_table
+         WHERE criterion_key = ?1 AND effective_from <= ?2 AND target IS NOT NULL
+         ORDER BY effective_from DESC LIMIT 1",
+        rusqlite::params![criterion_key, period],
+        |row| row.get::<_, f64>(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar target vigente de {}: {}", criterion_key, e))
+}
+
+/// Histórico de versões, da mais recente para a mais antiga
+pub fn load_versions(conn: &Connection, criterion_key: Option<&str>) -> Result<Vec<CriteriaVersion>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT v.version_id, v.criterion_key,
+                    COALESCE(d.display_name, CASE WHEN v.criterion_key = ?2 THEN 'Target' ELSE v.criterion_key END),
+                    v.weight, v.target, v.is_active, v.effective_from, v.created_at, v.created_by
+             FROM criteria_version_table v
+             LEFT JOIN criteria_definition_table d ON d.criterion_key = v.criterion_key
+             WHERE ?1 IS NULL OR v.criterion_key = ?1
+             ORDER BY v.effective_from DESC, v.criterion_key",
+        )
+        .map_err(|e| format!("Erro ao buscar histórico de critérios: {}", e))?;
+    let versions = stmt
+        .query_map(rusqlite::params![criterion_key, TOTAL_TARGET_KEY], |row| {
+            Ok(CriteriaVersion {
+                version_id: row.get(0)?,
+                criterion_key: row.get(1)?,
+                display_name: row.get(2)?,
+                weight: row.get(3)?,
+                target: row.get(4)?,
+                is_active: row.get::<_, Option<i32>>(5)?.map(|a| a != 0),
+                effective_from: row.get(6)?,
+                created_at: row.get(7)?,
+                created_by: row.get(8)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar histórico de critérios: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar histórico de critérios: {}", e))?;
+    Ok(versions)
+}
@@ -0,0 +1,417 @@
+// Ciclos de avaliação mensais
+// Cada mês avaliado tem data de abertura, prazo e data de fechamento. Sem ciclo
+// cadastrado vale o padrão: abre no dia seguinte ao fim do mês e o prazo é o da
+// configuração da fila de pendências (sem data de fechamento). O prazo do ciclo
+// também é o prazo dos itens da fila. O painel de acompanhamento conta, para os
+// fornecedores ativos (e os que já têm registro no mês), quantos critérios foram
+// preenchidos por critério, BU e avaliador; critérios dispensados em
+// pending_scores_override não entram na conta.
+
+use chrono::{Duration, NaiveDate};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::criteria;
+use crate::pending::{self, AssignmentResolver, DeadlineConfig, SupplierResponsibleAliases};
+use crate::periods;
+
+pub const PHASE_SCHEDULED: &str = "scheduled";
+pub const PHASE_OPEN: &str = "open";
+pub const PHASE_LATE: &str = "late";
+pub const PHASE_CLOSED: &str = "closed";
+
+/// Rótulo do grupo de itens sem avaliador / sem BU
+const UNASSIGNED_LABEL: &str = "(sem atribuição)";
+
+/// Ciclo de avaliação de um mês (datas AAAA-MM-DD)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvaluationCycle {
+    #[serde(default)]
+    pub period: String,
+    pub month: i32,
+    pub year: i32,
+    pub open_date: String,
+    pub deadline: String,
+    #[serde(default)]
+    pub close_date: Option<String>,
+    /// false = datas padrão (mês sem ciclo cadastrado)
+    #[serde(default)]
+    pub is_custom: bool,
+}
+
+/// Contagem de preenchimento de um grupo (critério, BU ou avaliador)
+#[derive(Debug, Serialize, Clone)]
+pub struct CompletionCount {
+    pub key: String,
+    pub label: String,
+    pub expected: usize,
+    pub completed: usize,
+    pub pending: usize,
+    pub percentage: f64,
+}
+
+/// Fornecedor ainda sem nota no critério
+#[derive(Debug, Serialize, Clone)]
+pub struct MissingSupplier {
+    pub supplier_id: String,
+    pub vendor_name: String,
+    pub bu: Option<String>,
+    pub evaluator: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct MissingByCriterion {
+    pub criterion_key: String,
+    pub criterion_name: String,
+    pub suppliers: Vec<MissingSupplier>,
+}
+
+/// Painel de acompanhamento do ciclo
+#[derive(Debug, Serialize, Clone)]
+pub struct CycleStatus {
+    pub cycle: EvaluationCycle,
+    pub phase: String,
+    pub period_status: String,
+    pub supplier_count: usize,
+    pub expected: usize,
+    pub completed: usize,
+    pub percentage: f64,
+    pub by_criterion: Vec<CompletionCount>,
+    pub by_bu: Vec<CompletionCount>,
+    pub by_evaluator: Vec<CompletionCount>,
+    pub missing: Vec<MissingByCriterion>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS evaluation_cycle_table (
+            period TEXT PRIMARY KEY,
+            month INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            open_date TEXT NOT NULL,
+            deadline TEXT NOT NULL,
+            close_date TEXT,
+            updated_at TEXT,
+            updated_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela evaluation_cycle_table: {}", e))?;
+    Ok(())
+}
+
+fn parse_date(label: &str, value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("Data de {} inválida: '{}' (use AAAA-MM-DD)", label, value))
+}
+
+fn format_date(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+/// Abertura <= prazo <= fechamento, abertura depois do início do mês avaliado
+pub fn validate(cycle: &EvaluationCycle) -> Result<(), String> {
+    if !(1..=12).contains(&cycle.month) {
+        return Err(format!("Mês inválido: {}", cycle.month));
+    }
+    let open = parse_date("abertura", &cycle.open_date)?;
+    let deadline = parse_date("prazo", &cycle.deadline)?;
+    let month_start = NaiveDate::from_ymd_opt(cycle.year, cycle.month as u32, 1)
+        .ok_or_else(|| format!("Período inválido: {:02}/{}", cycle.month, cycle.year))?;
+
+    if open < month_start {
+        return Err("A abertura não pode ser antes do início do mês avaliado".to_string());
+    }
+    if deadline < open {
+        return Err("O prazo não pode ser antes da abertura".to_string());
+    }
+    if let Some(close) = cycle.close_date.as_deref().filter(|c| !c.trim().is_empty()) {
+        if parse_date("fechamento", close)? < deadline {
+            return Err("O fechamento não pode ser antes do prazo".to_string());
+        }
+    }
+    Ok(())
+}
+
+/// Ciclo padrão do mês (sem cadastro)
+fn default_cycle(month: i32, year: i32, config: &DeadlineConfig) -> EvaluationCycle {
+    let month_end = pending::month_end(month, year);
+    EvaluationCycle {
+        period: criteria::period_key(month, year),
+        month,
+        year,
+        open_date: month_end.map(|d| format_date(d + Duration::days(1))).unwrap_or_default(),
+        deadline: pending::due_date(month, year, config).map(format_date).unwrap_or_default(),
+        close_date: None,
+        is_custom: false,
+    }
+}
+
+fn row_to_cycle(row: &rusqlite::Row) -> rusqlite::Result<EvaluationCycle> {
+    Ok(EvaluationCycle {
+        period: row.get(0)?,
+        month: row.get(1)?,
+        year: row.get(2)?,
+        open_date: row.get(3)?,
+        deadline: row.get(4)?,
+        close_date: row.get(5)?,
+        is_custom: true,
+    })
+}
+
+/// Ciclo do mês: o cadastrado ou o padrão
+pub fn load_cycle(conn: &Connection, month: i32, year: i32, config: &DeadlineConfig) -> Result<EvaluationCycle, String> {
+    let cycle = conn
+        .query_row(
+            "SELECT period, month, year, open_date, deadline, close_date
+             FROM evaluation_cycle_table WHERE period = ?1",
+            [criteria::period_key(month, year)],
+            row_to_cycle,
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar ciclo de avaliação: {}", e))?;
+    Ok(cycle.unwrap_or_else(|| default_cycle(month, year, config)))
+}
+
+/// Ciclos cadastrados (opcionalmente de um ano)
+pub fn load_cycles(conn: &Connection, year: Option<i32>) -> Result<Vec<EvaluationCycle>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT period, month, year, open_date, deadline, close_date
+             FROM evaluation_cycle_table
+             WHERE ?1 IS NULL OR year = ?1
+             ORDER BY period",
+        )
+        .map_err(|e| format!("Erro ao buscar ciclos de avaliação: {}", e))?;
+    let cycles = stmt
+        .query_map([year], row_to_cycle)
+        .map_err(|e| format!("Erro ao processar ciclos de avaliação: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar ciclos de avaliação: {}", e))?;
+    Ok(cycles)
+}
+
+pub fn save_cycle(conn: &Connection, cycle: &EvaluationCycle, updated_by: &str) -> Result<(), String> {
+    validate(cycle)?;
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let close_date = cycle.close_date.as_deref().map(str::trim).filter(|c| !c.is_empty());
+
+    conn.execute(
+        "INSERT INTO evaluation_cycle_table (period, month, year, open_date, deadline, close_date, updated_at, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(period) DO UPDATE SET
+            open_date = excluded.open_date, deadline = excluded.deadline, close_date = excluded.close_date,
+            updated_at = excluded.updated_at, updated_by = excluded.updated_by",
+        rusqlite::params![
+            criteria::period_key(cycle.month, cycle.year),
+            cycle.month,
+            cycle.year,
+            cycle.open_date.trim(),
+            cycle.deadline.trim(),
+            close_date,
+            now,
+            updated_by
+        ],
+    )
+    .map_err(|e| format!("Erro ao gravar ciclo de avaliação: {}", e))?;
+    Ok(())
+}
+
+/// Remove o ciclo cadastrado (o mês volta às datas padrão); devolve se existia
+pub fn delete_cycle(conn: &Connection, month: i32, year: i32) -> Result<bool, String> {
+    let deleted = conn
+        .execute("DELETE FROM evaluation_cycle_table WHERE period = ?1", [criteria::period_key(month, year)])
+        .map_err(|e| format!("Erro ao remover ciclo de avaliação: {}", e))?;
+    Ok(deleted > 0)
+}
+
+/// Prazo efetivo do mês (usado pela fila de pendências)
+pub fn deadline_for(conn: &Connection, month: i32, year: i32, config: &DeadlineConfig) -> Result<Option<NaiveDate>, String> {
+    let cycle = load_cycle(conn, month, year, config)?;
+    Ok(NaiveDate::parse_from_str(&cycle.deadline, "%Y-%m-%d").ok())
+}
+
+/// Fase do ciclo na data informada (mês fechado em period_status_table também encerra o ciclo)
+pub fn phase(cycle: &EvaluationCycle, period_status: &str, today: NaiveDate) -> &'static str {
+    let date = |value: &str| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok();
+    let closed_by_date = cycle.close_date.as_deref().and_then(date).map(|c| today > c).unwrap_or(false);
+
+    if period_status == periods::STATUS_CLOSED || closed_by_date {
+        PHASE_CLOSED
+    } else if date(&cycle.open_date).map(|o| today < o).unwrap_or(false) {
+        PHASE_SCHEDULED
+    } else if date(&cycle.deadline).map(|d| today > d).unwrap_or(false) {
+        PHASE_LATE
+    } else {
+        PHASE_OPEN
+    }
+}
+
+#[derive(Default)]
+struct Counter {
+    label: String,
+    expected: usize,
+    completed: usize,
+}
+
+impl Counter {
+    fn add(&mut self, completed: bool) {
+        self.expected += 1;
+        if completed {
+            self.completed += 1;
+        }
+    }
+}
+
+fn percentage(completed: usize, expected: usize) -> f64 {
+    if expected == 0 {
+        0.0
+    } else {
+        (completed as f64 / expected as f64 * 1000.0).round() / 10.0
+    }
+}
+
+fn to_counts(counters: BTreeMap<String, Counter>) -> Vec<CompletionCount> {
+    counters
+        .into_iter()
+        .map(|(key, c)| CompletionCount {
+            percentage: percentage(c.completed, c.expected),
+            pending: c.expected - c.completed,
+            label: if c.label.is_empty() { key.clone() } else { c.label },
+            key,
+            expected: c.expected,
+            completed: c.completed,
+        })
+        .collect()
+}
+
+/// Painel de preenchimento do mês
+pub fn cycle_status(conn: &Connection, month: i32, year: i32, today: NaiveDate) -> Result<CycleStatus, String> {
+    if !(1..=12).contains(&month) {
+        return Err(format!("Mês inválido: {}", month));
+    }
+    let config = pending::load_config(conn)?;
+    let cycle = load_cycle(conn, month, year, &config)?;
+    let period_status = periods::status_for(conn, month, year)?;
+    let definitions = criteria::load_definitions(conn, false)?;
+    let assignments = AssignmentResolver::new(conn)?;
+
+    // Fornecedores ativos + os que já têm registro no mês (mesmo se inativados depois)
+    let mut stmt = conn
+        .prepare(
+            "SELECT CAST(s.supplier_id AS TEXT), COALESCE(s.vendor_name, ''), s.bu,
+                    s.sqie, s.planner, s.continuity, s.sourcing, r.id
+             FROM supplier_database_table s
+             LEFT JOIN supplier_score_records_table r
+               ON lower(trim(r.supplier_id)) = lower(trim(s.supplier_id))
+              AND CAST(r.month AS INTEGER) = ?1 AND CAST(r.year AS INTEGER) = ?2
+             WHERE LOWER(COALESCE(s.supplier_status, '')) = 'active' OR r.id IS NOT NULL
+             ORDER BY s.vendor_name",
+        )
+        .map_err(|e| format!("Erro ao preparar painel do ciclo: {}", e))?;
+    let suppliers = stmt
+        .query_map([month, year], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                SupplierResponsibleAliases {
+                    sqie: row.get(3)?,
+                    planner: row.get(4)?,
+                    continuity: row.get(5)?,
+                    sourcing: row.get(6)?,
+                },
+                row.get::<_, Option<i64>>(7)?,
+            ))
+        })
+        .map_err(|e| format!("Erro ao executar painel do ciclo: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao processar fornecedores do ciclo: {}", e))?;
+
+    let mut dismissed_stmt = conn
+        .prepare("SELECT score_type FROM pending_scores_override WHERE record_id = ?1")
+        .map_err(|e| format!("Erro ao preparar query de pendências: {}", e))?;
+
+    let mut by_criterion: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut by_bu: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut by_evaluator: BTreeMap<String, Counter> = BTreeMap::new();
+    let mut missing: HashMap<String, Vec<MissingSupplier>> = HashMap::new();
+    let (mut expected, mut completed) = (0, 0);
+
+    for (supplier_id, vendor_name, bu, responsibles, record_id) in &suppliers {
+        let (values, dismissed) = match record_id {
+            Some(record_id) => (
+                criteria::read_values(conn, *record_id)?,
+                dismissed_stmt
+                    .query_map([record_id], |row| row.get::<_, String>(0))
+                    .map_err(|e| format!("Erro ao buscar pendências: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Erro ao coletar pendências: {}", e))?,
+            ),
+            None => (HashMap::new(), Vec::new()),
+        };
+        let bu_key = bu.as_deref().map(str::trim).filter(|b| !b.is_empty()).unwrap_or(UNASSIGNED_LABEL).to_string();
+
+        for definition in &definitions {
+            if dismissed.contains(&definition.criterion_key) {
+                continue;
+            }
+            let filled = values.contains_key(&definition.criterion_key);
+            let (evaluator, _) = assignments.resolve(supplier_id, &definition.criterion_key, responsibles);
+
+            expected += 1;
+            if filled {
+                completed += 1;
+            }
+            by_criterion
+                .entry(definition.criterion_key.clone())
+                .or_insert_with(|| Counter { label: definition.display_name.clone(), ..Counter::default() })
+                .add(filled);
+            by_bu.entry(bu_key.clone()).or_default().add(filled);
+            by_evaluator
+                .entry(evaluator.clone().unwrap_or_else(|| UNASSIGNED_LABEL.to_string()))
+                .or_default()
+                .add(filled);
+
+            if !filled {
+                missing.entry(definition.criterion_key.clone()).or_default().push(MissingSupplier {
+                    supplier_id: supplier_id.clone(),
+                    vendor_name: vendor_name.clone(),
+                    bu: bu.clone(),
+                    evaluator,
+                });
+            }
+        }
+    }
+
+    // Ordem dos critérios na tela, não alfabética
+    let mut by_criterion_counts = to_counts(by_criterion);
+    let order: HashMap<&str, i32> = definitions.iter().map(|d| (d.criterion_key.as_str(), d.sort_order)).collect();
+    by_criterion_counts.sort_by_key(|c| order.get(c.key.as_str()).copied().unwrap_or(i32::MAX));
+
+    let missing = definitions
+        .iter()
+        .filter_map(|d| {
+            missing.remove(&d.criterion_key).map(|suppliers| MissingByCriterion {
+                criterion_key: d.criterion_key.clone(),
+                criterion_name: d.display_name.clone(),
+                suppliers,
+            })
+        })
+        .collect();
+
+    Ok(CycleStatus {
+        phase: phase(&cycle, &period_status, today).to_string(),
+        cycle,
+        period_status,
+        supplier_count: suppliers.len(),
+        expected,
+        completed,
+        percentage: percentage(completed, expected),
+        by_criterion: by_criterion_counts,
+        by_bu: to_counts(by_bu),
+        by_evaluator: to_counts(by_evaluator),
+        missing,
+    })
+}
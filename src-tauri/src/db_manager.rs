@@ -16,6 +16,27 @@ use serde::Serialize;
 use std::path::PathBuf;
 use std::sync::Mutex;
 use once_cell::sync::Lazy;
+use crate::score_engine;
+use crate::criteria::{self, CriterionDefinition};
+use crate::carryover::{self, CarriedOverScore};
+use crate::comments::{self, ScoreComment};
+use crate::reasons::{self, ReasonCode, ReasonParetoEntry, ReasonParetoFilter};
+use crate::evidence::{self, ScoreEvidence};
+use crate::cycles::{self, CycleStatus, EvaluationCycle};
+use crate::deliveries::{self, DeliveryImportSummary, DeliveryLine, OtifConfig, OtifEvidence};
+use crate::incidents::{self, NilEvidence, NilImportSummary, NilIncident, NilRules};
+use crate::inspections::{self, Inspection, InspectionChecklist};
+use crate::tabular;
+use crate::pending::{self, DeadlineConfig, EvaluatorAssignment, PendingQueueFilter, PendingQueuePage};
+use crate::periods::{self, PeriodStatus, PeriodStatusEvent};
+use crate::validation::{self, ValidationReport, ValidationRule};
+use crate::workflow::{self, WorkflowEvent};
+use crate::tiering::{self, SupplierTier, TierEvent, TieringConfig, TieringSummary};
+use crate::targets::{self, ResolvedTargets, SupplierScope, TargetOverride, TargetResolver};
+use std::collections::{BTreeMap, HashMap};
+use crate::auth_provider::{
+    AuthConfig, AuthOutcome, AuthProvider, LdapAuthProvider, LocalAuthProvider, AUTH_PROVIDER_LDAP, AUTH_PROVIDER_LOCAL,
+};
 
 /// Estrutura para permissões do usuário
 #[derive(Debug, Serialize, Clone)]
@@ -24,6 +45,17 @@ pub struct UserPermissions {
     pub nil: String,
     pub pickup: String,
     pub package: String,
+    /// Permissão por critério ativo (inclui os critérios cadastrados além dos quatro originais)
+    pub criteria: BTreeMap<String, bool>,
+}
+
+/// Permissões de critério atribuídas na aprovação de um cadastro
+#[derive(Debug, Serialize, serde::Deserialize, Clone)]
+pub struct CriteriaPermissions {
+    pub otif: bool,
+    pub nil: bool,
+    pub pickup: bool,
+    pub package: bool,
 }
 
 /// Estrutura para score de um fornecedor
@@ -39,6 +71,10 @@ pub struct SupplierScore {
     pub package_score: Option<String>,
     pub total_score: Option<String>,
     pub comment: Option<String>,
+    /// Notas por critério configurado
+    pub criteria_scores: BTreeMap<String, f64>,
+    /// Estado no fluxo de revisão (draft, submitted, approved, rejected)
+    pub workflow_status: Option<String>,
 }
 
 /// Estrutura para registro de score histórico
@@ -52,6 +88,16 @@ pub struct ScoreRecord {
     pub quality_pickup: Option<f64>,
     pub quality_package: Option<f64>,
     pub total_score: Option<f64>,
+    /// Notas por critério configurado
+    pub criteria_scores: BTreeMap<String, f64>,
+    /// Evidência (numerador/denominador) por critério, quando informada
+    pub evidence: BTreeMap<String, ScoreEvidence>,
+    /// Códigos de motivo por critério
+    pub reason_codes: BTreeMap<String, Vec<String>>,
+    /// Critérios copiados de outro mês ainda não confirmados
+    pub carried_over: BTreeMap<String, CarriedOverScore>,
+    /// Estado no fluxo de revisão (draft, submitted, approved, rejected)
+    pub workflow_status: String,
 }
 
 /// Estrutura para critério de avaliação
@@ -64,6 +110,123 @@ pub struct Criteria {
     pub criteria_target: f64,
 }
 
+/// Intervalo de períodos (inclusivo) usado em operações em lote
+#[derive(Debug, serde::Deserialize, Clone)]
+pub struct PeriodRange {
+    pub start_month: i32,
+    pub start_year: i32,
+    pub end_month: i32,
+    pub end_year: i32,
+}
+
+impl PeriodRange {
+    fn validate(&self) -> Result<(i32, i32), String> {
+        for month in [self.start_month, self.end_month] {
+            if !(1..=12).contains(&month) {
+                return Err(format!("Mês inválido: {}", month));
+            }
+        }
+        let start = self.start_year * 100 + self.start_month;
+        let end = self.end_year * 100 + self.end_month;
+        if start > end {
+            return Err("O período inicial deve ser anterior ao final".to_string());
+        }
+        Ok((start, end))
+    }
+
+    fn label(&self) -> String {
+        format!("{:02}/{} a {:02}/{}", self.start_month, self.start_year, self.end_month, self.end_year)
+    }
+}
+
+/// Diferença de total_score de um registro no recálculo
+#[derive(Debug, Serialize, Clone)]
+pub struct RecalculationEntry {
+    pub record_id: i64,
+    pub supplier_id: String,
+    pub supplier_name: String,
+    pub month: i32,
+    pub year: i32,
+    pub old_total: Option<f64>,
+    pub new_total: Option<f64>,
+    pub delta: Option<f64>,
+    /// Posição do fornecedor no ranking do mês (1 = maior total)
+    pub old_rank: Option<i32>,
+    pub new_rank: Option<i32>,
+    /// Positivo = subiu no ranking
+    pub rank_change: Option<i32>,
+}
+
+/// Resultado (prévia ou aplicado) do recálculo em lote
+#[derive(Debug, Serialize, Clone)]
+pub struct RecalculationPreview {
+    pub total_records: usize,
+    pub changed_records: usize,
+    pub rank_changes: usize,
+    pub entries: Vec<RecalculationEntry>,
+}
+
+/// Nota usada na geração de notas cheias
+pub const FULL_SCORE_VALUE: f64 = 10.0;
+
+/// O que fazer com fornecedores que já têm registro no mês na geração de notas cheias
+#[derive(Debug, serde::Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OverwritePolicy {
+    /// Ignora o fornecedor se já existe registro no mês (comportamento original)
+    Skip,
+    /// Preenche apenas os critérios ainda vazios do registro existente
+    FillEmpty,
+    /// Sobrescreve os critérios selecionados com a nota cheia
+    Overwrite,
+}
+
+/// Filtro de fornecedores da geração de notas cheias (vazio = todos os ativos)
+#[derive(Debug, serde::Deserialize, Clone, Default)]
+pub struct SupplierFilter {
+    #[serde(default)]
+    pub include_inactive: bool,
+    #[serde(default)]
+    pub supplier_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub bu: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Tiers da segmentação (A/B/C); vazio = todos
+    #[serde(default)]
+    pub tiers: Option<Vec<String>>,
+}
+
+/// Resultado da geração (notas cheias ou copiadas) para um fornecedor
+#[derive(Debug, Serialize, Clone)]
+pub struct FullScoreSupplierResult {
+    pub supplier_id: String,
+    pub supplier_name: String,
+    /// created, updated ou skipped
+    pub action: String,
+    pub criteria_written: Vec<String>,
+    pub total_score: Option<String>,
+    /// Problemas de validação que impediram a gravação do fornecedor
+    pub errors: Vec<validation::FieldError>,
+}
+
+/// Nota gravada em lote para um critério e, na cópia, o registro e o mês (AAAA-MM) de origem
+struct BatchScoreValue {
+    value: String,
+    carried_from: Option<(i64, String)>,
+}
+
+/// Resumo da geração de notas cheias ou da cópia de outro mês
+#[derive(Debug, Serialize, Clone)]
+pub struct FullScoreSummary {
+    pub month: i32,
+    pub year: i32,
+    pub created: usize,
+    pub updated: usize,
+    pub skipped: usize,
+    pub suppliers: Vec<FullScoreSupplierResult>,
+}
+
 /// Estrutura para atualização de critério
 #[derive(Debug, serde::Deserialize)]
 #[allow(dead_code)]
@@ -100,6 +263,8 @@ pub struct Supplier {
     pub pickup_score: Option<String>,
     pub package_score: Option<String>,
     pub total_score: Option<String>,
+    /// Tier atual (A/B/C) da segmentação por desempenho
+    pub tier: Option<String>,
 }
 
 /// Estrutura para atualização de fornecedor
@@ -155,17 +320,82 @@ pub struct UserInfo {
     pub permissions: UserPermissions,
 }
 
+/// Usuário online (heartbeat recente) e a tela em que está trabalhando
+#[derive(Debug, Serialize, Clone)]
+pub struct OnlineUser {
+    pub user_id: i32,
+    pub user_name: String,
+    pub user_wwid: String,
+    pub user_privilege: String,
+    pub current_screen: Option<String>,
+    pub last_seen: String,
+    pub session_started: Option<String>,
+}
+
 /// Estrutura para resposta de login
 #[derive(Debug, Serialize)]
 pub struct LoginResponse {
     pub success: bool,
     pub message: String,
     pub user: Option<UserInfo>,
+    pub password_expired: bool,
+}
+
+/// Estrutura para a política de senhas (editável pelos admins em Settings)
+#[derive(Debug, Serialize, serde::Deserialize, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: i32,
+    pub require_uppercase: bool,
+    pub require_lowercase: bool,
+    pub require_digit: bool,
+    pub require_special: bool,
+    /// Quantidade de senhas anteriores que não podem ser reutilizadas (0 = sem restrição)
+    pub history_count: i32,
+    /// Validade da senha em dias (0 = não expira)
+    pub max_age_days: i32,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            min_length: 8,
+            require_uppercase: true,
+            require_lowercase: true,
+            require_digit: true,
+            require_special: false,
+            history_count: 5,
+            max_age_days: 90,
+        }
+    }
 }
 
 /// Conexão global do banco de dados (singleton)
 static DB_CONNECTION: Lazy<Mutex<Option<Connection>>> = Lazy::new(|| Mutex::new(None));
 
+/// Tentativas de login falhas (por WWID) antes do bloqueio temporário
+const MAX_FAILED_LOGIN_ATTEMPTS: i32 = 5;
+/// Duração do bloqueio temporário de uma conta, em minutos
+const LOGIN_LOCKOUT_MINUTES: i64 = 15;
+/// Falhas de login (todas as contas) dentro da janela global a partir das quais os logins passam a ser atrasados
+const GLOBAL_FAILED_LOGIN_THRESHOLD: i32 = 30;
+/// Janela da contagem global de falhas de login, em minutos
+const GLOBAL_FAILED_LOGIN_WINDOW_MINUTES: i64 = 5;
+/// Atraso por falha acima do limite global, em milissegundos
+const GLOBAL_LOGIN_DELAY_STEP_MS: u64 = 250;
+/// Teto do atraso global de login, em milissegundos
+const GLOBAL_LOGIN_MAX_DELAY_MS: u64 = 5000;
+/// Mensagem única para falhas de login (não revela se o WWID existe)
+const LOGIN_FAILED_MESSAGE: &str = "Usuário ou senha inválidos!";
+/// Eventos de autenticação gravados na log_table (não contam como contribuição)
+const AUTH_LOG_EVENTS: &str = "'Login', 'Logout', 'Login Failed', 'Login Blocked', 'Login Throttled', 'Unlock', 'Password Change'";
+/// Chave da política de senhas na app_settings_table
+const PASSWORD_POLICY_SETTING: &str = "password_policy";
+/// Chave da configuração de autenticação (provedor local/LDAP) na app_settings_table
+const AUTH_CONFIG_SETTING: &str = "auth_config";
+/// Usuário é considerado online se enviou heartbeat nos últimos N segundos
+/// (o frontend envia a cada 30s, então tolera duas falhas seguidas)
+const PRESENCE_ONLINE_WINDOW_SECONDS: i64 = 90;
+
 /// Gerenciador centralizado de banco de dados
 pub struct DatabaseManager;
 
@@ -229,14 +459,13 @@ impl DatabaseManager {
                 let mut db = DB_CONNECTION.lock().unwrap();
                 *db = Some(conn);
                 
-                // Se é um banco novo, criar estrutura
+                // Se é um banco novo, criar a estrutura base; as tabelas das demais
+                // funcionalidades vêm da migração, igual aos bancos existentes
+                drop(db); // Libera o lock antes de chamar outras funções
                 if is_new_db {
-                    drop(db); // Libera o lock antes de chamar outras funções
                     Self::create_initial_structure()?;
-                } else {
-                    drop(db); // Libera o lock antes de chamar a migração
-                    Self::migrate_database()?;
                 }
+                Self::migrate_database()?;
                 
                 println!("✅ Banco de dados pronto para uso!");
                 
@@ -263,16 +492,16 @@ impl DatabaseManager {
         // Criar tabela de usuários
         conn.execute(
             "CREATE TABLE IF NOT EXISTS users_table (
-                user_id TEXT PRIMARY KEY,
+                user_id INTEGER PRIMARY KEY AUTOINCREMENT,
                 user_name TEXT NOT NULL,
-                user_email TEXT UNIQUE NOT NULL,
+                user_wwid TEXT UNIQUE NOT NULL,
+                user_privilege TEXT,
+                user_status TEXT,
                 user_password TEXT NOT NULL,
-                user_role TEXT,
-                user_status TEXT DEFAULT 'active',
-                user_permissions_otif TEXT,
-                user_permissions_nil TEXT,
-                user_permissions_pickup TEXT,
-                user_permissions_package TEXT
+                otif TEXT,
+                nil TEXT,
+                pickup TEXT,
+                package TEXT
             )",
             [],
         ).map_err(|e| format!("Erro ao criar tabela users_table: {}", e))?;
@@ -280,8 +509,9 @@ impl DatabaseManager {
         // Criar tabela de fornecedores
         conn.execute(
             "CREATE TABLE IF NOT EXISTS supplier_database_table (
-                supplier_id TEXT PRIMARY KEY,
+                supplier_id INTEGER PRIMARY KEY AUTOINCREMENT,
                 vendor_name TEXT NOT NULL,
+                supplier_name TEXT,
                 supplier_po TEXT,
                 bu TEXT,
                 supplier_email TEXT,
@@ -297,68 +527,77 @@ impl DatabaseManager {
             [],
         ).map_err(|e| format!("Erro ao criar tabela supplier_database_table: {}", e))?;
         
-        // Criar tabela de scores
+        // Criar tabela de scores (um registro por fornecedor/mês)
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS supplier_score (
-                record_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            "CREATE TABLE IF NOT EXISTS supplier_score_records_table (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
                 supplier_id TEXT NOT NULL,
-                month INTEGER NOT NULL,
-                year INTEGER NOT NULL,
-                otif_score TEXT,
-                nil_score TEXT,
-                pickup_score TEXT,
-                package_score TEXT,
+                supplier_name TEXT,
+                month TEXT NOT NULL,
+                year TEXT NOT NULL,
+                otif TEXT,
+                nil TEXT,
+                quality_pickup TEXT,
+                quality_package TEXT,
                 total_score TEXT,
-                comments TEXT,
-                FOREIGN KEY (supplier_id) REFERENCES supplier_database_table(supplier_id)
+                comment TEXT,
+                register_date TEXT,
+                registered_by TEXT,
+                change_date TEXT,
+                changed_by TEXT
             )",
             [],
-        ).map_err(|e| format!("Erro ao criar tabela supplier_score: {}", e))?;
+        ).map_err(|e| format!("Erro ao criar tabela supplier_score_records_table: {}", e))?;
         
-        // Criar tabela de critérios
+        // Criar tabela de critérios (pesos e target geral)
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS criteria (
+            "CREATE TABLE IF NOT EXISTS criteria_table (
                 criteria_id INTEGER PRIMARY KEY AUTOINCREMENT,
-                criteria_target_id INTEGER,
-                criteria_name TEXT NOT NULL,
-                criteria_weight REAL NOT NULL,
-                criteria_target REAL NOT NULL
+                criteria_category TEXT NOT NULL,
+                value TEXT
             )",
             [],
-        ).map_err(|e| format!("Erro ao criar tabela criteria: {}", e))?;
+        ).map_err(|e| format!("Erro ao criar tabela criteria_table: {}", e))?;
 
-        // Criar tabela de overrides de pendência (para permitir salvar vazio)
+        // Criar tabela de logs
         conn.execute(
-            "CREATE TABLE IF NOT EXISTS pending_scores_override (
-                record_id INTEGER NOT NULL,
-                score_type TEXT NOT NULL,
-                dismissed INTEGER NOT NULL DEFAULT 1,
-                PRIMARY KEY (record_id, score_type)
+            "CREATE TABLE IF NOT EXISTS log_table (
+                log_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                date TEXT,
+                time TEXT,
+                user TEXT,
+                event TEXT,
+                wwid TEXT,
+                place TEXT,
+                supplier TEXT,
+                score_date TEXT,
+                old_value TEXT,
+                new_value TEXT
             )",
             [],
-        ).map_err(|e| format!("Erro ao criar tabela pending_scores_override: {}", e))?;
+        ).map_err(|e| format!("Erro ao criar tabela log_table: {}", e))?;
         
         // Criar usuário administrador padrão
         conn.execute(
-            "INSERT OR IGNORE INTO users (user_id, user_name, user_email, user_password, user_role, user_permissions_otif, user_permissions_nil, user_permissions_pickup, user_permissions_package)
-             VALUES ('admin', 'Administrador', 'admin@scoreapp.com', 'admin', 'admin', 'edit', 'edit', 'edit', 'edit')",
+            "INSERT OR IGNORE INTO users_table (user_name, user_wwid, user_privilege, user_status, user_password, otif, nil, pickup, package)
+             VALUES ('Administrador', 'admin', 'Super Admin', 'Active', 'admin', '1', '1', '1', '1')",
             [],
         ).map_err(|e| format!("Erro ao criar usuário admin: {}", e))?;
         
         // Criar critérios padrão
         let default_criteria = vec![
-            ("OTIF", 25.0, 95.0),
-            ("NIL", 25.0, 95.0),
-            ("Pickup", 25.0, 95.0),
-            ("Package", 25.0, 95.0),
+            ("OTIF", "0.25"),
+            ("NIL", "0.25"),
+            ("Quality Pickup", "0.25"),
+            ("Quality Package", "0.25"),
+            ("Target", "8.7"),
         ];
         
-        for (name, weight, target) in default_criteria {
+        for (category, value) in default_criteria {
             conn.execute(
-                "INSERT OR IGNORE INTO criteria (criteria_name, criteria_weight, criteria_target)
-                 VALUES (?1, ?2, ?3)",
-                rusqlite::params![name, weight, target],
-            ).map_err(|e| format!("Erro ao criar critério {}: {}", name, e))?;
+                "INSERT INTO criteria_table (criteria_category, value) VALUES (?1, ?2)",
+                rusqlite::params![category, value],
+            ).map_err(|e| format!("Erro ao criar critério {}: {}", category, e))?;
         }
         
         println!("✅ Estrutura inicial criada com sucesso!");
@@ -413,10 +652,150 @@ impl DatabaseManager {
         } else {
             println!("✅ Coluna 'score_date' já existe na log_table");
         }
+
+        Self::create_login_security_tables(conn)?;
+        Self::create_settings_table(conn)?;
+        Self::create_password_tables(conn)?;
+        Self::create_auth_source_column(conn)?;
+        Self::create_presence_table(conn)?;
+        Self::create_user_approval_table(conn)?;
+        criteria::create_tables(conn)?;
+        targets::create_tables(conn)?;
+        periods::create_tables(conn)?;
+        workflow::create_tables(conn)?;
+        validation::create_tables(conn)?;
+        deliveries::create_tables(conn)?;
+        incidents::create_tables(conn)?;
+        inspections::create_tables(conn)?;
+        evidence::create_tables(conn)?;
+        comments::create_tables(conn)?;
+        reasons::create_tables(conn)?;
+        pending::create_tables(conn)?;
+        cycles::create_tables(conn)?;
+        carryover::create_tables(conn)?;
+        tiering::create_tables(conn)?;
         
         Ok(())
     }
 
+    /// Cria a tabela de controle de tentativas de login (bloqueio por WWID)
+    fn create_login_security_tables(conn: &Connection) -> Result<(), String> {
+        // Indexada por WWID (e não por user_id) para que WWIDs inexistentes também
+        // sejam bloqueados, sem revelar quais contas existem
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS login_attempts_table (
+                wwid TEXT PRIMARY KEY,
+                failed_attempts INTEGER NOT NULL DEFAULT 0,
+                last_failed_at TEXT,
+                locked_until TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Erro ao criar tabela login_attempts_table: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Cria a tabela de configurações gerais (chave/valor, valores em JSON)
+    fn create_settings_table(conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS app_settings_table (
+                setting_key TEXT PRIMARY KEY,
+                setting_value TEXT NOT NULL,
+                updated_at TEXT,
+                updated_by TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Erro ao criar tabela app_settings_table: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Cria o histórico de senhas e a coluna de idade da senha em users_table
+    fn create_password_tables(conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS password_history_table (
+                history_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                password_hash TEXT NOT NULL,
+                changed_at TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Erro ao criar tabela password_history_table: {}", e))?;
+
+        if !Self::table_has_column(conn, "users_table", "password_changed_at")? {
+            println!("📝 Adicionando coluna 'password_changed_at' na tabela users_table...");
+            conn.execute("ALTER TABLE users_table ADD COLUMN password_changed_at TEXT", [])
+                .map_err(|e| format!("Erro ao adicionar coluna password_changed_at: {}", e))?;
+
+            // Senhas existentes passam a contar a validade a partir da migração
+            let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            conn.execute("UPDATE users_table SET password_changed_at = ?1", [&now])
+                .map_err(|e| format!("Erro ao inicializar password_changed_at: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Adiciona a origem de autenticação por usuário em users_table.
+    /// NULL = segue o provedor configurado, 'local' = conta de contingência, 'ldap' = provisionada pelo diretório
+    fn create_auth_source_column(conn: &Connection) -> Result<(), String> {
+        if !Self::table_has_column(conn, "users_table", "auth_source")? {
+            println!("📝 Adicionando coluna 'auth_source' na tabela users_table...");
+            conn.execute("ALTER TABLE users_table ADD COLUMN auth_source TEXT", [])
+                .map_err(|e| format!("Erro ao adicionar coluna auth_source: {}", e))?;
+        }
+
+        Ok(())
+    }
+
+    /// Cria a tabela de presença (heartbeats por usuário)
+    fn create_presence_table(conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS user_presence_table (
+                user_id INTEGER PRIMARY KEY,
+                last_seen TEXT NOT NULL,
+                current_screen TEXT,
+                session_started TEXT
+            )",
+            [],
+        ).map_err(|e| format!("Erro ao criar tabela user_presence_table: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Cria a tabela de decisões de aprovação/rejeição de cadastros
+    fn create_user_approval_table(conn: &Connection) -> Result<(), String> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS user_approval_table (
+                approval_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id INTEGER NOT NULL,
+                decision TEXT NOT NULL,
+                role TEXT,
+                permissions TEXT,
+                reason TEXT,
+                decided_by TEXT NOT NULL,
+                decided_by_wwid TEXT,
+                decided_at TEXT NOT NULL
+            )",
+            [],
+        ).map_err(|e| format!("Erro ao criar tabela user_approval_table: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Verifica se uma coluna existe em uma tabela qualquer
+    fn table_has_column(conn: &Connection, table_name: &str, column_name: &str) -> Result<bool, String> {
+        let count: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE lower(name) = lower(?2)",
+                rusqlite::params![table_name, column_name],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao inspecionar estrutura da tabela {}: {}", table_name, e))?;
+
+        Ok(count > 0)
+    }
+
     /// Verifica se uma coluna existe na tabela de logs
     fn log_table_has_column(conn: &Connection, column_name: &str) -> Result<bool, String> {
         let mut stmt = conn
@@ -479,605 +858,950 @@ impl DatabaseManager {
         println!("\n🔐 Iniciando validação de login...");
         println!("👤 Usuário: {}", username);
         
-        // Garante que a conexão está inicializada
-        let mut conn_guard = Self::get_connection()?;
+        // Verificações locais (bloqueios e origem de autenticação) sob o lock do banco
+        let (auth_config, use_directory, throttle) = {
+            // Garante que a conexão está inicializada
+            let mut conn_guard = Self::get_connection()?;
         
-        if conn_guard.is_none() {
-            println!("⚠️ Conexão não inicializada, inicializando agora...");
-            drop(conn_guard); // Libera o lock antes de inicializar
-            Self::initialize()?;
-            conn_guard = Self::get_connection()?;
-        }
+            if conn_guard.is_none() {
+                println!("⚠️ Conexão não inicializada, inicializando agora...");
+                drop(conn_guard); // Libera o lock antes de inicializar
+                Self::initialize()?;
+                conn_guard = Self::get_connection()?;
+            }
 
-        let conn = conn_guard.as_ref().unwrap();
+            let conn = conn_guard.as_ref().unwrap();
 
-        // Verificar se a coluna user_status existe, caso contrário, criá-la
-        let column_exists = conn.query_row(
-            "SELECT COUNT(*) FROM pragma_table_info('users_table') WHERE name='user_status'",
-            [],
-            |row| row.get::<_, i32>(0)
-        ).unwrap_or(0) > 0;
-
-        if !column_exists {
-            println!("⚠️ Coluna user_status não existe, criando...");
-            if let Err(e) = conn.execute(
-                "ALTER TABLE users_table ADD COLUMN user_status TEXT DEFAULT 'Active'",
-                []
-            ) {
-                println!("❌ Erro ao criar coluna user_status: {}", e);
-            } else {
-                println!("✅ Coluna user_status criada com sucesso");
+            // Verificar se a coluna user_status existe, caso contrário, criá-la
+            let column_exists = conn.query_row(
+                "SELECT COUNT(*) FROM pragma_table_info('users_table') WHERE name='user_status'",
+                [],
+                |row| row.get::<_, i32>(0)
+            ).unwrap_or(0) > 0;
+
+            if !column_exists {
+                println!("⚠️ Coluna user_status não existe, criando...");
+                if let Err(e) = conn.execute(
+                    "ALTER TABLE users_table ADD COLUMN user_status TEXT DEFAULT 'Active'",
+                    []
+                ) {
+                    println!("❌ Erro ao criar coluna user_status: {}", e);
+                } else {
+                    println!("✅ Coluna user_status criada com sucesso");
+                }
+            }
+
+            // Bloqueio por conta (vale também para WWIDs inexistentes)
+            if let Some(remaining_minutes) = Self::get_login_lock_remaining(conn, &username)? {
+                Self::insert_log(conn, &username, "Login Blocked", &username, "Login", None, None, None, Some("Conta bloqueada"))?;
+                return Ok(Self::locked_login_response(remaining_minutes));
             }
+
+            // Contas marcadas como 'local' autenticam sempre pela senha local (contingência)
+            let auth_config = Self::load_auth_config(conn)?;
+            let auth_source: Option<String> = conn
+                .query_row("SELECT auth_source FROM users_table WHERE user_wwid = ?1", [&username], |row| row.get(0))
+                .optional()
+                .map_err(|e| format!("Erro ao buscar origem de autenticação: {}", e))?
+                .flatten();
+            let use_directory = auth_config.provider == AUTH_PROVIDER_LDAP
+                && auth_source.as_deref() != Some(AUTH_PROVIDER_LOCAL);
+
+            // Freio global: atrasa (nunca recusa) logins quando há muitas falhas recentes de qualquer conta;
+            // contas locais de contingência não são atrasadas
+            let throttle = if auth_source.as_deref() == Some(AUTH_PROVIDER_LOCAL) {
+                None
+            } else {
+                Self::global_login_throttle(conn, &username)?
+            };
+
+            (auth_config, use_directory, throttle)
+        };
+
+        // O atraso corre fora do lock do banco
+        if let Some(delay) = throttle {
+            std::thread::sleep(delay);
         }
 
-        println!("🔍 Buscando usuário no banco de dados...");
+        // O diretório é consultado sem segurar o lock: um LDAP lento ou fora do ar não trava os demais comandos
+        let directory = if use_directory { Some(LdapAuthProvider::new(auth_config.ldap)) } else { None };
+        let directory_outcome = directory.as_ref().map(|provider| provider.authenticate(&username, &password));
 
-        // Prepara a query
-        let mut stmt = match conn.prepare(
-            "SELECT user_id, user_name, user_wwid, user_password, user_privilege, user_status, otif, nil, pickup, package
-             FROM users_table 
-             WHERE user_wwid = ?1"
-        ) {
-            Ok(stmt) => {
-                println!("✅ Query preparada com sucesso");
-                stmt
-            }
-            Err(e) => {
-                let error_msg = format!("❌ Erro ao preparar query: {}", e);
-                println!("{}", error_msg);
-                return Err(error_msg);
+        // O lock volta apenas para as gravações de bloqueio, log e sincronização
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let local_provider;
+        let provider: &dyn AuthProvider = match &directory {
+            Some(provider) => provider,
+            None => {
+                local_provider = LocalAuthProvider::new(conn);
+                &local_provider
             }
         };
 
-        // Executa a query
-        let user_result = stmt.query_row([&username], |row| {
-            Ok((
-                row.get::<_, i32>(0)?,      // user_id
-                row.get::<_, String>(1)?,    // user_name
-                row.get::<_, String>(2)?,    // user_wwid
-                row.get::<_, String>(3)?,    // user_password
-                row.get::<_, String>(4)?,    // user_privilege
-                row.get::<_, String>(5)?,    // user_status
-                row.get::<_, String>(6)?,    // otif
-                row.get::<_, String>(7)?,    // nil
-                row.get::<_, String>(8)?,    // pickup
-                row.get::<_, String>(9)?,    // package
-            ))
-        });
+        let outcome = match directory_outcome {
+            Some(outcome) => outcome,
+            None => provider.authenticate(&username, &password),
+        };
 
-        match user_result {
-            Ok((user_id, user_name, user_wwid, stored_password, user_privilege, user_status, otif, nil, pickup, package)) => {
-                println!("✅ Usuário encontrado: {} (ID: {}, Status: {})", user_name, user_id, user_status);
-                
-                // Verifica o status do usuário antes de validar senha
-                if user_status == "Pendent" {
-                    println!("⏳ Usuário com status Pendent - aguardando autorização");
-                    return Ok(LoginResponse {
-                        success: false,
-                        message: "Aguardando autorização do Admin".to_string(),
-                        user: None,
-                    });
-                }
-                
-                if user_status == "Inactive" {
-                    println!("🚫 Usuário com status Inactive");
-                    return Ok(LoginResponse {
-                        success: false,
-                        message: "Usuário inativo. Entre em contato com o administrador.".to_string(),
-                        user: None,
-                    });
-                }
-                
-                println!("🔑 Validando senha...");
-                
-                // Valida a senha
-                if stored_password == password {
-                    println!("✅ Senha correta! Login bem-sucedido!");
-                    
-                    // Atualiza is_online para 1
-                    if let Err(e) = conn.execute(
-                        "UPDATE users_table SET is_online = 1 WHERE user_id = ?1",
-                        [user_id]
-                    ) {
-                        println!("⚠️ Erro ao atualizar status online: {}", e);
-                    } else {
-                        println!("✅ Status online atualizado para user_id: {}", user_id);
-                    }
-                    
-                    Ok(LoginResponse {
-                        success: true,
-                        message: "Login realizado com sucesso!".to_string(),
-                        user: Some(UserInfo {
-                            user_id,
-                            user_name,
-                            user_wwid,
-                            user_privilege,
-                            permissions: UserPermissions {
-                                otif,
-                                nil,
-                                pickup,
-                                package,
-                            },
-                        }),
-                    })
-                } else {
-                    println!("❌ Senha incorreta!");
-                    Ok(LoginResponse {
-                        success: false,
-                        message: "Senha incorreta!".to_string(),
-                        user: None,
-                    })
-                }
+        // A senha é validada antes do status para não expor o status de contas alheias
+        let identity = match outcome {
+            Ok(AuthOutcome::Authenticated(identity)) => identity,
+            Ok(AuthOutcome::UnknownUser) => {
+                return Self::register_failed_login(conn, &username, &username, "Usuário inexistente");
+            }
+            Ok(AuthOutcome::InvalidCredentials) => {
+                return Self::register_failed_login(conn, &username, &username, "Senha incorreta");
             }
             Err(e) => {
-                println!("❌ Usuário não encontrado: {}", e);
-                Ok(LoginResponse {
+                // Falha de infraestrutura não conta como tentativa falha
+                Self::insert_log(conn, &username, "Login Blocked", &username, "Login", None, None, None, Some(&format!("Diretório indisponível: {}", e)))?;
+                return Ok(LoginResponse {
                     success: false,
-                    message: "Usuário não encontrado!".to_string(),
+                    message: "Servidor de autenticação indisponível. Tente novamente ou use uma conta local de contingência.".to_string(),
                     user: None,
-                })
+                    password_expired: false,
+                });
             }
+        };
+
+        // Credenciais corretas: zera o contador de falhas da conta
+        conn.execute("DELETE FROM login_attempts_table WHERE wwid = ?1", [&username])
+            .map_err(|e| format!("Erro ao zerar tentativas de login: {}", e))?;
+
+        if use_directory {
+            let Some(role) = identity.role.clone() else {
+                Self::insert_log(conn, &username, "Login Blocked", &username, "Login", None, None, None, Some("Sem grupo autorizado no diretório"))?;
+                return Ok(LoginResponse {
+                    success: false,
+                    message: "Seu usuário não pertence a nenhum grupo autorizado do ScoreApp. Entre em contato com o administrador.".to_string(),
+                    user: None,
+                    password_expired: false,
+                });
+            };
+            Self::sync_directory_user(conn, &username, identity.display_name.as_deref(), &role)?;
         }
-    }
 
-    /// Lista todos os usuários (para debug)
-    pub fn list_all_users() -> Result<Vec<(String, String)>, String> {
-        let conn_guard = Self::get_connection()?;
+        println!("🔍 Buscando usuário no banco de dados...");
+
+        let (user_id, user_name, user_wwid, user_privilege, user_status, otif, nil, pickup, package) = conn
+            .query_row(
+                "SELECT user_id, user_name, user_wwid, user_privilege, user_status, otif, nil, pickup, package
+                 FROM users_table 
+                 WHERE user_wwid = ?1",
+                [&username],
+                |row| {
+                    Ok((
+                        row.get::<_, i32>(0)?,      // user_id
+                        row.get::<_, String>(1)?,    // user_name
+                        row.get::<_, String>(2)?,    // user_wwid
+                        row.get::<_, String>(3)?,    // user_privilege
+                        row.get::<_, String>(4)?,    // user_status
+                        row.get::<_, String>(5)?,    // otif
+                        row.get::<_, String>(6)?,    // nil
+                        row.get::<_, String>(7)?,    // pickup
+                        row.get::<_, String>(8)?,    // package
+                    ))
+                },
+            )
+            .map_err(|e| format!("❌ Erro ao buscar usuário: {}", e))?;
+
+        println!("✅ Usuário encontrado: {} (ID: {}, Status: {})", user_name, user_id, user_status);
+
+        // Verifica o status do usuário
+        if user_status == "Pendent" {
+            println!("⏳ Usuário com status Pendent - aguardando autorização");
+            return Ok(LoginResponse {
+                success: false,
+                message: "Aguardando autorização do Admin".to_string(),
+                user: None,
+                password_expired: false,
+            });
+        }
         
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        if user_status == "Rejected" {
+            let reason: Option<String> = conn
+                .query_row(
+                    "SELECT reason FROM user_approval_table
+                     WHERE user_id = ?1 AND decision = 'Rejected'
+                     ORDER BY approval_id DESC LIMIT 1",
+                    [user_id],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| format!("Erro ao buscar motivo da rejeição: {}", e))?
+                .flatten();
+
+            return Ok(LoginResponse {
+                success: false,
+                message: match reason {
+                    Some(reason) => format!("Cadastro rejeitado pelo administrador. Motivo: {}", reason),
+                    None => "Cadastro rejeitado pelo administrador.".to_string(),
+                },
+                user: None,
+                password_expired: false,
+            });
+        }
+        
+        if user_status == "Inactive" {
+            println!("🚫 Usuário com status Inactive");
+            return Ok(LoginResponse {
+                success: false,
+                message: "Usuário inativo. Entre em contato com o administrador.".to_string(),
+                user: None,
+                password_expired: false,
+            });
         }
 
-        let conn = conn_guard.as_ref().unwrap();
+        // Senha expirada: exige troca antes de liberar o acesso
+        let policy = Self::load_password_policy(conn)?;
+        if provider.enforces_password_policy() && Self::is_password_expired(conn, user_id, &policy)? {
+            Self::insert_log(conn, &user_name, "Login Blocked", &user_wwid, "Login", None, None, None, Some("Senha expirada"))?;
+            return Ok(LoginResponse {
+                success: false,
+                message: "Sua senha expirou. Defina uma nova senha para continuar.".to_string(),
+                user: None,
+                password_expired: true,
+            });
+        }
 
-        let mut stmt = conn
-            .prepare("SELECT user_wwid, user_name FROM users_table")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        println!("✅ Senha correta! Login bem-sucedido!");
+        
+        // Abre a sessão de presença (o heartbeat do frontend mantém atualizada)
+        Self::touch_presence(conn, user_id, None, true)?;
+
+        Self::insert_log(conn, &user_name, "Login", &user_wwid, "Login", None, None, None, Some(&format!("Sucesso ({})", provider.name())))?;
+        
+        let criteria_permissions = criteria::load_user_permissions(conn, user_id as i64)?;
+
+        Ok(LoginResponse {
+            success: true,
+            message: "Login realizado com sucesso!".to_string(),
+            user: Some(UserInfo {
+                user_id,
+                user_name,
+                user_wwid,
+                user_privilege,
+                permissions: UserPermissions {
+                    otif,
+                    nil,
+                    pickup,
+                    package,
+                    criteria: criteria_permissions,
+                },
+            }),
+            password_expired: false,
+        })
+    }
 
-        let users = stmt
-            .query_map([], |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                ))
-            })
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<SqlResult<Vec<_>>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+    /// Atraso do freio global de login: cresce com as falhas (todas as contas) acima do limite da janela.
+    /// Ao ser acionado na janela, grava um evento de auditoria.
+    fn global_login_throttle(conn: &Connection, wwid: &str) -> Result<Option<std::time::Duration>, String> {
+        let window_start = (chrono::Local::now() - chrono::Duration::minutes(GLOBAL_FAILED_LOGIN_WINDOW_MINUTES))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
 
-        Ok(users)
-    }
+        let (failures, throttled): (i32, i32) = conn
+            .query_row(
+                "SELECT COALESCE(SUM(event = 'Login Failed'), 0), COALESCE(SUM(event = 'Login Throttled'), 0)
+                 FROM log_table
+                 WHERE event IN ('Login Failed', 'Login Throttled') AND (date || ' ' || time) >= ?1",
+                [&window_start],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Erro ao contar falhas de login: {}", e))?;
 
-    /// Obtém o total de fornecedores
-    pub fn get_total_suppliers() -> Result<i64, String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        if failures < GLOBAL_FAILED_LOGIN_THRESHOLD {
+            return Ok(None);
         }
 
-        let conn = conn_guard.as_ref().unwrap();
-
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
-            .map_err(|e| format!("Erro ao contar fornecedores: {}", e))?;
+        if throttled == 0 {
+            Self::insert_log(
+                conn,
+                wwid,
+                "Login Throttled",
+                wwid,
+                "Login",
+                None,
+                None,
+                None,
+                Some(&format!("{} falhas nos últimos {} min", failures, GLOBAL_FAILED_LOGIN_WINDOW_MINUTES)),
+            )?;
+        }
 
-        Ok(count)
+        let excess = (failures - GLOBAL_FAILED_LOGIN_THRESHOLD + 1) as u64;
+        let delay_ms = (excess * GLOBAL_LOGIN_DELAY_STEP_MS).min(GLOBAL_LOGIN_MAX_DELAY_MS);
+        Ok(Some(std::time::Duration::from_millis(delay_ms)))
     }
 
-    /// Obtém o total de avaliações
-    pub fn get_total_evaluations() -> Result<i64, String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+    /// Retorna os minutos restantes de bloqueio de um WWID (None se não estiver bloqueado)
+    fn get_login_lock_remaining(conn: &Connection, wwid: &str) -> Result<Option<i64>, String> {
+        let locked_until: Option<String> = conn
+            .query_row(
+                "SELECT locked_until FROM login_attempts_table WHERE wwid = ?1",
+                [wwid],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao verificar bloqueio de login: {}", e))?
+            .flatten();
 
-        let conn = conn_guard.as_ref().unwrap();
+        let Some(locked_until) = locked_until else {
+            return Ok(None);
+        };
 
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM supplier_score_records_table", [], |row| row.get(0))
-            .map_err(|e| format!("Erro ao contar avaliações: {}", e))?;
+        let until = match chrono::NaiveDateTime::parse_from_str(&locked_until, "%Y-%m-%d %H:%M:%S") {
+            Ok(until) => until,
+            Err(_) => return Ok(None),
+        };
 
-        Ok(count)
+        let now = chrono::Local::now().naive_local();
+        if until <= now {
+            return Ok(None);
+        }
+
+        // Arredonda para cima para nunca exibir "0 minutos"
+        let remaining_seconds = (until - now).num_seconds();
+        Ok(Some((remaining_seconds + 59) / 60))
     }
 
-    /// Obtém a média de score
-    pub fn get_average_score() -> Result<f64, String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+    /// Resposta padrão para contas bloqueadas temporariamente
+    fn locked_login_response(remaining_minutes: i64) -> LoginResponse {
+        LoginResponse {
+            success: false,
+            message: format!(
+                "Conta bloqueada temporariamente por excesso de tentativas. Tente novamente em {} minuto(s).",
+                remaining_minutes
+            ),
+            user: None,
+            password_expired: false,
         }
+    }
 
-        let conn = conn_guard.as_ref().unwrap();
+    /// Registra uma falha de login: incrementa o contador do WWID, bloqueia ao exceder o limite e grava o evento
+    fn register_failed_login(conn: &Connection, user_name: &str, wwid: &str, reason: &str) -> Result<LoginResponse, String> {
+        let now = chrono::Local::now();
+        let now_str = now.format("%Y-%m-%d %H:%M:%S").to_string();
 
-        let avg: Result<f64, rusqlite::Error> = conn.query_row(
-            "SELECT AVG(CAST(total_score AS REAL)) FROM supplier_score_records_table WHERE total_score IS NOT NULL AND total_score != ''",
-            [],
-            |row| row.get(0)
-        );
+        conn.execute(
+            "INSERT INTO login_attempts_table (wwid, failed_attempts, last_failed_at, locked_until)
+             VALUES (?1, 1, ?2, NULL)
+             ON CONFLICT(wwid) DO UPDATE SET
+                failed_attempts = failed_attempts + 1,
+                last_failed_at = excluded.last_failed_at",
+            rusqlite::params![wwid, now_str],
+        ).map_err(|e| format!("Erro ao registrar tentativa de login: {}", e))?;
+
+        let failed_attempts: i32 = conn
+            .query_row(
+                "SELECT failed_attempts FROM login_attempts_table WHERE wwid = ?1",
+                [wwid],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao ler tentativas de login: {}", e))?;
 
-        Ok(avg.unwrap_or(0.0))
+        Self::insert_log(
+            conn,
+            user_name,
+            "Login Failed",
+            wwid,
+            "Login",
+            None,
+            None,
+            None,
+            Some(&format!("{} (tentativa {}/{})", reason, failed_attempts, MAX_FAILED_LOGIN_ATTEMPTS)),
+        )?;
+
+        if failed_attempts >= MAX_FAILED_LOGIN_ATTEMPTS {
+            let locked_until = (now + chrono::Duration::minutes(LOGIN_LOCKOUT_MINUTES))
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string();
+
+            // O contador é zerado para que, após o bloqueio, a conta tenha novamente o limite completo
+            conn.execute(
+                "UPDATE login_attempts_table SET failed_attempts = 0, locked_until = ?1 WHERE wwid = ?2",
+                rusqlite::params![locked_until, wwid],
+            ).map_err(|e| format!("Erro ao bloquear conta: {}", e))?;
+
+            Self::insert_log(conn, user_name, "Login Blocked", wwid, "Login", None, None, None, Some(&format!("Conta bloqueada até {}", locked_until)))?;
+            return Ok(Self::locked_login_response(LOGIN_LOCKOUT_MINUTES));
+        }
+
+        Ok(LoginResponse {
+            success: false,
+            message: LOGIN_FAILED_MESSAGE.to_string(),
+            user: None,
+            password_expired: false,
+        })
     }
 
-    /// Obtém o total de usuários
-    pub fn get_total_users() -> Result<i64, String> {
+    /// Registra o logout de um usuário e o marca como offline
+    pub fn logout_user(user_id: i32) -> Result<(), String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let conn = conn_guard.as_ref().unwrap();
+        let user: Option<(String, String)> = conn
+            .query_row(
+                "SELECT user_name, user_wwid FROM users_table WHERE user_id = ?1",
+                [user_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
 
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM users_table", [], |row| row.get(0))
-            .map_err(|e| format!("Erro ao contar usuários: {}", e))?;
+        conn.execute("DELETE FROM user_presence_table WHERE user_id = ?1", [user_id])
+            .map_err(|e| format!("Erro ao encerrar presença: {}", e))?;
 
-        Ok(count)
+        if let Some((user_name, user_wwid)) = user {
+            Self::insert_log(conn, &user_name, "Logout", &user_wwid, "Login", None, None, None, None)?;
+        }
+
+        Ok(())
     }
 
-    /// Busca fornecedores por query de pesquisa (legado - retorna JSON)
-    pub fn search_suppliers_legacy(query: String) -> Result<Vec<serde_json::Value>, String> {
-        println!("🔍 Buscando fornecedores com query: '{}'", query);
-        
+    /// Lista os WWIDs com falhas de login registradas ou bloqueio ativo (admin)
+    pub fn get_login_lockouts(admin_wwid: String) -> Result<Vec<serde_json::Value>, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            println!("❌ Conexão não inicializada");
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-        let conn = conn_guard.as_ref().unwrap();
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
-        let search_query = format!("%{}%", query);
-        println!("🔍 Query SQL preparada: LIKE '{}'", search_query);
-        
-        // Conta total de fornecedores
-        let count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
-            .unwrap_or(0);
-        println!("📊 Total de fornecedores na tabela: {}", count);
-        
-        // Testa a query diretamente
-        let test_count: i64 = conn
-            .query_row(
-                     "SELECT COUNT(*) FROM supplier_database_table 
-                      WHERE LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1) 
-                          OR LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
-                          OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)",
-                [&search_query],
-                |row| row.get(0)
-            )
-            .unwrap_or(0);
-        println!("🔍 Registros que correspondem à busca: {}", test_count);
-        
         let mut stmt = conn
             .prepare(
-                "SELECT ROWID, vendor_name, COALESCE(CAST(supplier_po AS TEXT), '') AS supplier_po, bu 
-                 FROM supplier_database_table 
-                 WHERE LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1) 
-                    OR LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
-                    OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)
-                 LIMIT 50"
+                "SELECT a.wwid, u.user_id, u.user_name, a.failed_attempts, a.last_failed_at, a.locked_until
+                 FROM login_attempts_table a
+                 LEFT JOIN users_table u ON u.user_wwid = a.wwid
+                 ORDER BY a.last_failed_at DESC"
             )
-            .map_err(|e| {
-                println!("❌ Erro ao preparar query: {}", e);
-                format!("Erro ao preparar query: {}", e)
-            })?;
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let lockouts = stmt
+            .query_map([], |row| {
+                let locked_until: Option<String> = row.get(5)?;
+                let is_locked = locked_until.as_ref().map(|until| until.as_str() > now.as_str()).unwrap_or(false);
 
-        let suppliers_iter = stmt
-            .query_map([&search_query], |row| {
-                let rowid = row.get::<_, i64>(0)?;
-                let vendor_name = row.get::<_, Option<String>>(1)?;
-                
-                // Após CAST, supplier_po já vem como TEXT
-                let supplier_po_str = row.get::<_, String>(2).unwrap_or_default();
-                
-                let bu = row.get::<_, Option<String>>(3)?;
-                
-                println!("📋 Fornecedor encontrado: ROWID={}, Nome={}, PO={}, BU={}", 
-                    rowid,
-                    vendor_name.as_deref().unwrap_or("NULL"),
-                    &supplier_po_str,
-                    bu.as_deref().unwrap_or("NULL")
-                );
-                
                 Ok(serde_json::json!({
-                    "supplier_id": rowid.to_string(),
-                    "vendor_name": vendor_name.unwrap_or_else(|| "".to_string()),
-                    "supplier_po": supplier_po_str,
-                    "business_unit": bu.unwrap_or_else(|| "".to_string()),
-                    "supplier_status": "active",
+                    "wwid": row.get::<_, String>(0)?,
+                    "user_id": row.get::<_, Option<i32>>(1)?,
+                    "user_name": row.get::<_, Option<String>>(2)?,
+                    "failed_attempts": row.get::<_, i32>(3)?,
+                    "last_failed_at": row.get::<_, Option<String>>(4)?,
+                    "locked_until": locked_until,
+                    "is_locked": is_locked,
                 }))
             })
-            .map_err(|e| {
-                println!("❌ Erro ao executar query: {}", e);
-                format!("Erro ao executar query: {}", e)
-            })?;
-
-        let suppliers: Vec<serde_json::Value> = suppliers_iter
-            .enumerate()
-            .filter_map(|(idx, r)| {
-                match r {
-                    Ok(supplier) => Some(supplier),
-                    Err(e) => {
-                        println!("❌ Erro ao processar fornecedor #{}: {}", idx, e);
-                        None
-                    }
-                }
-            })
-            .collect();
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
 
-        println!("✅ Encontrados {} fornecedores", suppliers.len());
-        Ok(suppliers)
+        Ok(lockouts)
     }
 
-    /// Obtém todos os usuários do sistema
-    pub fn get_all_users() -> Result<Vec<serde_json::Value>, String> {
-        println!("🔍 Buscando todos os usuários...");
-        
+    /// Desbloqueia uma conta (ação de admin) e zera o contador de falhas
+    pub fn unlock_user_account(user_id: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            println!("❌ Conexão não inicializada");
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-        let conn = conn_guard.as_ref().unwrap();
+        let (user_name, user_wwid): (String, String) = conn
+            .query_row(
+                "SELECT user_name, user_wwid FROM users_table WHERE user_id = ?1",
+                [user_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
 
-        // Primeiro, vamos verificar se a tabela existe e tem dados
-        let count: Result<i64, _> = conn.query_row("SELECT COUNT(*) FROM users_table", [], |row| row.get(0));
-        match count {
-            Ok(c) => println!("📊 Total de registros na tabela users_table: {}", c),
-            Err(e) => {
-                println!("❌ Erro ao contar usuários: {}", e);
-                return Err(format!("Erro ao acessar tabela: {}", e));
-            }
-        }
+        conn.execute("DELETE FROM login_attempts_table WHERE wwid = ?1", [&user_wwid])
+            .map_err(|e| format!("Erro ao desbloquear conta: {}", e))?;
 
-        let mut stmt = conn
-            .prepare("SELECT user_id, user_name, user_wwid, user_privilege, user_status, otif, nil, pickup, package FROM users_table")
-            .map_err(|e| {
-                println!("❌ Erro ao preparar query: {}", e);
-                format!("Erro ao preparar query: {}", e)
-            })?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Unlock",
+            &admin_wwid,
+            "Users",
+            None,
+            None,
+            None,
+            Some(&format!("{} ({})", user_name, user_wwid)),
+        )?;
 
-        let users: Vec<serde_json::Value> = stmt
-            .query_map([], |row| {
-                let user_id: i32 = row.get(0)?;
-                
-                // user_name pode ser NULL, então usamos Option
-                let user_name: String = row.get::<_, Option<String>>(1)?.unwrap_or_default();
-                let user_wwid: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
-                let user_privilege: String = row.get::<_, Option<String>>(3)?.unwrap_or_default();
-                let user_status: String = row.get::<_, Option<String>>(4)?.unwrap_or_else(|| "Active".to_string());
-                
-                // otif, nil, pickup, package podem ser TEXT ou INTEGER
-                let otif: i32 = row.get::<_, Option<String>>(5)?
-                    .and_then(|s| s.parse::<i32>().ok())
-                    .or_else(|| row.get::<_, Option<i32>>(5).ok().flatten())
-                    .unwrap_or(0);
-                    
-                let nil: i32 = row.get::<_, Option<String>>(6)?
-                    .and_then(|s| s.parse::<i32>().ok())
-                    .or_else(|| row.get::<_, Option<i32>>(6).ok().flatten())
-                    .unwrap_or(0);
-                    
-                let pickup: i32 = row.get::<_, Option<String>>(7)?
-                    .and_then(|s| s.parse::<i32>().ok())
-                    .or_else(|| row.get::<_, Option<i32>>(7).ok().flatten())
-                    .unwrap_or(0);
-                    
-                let package: i32 = row.get::<_, Option<String>>(8)?
-                    .and_then(|s| s.parse::<i32>().ok())
-                    .or_else(|| row.get::<_, Option<i32>>(8).ok().flatten())
-                    .unwrap_or(0);
+        Ok(())
+    }
 
-                println!("📋 Usuário encontrado: ID={}, Nome={}, WWID={}, Status={}", user_id, user_name, user_wwid, user_status);
+    /// Lê um valor da app_settings_table
+    fn get_setting(conn: &Connection, key: &str) -> Result<Option<String>, String> {
+        conn.query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler configuração '{}': {}", key, e))
+    }
 
-                Ok(serde_json::json!({
-                    "user_id": user_id,
-                    "user_name": user_name,
-                    "user_wwid": user_wwid,
-                    "user_privilege": user_privilege,
-                    "user_status": user_status,
-                    "otif": otif,
-                    "nil": nil,
-                    "pickup": pickup,
-                    "package": package,
-                }))
-            })
-            .map_err(|e| {
-                println!("❌ Erro ao executar query: {}", e);
-                format!("Erro ao executar query: {}", e)
-            })?
-            .enumerate()
-            .filter_map(|(idx, r)| {
-                match r {
-                    Ok(user) => Some(user),
-                    Err(e) => {
-                        println!("❌ Erro ao processar usuário #{}: {}", idx, e);
-                        None
-                    }
-                }
-            })
-            .collect();
+    /// Grava um valor na app_settings_table
+    fn set_setting(conn: &Connection, key: &str, value: &str, updated_by: &str) -> Result<(), String> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        conn.execute(
+            "INSERT INTO app_settings_table (setting_key, setting_value, updated_at, updated_by)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(setting_key) DO UPDATE SET
+                setting_value = excluded.setting_value,
+                updated_at = excluded.updated_at,
+                updated_by = excluded.updated_by",
+            rusqlite::params![key, value, now, updated_by],
+        ).map_err(|e| format!("Erro ao salvar configuração '{}': {}", key, e))?;
 
-        println!("✅ Total de usuários encontrados: {}", users.len());
-        Ok(users)
+        Ok(())
     }
 
-    /// Cria um novo usuário
-    pub fn create_user(
-        name: String,
-        wwid: String,
-        privilege: String,
-        status: String,
-        password: String,
-        otif: i32,
-        nil: i32,
-        pickup: i32,
-        package: i32,
-    ) -> Result<i64, String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+    /// Carrega a política de senhas (usa o padrão se ainda não foi configurada)
+    fn load_password_policy(conn: &Connection) -> Result<PasswordPolicy, String> {
+        match Self::get_setting(conn, PASSWORD_POLICY_SETTING)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Política de senhas inválida no banco: {}", e)),
+            None => Ok(PasswordPolicy::default()),
         }
+    }
 
-        let conn = conn_guard.as_ref().unwrap();
+    /// Valida uma senha contra a política, retornando todas as regras violadas
+    fn validate_password_policy(policy: &PasswordPolicy, password: &str) -> Result<(), String> {
+        let mut violations: Vec<String> = Vec::new();
 
-        // Verifica se WWID já existe
-        let wwid_exists: Result<i32, _> = conn.query_row(
-            "SELECT COUNT(*) FROM users_table WHERE user_wwid = ?1",
-            [&wwid],
-            |row| row.get(0)
-        );
+        if password.trim().is_empty() {
+            return Err("A senha não pode ser vazia".to_string());
+        }
+        if (password.chars().count() as i32) < policy.min_length {
+            violations.push(format!("no mínimo {} caracteres", policy.min_length));
+        }
+        if policy.require_uppercase && !password.chars().any(|c| c.is_uppercase()) {
+            violations.push("uma letra maiúscula".to_string());
+        }
+        if policy.require_lowercase && !password.chars().any(|c| c.is_lowercase()) {
+            violations.push("uma letra minúscula".to_string());
+        }
+        if policy.require_digit && !password.chars().any(|c| c.is_ascii_digit()) {
+            violations.push("um número".to_string());
+        }
+        if policy.require_special && !password.chars().any(|c| !c.is_alphanumeric() && !c.is_whitespace()) {
+            violations.push("um caractere especial".to_string());
+        }
 
-        if let Ok(count) = wwid_exists {
-            if count > 0 {
-                return Err("WWID já cadastrado no sistema".to_string());
-            }
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("A senha deve conter: {}", violations.join(", ")))
+        }
+    }
+
+    /// Hash usado no histórico de senhas (o histórico nunca guarda a senha em texto)
+    fn hash_password_for_history(user_id: i64, password: &str) -> String {
+        use sha2::{Digest, Sha256};
+
+        let mut hasher = Sha256::new();
+        hasher.update(format!("{}:{}", user_id, password).as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Impede a reutilização da senha atual e das últimas N senhas do usuário
+    fn check_password_reuse(conn: &Connection, user_id: i64, password: &str, policy: &PasswordPolicy) -> Result<(), String> {
+        if policy.history_count <= 0 {
+            return Ok(());
+        }
+
+        let current_password: Option<String> = conn
+            .query_row("SELECT user_password FROM users_table WHERE user_id = ?1", [user_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Erro ao buscar senha atual: {}", e))?;
+
+        let reuse_error = format!("A nova senha não pode repetir nenhuma das últimas {} senhas", policy.history_count);
+
+        if current_password.as_deref() == Some(password) {
+            return Err(reuse_error);
+        }
+
+        let new_hash = Self::hash_password_for_history(user_id, password);
+        let mut stmt = conn
+            .prepare("SELECT password_hash FROM password_history_table WHERE user_id = ?1 ORDER BY history_id DESC LIMIT ?2")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let recent_hashes = stmt
+            .query_map(rusqlite::params![user_id, policy.history_count], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Erro ao buscar histórico de senhas: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar histórico de senhas: {}", e))?;
+
+        if recent_hashes.contains(&new_hash) {
+            return Err(reuse_error);
         }
 
+        Ok(())
+    }
+
+    /// Registra a troca de senha no histórico e reinicia a contagem de validade
+    fn record_password_change(conn: &Connection, user_id: i64, password: &str) -> Result<(), String> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
         conn.execute(
-            "INSERT INTO users_table (user_name, user_wwid, user_privilege, user_status, user_password, otif, nil, pickup, package) 
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-            [&name, &wwid, &privilege, &status, &password, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string()],
-        )
-        .map_err(|e| format!("Erro ao criar usuário: {}", e))?;
+            "INSERT INTO password_history_table (user_id, password_hash, changed_at) VALUES (?1, ?2, ?3)",
+            rusqlite::params![user_id, Self::hash_password_for_history(user_id, password), now],
+        ).map_err(|e| format!("Erro ao registrar histórico de senha: {}", e))?;
+
+        conn.execute(
+            "UPDATE users_table SET password_changed_at = ?1 WHERE user_id = ?2",
+            rusqlite::params![now, user_id],
+        ).map_err(|e| format!("Erro ao atualizar data da senha: {}", e))?;
 
-        Ok(conn.last_insert_rowid())
+        Ok(())
     }
 
-    /// Verifica se um WWID já existe no banco
-    pub fn check_wwid_exists(wwid: String) -> Result<bool, String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+    /// Verifica se a senha do usuário ultrapassou a validade da política
+    fn is_password_expired(conn: &Connection, user_id: i32, policy: &PasswordPolicy) -> Result<bool, String> {
+        if policy.max_age_days <= 0 {
+            return Ok(false);
         }
 
-        let conn = conn_guard.as_ref().unwrap();
+        let changed_at: Option<String> = conn
+            .query_row("SELECT password_changed_at FROM users_table WHERE user_id = ?1", [user_id], |row| row.get(0))
+            .map_err(|e| format!("Erro ao buscar data da senha: {}", e))?;
 
-        let count: Result<i32, _> = conn.query_row(
-            "SELECT COUNT(*) FROM users_table WHERE user_wwid = ?1",
-            [&wwid],
-            |row| row.get(0)
-        );
+        // Sem data registrada a senha é tratada como expirada
+        let Some(changed_at) = changed_at else {
+            return Ok(true);
+        };
 
-        match count {
-            Ok(c) => Ok(c > 0),
-            Err(e) => Err(format!("Erro ao verificar WWID: {}", e))
+        match chrono::NaiveDateTime::parse_from_str(&changed_at, "%Y-%m-%d %H:%M:%S") {
+            Ok(changed) => {
+                let age = chrono::Local::now().naive_local() - changed;
+                Ok(age.num_days() >= policy.max_age_days as i64)
+            }
+            Err(_) => Ok(true),
         }
     }
 
-    /// Atualiza um usuário existente
-    pub fn update_user(
-        user_id: i32,
-        name: String,
-        wwid: String,
-        privilege: String,
-        status: String,
-        password: Option<String>,
-        otif: i32,
-        nil: i32,
-        pickup: i32,
-        package: i32,
-    ) -> Result<(), String> {
+    /// Troca a senha do próprio usuário (usado também quando a senha expira no login)
+    pub fn change_password(wwid: String, current_password: String, new_password: String) -> Result<(), String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let conn = conn_guard.as_ref().unwrap();
+        if let Some(remaining_minutes) = Self::get_login_lock_remaining(conn, &wwid)? {
+            return Err(Self::locked_login_response(remaining_minutes).message);
+        }
 
-        if let Some(pwd) = password {
-            // Atualizar com nova senha
-            conn.execute(
-                "UPDATE users_table SET user_name = ?1, user_wwid = ?2, user_privilege = ?3, user_status = ?4, user_password = ?5, 
-                 otif = ?6, nil = ?7, pickup = ?8, package = ?9 WHERE user_id = ?10",
-                [&name, &wwid, &privilege, &status, &pwd, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string(), &user_id.to_string()],
-            )
-        } else {
-            // Atualizar sem mudar a senha
-            conn.execute(
-                "UPDATE users_table SET user_name = ?1, user_wwid = ?2, user_privilege = ?3, user_status = ?4, 
-                 otif = ?5, nil = ?6, pickup = ?7, package = ?8 WHERE user_id = ?9",
-                [&name, &wwid, &privilege, &status, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string(), &user_id.to_string()],
+        let user: Option<(i64, String, String)> = conn
+            .query_row(
+                "SELECT user_id, user_name, user_password FROM users_table WHERE user_wwid = ?1",
+                [&wwid],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
+
+        let auth_source: Option<String> = conn
+            .query_row("SELECT auth_source FROM users_table WHERE user_wwid = ?1", [&wwid], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Erro ao buscar origem de autenticação: {}", e))?
+            .flatten();
+        if auth_source.as_deref() == Some(AUTH_PROVIDER_LDAP) {
+            return Err("A senha deste usuário é gerenciada pelo diretório corporativo".to_string());
         }
-        .map_err(|e| format!("Erro ao atualizar usuário: {}", e))?;
+
+        // Senha atual incorreta conta como tentativa de login falha
+        let (user_id, user_name) = match user {
+            Some((user_id, user_name, stored_password)) if stored_password == current_password => (user_id, user_name),
+            Some((_, user_name, _)) => return Err(Self::register_failed_login(conn, &user_name, &wwid, "Senha atual incorreta")?.message),
+            None => return Err(Self::register_failed_login(conn, &wwid, &wwid, "Usuário inexistente")?.message),
+        };
+
+        let policy = Self::load_password_policy(conn)?;
+        Self::validate_password_policy(&policy, &new_password)?;
+        Self::check_password_reuse(conn, user_id, &new_password, &policy)?;
+
+        conn.execute(
+            "UPDATE users_table SET user_password = ?1 WHERE user_id = ?2",
+            rusqlite::params![new_password, user_id],
+        ).map_err(|e| format!("Erro ao atualizar senha: {}", e))?;
+
+        Self::record_password_change(conn, user_id, &new_password)?;
+        Self::insert_log(conn, &user_name, "Password Change", &wwid, "Login", None, None, None, None)?;
 
         Ok(())
     }
 
-    /// Exclui um usuário
-    pub fn delete_user(user_id: i32) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+    /// Carrega a configuração de autenticação (provedor local se ainda não foi configurada)
+    fn load_auth_config(conn: &Connection) -> Result<AuthConfig, String> {
+        match Self::get_setting(conn, AUTH_CONFIG_SETTING)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| format!("Configuração de autenticação inválida no banco: {}", e)),
+            None => Ok(AuthConfig::default()),
         }
+    }
 
-        let conn = conn_guard.as_ref().unwrap();
+    /// Cria ou atualiza o usuário autenticado pelo diretório (papel vem dos grupos mapeados)
+    fn sync_directory_user(conn: &Connection, wwid: &str, display_name: Option<&str>, role: &str) -> Result<(), String> {
+        let existing: Option<(i32, String, String)> = conn
+            .query_row(
+                "SELECT user_id, user_name, user_privilege FROM users_table WHERE user_wwid = ?1",
+                [wwid],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
 
-        conn.execute(
-            "DELETE FROM users_table WHERE user_id = ?1",
-            [&user_id],
-        )
-        .map_err(|e| format!("Erro ao excluir usuário: {}", e))?;
+        match existing {
+            Some((user_id, user_name, old_role)) => {
+                let new_name = display_name.unwrap_or(&user_name).to_string();
+                conn.execute(
+                    "UPDATE users_table SET user_name = ?1, user_privilege = ?2, auth_source = ?3 WHERE user_id = ?4",
+                    rusqlite::params![new_name, role, AUTH_PROVIDER_LDAP, user_id],
+                ).map_err(|e| format!("Erro ao sincronizar usuário do diretório: {}", e))?;
+
+                if old_role != role {
+                    Self::insert_log(conn, &new_name, "Update", wwid, "Users", None, None, Some(&old_role), Some(role))?;
+                }
+            }
+            None => {
+                // Senha local vazia: o usuário só entra pelo diretório
+                let name = display_name.unwrap_or(wwid);
+                conn.execute(
+                    "INSERT INTO users_table (user_name, user_wwid, user_privilege, user_status, user_password, otif, nil, pickup, package, auth_source)
+                     VALUES (?1, ?2, ?3, 'Active', '', '0', '0', '0', '0', ?4)",
+                    rusqlite::params![name, wwid, role, AUTH_PROVIDER_LDAP],
+                ).map_err(|e| format!("Erro ao provisionar usuário do diretório: {}", e))?;
+
+                Self::insert_log(conn, name, "Create", wwid, "Users", None, None, None, Some(role))?;
+            }
+        }
 
         Ok(())
     }
 
-    /// Busca a senha de um usuário
-    pub fn get_user_password(user_id: i32) -> Result<String, String> {
+    /// Busca a configuração de autenticação (a senha da conta de serviço não é devolvida)
+    pub fn get_auth_config() -> Result<AuthConfig, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let conn = conn_guard.as_ref().unwrap();
+        let mut config = Self::load_auth_config(conn)?;
+        config.ldap.bind_password = String::new();
+        Ok(config)
+    }
 
-        let password: String = conn
+    /// Garante que quem executa a ação é um Admin ou Super Admin ativo
+    fn ensure_admin(conn: &Connection, admin_wwid: &str) -> Result<(), String> {
+        let is_admin: i32 = conn
             .query_row(
-                "SELECT user_password FROM users_table WHERE user_id = ?1",
-                [&user_id],
+                "SELECT COUNT(*) FROM users_table
+                 WHERE user_wwid = ?1 AND user_status = 'Active' AND user_privilege IN ('Admin', 'Super Admin')",
+                [admin_wwid],
                 |row| row.get(0),
             )
-            .map_err(|e| format!("Erro ao buscar senha: {}", e))?;
+            .map_err(|e| format!("Erro ao verificar privilégio do usuário: {}", e))?;
 
-        Ok(password)
+        if is_admin == 0 {
+            return Err("Apenas administradores podem executar esta ação".to_string());
+        }
+        Ok(())
     }
 
-    /// Conta usuários pendentes
-    pub fn count_pending_users() -> Result<i32, String> {
+    /// Atualiza a configuração de autenticação (admin).
+    /// Senha da conta de serviço vazia mantém a senha já salva.
+    pub fn update_auth_config(mut config: AuthConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        config.validate()?;
+
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let old_config = Self::load_auth_config(conn)?;
+        if config.ldap.bind_password.is_empty() {
+            config.ldap.bind_password = old_config.ldap.bind_password.clone();
         }
 
-        let conn = conn_guard.as_ref().unwrap();
+        // Sem uma conta local de admin ativa, uma queda do diretório trancaria todos fora
+        if config.provider == AUTH_PROVIDER_LDAP {
+            let break_glass_admins: i32 = conn
+                .query_row(
+                    "SELECT COUNT(*) FROM users_table
+                     WHERE auth_source = ?1 AND user_status = 'Active' AND user_privilege IN ('Admin', 'Super Admin')",
+                    [AUTH_PROVIDER_LOCAL],
+                    |row| row.get(0),
+                )
+                .map_err(|e| format!("Erro ao verificar contas de contingência: {}", e))?;
 
-        let count: i32 = conn
+            if break_glass_admins == 0 {
+                return Err("Marque ao menos um administrador ativo como conta local de contingência antes de ativar o LDAP".to_string());
+            }
+        }
+
+        let new_json = serde_json::to_string(&config)
+            .map_err(|e| format!("Erro ao serializar configuração de autenticação: {}", e))?;
+        Self::set_setting(conn, AUTH_CONFIG_SETTING, &new_json, &admin_name)?;
+
+        // O log não guarda a senha da conta de serviço
+        let mut old_logged = old_config;
+        old_logged.ldap.bind_password = String::new();
+        let mut new_logged = config;
+        new_logged.ldap.bind_password = String::new();
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Authentication",
+            None,
+            None,
+            serde_json::to_string(&old_logged).ok().as_deref(),
+            serde_json::to_string(&new_logged).ok().as_deref(),
+        )?;
+
+        Ok(())
+    }
+
+    /// Testa uma configuração LDAP com as credenciais informadas, sem alterar nada no banco (admin).
+    /// A senha salva da conta de serviço só é reaproveitada para o mesmo servidor e bind DN.
+    pub fn test_ldap_connection(mut config: AuthConfig, wwid: String, password: String, admin_wwid: String) -> Result<serde_json::Value, String> {
+        config.provider = AUTH_PROVIDER_LDAP.to_string();
+        config.validate()?;
+
+        {
+            let conn_guard = Self::get_connection()?;
+            let conn = conn_guard.as_ref()
+                .ok_or_else(|| "Conexão não inicializada".to_string())?;
+            Self::ensure_admin(conn, &admin_wwid)?;
+
+            if config.ldap.bind_password.is_empty() && !config.ldap.bind_dn.is_empty() {
+                let saved = Self::load_auth_config(conn)?.ldap;
+                if saved.url != config.ldap.url || saved.bind_dn != config.ldap.bind_dn {
+                    return Err("Informe a senha da conta de serviço para testar um servidor ou bind DN diferente do salvo".to_string());
+                }
+                config.ldap.bind_password = saved.bind_password;
+            }
+        }
+
+        // O teste roda fora do lock do banco para não travar os demais comandos
+        let provider = LdapAuthProvider::new(config.ldap);
+        let result = match provider.authenticate(&wwid, &password)? {
+            AuthOutcome::Authenticated(identity) => serde_json::json!({
+                "success": identity.role.is_some(),
+                "message": match identity.role {
+                    Some(ref role) => format!("Autenticado como {}", role),
+                    None => "Autenticado, mas nenhum grupo do usuário está mapeado".to_string(),
+                },
+                "user_dn": identity.user_dn,
+                "display_name": identity.display_name,
+                "groups": identity.groups,
+                "role": identity.role,
+            }),
+            AuthOutcome::UnknownUser => serde_json::json!({
+                "success": false,
+                "message": "Usuário não encontrado no diretório",
+            }),
+            AuthOutcome::InvalidCredentials => serde_json::json!({
+                "success": false,
+                "message": "Senha inválida",
+            }),
+        };
+
+        Ok(result)
+    }
+
+    /// Define a origem de autenticação de um usuário (admin).
+    /// 'local' marca a conta como contingência; None volta a seguir o provedor configurado.
+    pub fn set_user_auth_source(user_id: i32, auth_source: Option<String>, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        if let Some(ref source) = auth_source {
+            if source != AUTH_PROVIDER_LOCAL && source != AUTH_PROVIDER_LDAP {
+                return Err(format!("Origem de autenticação inválida: {}", source));
+            }
+        }
+
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let (user_name, old_source): (String, Option<String>) = conn
             .query_row(
-                "SELECT COUNT(*) FROM users_table WHERE user_status = 'Pendent'",
-                [],
-                |row| row.get(0),
+                "SELECT user_name, auth_source FROM users_table WHERE user_id = ?1",
+                [user_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
             )
-            .map_err(|e| format!("Erro ao contar usuários pendentes: {}", e))?;
+            .map_err(|e| format!("Usuário não encontrado: {}", e))?;
 
-        Ok(count)
+        conn.execute(
+            "UPDATE users_table SET auth_source = ?1 WHERE user_id = ?2",
+            rusqlite::params![auth_source, user_id],
+        ).map_err(|e| format!("Erro ao atualizar origem de autenticação: {}", e))?;
+
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Users",
+            None,
+            None,
+            Some(&format!("{}: {}", user_name, old_source.as_deref().unwrap_or("padrão"))),
+            Some(&format!("{}: {}", user_name, auth_source.as_deref().unwrap_or("padrão"))),
+        )?;
+
+        Ok(())
     }
 
-    /// Busca usuários pendentes
-    pub fn get_pending_users() -> Result<Vec<serde_json::Value>, String> {
+    /// Busca a política de senhas vigente
+    pub fn get_password_policy() -> Result<PasswordPolicy, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        Self::load_password_policy(conn)
+    }
+
+    /// Atualiza a política de senhas (admin)
+    pub fn update_password_policy(policy: PasswordPolicy, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        if policy.min_length < 1 || policy.min_length > 128 {
+            return Err("O tamanho mínimo da senha deve estar entre 1 e 128".to_string());
+        }
+        if policy.history_count < 0 || policy.max_age_days < 0 {
+            return Err("Histórico e validade da senha não podem ser negativos".to_string());
+        }
+
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let old_policy = Self::get_setting(conn, PASSWORD_POLICY_SETTING)?;
+        let new_policy = serde_json::to_string(&policy)
+            .map_err(|e| format!("Erro ao serializar política de senhas: {}", e))?;
+
+        Self::set_setting(conn, PASSWORD_POLICY_SETTING, &new_policy, &admin_name)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Password Policy",
+            None,
+            None,
+            old_policy.as_deref(),
+            Some(&new_policy),
+        )?;
+
+        Ok(())
+    }
+
+    /// Lista todos os usuários (para debug)
+    pub fn list_all_users() -> Result<Vec<(String, String)>, String> {
         let conn_guard = Self::get_connection()?;
         
         if conn_guard.is_none() {
@@ -1087,26 +1811,25 @@ impl DatabaseManager {
         let conn = conn_guard.as_ref().unwrap();
 
         let mut stmt = conn
-            .prepare("SELECT user_id, user_name, user_wwid FROM users_table WHERE user_status = 'Pendent' ORDER BY user_id DESC")
+            .prepare("SELECT user_wwid, user_name FROM users_table")
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
-        let users: Vec<serde_json::Value> = stmt
+        let users = stmt
             .query_map([], |row| {
-                Ok(serde_json::json!({
-                    "user_id": row.get::<_, i32>(0)?,
-                    "user_name": row.get::<_, String>(1)?,
-                    "user_wwid": row.get::<_, String>(2)?,
-                }))
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                ))
             })
             .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .filter_map(|r| r.ok())
-            .collect();
+            .collect::<SqlResult<Vec<_>>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
 
         Ok(users)
     }
 
-    /// Aprova ou rejeita um usuário pendente
-    pub fn update_user_status(user_id: i32, status: String) -> Result<(), String> {
+    /// Obtém o total de fornecedores
+    pub fn get_total_suppliers() -> Result<i64, String> {
         let conn_guard = Self::get_connection()?;
         
         if conn_guard.is_none() {
@@ -1115,17 +1838,15 @@ impl DatabaseManager {
 
         let conn = conn_guard.as_ref().unwrap();
 
-        conn.execute(
-            "UPDATE users_table SET user_status = ?1 WHERE user_id = ?2",
-            [&status, &user_id.to_string()],
-        )
-        .map_err(|e| format!("Erro ao atualizar status: {}", e))?;
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
+            .map_err(|e| format!("Erro ao contar fornecedores: {}", e))?;
 
-        Ok(())
+        Ok(count)
     }
 
-    /// Busca os scores de fornecedores específicos para um mês/ano
-    pub fn get_supplier_scores(supplier_ids: Vec<String>, month: i32, year: i32) -> Result<Vec<SupplierScore>, String> {
+    /// Obtém o total de avaliações
+    pub fn get_total_evaluations() -> Result<i64, String> {
         let conn_guard = Self::get_connection()?;
         
         if conn_guard.is_none() {
@@ -1133,90 +1854,16 @@ impl DatabaseManager {
         }
 
         let conn = conn_guard.as_ref().unwrap();
-        
-        let mut scores = Vec::new();
-
-        for supplier_id in supplier_ids {
-            
-            // Nomes reais das colunas no banco:
-            // id, supplier_id, month (TEXT), year (TEXT), otif (REAL), nil (REAL), 
-            // quality_pickup (TEXT), quality_package (TEXT), total_score (TEXT), comment (TEXT)
-            let query = "SELECT id, supplier_id, month, year, otif, nil, quality_pickup, quality_package, total_score, comment 
-                         FROM supplier_score_records_table 
-                         WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3";
-            
-            let score = conn.query_row(
-                query,
-                rusqlite::params![&supplier_id, month.to_string(), year.to_string()],
-                |row| {
-                    let record_id: Option<i32> = row.get(0).ok();
-                    let sid: String = row.get(1)?;
-                    
-                    // month e year são TEXT no banco
-                    let m: i32 = row.get::<_, String>(2)?.parse().unwrap_or(0);
-                    let y: i32 = row.get::<_, String>(3)?.parse().unwrap_or(0);
-                    
-                    // otif e nil são REAL, mas vamos pegar como String
-                    let otif: Option<String> = match row.get::<_, f64>(4) {
-                        Ok(v) => Some(v.to_string()),
-                        Err(_) => row.get::<_, Option<String>>(4)?,
-                    };
-                    
-                    let nil: Option<String> = match row.get::<_, f64>(5) {
-                        Ok(v) => Some(v.to_string()),
-                        Err(_) => row.get::<_, Option<String>>(5)?,
-                    };
-                    
-                    // quality_pickup e quality_package são TEXT
-                    let pickup: Option<String> = row.get(6).ok();
-                    let package: Option<String> = row.get(7).ok();
-                    let total: Option<String> = row.get(8).ok();
-                    let comment: Option<String> = row.get(9).ok();
-                    
-                    Ok(SupplierScore {
-                        record_id,
-                        supplier_id: sid,
-                        month: m,
-                        year: y,
-                        otif_score: otif,
-                        nil_score: nil,
-                        pickup_score: pickup,
-                        package_score: package,
-                        total_score: total,
-                        comment,
-                    })
-                },
-            );
 
-            match score {
-                Ok(s) => {
-                    scores.push(s);
-                },
-                Err(_e) => {
-                    // Se não encontrar, retorna um score vazio para este fornecedor
-                    scores.push(SupplierScore {
-                        record_id: None,
-                        supplier_id: supplier_id.clone(),
-                        month,
-                        year,
-                        otif_score: None,
-                        nil_score: None,
-                        pickup_score: None,
-                        package_score: None,
-                        total_score: None,
-                        comment: None,
-                    });
-                }
-            }
-        }
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM supplier_score_records_table", [], |row| row.get(0))
+            .map_err(|e| format!("Erro ao contar avaliações: {}", e))?;
 
-        Ok(scores)
+        Ok(count)
     }
 
-    /// Busca todos os registros de score de um fornecedor específico
-    pub fn get_supplier_score_records(supplier_id: String) -> Result<Vec<ScoreRecord>, String> {
-        println!("\n🔍 Buscando registros de score para supplier_id: '{}'", supplier_id);
-        
+    /// Obtém a média de score
+    pub fn get_average_score(approved_only: bool) -> Result<f64, String> {
         let conn_guard = Self::get_connection()?;
         
         if conn_guard.is_none() {
@@ -1224,2119 +1871,5739 @@ impl DatabaseManager {
         }
 
         let conn = conn_guard.as_ref().unwrap();
-        
-        let query = "SELECT supplier_id, month, year, otif, nil, quality_pickup, quality_package, total_score
-                     FROM supplier_score_records_table 
-                     WHERE lower(trim(supplier_id)) = lower(trim(?1))
-                     ORDER BY year, month";
-        
-        let mut stmt = conn.prepare(query)
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let records = stmt.query_map([&supplier_id], |row| {
-            let supplier_id_val: String = row.get(0)?;
-            let month_str: String = row.get(1)?;
-            let year: String = row.get(2)?;
-            
-            // Função helper para tentar ler como f64 ou String
-            let get_score = |idx: usize| -> Option<f64> {
-                // Tenta primeiro como f64
-                if let Ok(val) = row.get::<_, Option<f64>>(idx) {
-                    return val;
-                }
-                // Se falhar, tenta como String e converte
-                if let Ok(Some(val)) = row.get::<_, Option<String>>(idx) {
-                    return val.parse::<f64>().ok();
-                }
-                None
-            };
-            
-            let otif = get_score(3);
-            let nil = get_score(4);
-            let quality_pickup = get_score(5);
-            let quality_package = get_score(6);
-            let total_score = get_score(7);
-            
-            // Converte month para número
-            let month_num: i32 = month_str.parse().unwrap_or(1);
-            
-            Ok(ScoreRecord {
-                supplier_id: supplier_id_val,
-                year,
-                month: month_num,
-                otif,
-                nil,
-                quality_pickup,
-                quality_package,
-                total_score,
-            })
-        })
-        .map_err(|e| format!("Erro ao executar query: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        println!("✅ {} registros encontrados para supplier_id: '{}'", records.len(), supplier_id);
-        for record in &records {
-            println!("   📊 Year: {}, Month: {}, OTIF: {:?}, NIL: {:?}, Pickup: {:?}, Package: {:?}", 
-                record.year, record.month, record.otif, record.nil, record.quality_pickup, record.quality_package);
-        }
-        Ok(records)
+
+        let avg: Result<f64, rusqlite::Error> = conn.query_row(
+            "SELECT AVG(CAST(total_score AS REAL)) FROM supplier_score_records_table
+             WHERE total_score IS NOT NULL AND total_score != '' AND (?1 = 0 OR workflow_status = ?2)",
+            rusqlite::params![approved_only, workflow::STATUS_APPROVED],
+            |row| row.get(0)
+        );
+
+        Ok(avg.unwrap_or(0.0))
     }
 
-    /// Salva ou atualiza o score de um fornecedor
-    /// Função auxiliar para inserir log
-    fn insert_log(
-        conn: &Connection,
-        user: &str,
-        event: &str,
-        wwid: &str,
-        place: &str,
-        supplier: Option<&str>,
-        score_date: Option<&str>,
-        old_value: Option<&str>,
-        new_value: Option<&str>,
-    ) -> Result<(), String> {
-        let now = chrono::Local::now();
-        let date = now.format("%Y-%m-%d").to_string();
-        let time = now.format("%H:%M:%S").to_string();
-        
-        println!("📝 Inserindo log: event='{}', wwid='{}', place='{}', supplier={:?}, score_date={:?}", 
-            event, wwid, place, supplier, score_date);
-        
-        conn.execute(
-            "INSERT INTO log_table (date, time, user, event, wwid, place, supplier, score_date, old_value, new_value)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-            rusqlite::params![
-                date,
-                time,
-                user,
-                event,
-                wwid,
-                place,
-                supplier,
-                score_date,
-                old_value,
-                new_value
-            ],
-        ).map_err(|e| format!("Erro ao inserir log: {}", e))?;
+    /// Obtém o total de usuários
+    pub fn get_total_users() -> Result<i64, String> {
+        let conn_guard = Self::get_connection()?;
         
-        Ok(())
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM users_table", [], |row| row.get(0))
+            .map_err(|e| format!("Erro ao contar usuários: {}", e))?;
+
+        Ok(count)
     }
 
-    pub fn save_supplier_score(
-        supplier_id: String,
-        supplier_name: String,
-        month: i32,
-        year: i32,
-        otif_score: Option<String>,
-        nil_score: Option<String>,
-        pickup_score: Option<String>,
-        package_score: Option<String>,
-        total_score: Option<String>,
-        comments: Option<String>,
-        user_name: String,
-        user_wwid: String,
-    ) -> Result<String, String> {
-        println!("\n💾 ========================================");
-        println!("💾 SAVE_SUPPLIER_SCORE CHAMADO!");
-        println!("💾 supplier_id: '{}'", supplier_id);
-        println!("💾 supplier_name: '{}'", supplier_name);
-        println!("💾 month: {}, year: {}", month, year);
-        println!("💾 otif_score: {:?}", otif_score);
-        println!("💾 nil_score: {:?}", nil_score);
-        println!("💾 pickup_score: {:?}", pickup_score);
-        println!("💾 package_score: {:?}", package_score);
-        println!("💾 total_score: {:?}", total_score);
-        println!("💾 comments: {:?}", comments);
-        println!("💾 user_name: '{}'", user_name);
-        println!("💾 user_wwid: '{}'", user_wwid);
-        println!("💾 ========================================\n");
+    /// Busca fornecedores por query de pesquisa (legado - retorna JSON)
+    pub fn search_suppliers_legacy(query: String) -> Result<Vec<serde_json::Value>, String> {
+        println!("🔍 Buscando fornecedores com query: '{}'", query);
         
         let conn_guard = Self::get_connection()?;
         
         if conn_guard.is_none() {
+            println!("❌ Conexão não inicializada");
             return Err("Conexão não inicializada".to_string());
         }
 
         let conn = conn_guard.as_ref().unwrap();
+
+        let search_query = format!("%{}%", query);
+        println!("🔍 Query SQL preparada: LIKE '{}'", search_query);
         
-        // Usa o total_score que veio do frontend (já calculado)
-        let total_score_value = total_score.unwrap_or_else(|| "0".to_string());
-        println!("📊 Total score a salvar: {}", total_score_value);
-        
-        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        // Conta total de fornecedores
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
+            .unwrap_or(0);
+        println!("📊 Total de fornecedores na tabela: {}", count);
         
-        println!("🔍 WWID recebido do frontend: '{}'", user_wwid);
+        // Testa a query diretamente
+        let test_count: i64 = conn
+            .query_row(
+                     "SELECT COUNT(*) FROM supplier_database_table 
+                      WHERE LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1) 
+                          OR LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
+                          OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)",
+                [&search_query],
+                |row| row.get(0)
+            )
+            .unwrap_or(0);
+        println!("🔍 Registros que correspondem à busca: {}", test_count);
         
-        // Verifica se já existe um registro e busca valores antigos para o log
-        let existing_data = conn.query_row(
-            "SELECT id, otif, nil, quality_pickup, quality_package, comment FROM supplier_score_records_table 
-             WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
-            rusqlite::params![&supplier_id, month.to_string(), year.to_string()],
-            |row| {
-                Ok((
-                    row.get::<_, i32>(0)?,
-                    row.get::<_, Option<f64>>(1)?,
-                    row.get::<_, Option<f64>>(2)?,
-                    row.get::<_, Option<String>>(3)?,
-                    row.get::<_, Option<String>>(4)?,
-                    row.get::<_, Option<String>>(5)?,
-                ))
-            }
-        );
-        
-        let supplier_info = format!("{} ({})", supplier_name, supplier_id);
-        let score_date_str = format!("{}/{}", month, year);
-        
-        match existing_data {
-            Ok((id, old_otif, old_nil, old_pickup, old_package, old_comment)) => {
-                // Registra logs para cada campo alterado
-                if let Some(ref new_otif) = otif_score {
-                    let old_val = old_otif.map(|v| v.to_string()).unwrap_or_else(|| "".to_string());
-                    // Normaliza comparação: converte ambos para f64 e compara
-                    let old_normalized = old_otif.unwrap_or(0.0);
-                    let new_normalized = new_otif.parse::<f64>().unwrap_or(0.0);
-                    
-                    if (old_normalized - new_normalized).abs() > 0.001 {
-                        Self::insert_log(
-                            conn,
-                            &user_name,
-                            "Update",
-                            &user_wwid,
-                            "OTIF",
-                            Some(&supplier_info),
-                            Some(&score_date_str),
-                            if old_val.is_empty() { None } else { Some(&old_val) },
-                            Some(new_otif)
-                        )?;
-                    }
-                }
+        let mut stmt = conn
+            .prepare(
+                "SELECT ROWID, vendor_name, COALESCE(CAST(supplier_po AS TEXT), '') AS supplier_po, bu 
+                 FROM supplier_database_table 
+                 WHERE LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1) 
+                    OR LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
+                    OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)
+                 LIMIT 50"
+            )
+            .map_err(|e| {
+                println!("❌ Erro ao preparar query: {}", e);
+                format!("Erro ao preparar query: {}", e)
+            })?;
+
+        let suppliers_iter = stmt
+            .query_map([&search_query], |row| {
+                let rowid = row.get::<_, i64>(0)?;
+                let vendor_name = row.get::<_, Option<String>>(1)?;
                 
-                if let Some(ref new_nil) = nil_score {
-                    let old_val = old_nil.map(|v| v.to_string()).unwrap_or_else(|| "".to_string());
-                    // Normaliza comparação: converte ambos para f64 e compara
-                    let old_normalized = old_nil.unwrap_or(0.0);
-                    let new_normalized = new_nil.parse::<f64>().unwrap_or(0.0);
-                    
-                    if (old_normalized - new_normalized).abs() > 0.001 {
-                        Self::insert_log(
-                            conn,
-                            &user_name,
-                            "Update",
-                            &user_wwid,
-                            "NIL",
-                            Some(&supplier_info),
-                            Some(&score_date_str),
-                            if old_val.is_empty() { None } else { Some(&old_val) },
-                            Some(new_nil)
-                        )?;
-                    }
-                }
+                // Após CAST, supplier_po já vem como TEXT
+                let supplier_po_str = row.get::<_, String>(2).unwrap_or_default();
                 
-                if let Some(ref new_pickup) = pickup_score {
-                    let old_val = old_pickup.clone().unwrap_or_else(|| "".to_string());
-                    // Normaliza comparação: converte ambos para f64 e compara
-                    let old_normalized = old_pickup.as_ref().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-                    let new_normalized = new_pickup.parse::<f64>().unwrap_or(0.0);
-                    
-                    if (old_normalized - new_normalized).abs() > 0.001 {
-                        Self::insert_log(
-                            conn,
-                            &user_name,
-                            "Update",
-                            &user_wwid,
-                            "Pickup",
-                            Some(&supplier_info),
-                            Some(&score_date_str),
-                            if old_val.is_empty() { None } else { Some(&old_val) },
-                            Some(new_pickup)
-                        )?;
-                    }
-                }
+                let bu = row.get::<_, Option<String>>(3)?;
                 
-                if let Some(ref new_package) = package_score {
-                    let old_val = old_package.clone().unwrap_or_else(|| "".to_string());
-                    // Normaliza comparação: converte ambos para f64 e compara
-                    let old_normalized = old_package.as_ref().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-                    let new_normalized = new_package.parse::<f64>().unwrap_or(0.0);
-                    
-                    if (old_normalized - new_normalized).abs() > 0.001 {
-                        Self::insert_log(
-                            conn,
-                            &user_name,
-                            "Update",
-                            &user_wwid,
-                            "Package",
-                            Some(&supplier_info),
-                            Some(&score_date_str),
-                            if old_val.is_empty() { None } else { Some(&old_val) },
-                            Some(new_package)
-                        )?;
-                    }
-                }
+                println!("📋 Fornecedor encontrado: ROWID={}, Nome={}, PO={}, BU={}", 
+                    rowid,
+                    vendor_name.as_deref().unwrap_or("NULL"),
+                    &supplier_po_str,
+                    bu.as_deref().unwrap_or("NULL")
+                );
                 
-                if let Some(ref new_comment) = comments {
-                    let old_val = old_comment.clone().unwrap_or_else(|| "".to_string());
-                    if old_val != *new_comment && !new_comment.is_empty() {
-                        Self::insert_log(
-                            conn,
-                            &user_name,
-                            "Update",
-                            &user_wwid,
-                            "Comment",
-                            Some(&supplier_info),
-                            Some(&score_date_str),
-                            if old_val.is_empty() { None } else { Some(&old_val) },
-                            Some(new_comment)
-                        )?;
+                Ok(serde_json::json!({
+                    "supplier_id": rowid.to_string(),
+                    "vendor_name": vendor_name.unwrap_or_else(|| "".to_string()),
+                    "supplier_po": supplier_po_str,
+                    "business_unit": bu.unwrap_or_else(|| "".to_string()),
+                    "supplier_status": "active",
+                }))
+            })
+            .map_err(|e| {
+                println!("❌ Erro ao executar query: {}", e);
+                format!("Erro ao executar query: {}", e)
+            })?;
+
+        let suppliers: Vec<serde_json::Value> = suppliers_iter
+            .enumerate()
+            .filter_map(|(idx, r)| {
+                match r {
+                    Ok(supplier) => Some(supplier),
+                    Err(e) => {
+                        println!("❌ Erro ao processar fornecedor #{}: {}", idx, e);
+                        None
                     }
                 }
+            })
+            .collect();
+
+        println!("✅ Encontrados {} fornecedores", suppliers.len());
+        Ok(suppliers)
+    }
+
+    /// Obtém todos os usuários do sistema
+    pub fn get_all_users() -> Result<Vec<serde_json::Value>, String> {
+        println!("🔍 Buscando todos os usuários...");
+        
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            println!("❌ Conexão não inicializada");
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Primeiro, vamos verificar se a tabela existe e tem dados
+        let count: Result<i64, _> = conn.query_row("SELECT COUNT(*) FROM users_table", [], |row| row.get(0));
+        match count {
+            Ok(c) => println!("📊 Total de registros na tabela users_table: {}", c),
+            Err(e) => {
+                println!("❌ Erro ao contar usuários: {}", e);
+                return Err(format!("Erro ao acessar tabela: {}", e));
+            }
+        }
+
+        let mut stmt = conn
+            .prepare("SELECT user_id, user_name, user_wwid, user_privilege, user_status, otif, nil, pickup, package FROM users_table")
+            .map_err(|e| {
+                println!("❌ Erro ao preparar query: {}", e);
+                format!("Erro ao preparar query: {}", e)
+            })?;
+
+        let users: Vec<serde_json::Value> = stmt
+            .query_map([], |row| {
+                let user_id: i32 = row.get(0)?;
                 
-                // Atualiza o registro existente - APENAS os campos que foram enviados (não nulos)
-                println!("📝 Atualizando registro existente (id: {})", id);
-                
-                // Se está enviando todas as notas e todas estão vazias, deleta o registro
-                let all_scores_sent = otif_score.is_some() && nil_score.is_some() && pickup_score.is_some() && package_score.is_some();
-                let all_scores_empty = otif_score.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true) 
-                    && nil_score.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true)
-                    && pickup_score.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true)
-                    && package_score.as_ref().map(|s| s.trim().is_empty()).unwrap_or(true);
+                // user_name pode ser NULL, então usamos Option
+                let user_name: String = row.get::<_, Option<String>>(1)?.unwrap_or_default();
+                let user_wwid: String = row.get::<_, Option<String>>(2)?.unwrap_or_default();
+                let user_privilege: String = row.get::<_, Option<String>>(3)?.unwrap_or_default();
+                let user_status: String = row.get::<_, Option<String>>(4)?.unwrap_or_else(|| "Active".to_string());
                 
-                if all_scores_sent && all_scores_empty {
-                    println!("🗑️ Todas as 4 notas estão vazias - Deletando registro (id: {})", id);
+                // otif, nil, pickup, package podem ser TEXT ou INTEGER
+                let otif: i32 = row.get::<_, Option<String>>(5)?
+                    .and_then(|s| s.parse::<i32>().ok())
+                    .or_else(|| row.get::<_, Option<i32>>(5).ok().flatten())
+                    .unwrap_or(0);
                     
-                    // Registra log de deleção
-                    Self::insert_log(
-                        conn,
-                        &user_name,
-                        "Delete",
-                        &user_wwid,
-                        "All Scores",
-                        Some(&supplier_info),
-                        Some(&score_date_str),
-                        Some(&format!("OTIF: {:?}, NIL: {:?}, Pickup: {:?}, Package: {:?}", 
-                            old_otif, old_nil, old_pickup, old_package)),
-                        None
-                    )?;
+                let nil: i32 = row.get::<_, Option<String>>(6)?
+                    .and_then(|s| s.parse::<i32>().ok())
+                    .or_else(|| row.get::<_, Option<i32>>(6).ok().flatten())
+                    .unwrap_or(0);
                     
-                    conn.execute(
-                        "DELETE FROM supplier_score_records_table WHERE id = ?",
-                        rusqlite::params![id]
-                    ).map_err(|e| format!("Erro ao deletar registro: {}", e))?;
+                let pickup: i32 = row.get::<_, Option<String>>(7)?
+                    .and_then(|s| s.parse::<i32>().ok())
+                    .or_else(|| row.get::<_, Option<i32>>(7).ok().flatten())
+                    .unwrap_or(0);
                     
-                    println!("✅ Registro deletado com sucesso!");
-                    return Ok("Registro deletado (todas as notas removidas)".to_string());
-                }
-                
-                // Monta a query dinamicamente para atualizar apenas os campos enviados
-                let mut updates = Vec::new();
-                let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-                
-                if let Some(ref otif) = otif_score {
-                    updates.push("otif = ?");
-                    let value: Option<f64> = if otif.trim().is_empty() { 
-                        None 
-                    } else { 
-                        otif.parse::<f64>().ok() 
-                    };
-                    params.push(Box::new(value));
-                }
-                
-                if let Some(ref nil) = nil_score {
-                    updates.push("nil = ?");
-                    let value: Option<f64> = if nil.trim().is_empty() { 
-                        None 
-                    } else { 
-                        nil.parse::<f64>().ok() 
-                    };
-                    params.push(Box::new(value));
-                }
-                
-                if let Some(ref pickup) = pickup_score {
-                    updates.push("quality_pickup = ?");
-                    let value: Option<String> = if pickup.trim().is_empty() { 
-                        None 
-                    } else { 
-                        Some(pickup.clone()) 
-                    };
-                    params.push(Box::new(value));
-                }
-                
-                if let Some(ref package) = package_score {
-                    updates.push("quality_package = ?");
-                    let value: Option<String> = if package.trim().is_empty() { 
-                        None 
-                    } else { 
-                        Some(package.clone()) 
-                    };
-                    params.push(Box::new(value));
-                }
-                
-                if let Some(ref comment) = comments {
-                    updates.push("comment = ?");
-                    params.push(Box::new(comment.clone()));
-                }
-                
-                // Sempre atualiza total_score, change_date e changed_by
-                updates.push("total_score = ?");
-                updates.push("change_date = ?");
-                updates.push("changed_by = ?");
-                params.push(Box::new(total_score_value.clone()));
-                params.push(Box::new(now.clone()));
-                params.push(Box::new(user_name.clone()));
-                
-                if updates.is_empty() {
-                    println!("⚠️ Nenhum campo para atualizar");
-                    return Ok("Nenhuma alteração para salvar".to_string());
+                let package: i32 = row.get::<_, Option<String>>(8)?
+                    .and_then(|s| s.parse::<i32>().ok())
+                    .or_else(|| row.get::<_, Option<i32>>(8).ok().flatten())
+                    .unwrap_or(0);
+
+                println!("📋 Usuário encontrado: ID={}, Nome={}, WWID={}, Status={}", user_id, user_name, user_wwid, user_status);
+
+                Ok(serde_json::json!({
+                    "user_id": user_id,
+                    "user_name": user_name,
+                    "user_wwid": user_wwid,
+                    "user_privilege": user_privilege,
+                    "user_status": user_status,
+                    "otif": otif,
+                    "nil": nil,
+                    "pickup": pickup,
+                    "package": package,
+                }))
+            })
+            .map_err(|e| {
+                println!("❌ Erro ao executar query: {}", e);
+                format!("Erro ao executar query: {}", e)
+            })?
+            .enumerate()
+            .filter_map(|(idx, r)| {
+                match r {
+                    Ok(user) => Some(user),
+                    Err(e) => {
+                        println!("❌ Erro ao processar usuário #{}: {}", idx, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+
+        // Permissões por critério configurado (inclui critérios além dos quatro originais)
+        let mut users = users;
+        for user in users.iter_mut() {
+            let user_id = user["user_id"].as_i64().unwrap_or(0);
+            let permissions = criteria::load_user_permissions(conn, user_id)?;
+            user["criteria_permissions"] = serde_json::json!(permissions);
+        }
+
+        println!("✅ Total de usuários encontrados: {}", users.len());
+        Ok(users)
+    }
+
+    /// Cria um novo usuário
+    pub fn create_user(
+        name: String,
+        wwid: String,
+        privilege: String,
+        status: String,
+        password: String,
+        otif: i32,
+        nil: i32,
+        pickup: i32,
+        package: i32,
+    ) -> Result<i64, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Verifica se WWID já existe
+        let wwid_exists: Result<i32, _> = conn.query_row(
+            "SELECT COUNT(*) FROM users_table WHERE user_wwid = ?1",
+            [&wwid],
+            |row| row.get(0)
+        );
+
+        if let Ok(count) = wwid_exists {
+            if count > 0 {
+                return Err("WWID já cadastrado no sistema".to_string());
+            }
+        }
+
+        let policy = Self::load_password_policy(conn)?;
+        Self::validate_password_policy(&policy, &password)?;
+
+        conn.execute(
+            "INSERT INTO users_table (user_name, user_wwid, user_privilege, user_status, user_password, otif, nil, pickup, package) 
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            [&name, &wwid, &privilege, &status, &password, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string()],
+        )
+        .map_err(|e| format!("Erro ao criar usuário: {}", e))?;
+
+        let user_id = conn.last_insert_rowid();
+        Self::record_password_change(conn, user_id, &password)?;
+        criteria::sync_builtin_permissions(conn, user_id, otif == 1, nil == 1, pickup == 1, package == 1)?;
+
+        Ok(user_id)
+    }
+
+    /// Verifica se um WWID já existe no banco
+    pub fn check_wwid_exists(wwid: String) -> Result<bool, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        let count: Result<i32, _> = conn.query_row(
+            "SELECT COUNT(*) FROM users_table WHERE user_wwid = ?1",
+            [&wwid],
+            |row| row.get(0)
+        );
+
+        match count {
+            Ok(c) => Ok(c > 0),
+            Err(e) => Err(format!("Erro ao verificar WWID: {}", e))
+        }
+    }
+
+    /// Atualiza um usuário existente
+    pub fn update_user(
+        user_id: i32,
+        name: String,
+        wwid: String,
+        privilege: String,
+        status: String,
+        password: Option<String>,
+        otif: i32,
+        nil: i32,
+        pickup: i32,
+        package: i32,
+    ) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        // A tela de usuários reenvia a senha atual ao editar: só é troca se for diferente
+        let current_password: Option<String> = conn
+            .query_row("SELECT user_password FROM users_table WHERE user_id = ?1", [user_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Erro ao buscar senha atual: {}", e))?;
+        let password = password.filter(|pwd| current_password.as_deref() != Some(pwd.as_str()));
+
+        if let Some(ref pwd) = password {
+            let policy = Self::load_password_policy(conn)?;
+            Self::validate_password_policy(&policy, pwd)?;
+            Self::check_password_reuse(conn, user_id as i64, pwd, &policy)?;
+        }
+
+        if let Some(ref pwd) = password {
+            // Atualizar com nova senha
+            conn.execute(
+                "UPDATE users_table SET user_name = ?1, user_wwid = ?2, user_privilege = ?3, user_status = ?4, user_password = ?5, 
+                 otif = ?6, nil = ?7, pickup = ?8, package = ?9 WHERE user_id = ?10",
+                [&name, &wwid, &privilege, &status, pwd, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string(), &user_id.to_string()],
+            )
+        } else {
+            // Atualizar sem mudar a senha
+            conn.execute(
+                "UPDATE users_table SET user_name = ?1, user_wwid = ?2, user_privilege = ?3, user_status = ?4, 
+                 otif = ?5, nil = ?6, pickup = ?7, package = ?8 WHERE user_id = ?9",
+                [&name, &wwid, &privilege, &status, &otif.to_string(), &nil.to_string(), &pickup.to_string(), &package.to_string(), &user_id.to_string()],
+            )
+        }
+        .map_err(|e| format!("Erro ao atualizar usuário: {}", e))?;
+
+        if let Some(ref pwd) = password {
+            Self::record_password_change(conn, user_id as i64, pwd)?;
+        }
+
+        criteria::sync_builtin_permissions(conn, user_id as i64, otif == 1, nil == 1, pickup == 1, package == 1)?;
+
+        Ok(())
+    }
+
+    /// Exclui um usuário
+    pub fn delete_user(user_id: i32) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        conn.execute(
+            "DELETE FROM users_table WHERE user_id = ?1",
+            [&user_id],
+        )
+        .map_err(|e| format!("Erro ao excluir usuário: {}", e))?;
+
+        conn.execute("DELETE FROM user_criteria_permissions_table WHERE user_id = ?1", [&user_id])
+            .map_err(|e| format!("Erro ao excluir permissões do usuário: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Busca a senha de um usuário
+    pub fn get_user_password(user_id: i32) -> Result<String, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        let password: String = conn
+            .query_row(
+                "SELECT user_password FROM users_table WHERE user_id = ?1",
+                [&user_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao buscar senha: {}", e))?;
+
+        Ok(password)
+    }
+
+    /// Conta usuários pendentes
+    pub fn count_pending_users() -> Result<i32, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        let count: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM users_table WHERE user_status = 'Pendent'",
+                [],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao contar usuários pendentes: {}", e))?;
+
+        Ok(count)
+    }
+
+    /// Busca usuários pendentes
+    pub fn get_pending_users() -> Result<Vec<serde_json::Value>, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        let mut stmt = conn
+            .prepare("SELECT user_id, user_name, user_wwid FROM users_table WHERE user_status = 'Pendent' ORDER BY user_id DESC")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let users: Vec<serde_json::Value> = stmt
+            .query_map([], |row| {
+                Ok(serde_json::json!({
+                    "user_id": row.get::<_, i32>(0)?,
+                    "user_name": row.get::<_, String>(1)?,
+                    "user_wwid": row.get::<_, String>(2)?,
+                }))
+            })
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .filter_map(|r| r.ok())
+            .collect();
+
+        Ok(users)
+    }
+
+    /// Aprova ou rejeita um usuário pendente
+    pub fn update_user_status(user_id: i32, status: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        conn.execute(
+            "UPDATE users_table SET user_status = ?1 WHERE user_id = ?2",
+            [&status, &user_id.to_string()],
+        )
+        .map_err(|e| format!("Erro ao atualizar status: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Busca um usuário aguardando decisão (Pendent, ou Rejected para reavaliação)
+    fn get_user_awaiting_decision(conn: &Connection, user_id: i32) -> Result<(String, String, String), String> {
+        let user: Option<(String, String, String)> = conn
+            .query_row(
+                "SELECT user_name, user_wwid, user_status FROM users_table WHERE user_id = ?1",
+                [user_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?;
+
+        match user {
+            Some((name, wwid, status)) if status == "Pendent" || status == "Rejected" => Ok((name, wwid, status)),
+            Some((_, _, status)) => Err(format!("Usuário não está pendente de aprovação (status atual: {})", status)),
+            None => Err("Usuário não encontrado".to_string()),
+        }
+    }
+
+    /// Aprova um cadastro pendente, atribuindo papel e permissões de critério no mesmo passo
+    pub fn approve_user(
+        user_id: i32,
+        role: String,
+        criteria_permissions: CriteriaPermissions,
+        approver_name: String,
+        approver_wwid: String,
+    ) -> Result<(), String> {
+        if !["User", "Admin", "Super Admin"].contains(&role.as_str()) {
+            return Err(format!("Privilégio inválido: {}", role));
+        }
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        Self::ensure_admin(conn, &approver_wwid)?;
+        let (user_name, user_wwid, _) = Self::get_user_awaiting_decision(conn, user_id)?;
+        if user_wwid.trim().eq_ignore_ascii_case(approver_wwid.trim()) {
+            return Err("Você não pode aprovar o próprio cadastro".to_string());
+        }
+        let permissions_json = serde_json::to_string(&criteria_permissions)
+            .map_err(|e| format!("Erro ao serializar permissões: {}", e))?;
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        tx.execute(
+            "UPDATE users_table
+             SET user_status = 'Active', user_privilege = ?1, otif = ?2, nil = ?3, pickup = ?4, package = ?5
+             WHERE user_id = ?6",
+            rusqlite::params![
+                role,
+                criteria_permissions.otif as i32,
+                criteria_permissions.nil as i32,
+                criteria_permissions.pickup as i32,
+                criteria_permissions.package as i32,
+                user_id,
+            ],
+        ).map_err(|e| format!("Erro ao aprovar usuário: {}", e))?;
+
+        criteria::sync_builtin_permissions(
+            &tx,
+            user_id as i64,
+            criteria_permissions.otif,
+            criteria_permissions.nil,
+            criteria_permissions.pickup,
+            criteria_permissions.package,
+        )?;
+
+        tx.execute(
+            "INSERT INTO user_approval_table (user_id, decision, role, permissions, reason, decided_by, decided_by_wwid, decided_at)
+             VALUES (?1, 'Approved', ?2, ?3, NULL, ?4, ?5, ?6)",
+            rusqlite::params![user_id, role, permissions_json, approver_name, approver_wwid, now],
+        ).map_err(|e| format!("Erro ao registrar aprovação: {}", e))?;
+
+        Self::insert_log(
+            &tx,
+            &approver_name,
+            "Approve",
+            &approver_wwid,
+            "Users",
+            None,
+            None,
+            None,
+            Some(&format!("{} ({}) como {}", user_name, user_wwid, role)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar aprovação: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Rejeita um cadastro pendente registrando o motivo (exibido ao usuário no login)
+    pub fn reject_user(user_id: i32, reason: String, approver_name: String, approver_wwid: String) -> Result<(), String> {
+        let reason = reason.trim().to_string();
+        if reason.is_empty() {
+            return Err("Informe o motivo da rejeição".to_string());
+        }
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        Self::ensure_admin(conn, &approver_wwid)?;
+        let (user_name, user_wwid, _) = Self::get_user_awaiting_decision(conn, user_id)?;
+        if user_wwid.trim().eq_ignore_ascii_case(approver_wwid.trim()) {
+            return Err("Você não pode decidir sobre o próprio cadastro".to_string());
+        }
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        tx.execute(
+            "UPDATE users_table SET user_status = 'Rejected' WHERE user_id = ?1",
+            [user_id],
+        ).map_err(|e| format!("Erro ao rejeitar usuário: {}", e))?;
+
+        tx.execute(
+            "INSERT INTO user_approval_table (user_id, decision, role, permissions, reason, decided_by, decided_by_wwid, decided_at)
+             VALUES (?1, 'Rejected', NULL, NULL, ?2, ?3, ?4, ?5)",
+            rusqlite::params![user_id, reason, approver_name, approver_wwid, now],
+        ).map_err(|e| format!("Erro ao registrar rejeição: {}", e))?;
+
+        Self::insert_log(
+            &tx,
+            &approver_name,
+            "Reject",
+            &approver_wwid,
+            "Users",
+            None,
+            None,
+            None,
+            Some(&format!("{} ({}): {}", user_name, user_wwid, reason)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar rejeição: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Histórico de decisões de aprovação/rejeição de um usuário
+    pub fn get_user_approval_history(user_id: i32) -> Result<Vec<serde_json::Value>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT approval_id, decision, role, permissions, reason, decided_by, decided_by_wwid, decided_at
+                 FROM user_approval_table WHERE user_id = ?1 ORDER BY approval_id DESC",
+            )
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let decisions = stmt
+            .query_map([user_id], |row| {
+                let permissions: Option<String> = row.get(3)?;
+                Ok(serde_json::json!({
+                    "approval_id": row.get::<_, i64>(0)?,
+                    "decision": row.get::<_, String>(1)?,
+                    "role": row.get::<_, Option<String>>(2)?,
+                    "permissions": permissions.and_then(|p| serde_json::from_str::<serde_json::Value>(&p).ok()),
+                    "reason": row.get::<_, Option<String>>(4)?,
+                    "decided_by": row.get::<_, String>(5)?,
+                    "decided_by_wwid": row.get::<_, Option<String>>(6)?,
+                    "decided_at": row.get::<_, String>(7)?,
+                }))
+            })
+            .map_err(|e| format!("Erro ao buscar histórico de aprovação: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar histórico de aprovação: {}", e))?;
+
+        Ok(decisions)
+    }
+
+    /// Busca os scores de fornecedores específicos para um mês/ano
+    pub fn get_supplier_scores(supplier_ids: Vec<String>, month: i32, year: i32) -> Result<Vec<SupplierScore>, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut scores = Vec::new();
+
+        for supplier_id in supplier_ids {
+            
+            // Nomes reais das colunas no banco:
+            // id, supplier_id, month (TEXT), year (TEXT), otif (REAL), nil (REAL), 
+            // quality_pickup (TEXT), quality_package (TEXT), total_score (TEXT), comment (TEXT)
+            let query = "SELECT id, supplier_id, month, year, otif, nil, quality_pickup, quality_package, total_score, comment, workflow_status 
+                         FROM supplier_score_records_table 
+                         WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3";
+            
+            let score = conn.query_row(
+                query,
+                rusqlite::params![&supplier_id, month.to_string(), year.to_string()],
+                |row| {
+                    let record_id: Option<i32> = row.get(0).ok();
+                    let sid: String = row.get(1)?;
+                    
+                    // month e year são TEXT no banco
+                    let m: i32 = row.get::<_, String>(2)?.parse().unwrap_or(0);
+                    let y: i32 = row.get::<_, String>(3)?.parse().unwrap_or(0);
+                    
+                    // otif e nil são REAL, mas vamos pegar como String
+                    let otif: Option<String> = match row.get::<_, f64>(4) {
+                        Ok(v) => Some(v.to_string()),
+                        Err(_) => row.get::<_, Option<String>>(4)?,
+                    };
+                    
+                    let nil: Option<String> = match row.get::<_, f64>(5) {
+                        Ok(v) => Some(v.to_string()),
+                        Err(_) => row.get::<_, Option<String>>(5)?,
+                    };
+                    
+                    // quality_pickup e quality_package são TEXT
+                    let pickup: Option<String> = row.get(6).ok();
+                    let package: Option<String> = row.get(7).ok();
+                    let total: Option<String> = row.get(8).ok();
+                    let comment: Option<String> = row.get(9).ok();
+                    let workflow_status: Option<String> = row.get(10).ok();
+                    
+                    Ok(SupplierScore {
+                        record_id,
+                        supplier_id: sid,
+                        month: m,
+                        year: y,
+                        otif_score: otif,
+                        nil_score: nil,
+                        pickup_score: pickup,
+                        package_score: package,
+                        total_score: total,
+                        comment,
+                        criteria_scores: BTreeMap::new(),
+                        workflow_status,
+                    })
+                },
+            );
+
+            match score {
+                Ok(mut s) => {
+                    if let Some(rid) = s.record_id {
+                        s.criteria_scores = criteria::read_values_sorted(conn, rid as i64)?;
+                    }
+                    scores.push(s);
+                },
+                Err(_e) => {
+                    // Se não encontrar, retorna um score vazio para este fornecedor
+                    scores.push(SupplierScore {
+                        record_id: None,
+                        supplier_id: supplier_id.clone(),
+                        month,
+                        year,
+                        otif_score: None,
+                        nil_score: None,
+                        pickup_score: None,
+                        package_score: None,
+                        total_score: None,
+                        comment: None,
+                        criteria_scores: BTreeMap::new(),
+                        workflow_status: None,
+                    });
+                }
+            }
+        }
+
+        Ok(scores)
+    }
+
+    /// Busca todos os registros de score de um fornecedor específico
+    pub fn get_supplier_score_records(supplier_id: String) -> Result<Vec<ScoreRecord>, String> {
+        println!("\n🔍 Buscando registros de score para supplier_id: '{}'", supplier_id);
+        
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let query = "SELECT supplier_id, month, year, otif, nil, quality_pickup, quality_package, total_score, id, workflow_status
+                     FROM supplier_score_records_table 
+                     WHERE lower(trim(supplier_id)) = lower(trim(?1))
+                     ORDER BY year, month";
+        
+        let mut stmt = conn.prepare(query)
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let records_with_ids = stmt.query_map([&supplier_id], |row| {
+            let supplier_id_val: String = row.get(0)?;
+            let month_str: String = row.get(1)?;
+            let year: String = row.get(2)?;
+            
+            // Função helper para tentar ler como f64 ou String
+            let get_score = |idx: usize| -> Option<f64> {
+                // Tenta primeiro como f64
+                if let Ok(val) = row.get::<_, Option<f64>>(idx) {
+                    return val;
+                }
+                // Se falhar, tenta como String e converte
+                if let Ok(Some(val)) = row.get::<_, Option<String>>(idx) {
+                    return val.parse::<f64>().ok();
+                }
+                None
+            };
+            
+            let otif = get_score(3);
+            let nil = get_score(4);
+            let quality_pickup = get_score(5);
+            let quality_package = get_score(6);
+            let total_score = get_score(7);
+            
+            // Converte month para número
+            let month_num: i32 = month_str.parse().unwrap_or(1);
+            let record_id: i64 = row.get(8)?;
+            let workflow_status: String = row.get(9)?;
+            
+            Ok((record_id, ScoreRecord {
+                supplier_id: supplier_id_val,
+                year,
+                month: month_num,
+                otif,
+                nil,
+                quality_pickup,
+                quality_package,
+                total_score,
+                criteria_scores: BTreeMap::new(),
+                evidence: BTreeMap::new(),
+                reason_codes: BTreeMap::new(),
+                carried_over: BTreeMap::new(),
+                workflow_status,
+            }))
+        })
+        .map_err(|e| format!("Erro ao executar query: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+
+        let mut records = Vec::with_capacity(records_with_ids.len());
+        for (record_id, mut record) in records_with_ids {
+            record.criteria_scores = criteria::read_values_sorted(conn, record_id)?;
+            record.evidence = evidence::read(conn, record_id)?;
+            record.reason_codes = reasons::read_codes(conn, record_id)?;
+            record.carried_over = carryover::read(conn, record_id)?;
+            records.push(record);
+        }
+        
+        println!("✅ {} registros encontrados para supplier_id: '{}'", records.len(), supplier_id);
+        for record in &records {
+            println!("   📊 Year: {}, Month: {}, OTIF: {:?}, NIL: {:?}, Pickup: {:?}, Package: {:?}", 
+                record.year, record.month, record.otif, record.nil, record.quality_pickup, record.quality_package);
+        }
+        Ok(records)
+    }
+
+    /// Salva ou atualiza o score de um fornecedor
+    /// Função auxiliar para inserir log
+    fn insert_log(
+        conn: &Connection,
+        user: &str,
+        event: &str,
+        wwid: &str,
+        place: &str,
+        supplier: Option<&str>,
+        score_date: Option<&str>,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+    ) -> Result<(), String> {
+        let now = chrono::Local::now();
+        let date = now.format("%Y-%m-%d").to_string();
+        let time = now.format("%H:%M:%S").to_string();
+        
+        println!("📝 Inserindo log: event='{}', wwid='{}', place='{}', supplier={:?}, score_date={:?}", 
+            event, wwid, place, supplier, score_date);
+        
+        conn.execute(
+            "INSERT INTO log_table (date, time, user, event, wwid, place, supplier, score_date, old_value, new_value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                date,
+                time,
+                user,
+                event,
+                wwid,
+                place,
+                supplier,
+                score_date,
+                old_value,
+                new_value
+            ],
+        ).map_err(|e| format!("Erro ao inserir log: {}", e))?;
+        
+        Ok(())
+    }
+
+    pub fn save_supplier_score(
+        supplier_id: String,
+        supplier_name: String,
+        month: i32,
+        year: i32,
+        otif_score: Option<String>,
+        nil_score: Option<String>,
+        pickup_score: Option<String>,
+        package_score: Option<String>,
+        // Notas dos demais critérios configurados (chave do critério -> nota)
+        criteria_scores: Option<HashMap<String, String>>,
+        // Evidência opcional por critério (chave do critério -> numerador/denominador)
+        criteria_evidence: Option<HashMap<String, ScoreEvidence>>,
+        // Comentários por critério, acrescentados à thread do registro com o autor
+        criteria_comments: Option<HashMap<String, String>>,
+        // Códigos de motivo por critério (substituem os já gravados do critério)
+        criteria_reasons: Option<HashMap<String, Vec<String>>>,
+        // Ignorado: o total é sempre calculado pelo score_engine a partir das notas salvas
+        _total_score: Option<String>,
+        comments: Option<String>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<String, String> {
+        
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        periods::ensure_open(conn, month, year)?;
+        
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        
+        println!("🔍 WWID recebido do frontend: '{}'", user_wwid);
+
+        // Junta as notas enviadas por critério ativo: os quatro parâmetros originais
+        // (ignorados se o critério foi desativado) e o mapa dos demais critérios
+        let definitions = criteria::load_definitions(conn, false)?;
+        let mut sent_by_key: HashMap<String, String> = HashMap::new();
+        for (key, value) in [("otif", otif_score), ("nil", nil_score), ("pickup", pickup_score), ("package", package_score)] {
+            if let Some(v) = value {
+                if definitions.iter().any(|d| d.criterion_key == key) {
+                    sent_by_key.insert(key.to_string(), v);
+                }
+            }
+        }
+        for (key, value) in criteria_scores.unwrap_or_default() {
+            let definition = criteria::find_definition(conn, &key)?;
+            if !definition.is_active {
+                return Err(format!("Critério inativo: {}", definition.display_name));
+            }
+            sent_by_key.insert(definition.criterion_key, value);
+        }
+        let sent: Vec<(&CriterionDefinition, String)> = definitions
+            .iter()
+            .filter_map(|d| sent_by_key.remove(&d.criterion_key).map(|v| (d, v)))
+            .collect();
+
+        let mut sent_evidence: Vec<(String, ScoreEvidence)> = Vec::new();
+        for (key, mut item) in criteria_evidence.unwrap_or_default() {
+            let definition = criteria::find_definition(conn, &key)?;
+            if !definition.is_active {
+                return Err(format!("Critério inativo: {}", definition.display_name));
+            }
+            item.criterion_key = definition.criterion_key;
+            evidence::validate(&item)?;
+            sent_evidence.push((definition.display_name, item));
+        }
+
+        let mut sent_comments: Vec<(String, String, String)> = Vec::new();
+        for (key, body) in criteria_comments.unwrap_or_default() {
+            if body.trim().is_empty() {
+                continue;
+            }
+            let definition = criteria::find_definition(conn, &key)?;
+            sent_comments.push((definition.criterion_key, definition.display_name, body.trim().to_string()));
+        }
+
+        let mut sent_reasons: Vec<(String, String, Vec<String>)> = Vec::new();
+        for (key, codes) in criteria_reasons.unwrap_or_default() {
+            let definition = criteria::find_definition(conn, &key)?;
+            let codes = reasons::check_codes(conn, &definition.criterion_key, &codes)
+                .map_err(|e| format!("{}: {}", validation::REASON_FIELD, e))?;
+            sent_reasons.push((definition.criterion_key, definition.display_name, codes));
+        }
+        
+        // Verifica se já existe um registro e busca valores antigos para o log
+        let existing_data = conn.query_row(
+            "SELECT id, comment FROM supplier_score_records_table 
+             WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+            rusqlite::params![&supplier_id, month.to_string(), year.to_string()],
+            |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                ))
+            }
+        );
+        
+        // Mesmas regras de validação de todas as gravações; o comentário já salvo vale para o "abaixo do target"
+        let sent_values: Vec<(String, String)> = sent.iter().map(|(d, v)| (d.criterion_key.clone(), v.clone())).collect();
+        let effective_comment = comments
+            .clone()
+            .filter(|c| !c.trim().is_empty())
+            .or_else(|| existing_data.as_ref().ok().and_then(|(_, c)| c.clone()));
+        let mut justification = match &existing_data {
+            Ok((id, _)) => validation::Justification {
+                comment: None,
+                criterion_comments: comments::latest_by_criterion(conn, *id as i64)?,
+                reason_codes: reasons::read_codes_map(conn, *id as i64)?,
+            },
+            Err(_) => validation::Justification::default(),
+        };
+        justification.comment = effective_comment;
+        for (key, _, body) in &sent_comments {
+            justification.criterion_comments.insert(key.clone(), body.clone());
+        }
+        for (key, _, codes) in &sent_reasons {
+            justification.reason_codes.insert(key.clone(), codes.clone());
+        }
+        let scope = targets::load_supplier_scope(conn, &supplier_id)?;
+        validation::validate_scores(conn, &scope, month, year, &sent_values, &justification)?.into_result()?;
+        
+        let supplier_info = format!("{} ({})", supplier_name, supplier_id);
+        let score_date_str = format!("{}/{}", month, year);
+        
+        match existing_data {
+            Ok((id, old_comment)) => {
+                let old_values = criteria::read_values(conn, id as i64)?;
+
+                // Registra logs para cada critério alterado
+                for (definition, new_value) in &sent {
+                    let old_score = old_values.get(&definition.criterion_key).copied();
+                    let old_val = old_score.map(|v| v.to_string()).unwrap_or_default();
+                    // Normaliza comparação: converte ambos para f64 e compara
+                    let old_normalized = old_score.unwrap_or(0.0);
+                    let new_normalized = score_engine::parse_score_text(new_value).unwrap_or(0.0);
+                    
+                    if (old_normalized - new_normalized).abs() > 0.001 {
+                        Self::insert_log(
+                            conn,
+                            &user_name,
+                            "Update",
+                            &user_wwid,
+                            &definition.display_name,
+                            Some(&supplier_info),
+                            Some(&score_date_str),
+                            if old_val.is_empty() { None } else { Some(&old_val) },
+                            Some(new_value)
+                        )?;
+                    }
                 }
                 
-                let query = format!(
-                    "UPDATE supplier_score_records_table SET {} WHERE id = ?",
-                    updates.join(", ")
-                );
+                if let Some(ref new_comment) = comments {
+                    let old_val = old_comment.clone().unwrap_or_else(|| "".to_string());
+                    if old_val != *new_comment && !new_comment.is_empty() {
+                        Self::insert_log(
+                            conn,
+                            &user_name,
+                            "Update",
+                            &user_wwid,
+                            "Comment",
+                            Some(&supplier_info),
+                            Some(&score_date_str),
+                            if old_val.is_empty() { None } else { Some(&old_val) },
+                            Some(new_comment)
+                        )?;
+                    }
+                }
+                
+                // Atualiza o registro existente - APENAS os campos que foram enviados (não nulos)
+                println!("📝 Atualizando registro existente (id: {})", id);
+                
+                // Se está enviando todas as notas dos critérios ativos e todas estão vazias, deleta o registro
+                let all_scores_sent = !definitions.is_empty() && sent.len() == definitions.len();
+                let all_scores_empty = sent.iter().all(|(_, v)| v.trim().is_empty());
+                
+                if all_scores_sent && all_scores_empty {
+
+                    let old_summary = definitions
+                        .iter()
+                        .map(|d| format!("{}: {:?}", d.display_name, old_values.get(&d.criterion_key)))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    
+                    // Registra log de deleção
+                    Self::insert_log(
+                        conn,
+                        &user_name,
+                        "Delete",
+                        &user_wwid,
+                        "All Scores",
+                        Some(&supplier_info),
+                        Some(&score_date_str),
+                        Some(&old_summary),
+                        None
+                    )?;
+                    
+                    criteria::delete_values(conn, id as i64)?;
+                    evidence::delete_for_record(conn, id as i64)?;
+                    comments::delete_for_record(conn, id as i64)?;
+                    reasons::delete_for_record(conn, id as i64)?;
+                    carryover::delete_for_record(conn, id as i64)?;
+                    workflow::delete_for_record(conn, id as i64)?;
+                    conn.execute(
+                        "DELETE FROM supplier_score_records_table WHERE id = ?",
+                        rusqlite::params![id]
+                    ).map_err(|e| format!("Erro ao deletar registro: {}", e))?;
+                    
+                    println!("✅ Registro deletado com sucesso!");
+                    return Ok("Registro deletado (todas as notas removidas)".to_string());
+                }
+                
+                for (definition, value) in &sent {
+                    criteria::write_value(conn, id as i64, &definition.criterion_key, value)?;
+                    // Nota copiada alterada no formulário deixa de aguardar confirmação
+                    if carryover::value_changed(old_values.get(&definition.criterion_key).copied(), score_engine::parse_score_text(value)) {
+                        carryover::clear(conn, id as i64, &definition.criterion_key)?;
+                    }
+                }
+                let old_evidence = evidence::read(conn, id as i64)?;
+                let mut evidence_changed = false;
+                for (display_name, item) in &sent_evidence {
+                    let old_item = old_evidence.get(&item.criterion_key);
+                    let unchanged = match old_item {
+                        Some(old) => old == item,
+                        None => item.is_empty(),
+                    };
+                    if unchanged {
+                        continue;
+                    }
+                    evidence::write(conn, id as i64, item, &user_name)?;
+                    evidence_changed = true;
+                    Self::insert_log(
+                        conn,
+                        &user_name,
+                        "Update",
+                        &user_wwid,
+                        &format!("{} Evidence", display_name),
+                        Some(&supplier_info),
+                        Some(&score_date_str),
+                        old_item.map(|e| e.describe()).as_deref(),
+                        if item.is_empty() { None } else { Some(item.describe()) }.as_deref(),
+                    )?;
+                }
+                if !sent.is_empty() || evidence_changed {
+                    workflow::mark_edited(conn, id as i64, &user_name, Some(&user_wwid))?;
+                }
+
+                let old_reasons = reasons::read_codes(conn, id as i64)?;
+                for (key, display_name, codes) in &sent_reasons {
+                    let old_codes = old_reasons.get(key).cloned().unwrap_or_default();
+                    if old_codes == *codes {
+                        continue;
+                    }
+                    reasons::write_codes(conn, id as i64, key, codes, &user_name)?;
+                    Self::insert_log(
+                        conn,
+                        &user_name,
+                        "Update",
+                        &user_wwid,
+                        &format!("{} Reason Codes", display_name),
+                        Some(&supplier_info),
+                        Some(&score_date_str),
+                        if old_codes.is_empty() { None } else { Some(old_codes.join(", ")) }.as_deref(),
+                        if codes.is_empty() { None } else { Some(codes.join(", ")) }.as_deref(),
+                    )?;
+                }
+
+                // Comentários entram na thread (o geral também fica na coluna comment, abaixo)
+                if let Some(ref comment) = comments {
+                    comments::add_if_changed(conn, id as i64, None, comment, &user_name, Some(&user_wwid))?;
+                }
+                for (key, display_name, body) in &sent_comments {
+                    let old_comment = comments::latest(conn, id as i64, Some(key))?;
+                    if comments::add_if_changed(conn, id as i64, Some(key), body, &user_name, Some(&user_wwid))? {
+                        Self::insert_log(
+                            conn,
+                            &user_name,
+                            "Update",
+                            &user_wwid,
+                            &format!("{} Comment", display_name),
+                            Some(&supplier_info),
+                            Some(&score_date_str),
+                            old_comment.as_deref(),
+                            Some(body),
+                        )?;
+                    }
+                }
+
+                // Sempre atualiza change_date e changed_by (o total é recalculado em seguida)
+                match comments {
+                    Some(ref comment) => conn.execute(
+                        "UPDATE supplier_score_records_table SET comment = ?1, change_date = ?2, changed_by = ?3 WHERE id = ?4",
+                        rusqlite::params![comment, now, user_name, id],
+                    ),
+                    None => conn.execute(
+                        "UPDATE supplier_score_records_table SET change_date = ?1, changed_by = ?2 WHERE id = ?3",
+                        rusqlite::params![now, user_name, id],
+                    ),
+                }
+                .map_err(|e| format!("Erro ao atualizar: {}", e))?;
+
+                score_engine::recalculate_record(conn, id as i64)?;
+                Ok("Score atualizado com sucesso".to_string())
+            }
+            Err(_) => {
+                // Insere novo registro
+                println!("➕ Criando novo registro");
+                
+                // Registra log de criação
+                let new_values = definitions
+                    .iter()
+                    .map(|d| {
+                        let value = sent
+                            .iter()
+                            .find(|(sd, _)| sd.criterion_key == d.criterion_key)
+                            .map(|(_, v)| v.as_str())
+                            .unwrap_or("—");
+                        format!("{}: {}", d.display_name, value)
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                
+                Self::insert_log(
+                    conn,
+                    &user_name,
+                    "Create",
+                    &user_wwid,
+                    "All Scores",
+                    Some(&supplier_info),
+                    Some(&score_date_str),
+                    None,
+                    Some(&new_values)
+                )?;
                 
-                params.push(Box::new(id));
+                conn.execute(
+                    "INSERT INTO supplier_score_records_table 
+                     (supplier_id, supplier_name, month, year, comment, register_date, registered_by, change_date, changed_by)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                    rusqlite::params![
+                        supplier_id,
+                        supplier_name,
+                        month.to_string(),
+                        year.to_string(),
+                        comments,
+                        now.clone(),
+                        user_name.clone(),
+                        now,
+                        user_name
+                    ],
+                ).map_err(|e| format!("Erro ao inserir: {}", e))?;
+
+                let record_id = conn.last_insert_rowid();
+                for (definition, value) in &sent {
+                    criteria::write_value(conn, record_id, &definition.criterion_key, value)?;
+                }
+                if let Some(ref comment) = comments {
+                    comments::add_if_changed(conn, record_id, None, comment, &user_name, Some(&user_wwid))?;
+                }
+                for (key, _, body) in &sent_comments {
+                    comments::add(conn, record_id, Some(key), body, &user_name, Some(&user_wwid))?;
+                }
+                for (key, _, codes) in &sent_reasons {
+                    reasons::write_codes(conn, record_id, key, codes, &user_name)?;
+                }
+                for (display_name, item) in &sent_evidence {
+                    if item.is_empty() {
+                        continue;
+                    }
+                    evidence::write(conn, record_id, item, &user_name)?;
+                    Self::insert_log(
+                        conn,
+                        &user_name,
+                        "Create",
+                        &user_wwid,
+                        &format!("{} Evidence", display_name),
+                        Some(&supplier_info),
+                        Some(&score_date_str),
+                        None,
+                        Some(&item.describe()),
+                    )?;
+                }
+
+                score_engine::recalculate_record(conn, record_id)?;
+                Ok("Score criado com sucesso".to_string())
+            }
+        }
+    }
+
+    /// Busca todos os critérios de avaliação
+    pub fn get_criteria() -> Result<Vec<Criteria>, String> {
+        println!("\n🔍 Buscando critérios de avaliação...");
+
+        let conn_guard = Self::get_connection()?;
+
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Busca TODOS os 5 registros
+        let mut stmt = conn
+            .prepare(
+                "SELECT criteria_id, criteria_category, value
+                 FROM criteria_table
+                 ORDER BY criteria_id"
+            )
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let criteria_iter = stmt
+            .query_map([], |row| {
+                let criteria_id = row.get::<_, i32>(0)?;
+                let criteria_category = row.get::<_, String>(1)?;
+                let value_str = row.get::<_, String>(2)?;
+                let value = value_str.parse::<f64>().unwrap_or(0.0);
+
+                println!("  📊 ID {}: '{}' = {}", criteria_id, criteria_category, value);
+
+                // Determina se é target ou weight pelo nome da categoria
+                let is_target = criteria_category.to_lowercase().contains("target");
+
+                Ok(Criteria {
+                    criteria_id,
+                    criteria_target_id: if is_target { None } else { None }, // Não precisa de target_id
+                    criteria_name: criteria_category,
+                    criteria_weight: if is_target { 0.0 } else { value },
+                    criteria_target: if is_target { value } else { 0.0 },
+                })
+            })
+            .map_err(|e| format!("Erro ao executar query: {}", e))?;
+
+        let result: Vec<Criteria> = criteria_iter
+            .filter_map(|r| r.ok())
+            .collect();
+
+        println!("✅ Encontrados {} critérios", result.len());
+        Ok(result)
+    }
+
+    /// Busca o valor do target configurado nos critérios
+    pub fn get_target() -> Result<f64, String> {
+        println!("\n🎯 Buscando target configurado...");
+
+        let conn_guard = Self::get_connection()?;
+
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+
+        // Target da versão vigente no mês corrente
+        if let Some(target) = criteria::target_for_period(conn, criteria::TOTAL_TARGET_KEY, &criteria::current_period())? {
+            return Ok(target);
+        }
+
+        // Sem versões registradas: busca o registro com "Target" no nome
+        let target = conn.query_row(
+            "SELECT value FROM criteria_table 
+             WHERE LOWER(criteria_category) LIKE '%target%'
+             LIMIT 1",
+            [],
+            |row| {
+                let value_str: String = row.get(0)?;
+                Ok(value_str.parse::<f64>().unwrap_or(8.7))
+            }
+        )
+        .unwrap_or(8.7);
+
+        println!("✅ Target encontrado: {}", target);
+        Ok(target)
+    }
+
+    /// Lista os targets sobrescritos (todos ou de um escopo: supplier, bu, category)
+    pub fn get_target_overrides(scope: Option<String>) -> Result<Vec<TargetOverride>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        if let Some(ref scope) = scope {
+            targets::validate_scope(scope)?;
+        }
+        targets::load_overrides(conn, scope.as_deref())
+    }
+
+    /// Define o target de um critério (ou do total) para uma categoria, BU ou fornecedor
+    pub fn save_target_override(
+        scope: String,
+        scope_value: String,
+        criterion_key: String,
+        target: f64,
+        admin_name: String,
+        admin_wwid: String,
+    ) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        targets::validate_scope(&scope)?;
+        let scope_value = scope_value.trim().to_string();
+        if scope_value.is_empty() {
+            return Err("Informe a categoria, BU ou fornecedor do target".to_string());
+        }
+        if !target.is_finite() || !(0.0..=10.0).contains(&target) {
+            return Err("O target deve estar entre 0 e 10".to_string());
+        }
+        let criterion_key = if criterion_key.trim().eq_ignore_ascii_case(criteria::TOTAL_TARGET_KEY) {
+            criteria::TOTAL_TARGET_KEY.to_string()
+        } else {
+            criteria::find_definition(conn, &criterion_key)?.criterion_key
+        };
+
+        let old_target: Option<f64> = conn
+            .query_row(
+                "SELECT target FROM criteria_target_override_table
+                 WHERE scope = ?1 AND lower(scope_value) = lower(?2) AND criterion_key = ?3",
+                rusqlite::params![scope, scope_value, criterion_key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar target atual: {}", e))?;
+
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        conn.execute(
+            "DELETE FROM criteria_target_override_table WHERE scope = ?1 AND lower(scope_value) = lower(?2) AND criterion_key = ?3",
+            rusqlite::params![scope, scope_value, criterion_key],
+        )
+        .map_err(|e| format!("Erro ao substituir target: {}", e))?;
+        conn.execute(
+            "INSERT INTO criteria_target_override_table (scope, scope_value, criterion_key, target, updated_at, updated_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![scope, scope_value, criterion_key, target, now, admin_name],
+        )
+        .map_err(|e| format!("Erro ao salvar target: {}", e))?;
+
+        Self::insert_log(
+            conn,
+            &admin_name,
+            if old_target.is_some() { "Update" } else { "Create" },
+            &admin_wwid,
+            "Targets",
+            if scope == targets::SCOPE_SUPPLIER { Some(scope_value.as_str()) } else { None },
+            None,
+            old_target.map(|t| format!("{} {} {}: {}", scope, scope_value, criterion_key, t)).as_deref(),
+            Some(&format!("{} {} {}: {}", scope, scope_value, criterion_key, target)),
+        )?;
+
+        Ok(())
+    }
+
+    /// Remove um target sobrescrito (o escopo volta a herdar o valor do nível acima)
+    pub fn delete_target_override(override_id: i64, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let (scope, scope_value, criterion_key, target): (String, String, String, f64) = conn
+            .query_row(
+                "SELECT scope, scope_value, criterion_key, target FROM criteria_target_override_table WHERE override_id = ?1",
+                [override_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar target: {}", e))?
+            .ok_or_else(|| "Target não encontrado".to_string())?;
+
+        conn.execute("DELETE FROM criteria_target_override_table WHERE override_id = ?1", [override_id])
+            .map_err(|e| format!("Erro ao excluir target: {}", e))?;
+
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Delete",
+            &admin_wwid,
+            "Targets",
+            if scope == targets::SCOPE_SUPPLIER { Some(scope_value.as_str()) } else { None },
+            None,
+            Some(&format!("{} {} {}: {}", scope, scope_value, criterion_key, target)),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Targets efetivos de um fornecedor (padrão: período corrente)
+    pub fn get_resolved_targets(supplier_id: String, month: Option<i32>, year: Option<i32>) -> Result<ResolvedTargets, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let period = match (month, year) {
+            (Some(m), Some(y)) => criteria::period_key(m, y),
+            _ => criteria::current_period(),
+        };
+        let scope = targets::load_supplier_scope(conn, &supplier_id)?;
+        TargetResolver::new(conn)?.resolve_all(conn, &scope, &period)
+    }
+
+    /// Busca fornecedores por nome ou ID
+    pub fn search_suppliers(query: String) -> Result<Vec<Supplier>, String> {
+        println!("\n🔍 ========================================");
+        println!("🔍 SEARCH_SUPPLIERS CHAMADO!");
+        println!("🔍 Query recebida: '{}'", query);
+        println!("🔍 Tamanho da query: {}", query.len());
+        println!("🔍 ========================================\n");
+        
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            println!("❌ Conexão não inicializada");
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        // DEBUG: Mostra estrutura da tabela apenas na primeira busca
+        static FIRST_RUN: std::sync::Once = std::sync::Once::new();
+        FIRST_RUN.call_once(|| {
+            println!("\n📋 === ESTRUTURA DA TABELA supplier_database_table ===");
+            if let Ok(mut stmt) = conn.prepare("PRAGMA table_info(supplier_database_table)") {
+                if let Ok(columns) = stmt.query_map([], |row| {
+                    let name: String = row.get(1)?;
+                    let col_type: String = row.get(2)?;
+                    Ok((name, col_type))
+                }) {
+                    for col in columns.filter_map(|c| c.ok()) {
+                        println!("   📌 {} ({})", col.0, col.1);
+                    }
+                }
+            }
+            println!("=================================================\n");
+        });
+        
+        let search_pattern = format!("%{}%", query);
+        println!("🔍 Padrão de busca: {}", search_pattern);
+        
+        // Primeiro, testa se há registros na tabela
+        let total_count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
+            .unwrap_or(0);
+        println!("📊 Total de registros na tabela: {}", total_count);
+        
+        // Testa quantos correspondem à busca
+        let match_count: i64 = conn
+            .query_row(
+                         "SELECT COUNT(*) FROM supplier_database_table 
+                          WHERE LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
+                              OR LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1)
+                              OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)",
+                [&search_pattern],
+                |row| row.get(0)
+            )
+            .unwrap_or(0);
+        println!("🔍 Registros que correspondem à busca: {}", match_count);
+        
+        let mut stmt = conn
+            .prepare(
+                     "SELECT DISTINCT 
+                          COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
+                          COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
+                          COALESCE(vendor_name, '') as vendor_name,
+                          bu,
+                          supplier_email,
+                          supplier_status,
+                          planner,
+                          country,
+                          supplier_category,
+                          continuity,
+                          sourcing,
+                          sqie,
+                          ssid
+                 FROM supplier_database_table 
+                          WHERE LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
+                              OR LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1)
+                              OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)
+                 ORDER BY vendor_name
+                 LIMIT 50"
+            )
+            .map_err(|e| {
+                println!("❌ Erro ao preparar statement: {}", e);
+                format!("Erro ao preparar query: {}", e)
+            })?;
+
+        println!("📝 Statement preparado com sucesso");
+
+        let suppliers_result = stmt
+            .query_map([&search_pattern], |row| {
+                // Lê os campos na ordem do SELECT
+                let supplier_id_value: String = row.get(0)?;
+                let supplier_po_value: String = row.get(1)?;
+                let vendor_name_value: String = row.get(2)?;
+                let bu: Option<String> = row.get(3).ok();
+                let supplier_email: Option<String> = row.get(4).ok();
+                let supplier_status: Option<String> = row.get(5).ok();
+                let planner: Option<String> = row.get(6).ok();
+                let country: Option<String> = row.get(7).ok();
+                let supplier_category: Option<String> = row.get(8).ok();
+                let continuity: Option<String> = row.get(9).ok();
+                let sourcing: Option<String> = row.get(10).ok();
+                let sqie: Option<String> = row.get(11).ok();
+                let ssid: Option<String> = row.get(12).ok();
                 
-                // Converte Vec<Box<dyn ToSql>> para &[&dyn ToSql]
-                let param_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+                Ok(Supplier {
+                    supplier_id: supplier_id_value,
+                    supplier_po: Some(supplier_po_value),
+                    supplier_name: vendor_name_value,
+                    bu,
+                    supplier_email,
+                    supplier_status,
+                    planner,
+                    country,
+                    supplier_category,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid,
+                    otif_target: None,
+                    nil_target: None,
+                    pickup_target: None,
+                    package_target: None,
+                    otif_score: None,
+                    nil_score: None,
+                    pickup_score: None,
+                    package_score: None,
+                    total_score: None,
+                    tier: None,
+                })
+            })
+            .map_err(|e| {
+                println!("❌ Erro ao executar query_map: {}", e);
+                format!("Erro ao executar query: {}", e)
+            })?;
+
+        println!("🔄 Processando resultados...");
+        
+        let mut suppliers = Vec::new();
+        for (idx, result) in suppliers_result.enumerate() {
+            println!("🔄 Processando resultado #{}", idx + 1);
+            match result {
+                Ok(supplier) => {
+                    println!("✅ Fornecedor {}: ID='{}', PO={:?}, Nome='{}'", 
+                        idx + 1, 
+                        supplier.supplier_id, 
+                        supplier.supplier_po,
+                        supplier.supplier_name
+                    );
+                    if !supplier.supplier_name.is_empty() {
+                        suppliers.push(supplier);
+                    } else {
+                        println!("⚠️ Fornecedor {} tem nome vazio, ignorando", idx + 1);
+                    }
+                }
+                Err(e) => {
+                    println!("❌ Erro ao processar fornecedor {}: {}", idx + 1, e);
+                }
+            }
+        }
+
+        Self::fill_supplier_targets(conn, &mut suppliers)?;
+        Self::fill_supplier_tiers(conn, &mut suppliers)?;
+
+        println!("\n✅ ========================================");
+        println!("✅ Total de fornecedores retornados: {}", suppliers.len());
+        println!("✅ ========================================\n");
+        Ok(suppliers)
+    }
+
+    /// Obtém todos os fornecedores por status (Active ou Active + Inactive), opcionalmente de um tier
+    pub fn get_all_suppliers_by_status(include_inactive: bool, tier: Option<String>) -> Result<Vec<Supplier>, String> {
+        println!("\n🔍 ========================================");
+        println!("🔍 GET_ALL_SUPPLIERS_BY_STATUS CHAMADO!");
+        println!("🔍 Include Inactive: {}", include_inactive);
+        println!("🔍 ========================================\n");
+        
+        let tier = match tier.as_deref().map(str::trim).filter(|t| !t.is_empty()) {
+            Some(tier) => Some(tiering::validate_tier(tier)?),
+            None => None,
+        };
+
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            println!("❌ Conexão não inicializada");
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let query = if include_inactive {
+            "SELECT DISTINCT 
+                COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
+                COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
+                COALESCE(vendor_name, '') as vendor_name,
+                bu,
+                supplier_email,
+                supplier_status,
+                planner,
+                country,
+                supplier_category,
+                continuity,
+                sourcing,
+                sqie,
+                ssid
+            FROM supplier_database_table 
+            WHERE LOWER(COALESCE(supplier_status, '')) IN ('active', 'inactive')
+            ORDER BY vendor_name"
+        } else {
+            "SELECT DISTINCT 
+                COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
+                COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
+                COALESCE(vendor_name, '') as vendor_name,
+                bu,
+                supplier_email,
+                supplier_status,
+                planner,
+                country,
+                supplier_category,
+                continuity,
+                sourcing,
+                sqie,
+                ssid
+            FROM supplier_database_table 
+            WHERE LOWER(COALESCE(supplier_status, '')) = 'active'
+            ORDER BY vendor_name"
+        };
+        
+        println!("📝 Executando query...");
+        
+        let mut stmt = conn
+            .prepare(query)
+            .map_err(|e| {
+                println!("❌ Erro ao preparar statement: {}", e);
+                format!("Erro ao preparar query: {}", e)
+            })?;
+
+        let suppliers_result = stmt
+            .query_map([], |row| {
+                let supplier_id_value: String = row.get(0)?;
+                let supplier_po_value: String = row.get(1)?;
+                let vendor_name_value: String = row.get(2)?;
+                let bu: Option<String> = row.get(3).ok();
+                let supplier_email: Option<String> = row.get(4).ok();
+                let supplier_status: Option<String> = row.get(5).ok();
+                let planner: Option<String> = row.get(6).ok();
+                let country: Option<String> = row.get(7).ok();
+                let supplier_category: Option<String> = row.get(8).ok();
+                let continuity: Option<String> = row.get(9).ok();
+                let sourcing: Option<String> = row.get(10).ok();
+                let sqie: Option<String> = row.get(11).ok();
+                let ssid: Option<String> = row.get(12).ok();
                 
-                conn.execute(&query, param_refs.as_slice())
-                    .map_err(|e| format!("Erro ao atualizar: {}", e))?;
+                Ok(Supplier {
+                    supplier_id: supplier_id_value,
+                    supplier_po: Some(supplier_po_value),
+                    supplier_name: vendor_name_value,
+                    bu,
+                    supplier_email,
+                    supplier_status,
+                    planner,
+                    country,
+                    supplier_category,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid,
+                    otif_target: None,
+                    nil_target: None,
+                    pickup_target: None,
+                    package_target: None,
+                    otif_score: None,
+                    nil_score: None,
+                    pickup_score: None,
+                    package_score: None,
+                    total_score: None,
+                    tier: None,
+                })
+            })
+            .map_err(|e| {
+                println!("❌ Erro ao executar query_map: {}", e);
+                format!("Erro ao executar query: {}", e)
+            })?;
+
+        let mut suppliers = Vec::new();
+        for result in suppliers_result {
+            if let Ok(supplier) = result {
+                if !supplier.supplier_name.is_empty() {
+                    suppliers.push(supplier);
+                }
+            }
+        }
+
+        Self::fill_supplier_targets(conn, &mut suppliers)?;
+        Self::fill_supplier_tiers(conn, &mut suppliers)?;
+        if let Some(tier) = tier {
+            suppliers.retain(|s| s.tier.as_deref() == Some(tier.as_str()));
+        }
+
+        println!("\n✅ ========================================");
+        println!("✅ Total de fornecedores retornados: {}", suppliers.len());
+        println!("✅ ========================================\n");
+        Ok(suppliers)
+    }
+
+    /// Obtém dados completos de um fornecedor
+    pub fn get_supplier(supplier_id: String) -> Result<Option<Supplier>, String> {
+        println!("\n📋 Buscando fornecedor: {}", supplier_id);
+        
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT 
+                    COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
+                    COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
+                    COALESCE(vendor_name, '') as vendor_name,
+                    bu,
+                    supplier_email,
+                    supplier_status,
+                    planner,
+                    country,
+                    supplier_category,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid
+                 FROM supplier_database_table 
+                 WHERE CAST(supplier_id AS TEXT) = ?1
+                 LIMIT 1"
+            )
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let mut rows = stmt
+            .query_map([&supplier_id], |row| {
+                let supplier_id_value: String = row.get(0)?;
+                let supplier_po_value: String = row.get(1)?;
+                let vendor_name_value: String = row.get(2)?;
+                let bu: Option<String> = row.get(3).ok();
+                let supplier_email: Option<String> = row.get(4).ok();
+                let supplier_status: Option<String> = row.get(5).ok();
+                let planner: Option<String> = row.get(6).ok();
+                let country: Option<String> = row.get(7).ok();
+                let supplier_category: Option<String> = row.get(8).ok();
+                let continuity: Option<String> = row.get(9).ok();
+                let sourcing: Option<String> = row.get(10).ok();
+                let sqie: Option<String> = row.get(11).ok();
+                let ssid: Option<String> = row.get(12).ok();
                 
-                println!("✅ Score atualizado com sucesso!");
-                Ok("Score atualizado com sucesso".to_string())
+                Ok(Supplier {
+                    supplier_id: supplier_id_value,
+                    supplier_po: Some(supplier_po_value),
+                    supplier_name: vendor_name_value,
+                    bu,
+                    supplier_email,
+                    supplier_status,
+                    planner,
+                    country,
+                    supplier_category,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid,
+                    otif_target: None,
+                    nil_target: None,
+                    pickup_target: None,
+                    package_target: None,
+                    otif_score: None,
+                    nil_score: None,
+                    pickup_score: None,
+                    package_score: None,
+                    total_score: None,
+                    tier: None,
+                })
+            })
+            .map_err(|e| format!("Erro ao executar query: {}", e))?;
+
+        let mut result = rows.next().transpose().map_err(|e| format!("Erro ao obter resultado: {}", e))?;
+        if let Some(supplier) = result.as_mut() {
+            Self::fill_supplier_targets(conn, std::slice::from_mut(supplier))?;
+            Self::fill_supplier_tiers(conn, std::slice::from_mut(supplier))?;
+        }
+        Ok(result)
+    }
+
+    /// Preenche os targets efetivos (com herança de categoria/BU) no período corrente
+    fn fill_supplier_targets(conn: &Connection, suppliers: &mut [Supplier]) -> Result<(), String> {
+        let period = criteria::current_period();
+        let mut resolver = TargetResolver::new(conn)?;
+
+        for supplier in suppliers.iter_mut() {
+            let scope = SupplierScope {
+                supplier_id: supplier.supplier_id.clone(),
+                bu: supplier.bu.clone(),
+                category: supplier.supplier_category.clone(),
+            };
+            let mut resolve = |key: &str| -> Result<Option<String>, String> {
+                Ok(Some(format!("{:.2}", resolver.resolve(conn, &scope, key, &period)?.target)))
+            };
+            supplier.otif_target = resolve("otif")?;
+            supplier.nil_target = resolve("nil")?;
+            supplier.pickup_target = resolve("pickup")?;
+            supplier.package_target = resolve("package")?;
+        }
+
+        Ok(())
+    }
+
+    /// Preenche o tier mais recente da segmentação
+    fn fill_supplier_tiers(conn: &Connection, suppliers: &mut [Supplier]) -> Result<(), String> {
+        let tiers = tiering::current_tiers(conn)?;
+        for supplier in suppliers.iter_mut() {
+            supplier.tier = tiers.get(&supplier.supplier_id.trim().to_lowercase()).cloned();
+        }
+        Ok(())
+    }
+
+    /// Atualiza dados do fornecedor
+    pub fn update_supplier(supplier: SupplierUpdate) -> Result<(), String> {
+        println!("\n💾 Atualizando fornecedor: {}", supplier.supplier_id);
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        let has_vendor_name = Self::supplier_table_has_column(conn, "vendor_name")?;
+        let has_supplier_name = Self::supplier_table_has_column(conn, "supplier_name")?;
+
+        let mut set_clauses: Vec<String> = Vec::new();
+        if has_vendor_name {
+            set_clauses.push("vendor_name = :supplier_name".to_string());
+        }
+        if has_supplier_name {
+            set_clauses.push("supplier_name = :supplier_name".to_string());
+        }
+        set_clauses.extend([
+            "supplier_po = :supplier_po",
+            "bu = :bu",
+            "supplier_email = :supplier_email",
+            "supplier_status = :supplier_status",
+            "planner = :planner",
+            "country = :country",
+            "supplier_category = :supplier_category",
+            "continuity = :continuity",
+            "sourcing = :sourcing",
+            "sqie = :sqie",
+            "ssid = :ssid",
+        ]
+        .iter()
+        .map(|s| s.to_string()));
+
+        let sql = format!(
+            "UPDATE supplier_database_table SET {} WHERE COALESCE(CAST(supplier_id AS TEXT), '') = :supplier_id",
+            set_clauses.join(", ")
+        );
+        // Atualiza todos os campos do fornecedor
+        let rows_affected = conn
+            .execute(
+                &sql,
+                rusqlite::named_params! {
+                    ":supplier_id": &supplier.supplier_id,
+                    ":supplier_name": &supplier.supplier_name,
+                    ":supplier_po": &supplier.supplier_po,
+                    ":bu": &supplier.bu,
+                    ":supplier_email": &supplier.supplier_email,
+                    ":supplier_status": &supplier.supplier_status,
+                    ":planner": &supplier.planner,
+                    ":country": &supplier.country,
+                    ":supplier_category": &supplier.supplier_category,
+                    ":continuity": &supplier.continuity,
+                    ":sourcing": &supplier.sourcing,
+                    ":sqie": &supplier.sqie,
+                    ":ssid": &supplier.ssid,
+                }
+            )
+            .map_err(|e| format!("Erro ao atualizar fornecedor: {}", e))?;
+
+        // Atualiza o nome do fornecedor em todos os registros de score relacionados
+        let update_score_sql = "UPDATE supplier_score_records_table SET supplier_name = ?1 WHERE supplier_id = ?2";
+        let score_rows_affected = conn
+            .execute(update_score_sql, rusqlite::params![&supplier.supplier_name, &supplier.supplier_id])
+            .map_err(|e| format!("Erro ao atualizar registros de score: {}", e))?;
+        println!("✅ {} registros de score atualizados", score_rows_affected);
+
+        println!("✅ {} registros de fornecedor atualizados", rows_affected);
+        Ok(())
+    }
+
+    /// Exclui um fornecedor e seus registros de score
+    pub fn delete_supplier(supplier_id: String) -> Result<(), String> {
+        println!("\n🗑️ Excluindo fornecedor: {}", supplier_id);
+        let mut conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_mut().unwrap();
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let record_ids: Vec<i64> = {
+            let mut stmt = tx
+                .prepare("SELECT id FROM supplier_score_records_table WHERE supplier_id = ?1")
+                .map_err(|e| format!("Erro ao buscar scores do fornecedor: {}", e))?;
+            let ids = stmt
+                .query_map([&supplier_id], |row| row.get(0))
+                .map_err(|e| format!("Erro ao buscar scores do fornecedor: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Erro ao coletar scores do fornecedor: {}", e))?;
+            ids
+        };
+
+        // Os ids de registro são reaproveitados: nada vinculado a eles pode sobrar
+        for record_id in &record_ids {
+            criteria::delete_values(&tx, *record_id)?;
+            evidence::delete_for_record(&tx, *record_id)?;
+            comments::delete_for_record(&tx, *record_id)?;
+            reasons::delete_for_record(&tx, *record_id)?;
+            carryover::delete_for_record(&tx, *record_id)?;
+            workflow::delete_for_record(&tx, *record_id)?;
+        }
+
+        tx.execute(
+            "DELETE FROM supplier_score_records_table WHERE supplier_id = ?1",
+            [&supplier_id],
+        )
+        .map_err(|e| format!("Erro ao excluir scores do fornecedor: {}", e))?;
+
+        let has_legacy_table: bool = tx
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='supplier_score'",
+                [],
+                |row| row.get::<_, i32>(0),
+            )
+            .map_err(|e| format!("Erro ao verificar tabela legacy: {}", e))? > 0;
+
+        if has_legacy_table {
+            tx.execute(
+                "DELETE FROM supplier_score WHERE supplier_id = ?1",
+                [&supplier_id],
+            )
+            .map_err(|e| format!("Erro ao excluir scores legados do fornecedor: {}", e))?;
+        }
+
+        tx.execute(
+            "DELETE FROM supplier_database_table WHERE COALESCE(CAST(supplier_id AS TEXT), '') = ?1",
+            [&supplier_id],
+        )
+        .map_err(|e| format!("Erro ao excluir fornecedor: {}", e))?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao finalizar transação: {}", e))?;
+
+        println!("✅ Fornecedor excluído com sucesso!");
+        Ok(())
+    }
+
+    /// Cria um novo fornecedor
+    pub fn create_supplier(supplier: SupplierUpdate) -> Result<(), String> {
+        println!("\n💾 Criando novo fornecedor: {}", supplier.supplier_name);
+        println!("📋 Dados do fornecedor:");
+        println!("  - ID: {}", supplier.supplier_id);
+        println!("  - Nome: {}", supplier.supplier_name);
+        println!("  - PO: {:?}", supplier.supplier_po);
+        println!("  - País: {:?}", supplier.country);
+        
+        println!("🔌 Obtendo conexão com banco de dados...");
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            println!("❌ Conexão não inicializada");
+            return Err("Conexão não inicializada".to_string());
+        }
+        println!("✅ Conexão obtida com sucesso");
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        println!("📝 Executando INSERT...");
+        
+        // IMPORTANTE: 
+        // - supplier_id é INTEGER PRIMARY KEY (auto-incremento, não incluir no INSERT)
+        // - supplier_name, bu, supplier_category, supplier_email são NOT NULL (obrigatórios)
+        // - supplier_po é INTEGER (não TEXT)
+        // - vendor_name e supplier_name são colunas diferentes (ambas existem)
+        
+        // Validar campos obrigatórios
+        let supplier_name_val = if supplier.supplier_name.trim().is_empty() {
+            return Err("Nome do fornecedor é obrigatório".to_string());
+        } else {
+            &supplier.supplier_name
+        };
+        
+        let bu_val = supplier.bu.as_ref()
+            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
+            .unwrap_or("N/A");
+            
+        let category_val = supplier.supplier_category.as_ref()
+            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
+            .unwrap_or("N/A");
+            
+        let email_val = supplier.supplier_email.as_ref()
+            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
+            .unwrap_or("");
+        
+        // Converter supplier_po para INTEGER (se vazio, usa NULL)
+        let po_val: Option<i32> = supplier.supplier_po.as_ref()
+            .and_then(|s| s.trim().parse::<i32>().ok());
+        
+        println!("  ✓ Nome: {}", supplier_name_val);
+        println!("  ✓ BU: {}", bu_val);
+        println!("  ✓ Categoria: {}", category_val);
+        println!("  ✓ Email: {}", email_val);
+        println!("  ✓ PO: {:?}", po_val);
+        
+        let result = conn
+            .execute(
+                "INSERT INTO supplier_database_table (
+                    vendor_name,
+                    supplier_name,
+                    bu,
+                    supplier_category,
+                    supplier_email,
+                    supplier_po,
+                    supplier_status,
+                    planner,
+                    country,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+                rusqlite::params![
+                    supplier_name_val,     // vendor_name
+                    supplier_name_val,     // supplier_name
+                    bu_val,                // bu (NOT NULL)
+                    category_val,          // supplier_category (NOT NULL)
+                    email_val,             // supplier_email (NOT NULL)
+                    po_val,                // supplier_po (INTEGER)
+                    supplier.supplier_status.as_deref().unwrap_or(""),
+                    supplier.planner.as_deref().unwrap_or(""),
+                    supplier.country.as_deref().unwrap_or(""),
+                    supplier.continuity.as_deref().unwrap_or(""),
+                    supplier.sourcing.as_deref().unwrap_or(""),
+                    supplier.sqie.as_deref().unwrap_or(""),
+                    supplier.ssid.as_deref().unwrap_or(""),
+                ]
+            )
+        .map_err(|e| {
+            println!("❌ Erro ao executar INSERT: {}", e);
+            format!("Erro ao criar fornecedor: {}", e)
+        })?;
+
+        println!("✅ INSERT executado. Linhas afetadas: {}", result);
+        println!("✅ Fornecedor criado com sucesso");
+        Ok(())
+    }
+
+    /// Verifica se um PO já existe (exceto para o fornecedor atual)
+    pub fn check_po_exists(po: &str, current_supplier_id: &str) -> Result<Option<Supplier>, String> {
+        let conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn
+            .prepare(
+                "SELECT 
+                    COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
+                    supplier_po,
+                    vendor_name,
+                    bu,
+                    supplier_email,
+                    supplier_status,
+                    planner,
+                    country,
+                    supplier_category,
+                    continuity,
+                    sourcing,
+                    sqie,
+                    ssid
+                FROM supplier_database_table
+                WHERE supplier_po = ?1 AND COALESCE(CAST(supplier_id AS TEXT), '') != ?2
+                LIMIT 1"
+            )
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let result = stmt
+            .query_row(rusqlite::params![po, current_supplier_id], |row| {
+                Ok(Supplier {
+                    supplier_id: row.get(0)?,
+                    supplier_po: row.get(1)?,
+                    supplier_name: row.get(2)?,
+                    bu: row.get(3)?,
+                    supplier_email: row.get(4)?,
+                    supplier_status: row.get(5)?,
+                    planner: row.get(6)?,
+                    country: row.get(7)?,
+                    supplier_category: row.get(8)?,
+                    continuity: row.get(9)?,
+                    sourcing: row.get(10)?,
+                    sqie: row.get(11)?,
+                    ssid: row.get(12)?,
+                    otif_target: None,
+                    nil_target: None,
+                    pickup_target: None,
+                    package_target: None,
+                    otif_score: None,
+                    nil_score: None,
+                    pickup_score: None,
+                    package_score: None,
+                    total_score: None,
+                    tier: None,
+                })
+            })
+            .optional()
+            .map_err(|e| format!("Erro ao verificar PO: {}", e))?;
+
+        Ok(result)
+    }
+
+    /// Verifica o schema da tabela supplier_database_table
+    pub fn check_table_schema() -> Result<(), String> {
+        println!("\n🔍 Verificando schema da tabela supplier_database_table...");
+        
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn
+            .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name='supplier_database_table'")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let schema: String = stmt.query_row([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao obter schema: {}", e))?;
+        
+        println!("📋 Schema atual da tabela:");
+        println!("{}", schema);
+        
+        Ok(())
+    }
+
+    /// Debug detalhado da estrutura da tabela supplier_database_table
+    pub fn debug_supplier_table() -> Result<String, String> {
+        println!("\n🔍 [DEBUG] Analisando tabela supplier_database_table...");
+        
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut result = String::new();
+        
+        // 1. Pega o schema da tabela
+        result.push_str("=== SCHEMA DA TABELA ===\n");
+        let mut stmt = conn
+            .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name='supplier_database_table'")
+            .map_err(|e| format!("Erro ao obter schema: {}", e))?;
+        
+        let schema: String = stmt.query_row([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao ler schema: {}", e))?;
+        result.push_str(&format!("{}\n\n", schema));
+        
+        // 2. Pega informações detalhadas de cada coluna
+        result.push_str("=== COLUNAS (PRAGMA table_info) ===\n");
+        let mut stmt = conn
+            .prepare("PRAGMA table_info(supplier_database_table)")
+            .map_err(|e| format!("Erro ao obter colunas: {}", e))?;
+        
+        let mut rows = stmt.query([])
+            .map_err(|e| format!("Erro ao executar PRAGMA: {}", e))?;
+        
+        while let Some(row) = rows.next().map_err(|e| format!("Erro ao iterar: {}", e))? {
+            let cid: i32 = row.get(0).unwrap_or(-1);
+            let name: String = row.get(1).unwrap_or_else(|_| "?".to_string());
+            let col_type: String = row.get(2).unwrap_or_else(|_| "?".to_string());
+            let notnull: i32 = row.get(3).unwrap_or(0);
+            let dflt_value: Option<String> = row.get(4).ok();
+            let pk: i32 = row.get(5).unwrap_or(0);
+            
+            result.push_str(&format!(
+                "  [{}] {} | Tipo: {} | NOT NULL: {} | Default: {:?} | PK: {}\n",
+                cid, name, col_type, notnull, dflt_value, pk
+            ));
+        }
+        
+        // 3. Conta quantos registros tem na tabela
+        result.push_str("\n=== ESTATÍSTICAS ===\n");
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM supplier_database_table",
+            [],
+            |row| row.get(0)
+        ).unwrap_or(0);
+        result.push_str(&format!("Total de registros: {}\n", count));
+        
+        println!("{}", result);
+        Ok(result)
+    }
+
+    /// Atualiza os critérios de avaliação
+    pub fn update_criteria(
+        criteria: Vec<Criteria>,
+        effective_from: Option<String>,
+        user_name: Option<String>,
+        user_wwid: Option<String>,
+    ) -> Result<(), String> {
+        println!("\n💾 Atualizando critérios...");
+        
+        let mut conn_guard = Self::get_connection()?;
+        
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+
+        let conn = conn_guard.as_mut().unwrap();
+
+        // Nova versão vigente a partir do período informado (padrão: mês corrente).
+        // Os valores "atuais" (criteria_table / definição) só mudam se a vigência já começou.
+        let effective_from = criteria::parse_effective_from(effective_from.as_deref())?;
+        let applies_now = effective_from <= criteria::current_period();
+        let user_name = user_name.unwrap_or_else(|| "System".to_string());
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        // Processa cada critério
+        for c in criteria {
+            let is_target = c.criteria_name.to_lowercase().contains("target");
+            
+            // Usa criteria_target se for target, criteria_weight se for weight
+            let value_to_save = if is_target {
+                c.criteria_target
+            } else {
+                c.criteria_weight
+            };
+
+            let version_key = if is_target {
+                Some(criteria::TOTAL_TARGET_KEY)
+            } else {
+                criteria::builtin_key_for_category(&c.criteria_name)
+            };
+            if let Some(key) = version_key {
+                let (weight, target) = if is_target { (None, Some(value_to_save)) } else { (Some(value_to_save), None) };
+                criteria::record_version(&tx, key, weight, target, &effective_from, &user_name)?;
+            }
+
+            if !applies_now {
+                continue;
+            }
+            
+            let value_str = value_to_save.to_string();
+            
+            tx.execute(
+                "UPDATE criteria_table SET value = ?1 WHERE criteria_id = ?2",
+                rusqlite::params![value_str, c.criteria_id],
+            )
+            .map_err(|e| format!("Erro ao atualizar ID {}: {}", c.criteria_id, e))?;
+            
+            // Mantém o peso do critério configurado em sincronia
+            if let Some(key) = criteria::builtin_key_for_category(&c.criteria_name) {
+                tx.execute(
+                    "UPDATE criteria_definition_table SET weight = ?1 WHERE criterion_key = ?2",
+                    rusqlite::params![value_to_save, key],
+                )
+                .map_err(|e| format!("Erro ao sincronizar peso de {}: {}", key, e))?;
+            }
+            
+            if is_target {
+                println!("✅ Target (ID {}) atualizado para: {}", c.criteria_id, value_to_save);
+            } else {
+                println!("✅ Weight (ID {}) atualizado para: {}", c.criteria_id, value_to_save);
+            }
+        }
+
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Update",
+            user_wwid.as_deref().unwrap_or(""),
+            "Criteria",
+            None,
+            Some(&effective_from),
+            None,
+            Some(&format!("Nova versão de pesos/target vigente a partir de {}", effective_from)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar critérios: {}", e))?;
+
+        println!("✅ Critérios atualizados com sucesso!");
+        Ok(())
+    }
+
+    /// Lista as versões de peso/target (todas ou de um critério), da mais recente para a mais antiga
+    pub fn get_criteria_history(criterion_key: Option<String>) -> Result<Vec<criteria::CriteriaVersion>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let key = criterion_key
+            .map(|k| k.trim().to_lowercase())
+            .filter(|k| !k.is_empty());
+        criteria::load_versions(conn, key.as_deref())
+    }
+
+    /// Calcula old/new/delta e a mudança de ranking mensal dos registros do intervalo, sem gravar
+    fn build_recalculation(conn: &Connection, range: &PeriodRange) -> Result<RecalculationPreview, String> {
+        let (start, end) = range.validate()?;
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, supplier_id, COALESCE(supplier_name, ''), month, year, total_score
+                 FROM supplier_score_records_table
+                 WHERE CAST(year AS INTEGER) * 100 + CAST(month AS INTEGER) BETWEEN ?1 AND ?2
+                 ORDER BY CAST(year AS INTEGER), CAST(month AS INTEGER), supplier_id",
+            )
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        let rows = stmt
+            .query_map([start, end], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar registros: {}", e))?;
+
+        // Pesos vigentes carregados uma vez por período
+        let mut weights_by_period: HashMap<(i32, i32), Vec<score_engine::CriterionWeight>> = HashMap::new();
+        let mut entries = Vec::with_capacity(rows.len());
+        for (record_id, supplier_id, supplier_name, month, year, old_total) in rows {
+            let month: i32 = month.trim().parse().unwrap_or(0);
+            let year: i32 = year.trim().parse().unwrap_or(0);
+            let weights = match weights_by_period.entry((month, year)) {
+                std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+                std::collections::hash_map::Entry::Vacant(e) => e.insert(score_engine::load_weights(conn, month, year)?),
+            };
+
+            let old_total = old_total.as_deref().and_then(score_engine::parse_score_text);
+            let new_total = score_engine::compute_record_total(conn, record_id, weights)?
+                .as_deref()
+                .and_then(score_engine::parse_score_text);
+            let delta = match (old_total, new_total) {
+                (Some(old), Some(new)) => Some(((new - old) * 100.0).round() / 100.0),
+                _ => None,
+            };
+
+            entries.push(RecalculationEntry {
+                record_id,
+                supplier_id,
+                supplier_name,
+                month,
+                year,
+                old_total,
+                new_total,
+                delta,
+                old_rank: None,
+                new_rank: None,
+                rank_change: None,
+            });
+        }
+
+        // Ranking por mês (maior total primeiro; empates dividem a posição)
+        fn assign_ranks(entries: &mut [RecalculationEntry], total: fn(&RecalculationEntry) -> Option<f64>, set: fn(&mut RecalculationEntry, i32)) {
+            let mut ranked: Vec<(usize, f64)> = entries.iter().enumerate().filter_map(|(i, e)| total(e).map(|t| (i, t))).collect();
+            ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
+            let mut rank = 0;
+            let mut previous: Option<f64> = None;
+            for (position, (idx, value)) in ranked.into_iter().enumerate() {
+                if previous.map(|p| (p - value).abs() > f64::EPSILON).unwrap_or(true) {
+                    rank = position as i32 + 1;
+                    previous = Some(value);
+                }
+                set(&mut entries[idx], rank);
+            }
+        }
+
+        let mut start_idx = 0;
+        while start_idx < entries.len() {
+            let period = (entries[start_idx].year, entries[start_idx].month);
+            let end_idx = entries[start_idx..]
+                .iter()
+                .position(|e| (e.year, e.month) != period)
+                .map(|offset| start_idx + offset)
+                .unwrap_or(entries.len());
+            let group = &mut entries[start_idx..end_idx];
+            assign_ranks(group, |e| e.old_total, |e, r| e.old_rank = Some(r));
+            assign_ranks(group, |e| e.new_total, |e, r| e.new_rank = Some(r));
+            start_idx = end_idx;
+        }
+
+        for entry in entries.iter_mut() {
+            entry.rank_change = match (entry.old_rank, entry.new_rank) {
+                (Some(old), Some(new)) => Some(old - new),
+                _ => None,
+            };
+        }
+
+        let changed_records = entries
+            .iter()
+            .filter(|e| match (e.old_total, e.new_total) {
+                (Some(old), Some(new)) => (old - new).abs() > 0.001,
+                (old, new) => old.is_some() != new.is_some(),
+            })
+            .count();
+        let rank_changes = entries.iter().filter(|e| e.old_rank != e.new_rank).count();
+
+        Ok(RecalculationPreview {
+            total_records: entries.len(),
+            changed_records,
+            rank_changes,
+            entries,
+        })
+    }
+
+    /// Prévia do recálculo dos totais com os pesos vigentes (nada é gravado)
+    pub fn preview_recalculation(period_range: PeriodRange) -> Result<RecalculationPreview, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        Self::build_recalculation(conn, &period_range)
+    }
+
+    /// Regrava os totais do intervalo em uma única transação, com um único registro de auditoria
+    pub fn apply_recalculation(period_range: PeriodRange, user_name: String, user_wwid: String) -> Result<RecalculationPreview, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let result = Self::build_recalculation(&tx, &period_range)?;
+
+        let mut closed: Vec<String> = Vec::new();
+        for entry in &result.entries {
+            let label = format!("{:02}/{}", entry.month, entry.year);
+            if !closed.contains(&label) && periods::status_for(&tx, entry.month, entry.year)? == periods::STATUS_CLOSED {
+                closed.push(label);
+            }
+        }
+        if !closed.is_empty() {
+            return Err(format!("Períodos fechados no intervalo: {}. Reabra-os antes de recalcular", closed.join(", ")));
+        }
+
+        for entry in &result.entries {
+            score_engine::write_total(&tx, entry.record_id, score_engine::format_total(entry.new_total).as_deref())?;
+            // Total alterado volta para revisão (aprovados já podem ter sido divulgados)
+            if carryover::value_changed(entry.old_total, entry.new_total) {
+                workflow::mark_edited(&tx, entry.record_id, &user_name, Some(&user_wwid))?;
+            }
+        }
+
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Recalculate",
+            &user_wwid,
+            "Total Score",
+            None,
+            Some(&period_range.label()),
+            None,
+            Some(&format!(
+                "{} registros recalculados, {} totais alterados, {} mudanças de ranking",
+                result.total_records, result.changed_records, result.rank_changes
+            )),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar recálculo: {}", e))?;
+
+        Ok(result)
+    }
+
+    /// Busca o registro fornecedor/mês ou cria um vazio (notas calculadas a partir de dados de origem)
+    fn find_or_create_score_record(conn: &Connection, supplier_id: &str, month: i32, year: i32, user_name: &str) -> Result<(i64, bool), String> {
+        let existing: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM supplier_score_records_table
+                 WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+                rusqlite::params![supplier_id, month.to_string(), year.to_string()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar registro de {}: {}", supplier_id, e))?;
+        if let Some(id) = existing {
+            return Ok((id, false));
+        }
+
+        let supplier_name: String = conn
+            .query_row(
+                "SELECT COALESCE(vendor_name, '') FROM supplier_database_table
+                 WHERE lower(trim(CAST(supplier_id AS TEXT))) = lower(trim(?1))",
+                [supplier_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar fornecedor {}: {}", supplier_id, e))?
+            .unwrap_or_default();
+
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        conn.execute(
+            "INSERT INTO supplier_score_records_table
+             (supplier_id, supplier_name, month, year, register_date, registered_by, change_date, changed_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?5, ?6)",
+            rusqlite::params![supplier_id, supplier_name, month.to_string(), year.to_string(), now, user_name],
+        )
+        .map_err(|e| format!("Erro ao inserir registro de {}: {}", supplier_id, e))?;
+        Ok((conn.last_insert_rowid(), true))
+    }
+
+    /// Grava uma nota calculada (OTIF/NIL/inspeção) respeitando período, validação e fluxo de revisão
+    fn write_derived_score(
+        conn: &Connection,
+        supplier_id: &str,
+        month: i32,
+        year: i32,
+        criterion_key: &str,
+        score: f64,
+        score_evidence: Option<ScoreEvidence>,
+        user_name: &str,
+        user_wwid: &str,
+    ) -> Result<Option<String>, String> {
+        let value = format!("{:.2}", score);
+        let rule = validation::load_rule(conn, criterion_key)?;
+        validation::check_value(&rule, &value).map_err(|e| format!("{} {}: {}", supplier_id, criterion_key, e))?;
+
+        let (record_id, created) = Self::find_or_create_score_record(conn, supplier_id, month, year, user_name)?;
+        criteria::write_value(conn, record_id, criterion_key, &value)?;
+        carryover::clear(conn, record_id, criterion_key)?;
+        if let Some(mut item) = score_evidence {
+            item.criterion_key = criterion_key.to_string();
+            evidence::write(conn, record_id, &item, user_name)?;
+        }
+        if !created {
+            workflow::mark_edited(conn, record_id, user_name, Some(user_wwid))?;
+            let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+            conn.execute(
+                "UPDATE supplier_score_records_table SET change_date = ?1, changed_by = ?2 WHERE id = ?3",
+                rusqlite::params![now, user_name, record_id],
+            )
+            .map_err(|e| format!("Erro ao atualizar registro {}: {}", record_id, e))?;
+        }
+        score_engine::recalculate_record(conn, record_id)
+    }
+
+    /// Configuração vigente do cálculo de OTIF
+    pub fn get_otif_config() -> Result<OtifConfig, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        deliveries::load_config(conn)
+    }
+
+    /// Atualiza tolerâncias e regra de quantidade parcial do OTIF (admin)
+    pub fn update_otif_config(config: OtifConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        deliveries::validate_config(&config)?;
+
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let old_config = Self::get_setting(conn, deliveries::OTIF_CONFIG_SETTING)?;
+        let new_config = serde_json::to_string(&config)
+            .map_err(|e| format!("Erro ao serializar configuração de OTIF: {}", e))?;
+
+        Self::set_setting(conn, deliveries::OTIF_CONFIG_SETTING, &new_config, &admin_name)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "OTIF Config",
+            None,
+            None,
+            old_config.as_deref(),
+            Some(&new_config),
+        )?;
+
+        Ok(())
+    }
+
+    /// Importa linhas de entrega (CSV/XLSX do ERP) ligadas ao fornecedor pelo supplier_po
+    pub fn import_delivery_lines(file_content: Vec<u8>, file_name: String, user_name: String, user_wwid: String) -> Result<DeliveryImportSummary, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let summary = deliveries::import_lines(&tx, &file_name, &file_content, &user_name)?;
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Import",
+            &user_wwid,
+            "Delivery Lines",
+            None,
+            None,
+            None,
+            Some(&format!(
+                "{}: {} novas, {} atualizadas, {} sem fornecedor, {} erros",
+                file_name, summary.imported, summary.updated, summary.unmatched, summary.errors.len()
+            )),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar importação: {}", e))?;
+
+        Ok(summary)
+    }
+
+    /// Linhas de entrega do fornecedor com data prometida no mês
+    pub fn get_delivery_lines(supplier_id: String, month: i32, year: i32) -> Result<Vec<DeliveryLine>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        deliveries::load_lines(conn, &supplier_id, month, year)
+    }
+
+    /// Prévia do OTIF do fornecedor no mês com a configuração vigente (nada é gravado)
+    pub fn calculate_otif(supplier_id: String, month: i32, year: i32) -> Result<OtifEvidence, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let config = deliveries::load_config(conn)?;
+        deliveries::calculate(conn, &supplier_id, month, year, &config)
+    }
+
+    /// Grava a nota de OTIF do mês calculada das linhas de entrega (todos os fornecedores com
+    /// entregas no mês ou só os informados), com a evidência de cada cálculo
+    pub fn apply_otif_scores(
+        month: i32,
+        year: i32,
+        supplier_ids: Option<Vec<String>>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<Vec<OtifEvidence>, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        periods::ensure_open(&tx, month, year)?;
+        let definition = criteria::find_definition(&tx, "otif")?;
+        if !definition.is_active {
+            return Err(format!("Critério inativo: {}", definition.display_name));
+        }
+
+        let config = deliveries::load_config(&tx)?;
+        let suppliers = match supplier_ids.filter(|ids| !ids.is_empty()) {
+            Some(ids) => ids,
+            None => deliveries::suppliers_with_lines(&tx, month, year)?,
+        };
+
+        let mut results = Vec::new();
+        for supplier_id in suppliers {
+            let mut evidence = deliveries::calculate(&tx, &supplier_id, month, year, &config)?;
+            let Some(score) = evidence.score else {
+                continue;
+            };
+            let otif_evidence = ScoreEvidence {
+                numerator: evidence.otif_pct.map(|pct| (pct / 100.0 * evidence.counted_lines as f64 * 100.0).round() / 100.0),
+                denominator: Some(evidence.counted_lines as f64),
+                numerator_label: Some("linhas OTIF".to_string()),
+                denominator_label: Some("linhas avaliadas".to_string()),
+                note: Some(format!("{} no prazo, {} completas", evidence.on_time_lines, evidence.in_full_lines)),
+                ..Default::default()
+            };
+            Self::write_derived_score(&tx, &supplier_id, month, year, &definition.criterion_key, score, Some(otif_evidence), &user_name, &user_wwid)?;
+            deliveries::save_evidence(&tx, &evidence, &user_name)?;
+            evidence.lines.clear();
+            results.push(evidence);
+        }
+
+        if !results.is_empty() {
+            Self::insert_log(
+                &tx,
+                &user_name,
+                "Calculate",
+                &user_wwid,
+                &definition.display_name,
+                Some("ALL"),
+                Some(&format!("{}/{}", month, year)),
+                None,
+                Some(&format!("{} suppliers - OTIF calculado das linhas de entrega", results.len())),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar cálculo de OTIF: {}", e))?;
+
+        Ok(results)
+    }
+
+    /// Evidência do OTIF gravado para o fornecedor no mês (None se a nota não veio das entregas)
+    pub fn get_otif_evidence(supplier_id: String, month: i32, year: i32) -> Result<Option<OtifEvidence>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        deliveries::load_evidence(conn, &supplier_id, month, year)
+    }
+
+    /// Recalcula a nota NIL do fornecedor no mês a partir dos incidentes (ignorado se o critério NIL estiver inativo)
+    fn refresh_nil_score(conn: &Connection, supplier_id: &str, month: i32, year: i32, rules: &NilRules, user_name: &str, user_wwid: &str) -> Result<Option<NilEvidence>, String> {
+        periods::ensure_open(conn, month, year)?;
+        let definition = criteria::find_definition(conn, "nil")?;
+        if !definition.is_active {
+            return Ok(None);
+        }
+
+        let evidence = incidents::evaluate(conn, supplier_id, month, year, rules)?;
+        let nil_evidence = ScoreEvidence {
+            numerator: Some(evidence.incident_count as f64),
+            numerator_label: Some("incidentes".to_string()),
+            note: Some(format!("dedução {:.2}", evidence.deduction)),
+            ..Default::default()
+        };
+        Self::write_derived_score(conn, supplier_id, month, year, &definition.criterion_key, evidence.score, Some(nil_evidence), user_name, user_wwid)?;
+        Ok(Some(evidence))
+    }
+
+    /// Regras vigentes do cálculo do NIL
+    pub fn get_nil_rules() -> Result<NilRules, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        incidents::load_rules(conn)
+    }
+
+    /// Atualiza as regras do cálculo do NIL (admin); as notas mudam no próximo apply_nil_scores
+    pub fn update_nil_rules(rules: NilRules, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        incidents::validate_rules(&rules)?;
+
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let old_rules = Self::get_setting(conn, incidents::NIL_RULES_SETTING)?;
+        let new_rules = serde_json::to_string(&rules)
+            .map_err(|e| format!("Erro ao serializar regras de NIL: {}", e))?;
+
+        Self::set_setting(conn, incidents::NIL_RULES_SETTING, &new_rules, &admin_name)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "NIL Rules",
+            None,
+            None,
+            old_rules.as_deref(),
+            Some(&new_rules),
+        )?;
+
+        Ok(())
+    }
+
+    /// Lista incidentes NIL (filtro opcional por fornecedor e mês/ano)
+    pub fn get_nil_incidents(supplier_id: Option<String>, month: Option<i32>, year: Option<i32>) -> Result<Vec<NilIncident>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let period = match (month, year) {
+            (Some(m), Some(y)) => Some(criteria::period_key(m, y)),
+            _ => None,
+        };
+        let supplier_id = supplier_id.filter(|s| !s.trim().is_empty());
+        incidents::load(conn, supplier_id.as_deref(), period.as_deref())
+    }
+
+    /// Cria (incident_id = 0) ou atualiza um incidente e recalcula a nota NIL dos meses afetados
+    pub fn save_nil_incident(incident: NilIncident, user_name: String, user_wwid: String) -> Result<i64, String> {
+        let mut incident = incident;
+        let (month, year) = incidents::normalize(&mut incident)?;
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let old_incident = if incident.incident_id > 0 {
+            Some(incidents::find(&tx, incident.incident_id)?.ok_or_else(|| format!("Incidente {} não encontrado", incident.incident_id))?)
+        } else {
+            None
+        };
+
+        let incident_id = incidents::save(&tx, &incident, &user_name)?;
+
+        let rules = incidents::load_rules(&tx)?;
+        let mut affected = vec![(incident.supplier_id.clone(), month, year)];
+        if let Some(old) = &old_incident {
+            let mut old = old.clone();
+            let old_period = incidents::normalize(&mut old)?;
+            let key = (old.supplier_id.clone(), old_period.0, old_period.1);
+            if !affected.contains(&key) {
+                affected.push(key);
+            }
+        }
+        for (supplier_id, m, y) in &affected {
+            Self::refresh_nil_score(&tx, supplier_id, *m, *y, &rules, &user_name, &user_wwid)?;
+        }
+
+        let describe = |i: &NilIncident| {
+            format!("{} {} {} qtd {:?}: {}", i.incident_date, i.severity, i.part_number.as_deref().unwrap_or("-"), i.quantity, i.description.as_deref().unwrap_or(""))
+        };
+        Self::insert_log(
+            &tx,
+            &user_name,
+            if old_incident.is_some() { "Update" } else { "Create" },
+            &user_wwid,
+            "NIL Incidents",
+            Some(&incident.supplier_id),
+            Some(&format!("{}/{}", month, year)),
+            old_incident.as_ref().map(describe).as_deref(),
+            Some(&describe(&incident)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar incidente: {}", e))?;
+
+        Ok(incident_id)
+    }
+
+    /// Exclui um incidente e recalcula a nota NIL do mês
+    pub fn delete_nil_incident(incident_id: i64, user_name: String, user_wwid: String) -> Result<(), String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let mut incident = incidents::find(&tx, incident_id)?
+            .ok_or_else(|| format!("Incidente {} não encontrado", incident_id))?;
+        let (month, year) = incidents::normalize(&mut incident)?;
+
+        incidents::delete(&tx, incident_id)?;
+        let rules = incidents::load_rules(&tx)?;
+        Self::refresh_nil_score(&tx, &incident.supplier_id, month, year, &rules, &user_name, &user_wwid)?;
+
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Delete",
+            &user_wwid,
+            "NIL Incidents",
+            Some(&incident.supplier_id),
+            Some(&format!("{}/{}", month, year)),
+            Some(&format!("{} {} {}", incident.incident_date, incident.severity, incident.description.as_deref().unwrap_or(""))),
+            None,
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar exclusão do incidente: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Importa incidentes NIL de Excel/CSV e recalcula a nota dos meses afetados
+    pub fn import_nil_incidents(file_content: Vec<u8>, file_name: String, user_name: String, user_wwid: String) -> Result<NilImportSummary, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let summary = incidents::import(&tx, &file_name, &file_content, &user_name)?;
+        let rules = incidents::load_rules(&tx)?;
+        for (supplier_id, month, year) in &summary.affected {
+            Self::refresh_nil_score(&tx, supplier_id, *month, *year, &rules, &user_name, &user_wwid)?;
+        }
+
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Import",
+            &user_wwid,
+            "NIL Incidents",
+            None,
+            None,
+            None,
+            Some(&format!(
+                "{}: {} incidentes, {} fornecedor/mês recalculados, {} erros",
+                file_name, summary.imported, summary.affected.len(), summary.errors.len()
+            )),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar importação de incidentes: {}", e))?;
+
+        Ok(summary)
+    }
+
+    /// Recalcula a nota NIL do mês pelas regras: fornecedores informados ou todos com
+    /// registro de nota ou incidente no mês (sem incidente = nota base)
+    pub fn apply_nil_scores(
+        month: i32,
+        year: i32,
+        supplier_ids: Option<Vec<String>>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<Vec<NilEvidence>, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let suppliers = match supplier_ids.filter(|ids| !ids.is_empty()) {
+            Some(ids) => ids,
+            None => {
+                let mut stmt = tx
+                    .prepare(
+                        "SELECT supplier_id FROM supplier_score_records_table WHERE month = ?1 AND year = ?2
+                         UNION
+                         SELECT supplier_id FROM nil_incident_table WHERE substr(incident_date, 1, 7) = ?3",
+                    )
+                    .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+                let ids = stmt
+                    .query_map(rusqlite::params![month.to_string(), year.to_string(), criteria::period_key(month, year)], |row| row.get::<_, String>(0))
+                    .map_err(|e| format!("Erro ao buscar fornecedores: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Erro ao coletar fornecedores: {}", e))?;
+                ids
+            }
+        };
+
+        let rules = incidents::load_rules(&tx)?;
+        let mut results = Vec::new();
+        for supplier_id in suppliers {
+            if let Some(mut evidence) = Self::refresh_nil_score(&tx, &supplier_id, month, year, &rules, &user_name, &user_wwid)? {
+                evidence.incidents.clear();
+                results.push(evidence);
+            }
+        }
+
+        if !results.is_empty() {
+            Self::insert_log(
+                &tx,
+                &user_name,
+                "Calculate",
+                &user_wwid,
+                "NIL",
+                Some("ALL"),
+                Some(&format!("{}/{}", month, year)),
+                None,
+                Some(&format!("{} suppliers - NIL calculado dos incidentes", results.len())),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar cálculo de NIL: {}", e))?;
+
+        Ok(results)
+    }
+
+    /// Nota NIL calculada e os incidentes do mês (evidência do registro)
+    pub fn get_nil_evidence(supplier_id: String, month: i32, year: i32) -> Result<NilEvidence, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let rules = incidents::load_rules(conn)?;
+        incidents::evaluate(conn, &supplier_id, month, year, &rules)
+    }
+
+    /// Recalcula a nota mensal do critério do checklist pela média das inspeções do mês;
+    /// sem inspeções a nota derivada é removida do registro existente
+    fn refresh_inspection_score(conn: &Connection, supplier_id: &str, criterion_key: &str, month: i32, year: i32, user_name: &str, user_wwid: &str) -> Result<Option<f64>, String> {
+        periods::ensure_open(conn, month, year)?;
+        let definition = criteria::find_definition(conn, criterion_key)?;
+        if !definition.is_active {
+            return Ok(None);
+        }
+
+        let (average, count) = inspections::monthly_score(conn, supplier_id, &definition.criterion_key, month, year)?;
+        match average {
+            Some(score) => {
+                let inspection_evidence = ScoreEvidence {
+                    numerator: Some(count as f64),
+                    numerator_label: Some("cargas inspecionadas".to_string()),
+                    note: Some("média das inspeções do mês".to_string()),
+                    ..Default::default()
+                };
+                Self::write_derived_score(conn, supplier_id, month, year, &definition.criterion_key, score, Some(inspection_evidence), user_name, user_wwid)?;
+            }
+            None => {
+                let record_id: Option<i64> = conn
+                    .query_row(
+                        "SELECT id FROM supplier_score_records_table
+                         WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+                        rusqlite::params![supplier_id, month.to_string(), year.to_string()],
+                        |row| row.get(0),
+                    )
+                    .optional()
+                    .map_err(|e| format!("Erro ao buscar registro de {}: {}", supplier_id, e))?;
+                if let Some(record_id) = record_id {
+                    criteria::write_value(conn, record_id, &definition.criterion_key, "")?;
+                    carryover::clear(conn, record_id, &definition.criterion_key)?;
+                    evidence::write(conn, record_id, &ScoreEvidence { criterion_key: definition.criterion_key.clone(), ..Default::default() }, user_name)?;
+                    workflow::mark_edited(conn, record_id, user_name, Some(user_wwid))?;
+                    score_engine::recalculate_record(conn, record_id)?;
+                }
+            }
+        }
+        Ok(average)
+    }
+
+    /// Lista os checklists de inspeção com seus itens
+    pub fn get_inspection_checklists(include_inactive: bool) -> Result<Vec<InspectionChecklist>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        inspections::load_checklists(conn, include_inactive)
+    }
+
+    /// Cria (checklist_id = 0) ou atualiza um checklist e seus itens (admin)
+    pub fn save_inspection_checklist(checklist: InspectionChecklist, admin_name: String, admin_wwid: String) -> Result<i64, String> {
+        let mut checklist = checklist;
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        checklist.criterion_key = criteria::find_definition(&tx, &checklist.criterion_key)?.criterion_key;
+        let old_checklist = if checklist.checklist_id > 0 {
+            Some(inspections::find_checklist(&tx, checklist.checklist_id)?)
+        } else {
+            None
+        };
+
+        let checklist_id = inspections::save_checklist(&tx, &checklist, &admin_name)?;
+
+        let describe = |c: &InspectionChecklist| {
+            let items = c
+                .items
+                .iter()
+                .filter(|i| i.is_active)
+                .map(|i| format!("{} (peso {}, {})", i.description, i.weight, i.answer_type))
+                .collect::<Vec<_>>()
+                .join("; ");
+            format!("{} [{}] ativo={}: {}", c.name, c.criterion_key, c.is_active, items)
+        };
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            if old_checklist.is_some() { "Update" } else { "Create" },
+            &admin_wwid,
+            "Inspection Checklists",
+            None,
+            None,
+            old_checklist.as_ref().map(describe).as_deref(),
+            Some(&describe(&checklist)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar checklist: {}", e))?;
+
+        Ok(checklist_id)
+    }
+
+    /// Registra (inspection_id = 0) ou corrige uma inspeção de embarque e recalcula a nota mensal do critério
+    pub fn record_inspection(inspection: Inspection, user_name: String, user_wwid: String) -> Result<Inspection, String> {
+        let mut inspection = inspection;
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let (month, year) = inspections::prepare(&tx, &mut inspection)?;
+        periods::ensure_open(&tx, month, year)?;
+
+        let old_inspection = if inspection.inspection_id > 0 {
+            Some(inspections::find_inspection(&tx, inspection.inspection_id)?
+                .ok_or_else(|| format!("Inspeção {} não encontrada", inspection.inspection_id))?)
+        } else {
+            None
+        };
+
+        inspection.inspection_id = inspections::save_inspection(&tx, &inspection, &user_name)?;
+
+        let criterion_key = inspection.criterion_key.clone().unwrap_or_default();
+        let mut affected = vec![(inspection.supplier_id.clone(), criterion_key, month, year)];
+        if let Some(old) = &old_inspection {
+            if let Some(date) = tabular::parse_date(&old.inspection_date) {
+                use chrono::Datelike;
+                let key = (old.supplier_id.clone(), old.criterion_key.clone().unwrap_or_default(), date.month() as i32, date.year());
+                if !affected.contains(&key) {
+                    affected.push(key);
+                }
             }
-            Err(_) => {
-                // Insere novo registro
-                println!("➕ Criando novo registro");
-                
-                // Registra log de criação
-                let new_values = format!(
-                    "OTIF: {}, NIL: {}, Pickup: {}, Package: {}",
-                    otif_score.as_ref().unwrap_or(&"—".to_string()),
-                    nil_score.as_ref().unwrap_or(&"—".to_string()),
-                    pickup_score.as_ref().unwrap_or(&"—".to_string()),
-                    package_score.as_ref().unwrap_or(&"—".to_string())
-                );
-                
-                Self::insert_log(
-                    conn,
-                    &user_name,
-                    "Create",
-                    &user_wwid,
-                    "All Scores",
-                    Some(&supplier_info),
-                    Some(&score_date_str),
-                    None,
-                    Some(&new_values)
-                )?;
-                
-                conn.execute(
-                    "INSERT INTO supplier_score_records_table 
-                     (supplier_id, supplier_name, month, year, otif, nil, quality_pickup, quality_package, 
-                      total_score, comment, register_date, registered_by, change_date, changed_by)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
-                    rusqlite::params![
-                        supplier_id,
-                        supplier_name,
-                        month.to_string(),
-                        year.to_string(),
-                        otif_score.as_ref().and_then(|s| if s.trim().is_empty() { None } else { s.parse::<f64>().ok() }),
-                        nil_score.as_ref().and_then(|s| if s.trim().is_empty() { None } else { s.parse::<f64>().ok() }),
-                        pickup_score.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.clone()) }),
-                        package_score.as_ref().and_then(|s| if s.trim().is_empty() { None } else { Some(s.clone()) }),
-                        total_score_value,
-                        comments,
-                        now.clone(),
-                        user_name.clone(),
-                        now,
-                        user_name
-                    ],
-                ).map_err(|e| format!("Erro ao inserir: {}", e))?;
-                
-                println!("✅ Score criado com sucesso!");
-                                Ok("Score criado com sucesso".to_string())
+        }
+        for (supplier_id, criterion_key, m, y) in &affected {
+            Self::refresh_inspection_score(&tx, supplier_id, criterion_key, *m, *y, &user_name, &user_wwid)?;
+        }
+
+        let describe = |i: &Inspection| {
+            format!(
+                "{} embarque {} checklist {} nota {:.2}",
+                i.inspection_date,
+                i.shipment_ref.as_deref().unwrap_or("-"),
+                i.checklist_id,
+                i.score.unwrap_or_default()
+            )
+        };
+        Self::insert_log(
+            &tx,
+            &user_name,
+            if old_inspection.is_some() { "Update" } else { "Create" },
+            &user_wwid,
+            "Inspections",
+            Some(&inspection.supplier_id),
+            Some(&format!("{}/{}", month, year)),
+            old_inspection.as_ref().map(describe).as_deref(),
+            Some(&describe(&inspection)),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar inspeção: {}", e))?;
+
+        Ok(inspection)
+    }
+
+    /// Exclui uma inspeção e recalcula a nota mensal do critério
+    pub fn delete_inspection(inspection_id: i64, user_name: String, user_wwid: String) -> Result<(), String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let inspection = inspections::find_inspection(&tx, inspection_id)?
+            .ok_or_else(|| format!("Inspeção {} não encontrada", inspection_id))?;
+        let date = tabular::parse_date(&inspection.inspection_date)
+            .ok_or_else(|| format!("Data da inspeção inválida: {}", inspection.inspection_date))?;
+        use chrono::Datelike;
+        let (month, year) = (date.month() as i32, date.year());
+        periods::ensure_open(&tx, month, year)?;
+
+        inspections::delete_inspection(&tx, inspection_id)?;
+        let criterion_key = inspection.criterion_key.clone().unwrap_or_default();
+        Self::refresh_inspection_score(&tx, &inspection.supplier_id, &criterion_key, month, year, &user_name, &user_wwid)?;
+
+        Self::insert_log(
+            &tx,
+            &user_name,
+            "Delete",
+            &user_wwid,
+            "Inspections",
+            Some(&inspection.supplier_id),
+            Some(&format!("{}/{}", month, year)),
+            Some(&format!(
+                "{} embarque {} nota {:.2}",
+                inspection.inspection_date,
+                inspection.shipment_ref.as_deref().unwrap_or("-"),
+                inspection.score.unwrap_or_default()
+            )),
+            None,
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar exclusão da inspeção: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Histórico de inspeções com respostas (filtros opcionais; períodos inicial/final por mês/ano)
+    pub fn get_inspection_history(
+        supplier_id: Option<String>,
+        criterion_key: Option<String>,
+        start_month: Option<i32>,
+        start_year: Option<i32>,
+        end_month: Option<i32>,
+        end_year: Option<i32>,
+    ) -> Result<Vec<Inspection>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let criterion_key = match criterion_key.filter(|c| !c.trim().is_empty()) {
+            Some(key) => Some(criteria::find_definition(conn, &key)?.criterion_key),
+            None => None,
+        };
+        let from = match (start_month, start_year) {
+            (Some(m), Some(y)) => Some(criteria::period_key(m, y)),
+            _ => None,
+        };
+        let to = match (end_month, end_year) {
+            (Some(m), Some(y)) => Some(criteria::period_key(m, y)),
+            _ => None,
+        };
+        let supplier_id = supplier_id.filter(|s| !s.trim().is_empty());
+        inspections::load_history(conn, supplier_id.as_deref(), criterion_key.as_deref(), from.as_deref(), to.as_deref())
+    }
+
+    /// Recalcula as notas mensais de pickup/package (todos os critérios com checklist) pelas inspeções do mês
+    pub fn apply_inspection_scores(
+        month: i32,
+        year: i32,
+        supplier_ids: Option<Vec<String>>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<usize, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let pairs = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT DISTINCT supplier_id, criterion_key FROM inspection_table
+                     WHERE substr(inspection_date, 1, 7) = ?1 ORDER BY supplier_id, criterion_key",
+                )
+                .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+            let rows = stmt
+                .query_map([criteria::period_key(month, year)], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+                .map_err(|e| format!("Erro ao buscar inspeções: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Erro ao coletar inspeções: {}", e))?;
+            rows
+        };
+
+        let filter: Option<Vec<String>> = supplier_ids
+            .filter(|ids| !ids.is_empty())
+            .map(|ids| ids.iter().map(|id| id.trim().to_lowercase()).collect());
+        let mut updated = 0;
+        for (supplier_id, criterion_key) in pairs {
+            if let Some(ids) = &filter {
+                if !ids.contains(&supplier_id.trim().to_lowercase()) {
+                    continue;
+                }
+            }
+            if Self::refresh_inspection_score(&tx, &supplier_id, &criterion_key, month, year, &user_name, &user_wwid)?.is_some() {
+                updated += 1;
+            }
+        }
+
+        if updated > 0 {
+            Self::insert_log(
+                &tx,
+                &user_name,
+                "Calculate",
+                &user_wwid,
+                "Inspections",
+                Some("ALL"),
+                Some(&format!("{}/{}", month, year)),
+                None,
+                Some(&format!("{} notas calculadas das inspeções", updated)),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar cálculo das inspeções: {}", e))?;
+
+        Ok(updated)
+    }
+
+    /// Política vigente para critérios sem nota no cálculo do total
+    pub fn get_missing_value_policy() -> Result<score_engine::MissingValuePolicy, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        score_engine::load_policy(conn)
+    }
+
+    /// Troca a política de dados faltantes (admin) e recalcula os totais dos meses não fechados;
+    /// meses fechados mantêm o total com que foram fechados. Retorna quantos registros foram recalculados.
+    pub fn set_missing_value_policy(
+        policy: score_engine::MissingValuePolicy,
+        admin_name: String,
+        admin_wwid: String,
+    ) -> Result<usize, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let old_policy = score_engine::load_policy(&tx)?;
+        let serialize = |p: &score_engine::MissingValuePolicy| {
+            serde_json::to_string(p).map_err(|e| format!("Erro ao serializar política de dados faltantes: {}", e))
+        };
+        Self::set_setting(&tx, score_engine::MISSING_VALUE_POLICY_SETTING, &serialize(&policy)?, &admin_name)?;
+
+        let records: Vec<(i64, i32, i32, Option<String>)> = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT r.id, CAST(r.month AS INTEGER), CAST(r.year AS INTEGER), r.total_score
+                     FROM supplier_score_records_table r
+                     WHERE NOT EXISTS (
+                        SELECT 1 FROM period_status_table p
+                        WHERE p.month = CAST(r.month AS INTEGER) AND p.year = CAST(r.year AS INTEGER) AND p.status = ?1
+                     )",
+                )
+                .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+            let rows = stmt
+                .query_map([periods::STATUS_CLOSED], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+                .map_err(|e| format!("Erro ao buscar registros: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Erro ao coletar registros: {}", e))?;
+            rows
+        };
+
+        let mut weights_by_period: HashMap<(i32, i32), Vec<score_engine::CriterionWeight>> = HashMap::new();
+        for (record_id, month, year, old_total) in &records {
+            let weights = match weights_by_period.entry((*month, *year)) {
+                std::collections::hash_map::Entry::Occupied(e) => e.into_mut(),
+                std::collections::hash_map::Entry::Vacant(e) => e.insert(score_engine::load_weights(&tx, *month, *year)?),
+            };
+            let new_total = score_engine::recalculate_record_with_weights(&tx, *record_id, weights)?;
+            // Total alterado volta para revisão (aprovados já podem ter sido divulgados)
+            let parse = |total: &Option<String>| total.as_deref().and_then(score_engine::parse_score_text);
+            if carryover::value_changed(parse(old_total), parse(&new_total)) {
+                workflow::mark_edited(&tx, *record_id, &admin_name, Some(&admin_wwid))?;
             }
         }
+
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Missing Data Policy",
+            None,
+            None,
+            Some(&serialize(&old_policy)?),
+            Some(&format!("{} ({} totais recalculados)", serialize(&policy)?, records.len())),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar política de dados faltantes: {}", e))?;
+
+        Ok(records.len())
+    }
+
+    /// Regras de validação dos critérios ativos
+    pub fn get_validation_rules() -> Result<Vec<ValidationRule>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        validation::load_rules(conn)
+    }
+
+    /// Salva a regra de validação de um critério
+    pub fn save_validation_rule(rule: ValidationRule, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let mut rule = rule;
+        rule.criterion_key = criteria::find_definition(conn, &rule.criterion_key)?.criterion_key;
+        let old_rule = validation::load_rule(conn, &rule.criterion_key)?;
+        validation::save_rule(conn, &rule, &admin_name)?;
+
+        let describe = |r: &ValidationRule| {
+            format!(
+                "{}: {}–{}, {} casas, comentário abaixo do target: {}",
+                r.criterion_key, r.min_value, r.max_value, r.decimal_places, r.comment_required_below_target
+            )
+        };
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Validation",
+            None,
+            None,
+            Some(&describe(&old_rule)),
+            Some(&describe(&rule)),
+        )?;
+
+        Ok(())
+    }
+
+    /// Valida notas sem gravar (a tela mostra os erros por campo antes de salvar)
+    pub fn validate_scores(
+        supplier_id: String,
+        month: i32,
+        year: i32,
+        scores: HashMap<String, String>,
+        comment: Option<String>,
+        criteria_comments: Option<HashMap<String, String>>,
+        criteria_reasons: Option<HashMap<String, Vec<String>>>,
+    ) -> Result<ValidationReport, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let mut values = Vec::with_capacity(scores.len());
+        for (key, value) in scores {
+            values.push((criteria::find_definition(conn, &key)?.criterion_key, value));
+        }
+        let mut justification = validation::Justification { comment, ..Default::default() };
+        for (key, body) in criteria_comments.unwrap_or_default() {
+            justification.criterion_comments.insert(criteria::find_definition(conn, &key)?.criterion_key, body);
+        }
+        for (key, codes) in criteria_reasons.unwrap_or_default() {
+            justification.reason_codes.insert(criteria::find_definition(conn, &key)?.criterion_key, codes);
+        }
+        let scope = targets::load_supplier_scope(conn, &supplier_id)?;
+        validation::validate_scores(conn, &scope, month, year, &values, &justification)
+    }
+
+    /// Acrescenta um comentário (geral ou de um critério) à thread do registro
+    pub fn add_score_comment(
+        record_id: i64,
+        criterion_key: Option<String>,
+        body: String,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<ScoreComment, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let (supplier_id, month, year): (String, String, String) = conn
+            .query_row(
+                "SELECT supplier_id, month, year FROM supplier_score_records_table WHERE id = ?1",
+                [record_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar registro {}: {}", record_id, e))?
+            .ok_or_else(|| format!("Registro {} não encontrado", record_id))?;
+
+        let definition = match criterion_key.filter(|k| !k.trim().is_empty()) {
+            Some(key) => Some(criteria::find_definition(conn, &key)?),
+            None => None,
+        };
+        let key = definition.as_ref().map(|d| d.criterion_key.as_str());
+        let comment_id = comments::add(conn, record_id, key, &body, &user_name, Some(&user_wwid))?;
+
+        if definition.is_none() {
+            conn.execute(
+                "UPDATE supplier_score_records_table SET comment = ?1 WHERE id = ?2",
+                rusqlite::params![body.trim(), record_id],
+            )
+            .map_err(|e| format!("Erro ao atualizar comentário do registro: {}", e))?;
+        }
+
+        let place = match &definition {
+            Some(d) => format!("{} Comment", d.display_name),
+            None => "Comment".to_string(),
+        };
+        Self::insert_log(
+            conn,
+            &user_name,
+            "Create",
+            &user_wwid,
+            &place,
+            Some(&supplier_id),
+            Some(&format!("{}/{}", month, year)),
+            None,
+            Some(body.trim()),
+        )?;
+
+        comments::load_thread(conn, record_id)?
+            .into_iter()
+            .find(|c| c.comment_id == comment_id)
+            .ok_or_else(|| "Comentário não encontrado após gravar".to_string())
+    }
+
+    /// Thread de comentários de um registro, em ordem cronológica
+    pub fn get_score_comments(record_id: i64) -> Result<Vec<ScoreComment>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        comments::load_thread(conn, record_id)
+    }
+
+    /// Comentários de um fornecedor no ano (mês opcional), usados no email do scorecard
+    pub fn get_supplier_comments(supplier_id: String, year: i32, month: Option<i32>) -> Result<Vec<ScoreComment>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        comments::load_for_supplier(conn, &supplier_id, year, month)
+    }
+
+    /// Aplica uma ação do fluxo de revisão a vários registros em uma única transação
+    fn apply_workflow_action(
+        record_ids: Vec<i64>,
+        action: &str,
+        comment: Option<String>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<usize, String> {
+        if record_ids.is_empty() {
+            return Err("Nenhum registro informado".to_string());
+        }
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        for record_id in &record_ids {
+            let (old_status, new_status) = workflow::transition(&tx, *record_id, action, comment.as_deref(), &user_name, &user_wwid)?;
+
+            let (supplier_info, score_date) = tx
+                .query_row(
+                    "SELECT supplier_id, COALESCE(supplier_name, ''), month, year FROM supplier_score_records_table WHERE id = ?1",
+                    [record_id],
+                    |row| {
+                        Ok((
+                            format!("{} ({})", row.get::<_, String>(1)?, row.get::<_, String>(0)?),
+                            format!("{}/{}", row.get::<_, String>(2)?, row.get::<_, String>(3)?),
+                        ))
+                    },
+                )
+                .map_err(|e| format!("Erro ao buscar registro {}: {}", record_id, e))?;
+
+            let new_value = match comment.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+                Some(c) => format!("{} - {}", new_status, c),
+                None => new_status,
+            };
+            let event = match action {
+                workflow::ACTION_SUBMIT => "Submit",
+                workflow::ACTION_APPROVE => "Approve",
+                _ => "Reject",
+            };
+            Self::insert_log(
+                &tx,
+                &user_name,
+                event,
+                &user_wwid,
+                "Workflow",
+                Some(&supplier_info),
+                Some(&score_date),
+                Some(&old_status),
+                Some(&new_value),
+            )?;
+        }
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar {}: {}", action, e))?;
+
+        Ok(record_ids.len())
     }
 
-    /// Busca todos os critérios de avaliação
-    pub fn get_criteria() -> Result<Vec<Criteria>, String> {
-        println!("\n🔍 Buscando critérios de avaliação...");
+    /// Envia registros em rascunho (ou rejeitados) para revisão
+    pub fn submit_scores(record_ids: Vec<i64>, comment: Option<String>, user_name: String, user_wwid: String) -> Result<usize, String> {
+        Self::apply_workflow_action(record_ids, workflow::ACTION_SUBMIT, comment, user_name, user_wwid)
+    }
+
+    /// Aprova registros enviados (quem enviou não pode aprovar)
+    pub fn approve_scores(record_ids: Vec<i64>, comment: Option<String>, user_name: String, user_wwid: String) -> Result<usize, String> {
+        Self::apply_workflow_action(record_ids, workflow::ACTION_APPROVE, comment, user_name, user_wwid)
+    }
+
+    /// Rejeita registros enviados; o comentário é obrigatório
+    pub fn reject_scores(record_ids: Vec<i64>, comment: String, user_name: String, user_wwid: String) -> Result<usize, String> {
+        Self::apply_workflow_action(record_ids, workflow::ACTION_REJECT, Some(comment), user_name, user_wwid)
+    }
 
+    /// Histórico de envios, aprovações, rejeições e edições de um registro
+    pub fn get_score_workflow_history(record_id: i64) -> Result<Vec<WorkflowEvent>, String> {
         let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+        workflow::load_history(conn, record_id)
+    }
 
-        let conn = conn_guard.as_ref().unwrap();
+    /// Registros de um mês em um estado do fluxo (ex.: fila de aprovação com 'submitted')
+    pub fn get_scores_by_workflow_status(status: String, month: i32, year: i32) -> Result<Vec<serde_json::Value>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        // Busca TODOS os 5 registros
         let mut stmt = conn
             .prepare(
-                "SELECT criteria_id, criteria_category, value
-                 FROM criteria_table
-                 ORDER BY criteria_id"
+                "SELECT id, supplier_id, COALESCE(supplier_name, ''), total_score, comment, changed_by, change_date
+                 FROM supplier_score_records_table
+                 WHERE workflow_status = ?1 AND month = ?2 AND year = ?3
+                 ORDER BY supplier_name",
             )
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        let records = stmt
+            .query_map(rusqlite::params![status.trim().to_lowercase(), month.to_string(), year.to_string()], |row| {
+                Ok(serde_json::json!({
+                    "record_id": row.get::<_, i64>(0)?,
+                    "supplier_id": row.get::<_, String>(1)?,
+                    "supplier_name": row.get::<_, String>(2)?,
+                    "total_score": row.get::<_, Option<String>>(3)?,
+                    "comment": row.get::<_, Option<String>>(4)?,
+                    "changed_by": row.get::<_, Option<String>>(5)?,
+                    "change_date": row.get::<_, Option<String>>(6)?,
+                    "workflow_status": status.trim().to_lowercase(),
+                }))
+            })
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar registros: {}", e))?;
+        Ok(records)
+    }
 
-        let criteria_iter = stmt
-            .query_map([], |row| {
-                let criteria_id = row.get::<_, i32>(0)?;
-                let criteria_category = row.get::<_, String>(1)?;
-                let value_str = row.get::<_, String>(2)?;
-                let value = value_str.parse::<f64>().unwrap_or(0.0);
+    /// Lista os meses com situação registrada (meses ausentes estão abertos)
+    pub fn get_period_statuses(year: Option<i32>) -> Result<Vec<PeriodStatus>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-                println!("  📊 ID {}: '{}' = {}", criteria_id, criteria_category, value);
+        periods::load_statuses(conn, year)
+    }
 
-                // Determina se é target ou weight pelo nome da categoria
-                let is_target = criteria_category.to_lowercase().contains("target");
+    /// Histórico de fechamentos e reaberturas de um mês
+    pub fn get_period_history(month: i32, year: i32) -> Result<Vec<PeriodStatusEvent>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-                Ok(Criteria {
-                    criteria_id,
-                    criteria_target_id: if is_target { None } else { None }, // Não precisa de target_id
-                    criteria_name: criteria_category,
-                    criteria_weight: if is_target { 0.0 } else { value },
-                    criteria_target: if is_target { value } else { 0.0 },
-                })
-            })
-            .map_err(|e| format!("Erro ao executar query: {}", e))?;
+        periods::load_history(conn, month, year)
+    }
 
-        let result: Vec<Criteria> = criteria_iter
-            .filter_map(|r| r.ok())
-            .collect();
+    /// Fecha um mês: nenhuma nota do período pode mais ser alterada
+    pub fn close_period(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-        println!("✅ Encontrados {} critérios", result.len());
-        Ok(result)
+        if periods::status_for(conn, month, year)? == periods::STATUS_CLOSED {
+            return Err(format!("Período {:02}/{} já está fechado", month, year));
+        }
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+        let old_status = periods::set_status(&tx, month, year, periods::STATUS_CLOSED, None, &admin_name)?;
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            "Close",
+            &admin_wwid,
+            "Period",
+            None,
+            Some(&format!("{}/{}", month, year)),
+            Some(&old_status),
+            Some(periods::STATUS_CLOSED),
+        )?;
+        // Segmentação do mês fechado (histórico mensal de tiers)
+        tiering::calculate(&tx, month, year, &admin_name)?;
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar fechamento: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Reabre um mês fechado; o motivo é obrigatório e fica no histórico e nos logs
+    pub fn reopen_period(month: i32, year: i32, reason: String, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        let reason = reason.trim().to_string();
+        if reason.is_empty() {
+            return Err("Informe o motivo da reabertura".to_string());
+        }
+
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        if periods::status_for(conn, month, year)? != periods::STATUS_CLOSED {
+            return Err(format!("Período {:02}/{} não está fechado", month, year));
+        }
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+        let old_status = periods::set_status(&tx, month, year, periods::STATUS_REOPENED, Some(&reason), &admin_name)?;
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            "Reopen",
+            &admin_wwid,
+            "Period",
+            None,
+            Some(&format!("{}/{}", month, year)),
+            Some(&old_status),
+            Some(&format!("{} - Motivo: {}", periods::STATUS_REOPENED, reason)),
+        )?;
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar reabertura: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Lista os critérios de avaliação configurados
+    pub fn get_criteria_definitions(include_inactive: bool) -> Result<Vec<CriterionDefinition>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        criteria::load_definitions(conn, include_inactive)
+    }
+
+    /// Cria ou atualiza um critério de avaliação (critérios não são excluídos, apenas desativados)
+    pub fn save_criterion_definition(
+        mut definition: CriterionDefinition,
+        effective_from: Option<String>,
+        admin_name: String,
+        admin_wwid: String,
+    ) -> Result<CriterionDefinition, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        definition.display_name = definition.display_name.trim().to_string();
+        if definition.display_name.is_empty() {
+            return Err("Informe o nome do critério".to_string());
+        }
+        if !definition.weight.is_finite() || definition.weight < 0.0 {
+            return Err("O peso do critério deve ser um número maior ou igual a zero".to_string());
+        }
+
+        let key_source = if definition.criterion_key.trim().is_empty() {
+            definition.display_name.clone()
+        } else {
+            definition.criterion_key.clone()
+        };
+        definition.criterion_key = criteria::normalize_key(&key_source);
+        if definition.criterion_key.is_empty() {
+            return Err("Nome de critério inválido".to_string());
+        }
+
+        let existing: Option<String> = conn
+            .query_row(
+                "SELECT display_name FROM criteria_definition_table WHERE criterion_key = ?1",
+                [&definition.criterion_key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar critério: {}", e))?;
+
+        let name_in_use: i32 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM criteria_definition_table WHERE lower(display_name) = lower(?1) AND criterion_key != ?2",
+                rusqlite::params![definition.display_name, definition.criterion_key],
+                |row| row.get(0),
+            )
+            .map_err(|e| format!("Erro ao verificar nome do critério: {}", e))?;
+        if name_in_use > 0 {
+            return Err(format!("Já existe um critério com o nome {}", definition.display_name));
+        }
+
+        // O peso é versionado: vale a partir da vigência informada (padrão: mês corrente)
+        let effective_from = criteria::parse_effective_from(effective_from.as_deref())?;
+        let current_weight: Option<f64> = conn
+            .query_row(
+                "SELECT weight FROM criteria_definition_table WHERE criterion_key = ?1",
+                [&definition.criterion_key],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar peso atual: {}", e))?;
+        let current_active: Option<bool> = conn
+            .query_row(
+                "SELECT is_active FROM criteria_definition_table WHERE criterion_key = ?1",
+                [&definition.criterion_key],
+                |row| row.get::<_, i32>(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar ativação atual: {}", e))?
+            .map(|a| a == 1);
+        let requested_weight = definition.weight;
+        let requested_active = definition.is_active;
+        if effective_from > criteria::current_period() {
+            if let Some(weight) = current_weight {
+                definition.weight = weight;
+            }
+            if let Some(active) = current_active {
+                definition.is_active = active;
+            }
+        }
+
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let new_value = format!(
+            "{} | peso {} a partir de {} | ordem {} | {}",
+            definition.display_name,
+            requested_weight,
+            effective_from,
+            definition.sort_order,
+            if requested_active { "ativo" } else { "inativo" }
+        );
+
+        if existing.is_some() {
+            conn.execute(
+                "UPDATE criteria_definition_table SET display_name = ?1, weight = ?2, sort_order = ?3, is_active = ?4
+                 WHERE criterion_key = ?5",
+                rusqlite::params![
+                    definition.display_name,
+                    definition.weight,
+                    definition.sort_order,
+                    definition.is_active as i32,
+                    definition.criterion_key,
+                ],
+            )
+        } else {
+            conn.execute(
+                "INSERT INTO criteria_definition_table (criterion_key, display_name, weight, sort_order, is_active, created_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![
+                    definition.criterion_key,
+                    definition.display_name,
+                    definition.weight,
+                    definition.sort_order,
+                    definition.is_active as i32,
+                    now,
+                ],
+            )
+        }
+        .map_err(|e| format!("Erro ao salvar critério: {}", e))?;
+
+        if current_weight.map(|w| (w - requested_weight).abs() > f64::EPSILON).unwrap_or(true) {
+            criteria::record_version(conn, &definition.criterion_key, Some(requested_weight), None, &effective_from, &admin_name)?;
+        }
+        // A ativação também é versionada: o histórico continua calculado com os critérios de cada mês
+        if current_active != Some(requested_active) {
+            criteria::record_activation(conn, &definition.criterion_key, requested_active, &effective_from, &admin_name)?;
+        }
+
+        // Critérios originais: o peso também aparece na tela de critérios (criteria_table)
+        if criteria::builtin(&definition.criterion_key).is_some() {
+            let rows: Vec<(i32, String)> = {
+                let mut stmt = conn
+                    .prepare("SELECT criteria_id, criteria_category FROM criteria_table")
+                    .map_err(|e| format!("Erro ao buscar critérios: {}", e))?;
+                let rows = stmt
+                    .query_map([], |row| Ok((row.get::<_, i32>(0)?, row.get::<_, String>(1)?)))
+                    .map_err(|e| format!("Erro ao processar critérios: {}", e))?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("Erro ao coletar critérios: {}", e))?;
+                rows
+            };
+            for (criteria_id, category) in rows {
+                if criteria::builtin_key_for_category(&category) == Some(definition.criterion_key.as_str()) {
+                    conn.execute(
+                        "UPDATE criteria_table SET value = ?1 WHERE criteria_id = ?2",
+                        rusqlite::params![definition.weight.to_string(), criteria_id],
+                    )
+                    .map_err(|e| format!("Erro ao atualizar ID {}: {}", criteria_id, e))?;
+                }
+            }
+        }
+
+        Self::insert_log(
+            conn,
+            &admin_name,
+            if existing.is_some() { "Update" } else { "Create" },
+            &admin_wwid,
+            "Criteria",
+            None,
+            None,
+            existing.as_deref(),
+            Some(&new_value),
+        )?;
+
+        Ok(definition)
+    }
+
+    /// Permissões de edição de um usuário por critério ativo
+    pub fn get_user_criteria_permissions(user_id: i32) -> Result<BTreeMap<String, bool>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        criteria::load_user_permissions(conn, user_id as i64)
+    }
+
+    /// Atualiza as permissões de edição de um usuário por critério
+    pub fn update_user_criteria_permissions(
+        user_id: i32,
+        permissions: HashMap<String, bool>,
+        admin_name: String,
+        admin_wwid: String,
+    ) -> Result<(), String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let user_wwid: String = conn
+            .query_row("SELECT user_wwid FROM users_table WHERE user_id = ?1", [user_id], |row| row.get(0))
+            .optional()
+            .map_err(|e| format!("Erro ao buscar usuário: {}", e))?
+            .ok_or_else(|| "Usuário não encontrado".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let mut granted = Vec::new();
+        for (key, can_edit) in &permissions {
+            let definition = criteria::find_definition(&tx, key)?;
+            criteria::set_user_permission(&tx, user_id as i64, &definition.criterion_key, *can_edit)?;
+            if *can_edit {
+                granted.push(definition.display_name);
+            }
+        }
+        granted.sort();
+
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Criteria Permissions",
+            None,
+            None,
+            None,
+            Some(&format!("{}: {}", user_wwid, granted.join(", "))),
+        )?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar permissões: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Busca lista de planners
+    pub fn get_planners() -> Result<Vec<String>, String> {
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT DISTINCT name FROM planner_table WHERE name IS NOT NULL ORDER BY name")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(names)
+    }
+
+    /// Busca lista de continuity
+    pub fn get_continuity_options() -> Result<Vec<String>, String> {
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT DISTINCT name FROM continuity_table WHERE name IS NOT NULL ORDER BY name")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(names)
+    }
+
+    /// Busca lista de sourcing
+    pub fn get_sourcing_options() -> Result<Vec<String>, String> {
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT DISTINCT name FROM sourcing_table WHERE name IS NOT NULL ORDER BY name")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(names)
+    }
+
+    /// Busca lista de SQIE
+    pub fn get_sqie_options() -> Result<Vec<String>, String> {
+        let conn_guard = Self::get_connection()?;
+        if conn_guard.is_none() {
+            return Err("Conexão não inicializada".to_string());
+        }
+        let conn = conn_guard.as_ref().unwrap();
+        
+        let mut stmt = conn.prepare("SELECT DISTINCT name FROM sqie_table WHERE name IS NOT NULL ORDER BY name")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(names)
     }
 
-    /// Busca o valor do target configurado nos critérios
-    pub fn get_target() -> Result<f64, String> {
-        println!("\n🎯 Buscando target configurado...");
-
+    /// Busca lista de Business Units
+    pub fn get_business_units() -> Result<Vec<String>, String> {
         let conn_guard = Self::get_connection()?;
-
         if conn_guard.is_none() {
             return Err("Conexão não inicializada".to_string());
         }
-
         let conn = conn_guard.as_ref().unwrap();
-
-        // Busca o registro com "Target" no nome
-        let target = conn.query_row(
-            "SELECT value FROM criteria_table 
-             WHERE LOWER(criteria_category) LIKE '%target%'
-             LIMIT 1",
-            [],
-            |row| {
-                let value_str: String = row.get(0)?;
-                Ok(value_str.parse::<f64>().unwrap_or(8.7))
-            }
-        )
-        .unwrap_or(8.7);
-
-        println!("✅ Target encontrado: {}", target);
-        Ok(target)
+        
+        let mut stmt = conn.prepare("SELECT DISTINCT bu FROM business_unit_table WHERE bu IS NOT NULL ORDER BY bu")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(names)
     }
 
-    /// Busca fornecedores por nome ou ID
-    pub fn search_suppliers(query: String) -> Result<Vec<Supplier>, String> {
-        println!("\n🔍 ========================================");
-        println!("🔍 SEARCH_SUPPLIERS CHAMADO!");
-        println!("🔍 Query recebida: '{}'", query);
-        println!("🔍 Tamanho da query: {}", query.len());
-        println!("🔍 ========================================\n");
-        
+    /// Busca lista de Categorias
+    pub fn get_categories() -> Result<Vec<String>, String> {
         let conn_guard = Self::get_connection()?;
-        
         if conn_guard.is_none() {
-            println!("❌ Conexão não inicializada");
             return Err("Conexão não inicializada".to_string());
         }
-
         let conn = conn_guard.as_ref().unwrap();
         
-        // DEBUG: Mostra estrutura da tabela apenas na primeira busca
-        static FIRST_RUN: std::sync::Once = std::sync::Once::new();
-        FIRST_RUN.call_once(|| {
-            println!("\n📋 === ESTRUTURA DA TABELA supplier_database_table ===");
-            if let Ok(mut stmt) = conn.prepare("PRAGMA table_info(supplier_database_table)") {
-                if let Ok(columns) = stmt.query_map([], |row| {
-                    let name: String = row.get(1)?;
-                    let col_type: String = row.get(2)?;
-                    Ok((name, col_type))
-                }) {
-                    for col in columns.filter_map(|c| c.ok()) {
-                        println!("   📌 {} ({})", col.0, col.1);
-                    }
-                }
-            }
-            println!("=================================================\n");
-        });
+        let mut stmt = conn.prepare("SELECT DISTINCT category FROM categories_table WHERE category IS NOT NULL ORDER BY category")
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
         
-        let search_pattern = format!("%{}%", query);
-        println!("🔍 Padrão de busca: {}", search_pattern);
+        let names = stmt.query_map([], |row| row.get(0))
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<String>, _>>()
+            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
         
-        // Primeiro, testa se há registros na tabela
-        let total_count: i64 = conn
-            .query_row("SELECT COUNT(*) FROM supplier_database_table", [], |row| row.get(0))
-            .unwrap_or(0);
-        println!("📊 Total de registros na tabela: {}", total_count);
+        Ok(names)
+    }
+
+    // ========== LISTS MANAGEMENT ==========
+
+    /// Obter todos os itens de uma tabela com 3 campos (name, alias, email)
+    pub fn get_list_items_three_fields(table_name: &str) -> Result<Vec<ListItemThreeFields>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
         
-        // Testa quantos correspondem à busca
-        let match_count: i64 = conn
-            .query_row(
-                         "SELECT COUNT(*) FROM supplier_database_table 
-                          WHERE LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
-                              OR LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1)
-                              OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)",
-                [&search_pattern],
-                |row| row.get(0)
-            )
-            .unwrap_or(0);
-        println!("🔍 Registros que correspondem à busca: {}", match_count);
+        let query = format!("SELECT name, alias, email FROM {}", table_name);
         
-        let mut stmt = conn
-            .prepare(
-                     "SELECT DISTINCT 
-                          COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
-                          COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
-                          COALESCE(vendor_name, '') as vendor_name,
-                          bu,
-                          supplier_email,
-                          supplier_status,
-                          planner,
-                          country,
-                          supplier_category,
-                          continuity,
-                          sourcing,
-                          sqie,
-                          ssid
-                 FROM supplier_database_table 
-                          WHERE LOWER(COALESCE(CAST(supplier_po AS TEXT), '')) LIKE LOWER(?1) 
-                              OR LOWER(COALESCE(vendor_name, '')) LIKE LOWER(?1)
-                              OR LOWER(COALESCE(bu, '')) LIKE LOWER(?1)
-                 ORDER BY vendor_name
-                 LIMIT 50"
-            )
-            .map_err(|e| {
-                println!("❌ Erro ao preparar statement: {}", e);
-                format!("Erro ao preparar query: {}", e)
-            })?;
+        let mut stmt = conn.prepare(&query)
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let items = stmt.query_map([], |row| {
+            Ok(ListItemThreeFields {
+                name: row.get(0)?,
+                alias: row.get(1)?,
+                email: row.get(2)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao executar query: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        
+        Ok(items)
+    }
 
-        println!("📝 Statement preparado com sucesso");
+    /// Adicionar item em tabela com 3 campos
+    pub fn add_list_item_three_fields(table_name: &str, item: ListItemThreeFields) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("INSERT INTO {} (name, alias, email) VALUES (?1, ?2, ?3)", table_name);
+        
+        conn.execute(&query, rusqlite::params![item.name, item.alias, item.email])
+            .map_err(|e| format!("Erro ao inserir item: {}", e))?;
+        
+        Ok(())
+    }
 
-        let suppliers_result = stmt
-            .query_map([&search_pattern], |row| {
-                // Lê os campos na ordem do SELECT
-                let supplier_id_value: String = row.get(0)?;
-                let supplier_po_value: String = row.get(1)?;
-                let vendor_name_value: String = row.get(2)?;
-                let bu: Option<String> = row.get(3).ok();
-                let supplier_email: Option<String> = row.get(4).ok();
-                let supplier_status: Option<String> = row.get(5).ok();
-                let planner: Option<String> = row.get(6).ok();
-                let country: Option<String> = row.get(7).ok();
-                let supplier_category: Option<String> = row.get(8).ok();
-                let continuity: Option<String> = row.get(9).ok();
-                let sourcing: Option<String> = row.get(10).ok();
-                let sqie: Option<String> = row.get(11).ok();
-                let ssid: Option<String> = row.get(12).ok();
-                
-                Ok(Supplier {
-                    supplier_id: supplier_id_value,
-                    supplier_po: Some(supplier_po_value),
-                    supplier_name: vendor_name_value,
-                    bu,
-                    supplier_email,
-                    supplier_status,
-                    planner,
-                    country,
-                    supplier_category,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid,
-                    otif_target: None,
-                    nil_target: None,
-                    pickup_target: None,
-                    package_target: None,
-                    otif_score: None,
-                    nil_score: None,
-                    pickup_score: None,
-                    package_score: None,
-                    total_score: None,
-                })
-            })
-            .map_err(|e| {
-                println!("❌ Erro ao executar query_map: {}", e);
-                format!("Erro ao executar query: {}", e)
-            })?;
+    /// Atualizar item em tabela com 3 campos
+    pub fn update_list_item_three_fields(table_name: &str, old_name: &str, item: ListItemThreeFields) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("UPDATE {} SET name = ?1, alias = ?2, email = ?3 WHERE name = ?4", table_name);
+        
+        conn.execute(&query, rusqlite::params![item.name, item.alias, item.email, old_name])
+            .map_err(|e| format!("Erro ao atualizar item: {}", e))?;
+        
+        Ok(())
+    }
 
-        println!("🔄 Processando resultados...");
+    /// Deletar item em tabela com 3 campos
+    pub fn delete_list_item_three_fields(table_name: &str, name: &str) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("DELETE FROM {} WHERE name = ?1", table_name);
+        
+        conn.execute(&query, rusqlite::params![name])
+            .map_err(|e| format!("Erro ao deletar item: {}", e))?;
+        
+        Ok(())
+    }
+
+    /// Obter todos os itens de uma tabela com 1 campo (name)
+    pub fn get_list_items_single_field(table_name: &str, field_name: &str) -> Result<Vec<ListItemSingleField>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("SELECT {} FROM {}", field_name, table_name);
+        
+        let mut stmt = conn.prepare(&query)
+            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        
+        let items = stmt.query_map([], |row| {
+            Ok(ListItemSingleField {
+                name: row.get(0)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao executar query: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
         
-        let mut suppliers = Vec::new();
-        for (idx, result) in suppliers_result.enumerate() {
-            println!("🔄 Processando resultado #{}", idx + 1);
-            match result {
-                Ok(supplier) => {
-                    println!("✅ Fornecedor {}: ID='{}', PO={:?}, Nome='{}'", 
-                        idx + 1, 
-                        supplier.supplier_id, 
-                        supplier.supplier_po,
-                        supplier.supplier_name
-                    );
-                    if !supplier.supplier_name.is_empty() {
-                        suppliers.push(supplier);
-                    } else {
-                        println!("⚠️ Fornecedor {} tem nome vazio, ignorando", idx + 1);
-                    }
-                }
-                Err(e) => {
-                    println!("❌ Erro ao processar fornecedor {}: {}", idx + 1, e);
-                }
-            }
-        }
+        Ok(items)
+    }
 
-        println!("\n✅ ========================================");
-        println!("✅ Total de fornecedores retornados: {}", suppliers.len());
-        println!("✅ ========================================\n");
-        Ok(suppliers)
+    /// Adicionar item em tabela com 1 campo
+    pub fn add_list_item_single_field(table_name: &str, field_name: &str, name: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("INSERT INTO {} ({}) VALUES (?1)", table_name, field_name);
+        
+        conn.execute(&query, rusqlite::params![name])
+            .map_err(|e| format!("Erro ao inserir item: {}", e))?;
+        
+        Ok(())
     }
 
-    /// Obtém todos os fornecedores por status (Active ou Active + Inactive)
-    pub fn get_all_suppliers_by_status(include_inactive: bool) -> Result<Vec<Supplier>, String> {
-        println!("\n🔍 ========================================");
-        println!("🔍 GET_ALL_SUPPLIERS_BY_STATUS CHAMADO!");
-        println!("🔍 Include Inactive: {}", include_inactive);
-        println!("🔍 ========================================\n");
+    /// Atualizar item em tabela com 1 campo
+    pub fn update_list_item_single_field(table_name: &str, field_name: &str, old_name: &str, new_name: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        let query = format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table_name, field_name, field_name);
         
+        conn.execute(&query, rusqlite::params![new_name, old_name])
+            .map_err(|e| format!("Erro ao atualizar item: {}", e))?;
+        
+        Ok(())
+    }
+
+    /// Deletar item em tabela com 1 campo
+    pub fn delete_list_item_single_field(table_name: &str, field_name: &str, name: &str) -> Result<(), String> {
         let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
         
-        if conn_guard.is_none() {
-            println!("❌ Conexão não inicializada");
-            return Err("Conexão não inicializada".to_string());
+        let query = format!("DELETE FROM {} WHERE {} = ?1", table_name, field_name);
+        
+        conn.execute(&query, rusqlite::params![name])
+            .map_err(|e| format!("Erro ao deletar item: {}", e))?;
+        
+        Ok(())
+    }
+
+    /// Obter os códigos de motivo (taxonomia de notas baixas)
+    pub fn get_reason_codes(include_inactive: bool) -> Result<Vec<ReasonCode>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        reasons::load_codes(conn, include_inactive)
+    }
+
+    /// Adicionar código de motivo
+    pub fn add_reason_code(item: ReasonCode) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        let mut item = item;
+        item.criterion_key = Self::resolve_reason_criterion(conn, item.criterion_key.take())?;
+        reasons::add_code(conn, &item)
+    }
+
+    /// Atualizar código de motivo (renomear propaga para as notas que já o usam)
+    pub fn update_reason_code(old_code: &str, item: ReasonCode) -> Result<(), String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+
+        let mut item = item;
+        item.criterion_key = Self::resolve_reason_criterion(&tx, item.criterion_key.take())?;
+        reasons::update_code(&tx, old_code, &item)?;
+
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar código de motivo: {}", e))
+    }
+
+    /// Deletar código de motivo (só se nunca foi usado; caso contrário desative)
+    pub fn delete_reason_code(code: &str) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        reasons::delete_code(conn, code)
+    }
+
+    /// Critério do código de motivo pela chave ou nome; vazio = todos os critérios
+    fn resolve_reason_criterion(conn: &Connection, criterion_key: Option<String>) -> Result<Option<String>, String> {
+        match criterion_key.filter(|k| !k.trim().is_empty()) {
+            Some(key) => Ok(Some(criteria::find_definition(conn, &key)?.criterion_key)),
+            None => Ok(None),
         }
+    }
 
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let query = if include_inactive {
-            "SELECT DISTINCT 
-                COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
-                COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
-                COALESCE(vendor_name, '') as vendor_name,
-                bu,
-                supplier_email,
-                supplier_status,
-                planner,
-                country,
-                supplier_category,
-                continuity,
-                sourcing,
-                sqie,
-                ssid
-            FROM supplier_database_table 
-            WHERE LOWER(COALESCE(supplier_status, '')) IN ('active', 'inactive')
-            ORDER BY vendor_name"
-        } else {
-            "SELECT DISTINCT 
-                COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
-                COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
-                COALESCE(vendor_name, '') as vendor_name,
-                bu,
-                supplier_email,
-                supplier_status,
-                planner,
-                country,
-                supplier_category,
-                continuity,
-                sourcing,
-                sqie,
-                ssid
-            FROM supplier_database_table 
-            WHERE LOWER(COALESCE(supplier_status, '')) = 'active'
-            ORDER BY vendor_name"
+    /// Pareto dos códigos de motivo, com filtros opcionais de critério, BU, categoria, fornecedor e período
+    pub fn get_reason_pareto(
+        criterion_key: Option<String>,
+        bu: Option<String>,
+        category: Option<String>,
+        supplier_id: Option<String>,
+        start_month: Option<i32>,
+        start_year: Option<i32>,
+        end_month: Option<i32>,
+        end_year: Option<i32>,
+    ) -> Result<Vec<ReasonParetoEntry>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let non_empty = |value: Option<String>| value.filter(|v| !v.trim().is_empty());
+        let period = |month: Option<i32>, year: Option<i32>| match (month, year) {
+            (Some(m), Some(y)) => Some(criteria::period_key(m, y)),
+            _ => None,
+        };
+        let filter = ReasonParetoFilter {
+            criterion_key: Self::resolve_reason_criterion(conn, criterion_key)?,
+            bu: non_empty(bu),
+            category: non_empty(category),
+            supplier_id: non_empty(supplier_id),
+            from_period: period(start_month, start_year),
+            to_period: period(end_month, end_year),
         };
+        reasons::pareto(conn, &filter)
+    }
+
+    /// Início da janela de presença: quem tem last_seen depois disso está online
+    fn presence_window_start() -> String {
+        (chrono::Local::now() - chrono::Duration::seconds(PRESENCE_ONLINE_WINDOW_SECONDS))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string()
+    }
+
+    /// Atualiza o last_seen do usuário (e a tela atual, se informada)
+    fn touch_presence(conn: &Connection, user_id: i32, screen: Option<&str>, new_session: bool) -> Result<(), String> {
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        // Sessão nova se pedida explicitamente ou se o último heartbeat já expirou
+        conn.execute(
+            "INSERT INTO user_presence_table (user_id, last_seen, current_screen, session_started)
+             VALUES (?1, ?2, ?3, ?2)
+             ON CONFLICT(user_id) DO UPDATE SET
+                session_started = CASE
+                    WHEN ?4 = 1 OR user_presence_table.last_seen < ?5 THEN excluded.last_seen
+                    ELSE user_presence_table.session_started
+                END,
+                last_seen = excluded.last_seen,
+                current_screen = COALESCE(excluded.current_screen, user_presence_table.current_screen)",
+            rusqlite::params![user_id, now, screen, new_session as i32, Self::presence_window_start()],
+        ).map_err(|e| format!("Erro ao atualizar presença: {}", e))?;
+
+        Ok(())
+    }
+
+    /// Conta o número de usuários online (heartbeat dentro da janela de presença)
+    pub fn get_online_users_count() -> Result<i32, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
         
-        println!("📝 Executando query...");
+        let count: i32 = conn.query_row(
+            "SELECT COUNT(*) FROM user_presence_table p
+             INNER JOIN users_table u ON u.user_id = p.user_id
+             WHERE p.last_seen >= ?1",
+            [Self::presence_window_start()],
+            |row| row.get(0)
+        )
+        .map_err(|e| format!("Erro ao contar usuários online: {}", e))?;
         
-        let mut stmt = conn
-            .prepare(query)
-            .map_err(|e| {
-                println!("❌ Erro ao preparar statement: {}", e);
-                format!("Erro ao preparar query: {}", e)
-            })?;
+        Ok(count)
+    }
 
-        let suppliers_result = stmt
-            .query_map([], |row| {
-                let supplier_id_value: String = row.get(0)?;
-                let supplier_po_value: String = row.get(1)?;
-                let vendor_name_value: String = row.get(2)?;
-                let bu: Option<String> = row.get(3).ok();
-                let supplier_email: Option<String> = row.get(4).ok();
-                let supplier_status: Option<String> = row.get(5).ok();
-                let planner: Option<String> = row.get(6).ok();
-                let country: Option<String> = row.get(7).ok();
-                let supplier_category: Option<String> = row.get(8).ok();
-                let continuity: Option<String> = row.get(9).ok();
-                let sourcing: Option<String> = row.get(10).ok();
-                let sqie: Option<String> = row.get(11).ok();
-                let ssid: Option<String> = row.get(12).ok();
-                
-                Ok(Supplier {
-                    supplier_id: supplier_id_value,
-                    supplier_po: Some(supplier_po_value),
-                    supplier_name: vendor_name_value,
-                    bu,
-                    supplier_email,
-                    supplier_status,
-                    planner,
-                    country,
-                    supplier_category,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid,
-                    otif_target: None,
-                    nil_target: None,
-                    pickup_target: None,
-                    package_target: None,
-                    otif_score: None,
-                    nil_score: None,
-                    pickup_score: None,
-                    package_score: None,
-                    total_score: None,
+    /// Lista os usuários online e a tela em que cada um está
+    pub fn get_online_users() -> Result<Vec<OnlineUser>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        let mut stmt = conn.prepare(
+            "SELECT u.user_id, u.user_name, u.user_wwid, u.user_privilege, p.current_screen, p.last_seen, p.session_started
+             FROM user_presence_table p
+             INNER JOIN users_table u ON u.user_id = p.user_id
+             WHERE p.last_seen >= ?1
+             ORDER BY u.user_name"
+        )
+        .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+
+        let users = stmt
+            .query_map([Self::presence_window_start()], |row| {
+                Ok(OnlineUser {
+                    user_id: row.get(0)?,
+                    user_name: row.get(1)?,
+                    user_wwid: row.get(2)?,
+                    user_privilege: row.get(3)?,
+                    current_screen: row.get(4)?,
+                    last_seen: row.get(5)?,
+                    session_started: row.get(6)?,
                 })
             })
-            .map_err(|e| {
-                println!("❌ Erro ao executar query_map: {}", e);
-                format!("Erro ao executar query: {}", e)
-            })?;
+            .map_err(|e| format!("Erro ao buscar usuários online: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar usuários online: {}", e))?;
 
-        let mut suppliers = Vec::new();
-        for result in suppliers_result {
-            if let Ok(supplier) = result {
-                if !supplier.supplier_name.is_empty() {
-                    suppliers.push(supplier);
-                }
-            }
+        Ok(users)
+    }
+
+    /// Heartbeat do frontend: mantém o usuário online e registra a tela atual
+    pub fn record_heartbeat(user_id: i32, screen: Option<String>) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+
+        Self::touch_presence(conn, user_id, screen.as_deref(), false)
+    }
+
+    /// Atualiza o status online de um usuário (true = heartbeat, false = encerra a presença)
+    pub fn set_user_online_status(user_id: i32, is_online: bool) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        
+        if is_online {
+            return Self::touch_presence(conn, user_id, None, false);
         }
 
-        println!("\n✅ ========================================");
-        println!("✅ Total de fornecedores retornados: {}", suppliers.len());
-        println!("✅ ========================================\n");
-        Ok(suppliers)
+        conn.execute(
+            "DELETE FROM user_presence_table WHERE user_id = ?1",
+            [user_id]
+        )
+        .map_err(|e| format!("Erro ao atualizar status online: {}", e))?;
+        
+        Ok(())
     }
 
-    /// Obtém dados completos de um fornecedor
-    pub fn get_supplier(supplier_id: String) -> Result<Option<Supplier>, String> {
-        println!("\n📋 Buscando fornecedor: {}", supplier_id);
-        
+    /// Busca scores pendentes de avaliação para um usuário baseado em suas permissões
+    /// (visão agrupada por registro; a fila paginada com avaliadores é `get_pending_queue`)
+    pub fn get_pending_scores(user_id: i32) -> Result<Vec<serde_json::Value>, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        // PERMISSÕES DO USUÁRIO por critério ativo
+        let definitions = criteria::load_definitions(conn, false)?;
+        let permissions = criteria::load_user_permissions(conn, user_id as i64)?;
+        let editable: Vec<&CriterionDefinition> = definitions
+            .iter()
+            .filter(|d| permissions.get(&d.criterion_key).copied().unwrap_or(false))
+            .collect();
+
+        if editable.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let conn = conn_guard.as_ref().unwrap();
-        
+        // Busca registros com algum critério ativo sem nota (ou com nota copiada não confirmada)
+        // MAS que tenham pelo menos UMA nota (não considera registros com TODAS as notas vazias)
+        let query = "
+            SELECT 
+                r.id,
+                r.supplier_id,
+                COALESCE(r.supplier_name, s.vendor_name, '') AS supplier_name,
+                r.month,
+                r.year,
+                (SELECT COUNT(*) FROM supplier_score_values_table v
+                 JOIN criteria_definition_table d ON d.criterion_key = v.criterion_key AND d.is_active = 1
+                 WHERE v.record_id = r.id) AS filled_count
+            FROM supplier_score_records_table r
+            LEFT JOIN supplier_database_table s ON s.supplier_id = r.supplier_id
+            WHERE filled_count > 0
+            AND (filled_count < (SELECT COUNT(*) FROM criteria_definition_table WHERE is_active = 1)
+                 OR EXISTS (SELECT 1 FROM carried_over_score_table c WHERE c.record_id = r.id))
+            ORDER BY r.year DESC, r.month DESC, supplier_name
+        ";
+
         let mut stmt = conn
-            .prepare(
-                "SELECT DISTINCT 
-                    COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
-                    COALESCE(CAST(supplier_po AS TEXT), '') as supplier_po, 
-                    COALESCE(vendor_name, '') as vendor_name,
-                    bu,
-                    supplier_email,
-                    supplier_status,
-                    planner,
-                    country,
-                    supplier_category,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid
-                 FROM supplier_database_table 
-                 WHERE CAST(supplier_id AS TEXT) = ?1
-                 LIMIT 1"
-            )
+            .prepare(query)
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
-        let mut rows = stmt
-            .query_map([&supplier_id], |row| {
-                let supplier_id_value: String = row.get(0)?;
-                let supplier_po_value: String = row.get(1)?;
-                let vendor_name_value: String = row.get(2)?;
-                let bu: Option<String> = row.get(3).ok();
-                let supplier_email: Option<String> = row.get(4).ok();
-                let supplier_status: Option<String> = row.get(5).ok();
-                let planner: Option<String> = row.get(6).ok();
-                let country: Option<String> = row.get(7).ok();
-                let supplier_category: Option<String> = row.get(8).ok();
-                let continuity: Option<String> = row.get(9).ok();
-                let sourcing: Option<String> = row.get(10).ok();
-                let sqie: Option<String> = row.get(11).ok();
-                let ssid: Option<String> = row.get(12).ok();
-                
-                Ok(Supplier {
-                    supplier_id: supplier_id_value,
-                    supplier_po: Some(supplier_po_value),
-                    supplier_name: vendor_name_value,
-                    bu,
-                    supplier_email,
-                    supplier_status,
-                    planner,
-                    country,
-                    supplier_category,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid,
-                    otif_target: None,
-                    nil_target: None,
-                    pickup_target: None,
-                    package_target: None,
-                    otif_score: None,
-                    nil_score: None,
-                    pickup_score: None,
-                    package_score: None,
-                    total_score: None,
-                })
+        let candidates = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                ))
             })
-            .map_err(|e| format!("Erro ao executar query: {}", e))?;
+            .map_err(|e| format!("Erro ao executar query: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao processar registro pendente: {}", e))?;
 
-        let result = rows.next().transpose().map_err(|e| format!("Erro ao obter resultado: {}", e))?;
-        Ok(result)
-    }
+        let mut dismissed_stmt = conn
+            .prepare("SELECT score_type FROM pending_scores_override WHERE record_id = ?1")
+            .map_err(|e| format!("Erro ao preparar query de pendências: {}", e))?;
 
-    /// Atualiza dados do fornecedor
-    pub fn update_supplier(supplier: SupplierUpdate) -> Result<(), String> {
-        println!("\n💾 Atualizando fornecedor: {}", supplier.supplier_id);
-        let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        let has_vendor_name = Self::supplier_table_has_column(conn, "vendor_name")?;
-        let has_supplier_name = Self::supplier_table_has_column(conn, "supplier_name")?;
+        let deadline = pending::load_config(conn)?;
+        let today = chrono::Local::now().date_naive();
+        let mut resolver = TargetResolver::new(conn)?;
+        let mut results = Vec::new();
+        for (record_id, supplier_id, supplier_name, month, year) in candidates {
+            let values = criteria::read_values(conn, record_id as i64)?;
+            let carried = carryover::read(conn, record_id as i64)?;
+            let dismissed: Vec<String> = dismissed_stmt
+                .query_map([record_id], |row| row.get::<_, String>(0))
+                .map_err(|e| format!("Erro ao buscar pendências: {}", e))?
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("Erro ao coletar pendências: {}", e))?;
+
+            let pending_for_user: Vec<&CriterionDefinition> = editable
+                .iter()
+                .copied()
+                .filter(|d| {
+                    (!values.contains_key(&d.criterion_key) || carried.contains_key(&d.criterion_key))
+                        && !dismissed.contains(&d.criterion_key)
+                })
+                .collect();
 
-        let mut set_clauses: Vec<String> = Vec::new();
-        if has_vendor_name {
-            set_clauses.push("vendor_name = :supplier_name".to_string());
-        }
-        if has_supplier_name {
-            set_clauses.push("supplier_name = :supplier_name".to_string());
-        }
-        set_clauses.extend([
-            "supplier_po = :supplier_po",
-            "bu = :bu",
-            "supplier_email = :supplier_email",
-            "supplier_status = :supplier_status",
-            "planner = :planner",
-            "country = :country",
-            "supplier_category = :supplier_category",
-            "continuity = :continuity",
-            "sourcing = :sourcing",
-            "sqie = :sqie",
-            "ssid = :ssid",
-        ]
-        .iter()
-        .map(|s| s.to_string()));
+            if pending_for_user.is_empty() {
+                continue;
+            }
 
-        let sql = format!(
-            "UPDATE supplier_database_table SET {} WHERE COALESCE(CAST(supplier_id AS TEXT), '') = :supplier_id",
-            set_clauses.join(", ")
-        );
-        // Atualiza todos os campos do fornecedor
-        let rows_affected = conn
-            .execute(
-                &sql,
-                rusqlite::named_params! {
-                    ":supplier_id": &supplier.supplier_id,
-                    ":supplier_name": &supplier.supplier_name,
-                    ":supplier_po": &supplier.supplier_po,
-                    ":bu": &supplier.bu,
-                    ":supplier_email": &supplier.supplier_email,
-                    ":supplier_status": &supplier.supplier_status,
-                    ":planner": &supplier.planner,
-                    ":country": &supplier.country,
-                    ":supplier_category": &supplier.supplier_category,
-                    ":continuity": &supplier.continuity,
-                    ":sourcing": &supplier.sourcing,
-                    ":sqie": &supplier.sqie,
-                    ":ssid": &supplier.ssid,
+            // Targets efetivos do fornecedor no período do registro (com herança de categoria/BU)
+            let scope = targets::load_supplier_scope(conn, &supplier_id)?;
+            let period = criteria::period_key(month.trim().parse().unwrap_or(0), year.trim().parse().unwrap_or(0));
+            let mut pending_targets = serde_json::Map::new();
+            for definition in &pending_for_user {
+                let resolved = resolver.resolve(conn, &scope, &definition.criterion_key, &period)?;
+                pending_targets.insert(definition.criterion_key.clone(), serde_json::json!(resolved.target));
+            }
+            let mut below_target = Vec::new();
+            let due = cycles::deadline_for(conn, month.trim().parse().unwrap_or(0), year.trim().parse().unwrap_or(0), &deadline)?;
+            for definition in &editable {
+                if let Some(value) = values.get(&definition.criterion_key) {
+                    if *value < resolver.resolve(conn, &scope, &definition.criterion_key, &period)?.target {
+                        below_target.push(definition.criterion_key.clone());
+                    }
                 }
-            )
-            .map_err(|e| format!("Erro ao atualizar fornecedor: {}", e))?;
+            }
 
-        // Atualiza o nome do fornecedor em todos os registros de score relacionados
-        let update_score_sql = "UPDATE supplier_score_records_table SET supplier_name = ?1 WHERE supplier_id = ?2";
-        let score_rows_affected = conn
-            .execute(update_score_sql, rusqlite::params![&supplier.supplier_name, &supplier.supplier_id])
-            .map_err(|e| format!("Erro ao atualizar registros de score: {}", e))?;
-        println!("✅ {} registros de score atualizados", score_rows_affected);
+            results.push(serde_json::json!({
+                "record_id": record_id,
+                "supplier_id": supplier_id,
+                "supplier_name": supplier_name,
+                "month": month,
+                "year": year,
+                "pending_fields": pending_for_user.iter().map(|d| d.display_name.clone()).collect::<Vec<_>>(),
+                "pending_criteria": pending_for_user.iter().map(|d| d.criterion_key.clone()).collect::<Vec<_>>(),
+                "targets": pending_targets,
+                "below_target": below_target,
+                "carried_over": pending_for_user.iter().filter(|d| carried.contains_key(&d.criterion_key)).map(|d| d.criterion_key.clone()).collect::<Vec<_>>(),
+                "due_date": due.map(|d| d.format("%Y-%m-%d").to_string()),
+                "overdue": due.map(|d| today > d).unwrap_or(false),
+            }));
+        }
 
-        println!("✅ {} registros de fornecedor atualizados", rows_affected);
-        Ok(())
+        Ok(results)
     }
 
-    /// Exclui um fornecedor e seus registros de score
-    pub fn delete_supplier(supplier_id: String) -> Result<(), String> {
-        println!("\n🗑️ Excluindo fornecedor: {}", supplier_id);
-        let mut conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_mut().unwrap();
+    /// Fila de pendências paginada, com avaliador, prazo e atraso por critério
+    pub fn get_pending_queue(filter: PendingQueueFilter) -> Result<PendingQueuePage, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let tx = conn
-            .transaction()
-            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+        let today = chrono::Local::now().date_naive();
+        let items = pending::load_queue(conn, &filter, today)?;
+        Ok(pending::paginate(items, filter.page, filter.page_size))
+    }
 
-        tx.execute(
-            "DELETE FROM supplier_score_records_table WHERE supplier_id = ?1",
-            [&supplier_id],
-        )
-        .map_err(|e| format!("Erro ao excluir scores do fornecedor: {}", e))?;
+    /// Regras de atribuição de avaliadores
+    pub fn get_evaluator_assignments() -> Result<Vec<EvaluatorAssignment>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let has_legacy_table: bool = tx
-            .query_row(
-                "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='supplier_score'",
-                [],
-                |row| row.get::<_, i32>(0),
-            )
-            .map_err(|e| format!("Erro ao verificar tabela legacy: {}", e))? > 0;
+        pending::load_assignments(conn)
+    }
 
-        if has_legacy_table {
-            tx.execute(
-                "DELETE FROM supplier_score WHERE supplier_id = ?1",
-                [&supplier_id],
-            )
-            .map_err(|e| format!("Erro ao excluir scores legados do fornecedor: {}", e))?;
-        }
+    /// Cria ou atualiza uma regra de atribuição (admin); devolve o id
+    pub fn save_evaluator_assignment(mut assignment: EvaluatorAssignment, admin_name: String, admin_wwid: String) -> Result<i64, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-        tx.execute(
-            "DELETE FROM supplier_database_table WHERE COALESCE(CAST(supplier_id AS TEXT), '') = ?1",
-            [&supplier_id],
-        )
-        .map_err(|e| format!("Erro ao excluir fornecedor: {}", e))?;
+        pending::validate_assignment(conn, &mut assignment)?;
+        let old_value = if assignment.assignment_id > 0 {
+            Some(serde_json::to_string(&pending::find_assignment(conn, assignment.assignment_id)?)
+                .map_err(|e| format!("Erro ao serializar atribuição: {}", e))?)
+        } else {
+            None
+        };
 
-        tx.commit()
-            .map_err(|e| format!("Erro ao finalizar transação: {}", e))?;
+        let assignment_id = pending::save_assignment(conn, &assignment, &admin_name)?;
+        assignment.assignment_id = assignment_id;
+        let new_value = serde_json::to_string(&assignment)
+            .map_err(|e| format!("Erro ao serializar atribuição: {}", e))?;
 
-        println!("✅ Fornecedor excluído com sucesso!");
-        Ok(())
+        Self::insert_log(
+            conn,
+            &admin_name,
+            if old_value.is_some() { "Update" } else { "Create" },
+            &admin_wwid,
+            "Evaluator Assignment",
+            assignment.supplier_id.as_deref(),
+            None,
+            old_value.as_deref(),
+            Some(&new_value),
+        )?;
+
+        Ok(assignment_id)
     }
 
-    /// Cria um novo fornecedor
-    pub fn create_supplier(supplier: SupplierUpdate) -> Result<(), String> {
-        println!("\n💾 Criando novo fornecedor: {}", supplier.supplier_name);
-        println!("📋 Dados do fornecedor:");
-        println!("  - ID: {}", supplier.supplier_id);
-        println!("  - Nome: {}", supplier.supplier_name);
-        println!("  - PO: {:?}", supplier.supplier_po);
-        println!("  - País: {:?}", supplier.country);
-        
-        println!("🔌 Obtendo conexão com banco de dados...");
+    /// Remove uma regra de atribuição (admin)
+    pub fn delete_evaluator_assignment(assignment_id: i64, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let assignment = pending::find_assignment(conn, assignment_id)?;
+        let old_value = serde_json::to_string(&assignment)
+            .map_err(|e| format!("Erro ao serializar atribuição: {}", e))?;
+
+        pending::delete_assignment(conn, assignment_id)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Delete",
+            &admin_wwid,
+            "Evaluator Assignment",
+            assignment.supplier_id.as_deref(),
+            None,
+            Some(&old_value),
+            None,
+        )?;
+
+        Ok(())
+    }
+
+    /// Prazo de avaliação (dias após o fim do mês)
+    pub fn get_pending_deadline_config() -> Result<DeadlineConfig, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            println!("❌ Conexão não inicializada");
-            return Err("Conexão não inicializada".to_string());
-        }
-        println!("✅ Conexão obtida com sucesso");
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let conn = conn_guard.as_ref().unwrap();
-        
-        println!("📝 Executando INSERT...");
-        
-        // IMPORTANTE: 
-        // - supplier_id é INTEGER PRIMARY KEY (auto-incremento, não incluir no INSERT)
-        // - supplier_name, bu, supplier_category, supplier_email são NOT NULL (obrigatórios)
-        // - supplier_po é INTEGER (não TEXT)
-        // - vendor_name e supplier_name são colunas diferentes (ambas existem)
-        
-        // Validar campos obrigatórios
-        let supplier_name_val = if supplier.supplier_name.trim().is_empty() {
-            return Err("Nome do fornecedor é obrigatório".to_string());
-        } else {
-            &supplier.supplier_name
-        };
-        
-        let bu_val = supplier.bu.as_ref()
-            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
-            .unwrap_or("N/A");
-            
-        let category_val = supplier.supplier_category.as_ref()
-            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
-            .unwrap_or("N/A");
-            
-        let email_val = supplier.supplier_email.as_ref()
-            .and_then(|s| if s.trim().is_empty() { None } else { Some(s.as_str()) })
-            .unwrap_or("");
-        
-        // Converter supplier_po para INTEGER (se vazio, usa NULL)
-        let po_val: Option<i32> = supplier.supplier_po.as_ref()
-            .and_then(|s| s.trim().parse::<i32>().ok());
-        
-        println!("  ✓ Nome: {}", supplier_name_val);
-        println!("  ✓ BU: {}", bu_val);
-        println!("  ✓ Categoria: {}", category_val);
-        println!("  ✓ Email: {}", email_val);
-        println!("  ✓ PO: {:?}", po_val);
-        
-        let result = conn
-            .execute(
-                "INSERT INTO supplier_database_table (
-                    vendor_name,
-                    supplier_name,
-                    bu,
-                    supplier_category,
-                    supplier_email,
-                    supplier_po,
-                    supplier_status,
-                    planner,
-                    country,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
-                rusqlite::params![
-                    supplier_name_val,     // vendor_name
-                    supplier_name_val,     // supplier_name
-                    bu_val,                // bu (NOT NULL)
-                    category_val,          // supplier_category (NOT NULL)
-                    email_val,             // supplier_email (NOT NULL)
-                    po_val,                // supplier_po (INTEGER)
-                    supplier.supplier_status.as_deref().unwrap_or(""),
-                    supplier.planner.as_deref().unwrap_or(""),
-                    supplier.country.as_deref().unwrap_or(""),
-                    supplier.continuity.as_deref().unwrap_or(""),
-                    supplier.sourcing.as_deref().unwrap_or(""),
-                    supplier.sqie.as_deref().unwrap_or(""),
-                    supplier.ssid.as_deref().unwrap_or(""),
-                ]
-            )
-        .map_err(|e| {
-            println!("❌ Erro ao executar INSERT: {}", e);
-            format!("Erro ao criar fornecedor: {}", e)
-        })?;
+        pending::load_config(conn)
+    }
+
+    /// Atualiza o prazo de avaliação (admin)
+    pub fn update_pending_deadline_config(config: DeadlineConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        pending::validate_config(&config)?;
+
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let old_config = Self::get_setting(conn, pending::DEADLINE_SETTING)?;
+        let new_config = serde_json::to_string(&config)
+            .map_err(|e| format!("Erro ao serializar configuração de prazo: {}", e))?;
+
+        Self::set_setting(conn, pending::DEADLINE_SETTING, &new_config, &admin_name)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Pending Deadline Config",
+            None,
+            None,
+            old_config.as_deref(),
+            Some(&new_config),
+        )?;
 
-        println!("✅ INSERT executado. Linhas afetadas: {}", result);
-        println!("✅ Fornecedor criado com sucesso");
         Ok(())
     }
 
-    /// Verifica se um PO já existe (exceto para o fornecedor atual)
-    pub fn check_po_exists(po: &str, current_supplier_id: &str) -> Result<Option<Supplier>, String> {
+    /// Ciclos de avaliação cadastrados (opcionalmente de um ano)
+    pub fn get_evaluation_cycles(year: Option<i32>) -> Result<Vec<EvaluationCycle>, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn
-            .prepare(
-                "SELECT 
-                    COALESCE(CAST(supplier_id AS TEXT), '') as supplier_id,
-                    supplier_po,
-                    vendor_name,
-                    bu,
-                    supplier_email,
-                    supplier_status,
-                    planner,
-                    country,
-                    supplier_category,
-                    continuity,
-                    sourcing,
-                    sqie,
-                    ssid
-                FROM supplier_database_table
-                WHERE supplier_po = ?1 AND COALESCE(CAST(supplier_id AS TEXT), '') != ?2
-                LIMIT 1"
-            )
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        cycles::load_cycles(conn, year)
+    }
 
-        let result = stmt
-            .query_row(rusqlite::params![po, current_supplier_id], |row| {
-                Ok(Supplier {
-                    supplier_id: row.get(0)?,
-                    supplier_po: row.get(1)?,
-                    supplier_name: row.get(2)?,
-                    bu: row.get(3)?,
-                    supplier_email: row.get(4)?,
-                    supplier_status: row.get(5)?,
-                    planner: row.get(6)?,
-                    country: row.get(7)?,
-                    supplier_category: row.get(8)?,
-                    continuity: row.get(9)?,
-                    sourcing: row.get(10)?,
-                    sqie: row.get(11)?,
-                    ssid: row.get(12)?,
-                    otif_target: None,
-                    nil_target: None,
-                    pickup_target: None,
-                    package_target: None,
-                    otif_score: None,
-                    nil_score: None,
-                    pickup_score: None,
-                    package_score: None,
-                    total_score: None,
-                })
-            })
-            .optional()
-            .map_err(|e| format!("Erro ao verificar PO: {}", e))?;
+    /// Ciclo de um mês (cadastrado ou com as datas padrão)
+    pub fn get_evaluation_cycle(month: i32, year: i32) -> Result<EvaluationCycle, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        Ok(result)
+        let config = pending::load_config(conn)?;
+        cycles::load_cycle(conn, month, year, &config)
     }
 
-    /// Verifica o schema da tabela supplier_database_table
-    pub fn check_table_schema() -> Result<(), String> {
-        println!("\n🔍 Verificando schema da tabela supplier_database_table...");
-        
+    /// Cadastra ou altera as datas do ciclo de um mês (admin)
+    pub fn save_evaluation_cycle(cycle: EvaluationCycle, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        cycles::validate(&cycle)?;
+
         let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name='supplier_database_table'")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let schema: String = stmt.query_row([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao obter schema: {}", e))?;
-        
-        println!("📋 Schema atual da tabela:");
-        println!("{}", schema);
-        
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        let config = pending::load_config(conn)?;
+        let old_cycle = cycles::load_cycle(conn, cycle.month, cycle.year, &config)?;
+        cycles::save_cycle(conn, &cycle, &admin_name)?;
+        let new_cycle = cycles::load_cycle(conn, cycle.month, cycle.year, &config)?;
+
+        let describe = |c: &EvaluationCycle| {
+            format!("open {} / deadline {} / close {}", c.open_date, c.deadline, c.close_date.as_deref().unwrap_or("-"))
+        };
+        Self::insert_log(
+            conn,
+            &admin_name,
+            if old_cycle.is_custom { "Update" } else { "Create" },
+            &admin_wwid,
+            "Evaluation Cycle",
+            None,
+            Some(&new_cycle.period),
+            old_cycle.is_custom.then(|| describe(&old_cycle)).as_deref(),
+            Some(&describe(&new_cycle)),
+        )?;
+
         Ok(())
     }
 
-    /// Debug detalhado da estrutura da tabela supplier_database_table
-    pub fn debug_supplier_table() -> Result<String, String> {
-        println!("\n🔍 [DEBUG] Analisando tabela supplier_database_table...");
-        
+    /// Remove o ciclo cadastrado do mês, que volta às datas padrão (admin)
+    pub fn delete_evaluation_cycle(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
         let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut result = String::new();
-        
-        // 1. Pega o schema da tabela
-        result.push_str("=== SCHEMA DA TABELA ===\n");
-        let mut stmt = conn
-            .prepare("SELECT sql FROM sqlite_master WHERE type='table' AND name='supplier_database_table'")
-            .map_err(|e| format!("Erro ao obter schema: {}", e))?;
-        
-        let schema: String = stmt.query_row([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao ler schema: {}", e))?;
-        result.push_str(&format!("{}\n\n", schema));
-        
-        // 2. Pega informações detalhadas de cada coluna
-        result.push_str("=== COLUNAS (PRAGMA table_info) ===\n");
-        let mut stmt = conn
-            .prepare("PRAGMA table_info(supplier_database_table)")
-            .map_err(|e| format!("Erro ao obter colunas: {}", e))?;
-        
-        let mut rows = stmt.query([])
-            .map_err(|e| format!("Erro ao executar PRAGMA: {}", e))?;
-        
-        while let Some(row) = rows.next().map_err(|e| format!("Erro ao iterar: {}", e))? {
-            let cid: i32 = row.get(0).unwrap_or(-1);
-            let name: String = row.get(1).unwrap_or_else(|_| "?".to_string());
-            let col_type: String = row.get(2).unwrap_or_else(|_| "?".to_string());
-            let notnull: i32 = row.get(3).unwrap_or(0);
-            let dflt_value: Option<String> = row.get(4).ok();
-            let pk: i32 = row.get(5).unwrap_or(0);
-            
-            result.push_str(&format!(
-                "  [{}] {} | Tipo: {} | NOT NULL: {} | Default: {:?} | PK: {}\n",
-                cid, name, col_type, notnull, dflt_value, pk
-            ));
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
+
+        if !cycles::delete_cycle(conn, month, year)? {
+            return Err(format!("Não há ciclo cadastrado para {:02}/{}", month, year));
         }
-        
-        // 3. Conta quantos registros tem na tabela
-        result.push_str("\n=== ESTATÍSTICAS ===\n");
-        let count: i64 = conn.query_row(
-            "SELECT COUNT(*) FROM supplier_database_table",
-            [],
-            |row| row.get(0)
-        ).unwrap_or(0);
-        result.push_str(&format!("Total de registros: {}\n", count));
-        
-        println!("{}", result);
-        Ok(result)
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Delete",
+            &admin_wwid,
+            "Evaluation Cycle",
+            None,
+            Some(&criteria::period_key(month, year)),
+            None,
+            None,
+        )?;
+
+        Ok(())
     }
 
-    /// Atualiza os critérios de avaliação
-    pub fn update_criteria(criteria: Vec<Criteria>) -> Result<(), String> {
-        println!("\n💾 Atualizando critérios...");
-        
+    /// Painel de preenchimento do mês: por critério, BU e avaliador, com os fornecedores faltantes
+    pub fn get_cycle_status(month: i32, year: i32) -> Result<CycleStatus, String> {
         let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        cycles::cycle_status(conn, month, year, chrono::Local::now().date_naive())
+    }
+
+    /// Salva um score individual usando o ID do registro
+    pub fn save_individual_score(
+        record_id: i32,  // Mudei para usar o ID direto
+        score_type: String,  // chave do critério ("otif", "nil", "pickup", "package", ...)
+        score_value: String,
+        // Evidência opcional do critério (numerador/denominador)
+        score_evidence: Option<ScoreEvidence>,
+        // Comentário opcional do critério, acrescentado à thread do registro
+        score_comment: Option<String>,
+        // Códigos de motivo do critério (None mantém os atuais)
+        reason_codes: Option<Vec<String>>,
+        user_name: String,
+    ) -> Result<String, String> {
+        println!("\n💾 Salvando score individual...");
+        println!("  Record ID: {}", record_id);
+        println!("  Tipo: {}, Valor: {}", score_type, score_value);
         
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+        // Determina qual critério atualizar
+        let definition = criteria::find_definition(conn, &score_type)
+            .map_err(|_| format!("Tipo de score inválido: {}", score_type))?;
+        if !definition.is_active {
+            return Err(format!("Critério inativo: {}", definition.display_name));
+        }
+        let criterion_key = definition.criterion_key;
+        let score_evidence = score_evidence.map(|item| ScoreEvidence { criterion_key: criterion_key.clone(), ..item });
+        if let Some(item) = &score_evidence {
+            evidence::validate(item)?;
         }
 
-        let conn = conn_guard.as_ref().unwrap();
+        periods::ensure_record_open(conn, record_id as i64)?;
 
-        // Processa cada critério
-        for c in criteria {
-            let is_target = c.criteria_name.to_lowercase().contains("target");
-            
-            // Usa criteria_target se for target, criteria_weight se for weight
-            let value_to_save = if is_target {
-                c.criteria_target
-            } else {
-                c.criteria_weight
-            };
-            
-            let value_str = value_to_save.to_string();
-            
-            conn.execute(
-                "UPDATE criteria_table SET value = ?1 WHERE criteria_id = ?2",
-                rusqlite::params![value_str, c.criteria_id],
+        let (supplier_id, month, year, comment) = conn
+            .query_row(
+                "SELECT supplier_id, month, year, comment FROM supplier_score_records_table WHERE id = ?1",
+                [record_id],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, String>(1)?.trim().parse::<i32>().unwrap_or(0),
+                        row.get::<_, String>(2)?.trim().parse::<i32>().unwrap_or(0),
+                        row.get::<_, Option<String>>(3)?,
+                    ))
+                },
             )
-            .map_err(|e| format!("Erro ao atualizar ID {}: {}", c.criteria_id, e))?;
-            
-            if is_target {
-                println!("✅ Target (ID {}) atualizado para: {}", c.criteria_id, value_to_save);
-            } else {
-                println!("✅ Weight (ID {}) atualizado para: {}", c.criteria_id, value_to_save);
-            }
+            .map_err(|_| "Nenhum registro foi atualizado".to_string())?;
+        let score_comment = score_comment.filter(|c| !c.trim().is_empty());
+        let reason_codes = match reason_codes {
+            Some(codes) => Some(
+                reasons::check_codes(conn, &criterion_key, &codes)
+                    .map_err(|e| format!("{}: {}", validation::REASON_FIELD, e))?,
+            ),
+            None => None,
+        };
+        let mut justification = validation::Justification {
+            comment,
+            criterion_comments: comments::latest_by_criterion(conn, record_id as i64)?,
+            reason_codes: reasons::read_codes_map(conn, record_id as i64)?,
+        };
+        if let Some(body) = &score_comment {
+            justification.criterion_comments.insert(criterion_key.clone(), body.clone());
         }
+        if let Some(codes) = &reason_codes {
+            justification.reason_codes.insert(criterion_key.clone(), codes.clone());
+        }
+        let scope = targets::load_supplier_scope(conn, &supplier_id)?;
+        validation::validate_scores(conn, &scope, month, year, &[(criterion_key.clone(), score_value.clone())], &justification)?
+            .into_result()?;
 
-        println!("✅ Critérios atualizados com sucesso!");
-        Ok(())
-    }
+        let rows_affected = conn.execute(
+            "UPDATE supplier_score_records_table 
+             SET change_date = ?1, changed_by = ?2
+             WHERE id = ?3",
+            rusqlite::params![now, user_name, record_id],
+        ).map_err(|e| format!("Erro ao atualizar score: {}", e))?;
 
-    /// Busca lista de planners
-    pub fn get_planners() -> Result<Vec<String>, String> {
-        let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        if rows_affected == 0 {
+            return Err("Nenhum registro foi atualizado".to_string());
         }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn.prepare("SELECT DISTINCT name FROM planner_table WHERE name IS NOT NULL ORDER BY name")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(names)
-    }
 
-    /// Busca lista de continuity
-    pub fn get_continuity_options() -> Result<Vec<String>, String> {
-        let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        let score_value_trimmed = score_value.trim();
+        criteria::write_value(conn, record_id as i64, &criterion_key, score_value_trimmed)?;
+        carryover::clear(conn, record_id as i64, &criterion_key)?;
+        if let Some(item) = &score_evidence {
+            evidence::write(conn, record_id as i64, item, &user_name)?;
         }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn.prepare("SELECT DISTINCT name FROM continuity_table WHERE name IS NOT NULL ORDER BY name")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        if let Some(body) = &score_comment {
+            comments::add_if_changed(conn, record_id as i64, Some(&criterion_key), body, &user_name, None)?;
+        }
+        if let Some(codes) = &reason_codes {
+            reasons::write_codes(conn, record_id as i64, &criterion_key, codes, &user_name)?;
+        }
+        workflow::mark_edited(conn, record_id as i64, &user_name, None)?;
+
+        // Controle de pendência: se salvou vazio, marca como avaliado; se salvou valor, remove override
+        if score_value_trimmed.is_empty() {
+            conn.execute(
+                "INSERT OR REPLACE INTO pending_scores_override (record_id, score_type, dismissed)
+                 VALUES (?1, ?2, 1)",
+                rusqlite::params![record_id, criterion_key],
+            ).map_err(|e| format!("Erro ao registrar override de pendência: {}", e))?;
+        } else {
+            conn.execute(
+                "DELETE FROM pending_scores_override WHERE record_id = ?1 AND score_type = ?2",
+                rusqlite::params![record_id, criterion_key],
+            ).map_err(|e| format!("Erro ao remover override de pendência: {}", e))?;
+        }
+
+        println!("✅ Score atualizado com sucesso!");
         
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+        // Recalcula o total_score
+        score_engine::recalculate_record(conn, record_id as i64)?;
         
-        Ok(names)
+        Ok(format!("Score {} atualizado com sucesso", criterion_key))
     }
 
-    /// Busca lista de sourcing
-    pub fn get_sourcing_options() -> Result<Vec<String>, String> {
+    /// Função de teste para consultar um registro específico (DEBUG)
+    pub fn debug_get_record(record_id: i32) -> Result<String, String> {
         let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn.prepare("SELECT DISTINCT name FROM sourcing_table WHERE name IS NOT NULL ORDER BY name")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(names)
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let result = conn.query_row(
+            "SELECT id, supplier_id, supplier_name, month, year, otif, nil, quality_pickup, quality_package, total_score 
+             FROM supplier_score_records_table 
+             WHERE id = ?1",
+            [record_id],
+            |row| {
+                Ok(format!(
+                    "ID: {}\nSupplier ID: {}\nSupplier Name: {}\nMonth: {}\nYear: {}\nOTIF: {:?}\nNIL: {:?}\nQuality Pickup: {:?}\nQuality Package: {:?}\nTotal Score: {:?}",
+                    row.get::<_, i32>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, Option<f64>>(5)?,
+                    row.get::<_, Option<f64>>(6)?,
+                    row.get::<_, Option<String>>(7)?,
+                    row.get::<_, Option<String>>(8)?,
+                    row.get::<_, Option<String>>(9)?
+                ))
+            }
+        ).map_err(|e| format!("Erro ao buscar registro: {}", e))?;
+
+        Ok(result)
     }
 
-    /// Busca lista de SQIE
-    pub fn get_sqie_options() -> Result<Vec<String>, String> {
+    /// Busca os responsáveis (SQIE, Planner, Continuity, Sourcing) de um fornecedor com JOIN
+    pub fn get_supplier_responsibles(supplier_id: &str) -> Result<SupplierResponsibles, String> {
         let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn.prepare("SELECT DISTINCT name FROM sqie_table WHERE name IS NOT NULL ORDER BY name")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(names)
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        let result = conn.query_row(
+            "SELECT 
+                s.sqie, sq.name as sqie_name, sq.email as sqie_email,
+                s.planner, p.name as planner_name, p.email as planner_email,
+                s.continuity, c.name as continuity_name, c.email as continuity_email,
+                s.sourcing, so.name as sourcing_name, so.email as sourcing_email
+             FROM supplier_database_table s
+             LEFT JOIN sqie_list_table sq ON s.sqie = sq.alias
+             LEFT JOIN planner_list_table p ON s.planner = p.alias
+             LEFT JOIN continuity_list_table c ON s.continuity = c.alias
+             LEFT JOIN sourcing_list_table so ON s.sourcing = so.alias
+             WHERE s.supplier_id = ?1",
+            [supplier_id],
+            |row| {
+                Ok(SupplierResponsibles {
+                    sqie: ResponsibleInfo {
+                        alias: row.get(0)?,
+                        name: row.get(1)?,
+                        email: row.get(2)?,
+                    },
+                    planner: ResponsibleInfo {
+                        alias: row.get(3)?,
+                        name: row.get(4)?,
+                        email: row.get(5)?,
+                    },
+                    continuity: ResponsibleInfo {
+                        alias: row.get(6)?,
+                        name: row.get(7)?,
+                        email: row.get(8)?,
+                    },
+                    sourcing: ResponsibleInfo {
+                        alias: row.get(9)?,
+                        name: row.get(10)?,
+                        email: row.get(11)?,
+                    },
+                })
+            }
+        ).optional()
+        .map_err(|e| format!("Erro ao buscar responsáveis: {}", e))?
+        .ok_or_else(|| "Fornecedor não encontrado".to_string())?;
+
+        Ok(result)
     }
+}
+
+/// Estrutura para informações de um responsável
+#[derive(Debug, Serialize, Clone)]
+pub struct ResponsibleInfo {
+    pub alias: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+/// Estrutura para todos os responsáveis de um fornecedor
+#[derive(Debug, Serialize, Clone)]
+pub struct SupplierResponsibles {
+    pub sqie: ResponsibleInfo,
+    pub planner: ResponsibleInfo,
+    pub continuity: ResponsibleInfo,
+    pub sourcing: ResponsibleInfo,
+}
+
+/// Estrutura para itens de lista com 3 campos
+#[derive(Debug, Serialize, serde::Deserialize, Clone)]
+pub struct ListItemThreeFields {
+    pub name: String,
+    pub alias: String,
+    pub email: String,
+}
+
+/// Estrutura para itens de lista com 1 campo
+#[derive(Debug, Serialize, serde::Deserialize, Clone)]
+pub struct ListItemSingleField {
+    pub name: String,
+}
+
+/// Estrutura para fornecedores em risco
+#[derive(Debug, Serialize, Clone)]
+pub struct RiskSupplier {
+    pub supplier_id: String,
+    pub ssid: Option<String>,
+    pub vendor_name: String,
+    pub bu: Option<String>,
+    pub country: Option<String>,
+    pub po: Option<String>,
+    pub avg_score: f64,
+    pub q1: Option<f64>,
+    pub q2: Option<f64>,
+    pub q3: Option<f64>,
+    pub q4: Option<f64>,
+    /// Target efetivo do fornecedor (com herança de categoria/BU) e sua origem
+    pub target: f64,
+    pub target_source: String,
+    /// Tier atual (A/B/C) da segmentação por desempenho
+    pub tier: Option<String>,
+}
 
-    /// Busca lista de Business Units
-    pub fn get_business_units() -> Result<Vec<String>, String> {
-        let conn_guard = Self::get_connection()?;
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
-        }
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let mut stmt = conn.prepare("SELECT DISTINCT bu FROM business_unit_table WHERE bu IS NOT NULL ORDER BY bu")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+impl DatabaseManager {
+    /// Busca fornecedores em risco (abaixo da meta) com scores por trimestre
+    /// Cada fornecedor é comparado com o seu target efetivo; `target`, se informado,
+    /// substitui apenas o target global (overrides de categoria/BU/fornecedor prevalecem)
+    pub fn get_suppliers_at_risk(year: i32, target: Option<f64>, approved_only: bool) -> Result<Vec<RiskSupplier>, String> {
+        println!("\n🔍 Buscando fornecedores em risco - Ano: {}, Meta global: {:?}, Só aprovados: {}", year, target, approved_only);
         
-        Ok(names)
-    }
-
-    /// Busca lista de Categorias
-    pub fn get_categories() -> Result<Vec<String>, String> {
         let conn_guard = Self::get_connection()?;
+        
         if conn_guard.is_none() {
             return Err("Conexão não inicializada".to_string());
         }
+
         let conn = conn_guard.as_ref().unwrap();
         
-        let mut stmt = conn.prepare("SELECT DISTINCT category FROM categories_table WHERE category IS NOT NULL ORDER BY category")
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let names = stmt.query_map([], |row| row.get(0))
-            .map_err(|e| format!("Erro ao executar query: {}", e))?
-            .collect::<Result<Vec<String>, _>>()
-            .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(names)
-    }
-
-    // ========== LISTS MANAGEMENT ==========
-
-    /// Obter todos os itens de uma tabela com 3 campos (name, alias, email)
-    pub fn get_list_items_three_fields(table_name: &str) -> Result<Vec<ListItemThreeFields>, String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
+        let year_str = year.to_string();
+        let workflow_filter = if approved_only {
+            format!("AND score.workflow_status = '{}'", workflow::STATUS_APPROVED)
+        } else {
+            String::new()
+        };
         
-        let query = format!("SELECT name, alias, email FROM {}", table_name);
+        // Query com JOIN entre supplier_database_table e supplier_score_records_table
+        // Um registro só conta se tiver pelo menos uma nota de critério preenchida
+        let query = format!(
+            "SELECT 
+                CAST(sup.supplier_id AS TEXT) AS supplier_id,
+                sup.ssid,
+                sup.vendor_name,
+                sup.bu,
+                sup.country,
+                CAST(sup.supplier_po AS TEXT) AS supplier_po,
+                sup.supplier_category,
+                AVG(CASE WHEN score.month IN ('1','2','3') AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id) THEN CAST(score.total_score AS REAL) END) as q1,
+                AVG(CASE WHEN score.month IN ('4','5','6') AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id) THEN CAST(score.total_score AS REAL) END) as q2,
+                AVG(CASE WHEN score.month IN ('7','8','9') AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id) THEN CAST(score.total_score AS REAL) END) as q3,
+                AVG(CASE WHEN score.month IN ('10','11','12') AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id) THEN CAST(score.total_score AS REAL) END) as q4,
+                AVG(CASE WHEN EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id) THEN CAST(score.total_score AS REAL) END) as avg_score
+            FROM supplier_database_table sup
+            INNER JOIN supplier_score_records_table score 
+                ON sup.supplier_id = score.supplier_id
+            WHERE score.year = '{}' 
+                AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = score.id)
+                {}
+            GROUP BY sup.supplier_id, sup.ssid, sup.vendor_name, sup.bu, sup.country, sup.supplier_po, sup.supplier_category
+            HAVING avg_score IS NOT NULL
+            ORDER BY avg_score ASC",
+            year_str, workflow_filter
+        );
         
         let mut stmt = conn.prepare(&query)
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
         
-        let items = stmt.query_map([], |row| {
-            Ok(ListItemThreeFields {
-                name: row.get(0)?,
-                alias: row.get(1)?,
-                email: row.get(2)?,
-            })
+        let suppliers = stmt.query_map([], |row| {
+            let supplier_id: String = row.get(0)?;
+            let ssid: Option<String> = row.get(1)?;
+            let vendor_name: String = row.get(2)?;
+            let bu: Option<String> = row.get(3)?;
+            let country: Option<String> = row.get(4)?;
+            let po: Option<String> = row.get(5)?;
+            let category: Option<String> = row.get(6)?;
+            let q1: Option<f64> = row.get(7)?;
+            let q2: Option<f64> = row.get(8)?;
+            let q3: Option<f64> = row.get(9)?;
+            let q4: Option<f64> = row.get(10)?;
+            let avg_score: f64 = row.get(11)?;
+            
+            Ok((category, RiskSupplier {
+                supplier_id,
+                ssid,
+                vendor_name,
+                bu,
+                country,
+                po,
+                q1,
+                q2,
+                q3,
+                q4,
+                avg_score,
+                target: 0.0,
+                target_source: String::new(),
+                tier: None,
+            }))
         })
         .map_err(|e| format!("Erro ao executar query: {}", e))?
         .collect::<Result<Vec<_>, _>>()
         .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(items)
-    }
 
-    /// Adicionar item em tabela com 3 campos
-    pub fn add_list_item_three_fields(table_name: &str, item: ListItemThreeFields) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("INSERT INTO {} (name, alias, email) VALUES (?1, ?2, ?3)", table_name);
+        // Target vigente no fim do ano (ou no mês corrente, se o ano ainda não acabou)
+        let period = criteria::period_key(12, year).min(criteria::current_period());
+        let mut resolver = TargetResolver::new(conn)?;
+        let tiers = tiering::current_tiers(conn)?;
+        let mut at_risk = Vec::new();
+        for (category, mut supplier) in suppliers {
+            let scope = SupplierScope {
+                supplier_id: supplier.supplier_id.clone(),
+                bu: supplier.bu.clone(),
+                category,
+            };
+            let resolved = resolver.resolve(conn, &scope, criteria::TOTAL_TARGET_KEY, &period)?;
+            let (effective, source) = match (resolved.source.as_str(), target) {
+                (targets::SCOPE_GLOBAL, Some(global)) => (global, targets::SCOPE_GLOBAL.to_string()),
+                _ => (resolved.target, resolved.source),
+            };
+            if supplier.avg_score < effective {
+                supplier.target = effective;
+                supplier.target_source = source;
+                supplier.tier = tiers.get(&supplier.supplier_id.trim().to_lowercase()).cloned();
+                at_risk.push(supplier);
+            }
+        }
+        let suppliers = at_risk;
         
-        conn.execute(&query, rusqlite::params![item.name, item.alias, item.email])
-            .map_err(|e| format!("Erro ao inserir item: {}", e))?;
+        println!("✅ Encontrados {} fornecedores em risco (abaixo do target efetivo)", suppliers.len());
         
-        Ok(())
+        Ok(suppliers)
     }
 
-    /// Atualizar item em tabela com 3 campos
-    pub fn update_list_item_three_fields(table_name: &str, old_name: &str, item: ListItemThreeFields) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("UPDATE {} SET name = ?1, alias = ?2, email = ?3 WHERE name = ?4", table_name);
-        
-        conn.execute(&query, rusqlite::params![item.name, item.alias, item.email, old_name])
-            .map_err(|e| format!("Erro ao atualizar item: {}", e))?;
-        
-        Ok(())
+    /// Critérios pedidos em uma operação em lote (vazio = todos os ativos), sem repetição
+    fn select_criteria(conn: &Connection, requested: Option<Vec<String>>) -> Result<Vec<CriterionDefinition>, String> {
+        let Some(requested) = requested.filter(|c| !c.is_empty()) else {
+            return criteria::load_definitions(conn, false);
+        };
+        let mut selected = Vec::new();
+        for key in requested {
+            let definition = criteria::find_definition(conn, &key)?;
+            if !definition.is_active {
+                return Err(format!("Critério inativo: {}", definition.display_name));
+            }
+            if !selected.iter().any(|d: &CriterionDefinition| d.criterion_key == definition.criterion_key) {
+                selected.push(definition);
+            }
+        }
+        Ok(selected)
     }
 
-    /// Deletar item em tabela com 3 campos
-    pub fn delete_list_item_three_fields(table_name: &str, name: &str) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("DELETE FROM {} WHERE name = ?1", table_name);
-        
-        conn.execute(&query, rusqlite::params![name])
-            .map_err(|e| format!("Erro ao deletar item: {}", e))?;
-        
-        Ok(())
-    }
+    /// Fornecedores (id, nome) do filtro de operações em lote: ativos (e inativos, se pedido)
+    /// restritos por ids, BU, categoria e tier
+    fn select_suppliers(conn: &Connection, filter: &SupplierFilter) -> Result<Vec<(String, String)>, String> {
+        let wanted_ids: Option<Vec<String>> = filter
+            .supplier_ids
+            .as_ref()
+            .map(|ids| ids.iter().map(|id| id.trim().to_lowercase()).collect());
+        let matches = |value: &Option<String>, wanted: &Option<String>| match wanted.as_deref().map(str::trim).filter(|w| !w.is_empty()) {
+            Some(w) => value.as_deref().map(|v| v.trim().eq_ignore_ascii_case(w)).unwrap_or(false),
+            None => true,
+        };
 
-    /// Obter todos os itens de uma tabela com 1 campo (name)
-    pub fn get_list_items_single_field(table_name: &str, field_name: &str) -> Result<Vec<ListItemSingleField>, String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("SELECT {} FROM {}", field_name, table_name);
-        
-        let mut stmt = conn.prepare(&query)
+        let mut stmt = conn
+            .prepare(
+                "SELECT COALESCE(CAST(supplier_id AS TEXT), ''), COALESCE(vendor_name, ''), bu, supplier_category
+                 FROM supplier_database_table
+                 WHERE LOWER(COALESCE(supplier_status, '')) = 'active'
+                    OR (?1 AND LOWER(COALESCE(supplier_status, '')) = 'inactive')
+                 ORDER BY vendor_name",
+            )
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let items = stmt.query_map([], |row| {
-            Ok(ListItemSingleField {
-                name: row.get(0)?,
+        let rows = stmt
+            .query_map([filter.include_inactive], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<String>>(3)?,
+                ))
             })
-        })
-        .map_err(|e| format!("Erro ao executar query: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        Ok(items)
-    }
-
-    /// Adicionar item em tabela com 1 campo
-    pub fn add_list_item_single_field(table_name: &str, field_name: &str, name: String) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("INSERT INTO {} ({}) VALUES (?1)", table_name, field_name);
-        
-        conn.execute(&query, rusqlite::params![name])
-            .map_err(|e| format!("Erro ao inserir item: {}", e))?;
-        
-        Ok(())
-    }
-
-    /// Atualizar item em tabela com 1 campo
-    pub fn update_list_item_single_field(table_name: &str, field_name: &str, old_name: &str, new_name: String) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("UPDATE {} SET {} = ?1 WHERE {} = ?2", table_name, field_name, field_name);
-        
-        conn.execute(&query, rusqlite::params![new_name, old_name])
-            .map_err(|e| format!("Erro ao atualizar item: {}", e))?;
-        
-        Ok(())
-    }
-
-    /// Deletar item em tabela com 1 campo
-    pub fn delete_list_item_single_field(table_name: &str, field_name: &str, name: &str) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let query = format!("DELETE FROM {} WHERE {} = ?1", table_name, field_name);
-        
-        conn.execute(&query, rusqlite::params![name])
-            .map_err(|e| format!("Erro ao deletar item: {}", e))?;
-        
-        Ok(())
-    }
-
-    /// Conta o número de usuários online (is_online = 1)
-    pub fn get_online_users_count() -> Result<i32, String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let count: i32 = conn.query_row(
-            "SELECT COUNT(*) FROM users_table WHERE is_online = 1",
-            [],
-            |row| row.get(0)
-        )
-        .map_err(|e| format!("Erro ao contar usuários online: {}", e))?;
-        
-        Ok(count)
-    }
-
-    /// Atualiza o status online de um usuário
-    pub fn set_user_online_status(user_id: i32, is_online: bool) -> Result<(), String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let online_value = if is_online { 1 } else { 0 };
-        
-        conn.execute(
-            "UPDATE users_table SET is_online = ?1 WHERE user_id = ?2",
-            rusqlite::params![online_value, user_id]
-        )
-        .map_err(|e| format!("Erro ao atualizar status online: {}", e))?;
-        
-        Ok(())
-    }
+            .map_err(|e| format!("Erro ao buscar fornecedores: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar fornecedores: {}", e))?;
 
-    /// Reseta todos os usuários para offline (is_online = 0)
-    /// Deve ser chamado ao iniciar a aplicação
-    pub fn reset_all_users_offline() -> Result<(), String> {
-        println!("🔄 reset_all_users_offline - Iniciando...");
-        
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Database connection not initialized".to_string())?;
-        
-        let rows_affected = conn.execute(
-            "UPDATE users_table SET is_online = 0",
-            []
-        )
-        .map_err(|e| format!("Erro ao resetar status online dos usuários: {}", e))?;
-        
-        println!("✅ reset_all_users_offline concluído - {} usuário(s) atualizado(s)", rows_affected);
-        Ok(())
+        let wanted_tiers: Option<Vec<String>> = match filter.tiers.as_ref().filter(|t| !t.is_empty()) {
+            Some(tiers) => Some(tiers.iter().map(|t| tiering::validate_tier(t)).collect::<Result<_, _>>()?),
+            None => None,
+        };
+        let tiers = match wanted_tiers {
+            Some(_) => tiering::current_tiers(conn)?,
+            None => HashMap::new(),
+        };
+        let tier_matches = |id: &str| match &wanted_tiers {
+            Some(wanted) => tiers.get(&id.trim().to_lowercase()).map(|t| wanted.contains(t)).unwrap_or(false),
+            None => true,
+        };
+
+        Ok(rows
+            .into_iter()
+            .filter(|(id, _, _, _)| !id.trim().is_empty())
+            .filter(|(id, _, _, _)| wanted_ids.as_ref().map(|ids| ids.contains(&id.trim().to_lowercase())).unwrap_or(true))
+            .filter(|(_, _, bu, category)| matches(bu, &filter.bu) && matches(category, &filter.category))
+            .filter(|(id, _, _, _)| tier_matches(id))
+            .map(|(id, name, _, _)| (id, name))
+            .collect())
     }
 
-    /// Busca scores pendentes de avaliação para um usuário baseado em suas permissões
-    pub fn get_pending_scores(user_id: i32) -> Result<Vec<serde_json::Value>, String> {
+    /// Parâmetros da segmentação de fornecedores (tiers A/B/C)
+    pub fn get_tiering_config() -> Result<TieringConfig, String> {
         let conn_guard = Self::get_connection()?;
         let conn = conn_guard.as_ref()
             .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        // PERMISSÕES DO USUÁRIO (podem estar como TEXT ou INTEGER no SQLite)
-        let permissions = conn.query_row(
-            "SELECT CAST(otif AS INTEGER), CAST(nil AS INTEGER), CAST(pickup AS INTEGER), CAST(package AS INTEGER) FROM users_table WHERE user_id = ?1",
-            [user_id],
-            |row| {
-                Ok((
-                    row.get::<_, i32>(0)?, // otif
-                    row.get::<_, i32>(1)?, // nil
-                    row.get::<_, i32>(2)?, // pickup
-                    row.get::<_, i32>(3)?, // package
-                ))
-            }
-        ).map_err(|e| format!("Erro ao buscar permissões do usuário: {}", e))?;
+        tiering::load_config(conn)
+    }
 
-        let can_edit_otif = permissions.0 == 1;
-        let can_edit_nil = permissions.1 == 1;
-        let can_edit_pickup = permissions.2 == 1;
-        let can_edit_package = permissions.3 == 1;
+    /// Atualiza os parâmetros da segmentação (admin)
+    pub fn update_tiering_config(config: TieringConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+        tiering::validate_config(&config)?;
 
-        if !can_edit_otif && !can_edit_nil && !can_edit_pickup && !can_edit_package {
-            return Ok(Vec::new());
-        }
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-        // Busca registros com qualquer campo pendente (NULL ou vazio)
-        // MAS que tenham pelo menos UM campo preenchido (não considera registros com TODAS as notas vazias)
-        let query = "
-            SELECT 
-                r.id,
-                r.supplier_id,
-                COALESCE(r.supplier_name, s.vendor_name, '') AS supplier_name,
-                r.month,
-                r.year,
-                CASE WHEN r.otif IS NULL THEN NULL ELSE CAST(r.otif AS TEXT) END AS otif_value,
-                CASE WHEN r.nil IS NULL THEN NULL ELSE CAST(r.nil AS TEXT) END AS nil_value,
-                CASE WHEN r.quality_pickup IS NULL THEN NULL ELSE r.quality_pickup END AS pickup_value,
-                CASE WHEN r.quality_package IS NULL THEN NULL ELSE r.quality_package END AS package_value,
-                o_otif.record_id AS otif_dismissed,
-                o_nil.record_id AS nil_dismissed,
-                o_pickup.record_id AS pickup_dismissed,
-                o_package.record_id AS package_dismissed
-            FROM supplier_score_records_table r
-            LEFT JOIN supplier_database_table s ON s.supplier_id = r.supplier_id
-            LEFT JOIN pending_scores_override o_otif ON o_otif.record_id = r.id AND o_otif.score_type = 'otif'
-            LEFT JOIN pending_scores_override o_nil ON o_nil.record_id = r.id AND o_nil.score_type = 'nil'
-            LEFT JOIN pending_scores_override o_pickup ON o_pickup.record_id = r.id AND o_pickup.score_type = 'pickup'
-            LEFT JOIN pending_scores_override o_package ON o_package.record_id = r.id AND o_package.score_type = 'package'
-            WHERE (
-                r.otif IS NULL OR CAST(r.otif AS TEXT) = '' OR
-                r.nil IS NULL OR CAST(r.nil AS TEXT) = '' OR
-                r.quality_pickup IS NULL OR r.quality_pickup = '' OR
-                r.quality_package IS NULL OR r.quality_package = ''
-            )
-            AND (
-                (r.otif IS NOT NULL AND CAST(r.otif AS TEXT) != '') OR
-                (r.nil IS NOT NULL AND CAST(r.nil AS TEXT) != '') OR
-                (r.quality_pickup IS NOT NULL AND r.quality_pickup != '') OR
-                (r.quality_package IS NOT NULL AND r.quality_package != '')
-            )
-            ORDER BY r.year DESC, r.month DESC, supplier_name
-            LIMIT 150
-        ";
+        let old_config = Self::get_setting(conn, tiering::TIERING_CONFIG_SETTING)?;
+        let new_config = serde_json::to_string(&config)
+            .map_err(|e| format!("Erro ao serializar configuração de segmentação: {}", e))?;
 
-        let mut stmt = conn
-            .prepare(query)
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+        Self::set_setting(conn, tiering::TIERING_CONFIG_SETTING, &new_config, &admin_name)?;
+        Self::insert_log(
+            conn,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Tiering Config",
+            None,
+            None,
+            old_config.as_deref(),
+            Some(&new_config),
+        )?;
 
-        let rows = stmt
-            .query_map([], |row| {
-                let record_id: i32 = row.get(0)?;
-                let supplier_id: String = row.get(1)?;
-                let supplier_name: String = row.get(2)?;
-                let month: String = row.get(3)?;
-                let year: String = row.get(4)?;
-
-                let otif_value: Option<String> = row.get(5).ok();
-                let nil_value: Option<String> = row.get(6).ok();
-                let pickup_value: Option<String> = row.get(7).ok();
-                let package_value: Option<String> = row.get(8).ok();
-
-                let otif_dismissed: Option<i32> = row.get(9).ok();
-                let nil_dismissed: Option<i32> = row.get(10).ok();
-                let pickup_dismissed: Option<i32> = row.get(11).ok();
-                let package_dismissed: Option<i32> = row.get(12).ok();
-
-                let otif_empty = otif_value.as_ref().map(|v| v.trim().is_empty()).unwrap_or(true);
-                let nil_empty = nil_value.as_ref().map(|v| v.trim().is_empty()).unwrap_or(true);
-                let pickup_empty = pickup_value.as_ref().map(|v| v.trim().is_empty()).unwrap_or(true);
-                let package_empty = package_value.as_ref().map(|v| v.trim().is_empty()).unwrap_or(true);
-
-                let mut pending_for_user: Vec<&str> = Vec::new();
-                if can_edit_otif && otif_empty && otif_dismissed.is_none() {
-                    pending_for_user.push("OTIF");
-                }
-                if can_edit_nil && nil_empty && nil_dismissed.is_none() {
-                    pending_for_user.push("NIL");
-                }
-                if can_edit_pickup && pickup_empty && pickup_dismissed.is_none() {
-                    pending_for_user.push("Pickup");
-                }
-                if can_edit_package && package_empty && package_dismissed.is_none() {
-                    pending_for_user.push("Package");
-                }
+        Ok(())
+    }
 
-                if pending_for_user.is_empty() {
-                    return Ok(None);
-                }
+    /// Recalcula os tiers de um mês (admin); o fechamento do mês já calcula automaticamente
+    pub fn calculate_supplier_tiers(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<TieringSummary, String> {
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        Self::ensure_admin(conn, &admin_wwid)?;
 
-                Ok(Some(serde_json::json!({
-                    "record_id": record_id,
-                    "supplier_id": supplier_id,
-                    "supplier_name": supplier_name,
-                    "month": month,
-                    "year": year,
-                    "pending_fields": pending_for_user,
-                })))
-            })
-            .map_err(|e| format!("Erro ao executar query: {}", e))?;
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
+        let summary = tiering::calculate(&tx, month, year, &admin_name)?;
+        Self::insert_log(
+            &tx,
+            &admin_name,
+            "Update",
+            &admin_wwid,
+            "Supplier Tiers",
+            Some("ALL"),
+            Some(&format!("{}/{}", month, year)),
+            None,
+            Some(&format!(
+                "classified: {}, unrated: {}, changes: {} - {:?}",
+                summary.classified, summary.unrated, summary.changes.len(), summary.counts
+            )),
+        )?;
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar segmentação: {}", e))?;
 
-        let mut results = Vec::new();
-        for row_result in rows {
-            match row_result {
-                Ok(Some(value)) => results.push(value),
-                Ok(None) => {},
-                Err(e) => return Err(format!("Erro ao processar registro pendente: {}", e)),
-            }
-        }
+        Ok(summary)
+    }
 
-        Ok(results)
+    /// Histórico mensal de tiers de um fornecedor
+    pub fn get_supplier_tier_history(supplier_id: String) -> Result<Vec<SupplierTier>, String> {
+        let conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        tiering::load_history(conn, &supplier_id)
     }
 
-    /// Salva um score individual usando o ID do registro
-    pub fn save_individual_score(
-        record_id: i32,  // Mudei para usar o ID direto
-        score_type: String,  // "otif", "nil", "pickup", "package"
-        score_value: String,
-        user_name: String,
-    ) -> Result<String, String> {
-        println!("\n💾 Salvando score individual...");
-        println!("  Record ID: {}", record_id);
-        println!("  Tipo: {}, Valor: {}", score_type, score_value);
-        
+    /// Mudanças de tier, opcionalmente de um fornecedor e a partir de um mês
+    pub fn get_tier_events(supplier_id: Option<String>, start_month: Option<i32>, start_year: Option<i32>) -> Result<Vec<TierEvent>, String> {
         let conn_guard = Self::get_connection()?;
         let conn = conn_guard.as_ref()
             .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
+        let from_period = match (start_month, start_year) {
+            (Some(month), Some(year)) => Some(criteria::period_key(month, year)),
+            _ => None,
+        };
+        tiering::load_events(
+            conn,
+            supplier_id.as_deref().map(str::trim).filter(|s| !s.is_empty()),
+            from_period.as_deref(),
+        )
+    }
+
+    /// Grava notas em lote no mês (notas cheias ou cópia de outro mês) e devolve o resumo.
+    /// Política de sobrescrita, validação, período fechado e recálculo ficam concentrados aqui;
+    /// `value_for` devolve a nota de cada critério do fornecedor (None = nada a gravar).
+    fn write_batch_scores<F>(
+        tx: &Connection,
+        month: i32,
+        year: i32,
+        selected: &[CriterionDefinition],
+        suppliers: &[(String, String)],
+        overwrite_policy: OverwritePolicy,
+        user_name: &str,
+        user_wwid: &str,
+        mut value_for: F,
+    ) -> Result<FullScoreSummary, String>
+    where
+        F: FnMut(&str, &CriterionDefinition) -> Result<Option<BatchScoreValue>, String>,
+    {
+        periods::ensure_open(tx, month, year)?;
+
+        let weights = score_engine::load_weights(tx, month, year)?;
         let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+        let mut results = Vec::with_capacity(suppliers.len());
+
+        for (supplier_id, supplier_name) in suppliers {
+            let skipped = |errors: Vec<validation::FieldError>| FullScoreSupplierResult {
+                supplier_id: supplier_id.clone(),
+                supplier_name: supplier_name.clone(),
+                action: "skipped".to_string(),
+                criteria_written: Vec::new(),
+                total_score: None,
+                errors,
+            };
 
-        // Determina qual coluna atualizar
-        let column_name = match score_type.as_str() {
-            "otif" => "otif",
-            "nil" => "nil",
-            "pickup" => "quality_pickup",
-            "package" => "quality_package",
-            _ => return Err(format!("Tipo de score inválido: {}", score_type)),
-        };
+            let existing: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM supplier_score_records_table
+                     WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+                    rusqlite::params![supplier_id, month.to_string(), year.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| format!("Erro ao buscar registro de {}: {}", supplier_id, e))?;
 
-        // Atualiza apenas a coluna específica usando o ID
-        let query = format!(
-            "UPDATE supplier_score_records_table 
-             SET {} = ?1, change_date = ?2, changed_by = ?3
-             WHERE id = ?4",
-            column_name
-        );
+            if existing.is_some() && overwrite_policy == OverwritePolicy::Skip {
+                results.push(skipped(Vec::new()));
+                continue;
+            }
+            let current = match existing {
+                Some(id) => criteria::read_values(tx, id)?,
+                None => HashMap::new(),
+            };
 
-        let score_value_trimmed = score_value.trim();
+            let mut to_write = Vec::new();
+            for definition in selected {
+                let key = &definition.criterion_key;
+                if overwrite_policy != OverwritePolicy::Overwrite && current.contains_key(key) {
+                    continue;
+                }
+                let Some(score) = value_for(supplier_id, definition)? else {
+                    continue;
+                };
+                to_write.push((key.clone(), score));
+            }
+            if to_write.is_empty() {
+                results.push(skipped(Vec::new()));
+                continue;
+            }
 
-        let rows_affected = if score_type == "otif" || score_type == "nil" {
-            // OTIF e NIL são REAL (permite vazio -> NULL)
-            let score_numeric: Option<f64> = if score_value_trimmed.is_empty() {
-                None
-            } else {
-                Some(
-                    score_value_trimmed
-                        .parse::<f64>()
-                        .map_err(|_| format!("Valor inválido para {}: {}", score_type, score_value))?
-                )
+            // Mesmas regras das demais gravações, com as justificativas já registradas no registro
+            let justification = match existing {
+                Some(id) => validation::Justification {
+                    comment: tx
+                        .query_row("SELECT comment FROM supplier_score_records_table WHERE id = ?1", [id], |row| row.get(0))
+                        .map_err(|e| format!("Erro ao buscar comentário de {}: {}", supplier_id, e))?,
+                    criterion_comments: comments::latest_by_criterion(tx, id)?,
+                    reason_codes: reasons::read_codes_map(tx, id)?,
+                },
+                None => validation::Justification::default(),
             };
-            
-            conn.execute(
-                &query,
-                rusqlite::params![score_numeric, now, user_name, record_id],
-            )
-        } else {
-            // Pickup e Package são TEXT (permite vazio -> NULL)
-            let score_text: Option<String> = if score_value_trimmed.is_empty() {
-                None
-            } else {
-                Some(score_value.clone())
+            let values: Vec<(String, String)> = to_write.iter().map(|(key, score)| (key.clone(), score.value.clone())).collect();
+            let scope = targets::load_supplier_scope(tx, supplier_id)?;
+            let report = validation::validate_scores(tx, &scope, month, year, &values, &justification)?;
+            if !report.valid {
+                results.push(skipped(report.errors));
+                continue;
+            }
+
+            let (record_id, action) = match existing {
+                Some(id) => (id, "updated"),
+                None => {
+                    tx.execute(
+                        "INSERT INTO supplier_score_records_table
+                         (supplier_id, supplier_name, month, year, register_date, registered_by, change_date, changed_by)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?5, ?6)",
+                        rusqlite::params![supplier_id, supplier_name, month.to_string(), year.to_string(), &now, user_name],
+                    )
+                    .map_err(|e| format!("Erro ao inserir registro de {}: {}", supplier_id, e))?;
+                    (tx.last_insert_rowid(), "created")
+                }
             };
 
-            conn.execute(
-                &query,
-                rusqlite::params![score_text, now, user_name, record_id],
-            )
-        }.map_err(|e| format!("Erro ao atualizar score: {}", e))?;
+            for (key, score) in &to_write {
+                criteria::write_value(tx, record_id, key, &score.value)?;
+                match &score.carried_from {
+                    Some((source_id, source_period)) => carryover::mark(tx, record_id, key, *source_id, source_period, user_name)?,
+                    None => {
+                        carryover::clear(tx, record_id, key)?;
+                    }
+                }
+            }
+            if action == "updated" {
+                workflow::mark_edited(tx, record_id, user_name, Some(user_wwid))?;
+                tx.execute(
+                    "UPDATE supplier_score_records_table SET change_date = ?1, changed_by = ?2 WHERE id = ?3",
+                    rusqlite::params![&now, user_name, record_id],
+                )
+                .map_err(|e| format!("Erro ao atualizar registro de {}: {}", supplier_id, e))?;
+            }
+            let total_score = score_engine::recalculate_record_with_weights(tx, record_id, &weights)?;
 
-        // Controle de pendência: se salvou vazio, marca como avaliado; se salvou valor, remove override
-        if score_value_trimmed.is_empty() {
-            conn.execute(
-                "INSERT OR REPLACE INTO pending_scores_override (record_id, score_type, dismissed)
-                 VALUES (?1, ?2, 1)",
-                rusqlite::params![record_id, score_type],
-            ).map_err(|e| format!("Erro ao registrar override de pendência: {}", e))?;
-        } else {
-            conn.execute(
-                "DELETE FROM pending_scores_override WHERE record_id = ?1 AND score_type = ?2",
-                rusqlite::params![record_id, score_type],
-            ).map_err(|e| format!("Erro ao remover override de pendência: {}", e))?;
+            results.push(FullScoreSupplierResult {
+                supplier_id: supplier_id.clone(),
+                supplier_name: supplier_name.clone(),
+                action: action.to_string(),
+                criteria_written: to_write.into_iter().map(|(key, _)| key).collect(),
+                total_score,
+                errors: Vec::new(),
+            });
         }
 
-        if rows_affected > 0 {
-            println!("✅ Score atualizado com sucesso!");
-            
-            // Busca supplier_id, month, year do registro para recalcular total
-            let (supplier_id, month, year): (String, String, String) = conn.query_row(
-                "SELECT supplier_id, month, year FROM supplier_score_records_table WHERE id = ?1",
-                [record_id],
-                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?))
-            ).map_err(|e| format!("Erro ao buscar dados do registro: {}", e))?;
-            
-            // Recalcula o total_score
-            Self::recalculate_total_score_with_conn(conn, &supplier_id, &month, &year)?;
-            
-            Ok(format!("Score {} atualizado com sucesso", score_type))
-        } else {
-            Err("Nenhum registro foi atualizado".to_string())
-        }
+        let count = |action: &str| results.iter().filter(|r| r.action == action).count();
+        Ok(FullScoreSummary {
+            month,
+            year,
+            created: count("created"),
+            updated: count("updated"),
+            skipped: count("skipped"),
+            suppliers: results,
+        })
     }
 
-    /// Recalcula o total_score de um registro (com conexão externa)
-    fn recalculate_total_score_with_conn(
-        conn: &rusqlite::Connection,
-        supplier_id: &str, 
-        month: &str, 
-        year: &str
-    ) -> Result<(), String> {
-
-        // Busca os scores atuais
-        let (otif, nil, pickup, package) = conn.query_row(
-            "SELECT otif, nil, quality_pickup, quality_package 
-             FROM supplier_score_records_table 
-             WHERE supplier_id = ?1 AND month = ?2 AND year = ?3",
-            rusqlite::params![supplier_id, month, year],
-            |row| {
-                Ok((
-                    row.get::<_, Option<f64>>(0)?,
-                    row.get::<_, Option<f64>>(1)?,
-                    row.get::<_, Option<String>>(2)?,
-                    row.get::<_, Option<String>>(3)?,
-                ))
-            }
-        ).map_err(|e| format!("Erro ao buscar scores: {}", e))?;
+    /// Gera notas cheias para os fornecedores do filtro em uma única transação
+    pub fn generate_full_scores(
+        month: i32,
+        year: i32,
+        criteria: Option<Vec<String>>,
+        supplier_filter: Option<SupplierFilter>,
+        overwrite_policy: OverwritePolicy,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<FullScoreSummary, String> {
+        if !(1..=12).contains(&month) {
+            return Err(format!("Mês inválido: {}", month));
+        }
+        if criteria::period_key(month, year) > criteria::current_period() {
+            return Err("Não é possível gerar notas para meses futuros".to_string());
+        }
 
-        // Busca os pesos dos critérios
-        let mut weights = std::collections::HashMap::new();
-        weights.insert("otif", 25.0);
-        weights.insert("nil", 25.0);
-        weights.insert("pickup", 25.0);
-        weights.insert("package", 25.0);
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        // Calcula total
-        let mut total = 0.0;
-        let mut count = 0.0;
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
 
-        if let Some(v) = otif {
-            total += v * (weights["otif"] / 100.0);
-            count += weights["otif"] / 100.0;
+        // Critérios a preencher (vazio = todos os ativos)
+        let selected = Self::select_criteria(&tx, criteria)?;
+        if selected.is_empty() {
+            return Err("Nenhum critério ativo para gerar notas".to_string());
         }
-        if let Some(v) = nil {
-            total += v * (weights["nil"] / 100.0);
-            count += weights["nil"] / 100.0;
-        }
-        if let Some(v) = pickup.and_then(|s| s.parse::<f64>().ok()) {
-            total += v * (weights["pickup"] / 100.0);
-            count += weights["pickup"] / 100.0;
-        }
-        if let Some(v) = package.and_then(|s| s.parse::<f64>().ok()) {
-            total += v * (weights["package"] / 100.0);
-            count += weights["package"] / 100.0;
+        // A nota cheia precisa valer para todos os critérios pedidos: falha aqui em vez de pular
+        let full_score = FULL_SCORE_VALUE.to_string();
+        for definition in &selected {
+            let rule = validation::load_rule(&tx, &definition.criterion_key)?;
+            validation::check_value(&rule, &full_score)
+                .map_err(|e| format!("{}: {}", definition.display_name, e))?;
         }
 
-        let total_score = if count > 0.0 {
-            format!("{:.2}", total / count)
-        } else {
-            "0.00".to_string()
-        };
+        let suppliers = Self::select_suppliers(&tx, &supplier_filter.unwrap_or_default())?;
 
-        // Atualiza o total_score
-        conn.execute(
-            "UPDATE supplier_score_records_table 
-             SET total_score = ?1 
-             WHERE supplier_id = ?2 AND month = ?3 AND year = ?4",
-            rusqlite::params![total_score, supplier_id, month, year],
-        ).map_err(|e| format!("Erro ao atualizar total_score: {}", e))?;
+        let summary = Self::write_batch_scores(
+            &tx,
+            month,
+            year,
+            &selected,
+            &suppliers,
+            overwrite_policy,
+            &user_name,
+            &user_wwid,
+            |_, _| Ok(Some(BatchScoreValue { value: full_score.clone(), carried_from: None })),
+        )?;
 
-        println!("✅ Total score recalculado: {}", total_score);
-        Ok(())
-    }
+        if summary.created + summary.updated > 0 {
+            let criteria_names = selected.iter().map(|d| d.display_name.as_str()).collect::<Vec<_>>().join(", ");
+            Self::insert_log(
+                &tx,
+                &user_name,
+                "Create",
+                &user_wwid,
+                "All Scores",
+                Some("ALL"),
+                Some(&format!("{}/{}", month, year)),
+                None,
+                Some(&format!(
+                    "{} suppliers - Month: {}/{} - created: {}, updated: {}, skipped: {} - criteria: {}",
+                    summary.created + summary.updated, month, year, summary.created, summary.updated, summary.skipped, criteria_names
+                )),
+            )?;
+        }
 
-    /// Função de teste para consultar um registro específico (DEBUG)
-    pub fn debug_get_record(record_id: i32) -> Result<String, String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
-            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar geração de notas: {}", e))?;
+        Ok(summary)
+    }
 
-        let result = conn.query_row(
-            "SELECT id, supplier_id, supplier_name, month, year, otif, nil, quality_pickup, quality_package, total_score 
-             FROM supplier_score_records_table 
-             WHERE id = ?1",
-            [record_id],
-            |row| {
-                Ok(format!(
-                    "ID: {}\nSupplier ID: {}\nSupplier Name: {}\nMonth: {}\nYear: {}\nOTIF: {:?}\nNIL: {:?}\nQuality Pickup: {:?}\nQuality Package: {:?}\nTotal Score: {:?}",
-                    row.get::<_, i32>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                    row.get::<_, String>(4)?,
-                    row.get::<_, Option<f64>>(5)?,
-                    row.get::<_, Option<f64>>(6)?,
-                    row.get::<_, Option<String>>(7)?,
-                    row.get::<_, Option<String>>(8)?,
-                    row.get::<_, Option<String>>(9)?
-                ))
+    /// Copia as notas de um mês para outro como ponto de partida. Só preenche critérios
+    /// vazios no mês de destino; cada nota copiada fica marcada até ser confirmada.
+    pub fn copy_scores_from_period(
+        source_month: i32,
+        source_year: i32,
+        target_month: i32,
+        target_year: i32,
+        criteria: Option<Vec<String>>,
+        supplier_filter: Option<SupplierFilter>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<FullScoreSummary, String> {
+        for month in [source_month, target_month] {
+            if !(1..=12).contains(&month) {
+                return Err(format!("Mês inválido: {}", month));
             }
-        ).map_err(|e| format!("Erro ao buscar registro: {}", e))?;
-
-        Ok(result)
-    }
+        }
+        let source_period = criteria::period_key(source_month, source_year);
+        if source_period == criteria::period_key(target_month, target_year) {
+            return Err("Os meses de origem e destino devem ser diferentes".to_string());
+        }
+        if criteria::period_key(target_month, target_year) > criteria::current_period() {
+            return Err("Não é possível copiar notas para meses futuros".to_string());
+        }
 
-    /// Busca os responsáveis (SQIE, Planner, Continuity, Sourcing) de um fornecedor com JOIN
-    pub fn get_supplier_responsibles(supplier_id: &str) -> Result<SupplierResponsibles, String> {
-        let conn_guard = Self::get_connection()?;
-        let conn = conn_guard.as_ref()
+        let mut conn_guard = Self::get_connection()?;
+        let conn = conn_guard.as_mut()
             .ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        let result = conn.query_row(
-            "SELECT 
-                s.sqie, sq.name as sqie_name, sq.email as sqie_email,
-                s.planner, p.name as planner_name, p.email as planner_email,
-                s.continuity, c.name as continuity_name, c.email as continuity_email,
-                s.sourcing, so.name as sourcing_name, so.email as sourcing_email
-             FROM supplier_database_table s
-             LEFT JOIN sqie_list_table sq ON s.sqie = sq.alias
-             LEFT JOIN planner_list_table p ON s.planner = p.alias
-             LEFT JOIN continuity_list_table c ON s.continuity = c.alias
-             LEFT JOIN sourcing_list_table so ON s.sourcing = so.alias
-             WHERE s.supplier_id = ?1",
-            [supplier_id],
-            |row| {
-                Ok(SupplierResponsibles {
-                    sqie: ResponsibleInfo {
-                        alias: row.get(0)?,
-                        name: row.get(1)?,
-                        email: row.get(2)?,
-                    },
-                    planner: ResponsibleInfo {
-                        alias: row.get(3)?,
-                        name: row.get(4)?,
-                        email: row.get(5)?,
-                    },
-                    continuity: ResponsibleInfo {
-                        alias: row.get(6)?,
-                        name: row.get(7)?,
-                        email: row.get(8)?,
-                    },
-                    sourcing: ResponsibleInfo {
-                        alias: row.get(9)?,
-                        name: row.get(10)?,
-                        email: row.get(11)?,
-                    },
-                })
-            }
-        ).optional()
-        .map_err(|e| format!("Erro ao buscar responsáveis: {}", e))?
-        .ok_or_else(|| "Fornecedor não encontrado".to_string())?;
+        let tx = conn.transaction()
+            .map_err(|e| format!("Erro ao iniciar transação: {}", e))?;
 
-        Ok(result)
-    }
-}
+        let selected = Self::select_criteria(&tx, criteria)?;
+        if selected.is_empty() {
+            return Err("Nenhum critério ativo para copiar".to_string());
+        }
 
-/// Estrutura para informações de um responsável
-#[derive(Debug, Serialize, Clone)]
-pub struct ResponsibleInfo {
-    pub alias: Option<String>,
-    pub name: Option<String>,
-    pub email: Option<String>,
-}
+        let suppliers = Self::select_suppliers(&tx, &supplier_filter.unwrap_or_default())?;
+
+        // Registro e notas de origem de cada fornecedor (sem registro na origem = ignorado)
+        let mut sources = HashMap::new();
+        for (supplier_id, _) in &suppliers {
+            let source_id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM supplier_score_records_table
+                     WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+                    rusqlite::params![supplier_id, source_month.to_string(), source_year.to_string()],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|e| format!("Erro ao buscar registro de {}: {}", supplier_id, e))?;
+            if let Some(source_id) = source_id {
+                sources.insert(supplier_id.clone(), (source_id, criteria::read_values(&tx, source_id)?));
+            }
+        }
 
-/// Estrutura para todos os responsáveis de um fornecedor
-#[derive(Debug, Serialize, Clone)]
-pub struct SupplierResponsibles {
-    pub sqie: ResponsibleInfo,
-    pub planner: ResponsibleInfo,
-    pub continuity: ResponsibleInfo,
-    pub sourcing: ResponsibleInfo,
-}
+        let summary = Self::write_batch_scores(
+            &tx,
+            target_month,
+            target_year,
+            &selected,
+            &suppliers,
+            OverwritePolicy::FillEmpty,
+            &user_name,
+            &user_wwid,
+            |supplier_id, definition| {
+                Ok(sources.get(supplier_id).and_then(|(source_id, values)| {
+                    values.get(&definition.criterion_key).map(|value| BatchScoreValue {
+                        value: value.to_string(),
+                        carried_from: Some((*source_id, source_period.clone())),
+                    })
+                }))
+            },
+        )?;
 
-/// Estrutura para itens de lista com 3 campos
-#[derive(Debug, Serialize, serde::Deserialize, Clone)]
-pub struct ListItemThreeFields {
-    pub name: String,
-    pub alias: String,
-    pub email: String,
-}
+        if summary.created + summary.updated > 0 {
+            let criteria_names = selected.iter().map(|d| d.display_name.as_str()).collect::<Vec<_>>().join(", ");
+            Self::insert_log(
+                &tx,
+                &user_name,
+                "Create",
+                &user_wwid,
+                "Copied Scores",
+                Some("ALL"),
+                Some(&format!("{}/{}", target_month, target_year)),
+                None,
+                Some(&format!(
+                    "{} suppliers - From: {}/{} - created: {}, updated: {}, skipped: {} - criteria: {}",
+                    summary.created + summary.updated, source_month, source_year, summary.created, summary.updated, summary.skipped, criteria_names
+                )),
+            )?;
+        }
 
-/// Estrutura para itens de lista com 1 campo
-#[derive(Debug, Serialize, serde::Deserialize, Clone)]
-pub struct ListItemSingleField {
-    pub name: String,
-}
+        tx.commit()
+            .map_err(|e| format!("Erro ao confirmar cópia de notas: {}", e))?;
 
-/// Estrutura para fornecedores em risco
-#[derive(Debug, Serialize, Clone)]
-pub struct RiskSupplier {
-    pub supplier_id: String,
-    pub ssid: Option<String>,
-    pub vendor_name: String,
-    pub bu: Option<String>,
-    pub country: Option<String>,
-    pub po: Option<String>,
-    pub avg_score: f64,
-    pub q1: Option<f64>,
-    pub q2: Option<f64>,
-    pub q3: Option<f64>,
-    pub q4: Option<f64>,
-}
+        Ok(summary)
+    }
 
-impl DatabaseManager {
-    /// Busca fornecedores em risco (abaixo da meta) com scores por trimestre
-    pub fn get_suppliers_at_risk(year: i32, target: f64) -> Result<Vec<RiskSupplier>, String> {
-        println!("\n🔍 Buscando fornecedores em risco - Ano: {}, Meta: {}", year, target);
-        
+    /// Confirma notas copiadas de outro mês (todas do registro ou só os critérios informados); devolve quantas
+    pub fn confirm_carried_over_scores(
+        record_id: i64,
+        criteria: Option<Vec<String>>,
+        user_name: String,
+        user_wwid: String,
+    ) -> Result<usize, String> {
         let conn_guard = Self::get_connection()?;
-        
-        if conn_guard.is_none() {
-            return Err("Conexão não inicializada".to_string());
+        let conn = conn_guard.as_ref()
+            .ok_or_else(|| "Conexão não inicializada".to_string())?;
+
+        periods::ensure_record_open(conn, record_id)?;
+        let (supplier_id, month, year): (String, String, String) = conn
+            .query_row(
+                "SELECT supplier_id, month, year FROM supplier_score_records_table WHERE id = ?1",
+                [record_id],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar registro {}: {}", record_id, e))?
+            .ok_or_else(|| format!("Registro {} não encontrado", record_id))?;
+
+        let carried = carryover::read(conn, record_id)?;
+        let keys: Vec<String> = match criteria.filter(|c| !c.is_empty()) {
+            Some(requested) => {
+                let mut keys = Vec::new();
+                for key in requested {
+                    keys.push(criteria::find_definition(conn, &key)?.criterion_key);
+                }
+                keys
+            }
+            None => carried.keys().cloned().collect(),
+        };
+
+        let mut confirmed = Vec::new();
+        for key in keys {
+            if carryover::clear(conn, record_id, &key)? {
+                confirmed.push(carried.get(&key).map(|c| format!("{} ({})", key, c.source_period)).unwrap_or(key));
+            }
+        }
+        if confirmed.is_empty() {
+            return Ok(0);
         }
 
-        let conn = conn_guard.as_ref().unwrap();
-        
-        let year_str = year.to_string();
-        
-        // Query com JOIN entre supplier_database_table e supplier_score_records_table
-        // Um registro só conta se tiver pelo menos uma das 4 notas preenchidas
-        let query = format!(
-            "SELECT 
-                CAST(sup.supplier_id AS TEXT) AS supplier_id,
-                sup.ssid,
-                sup.vendor_name,
-                sup.bu,
-                sup.country,
-                CAST(sup.supplier_po AS TEXT) AS supplier_po,
-                AVG(CASE WHEN score.month IN ('1','2','3') AND (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL) THEN CAST(score.total_score AS REAL) END) as q1,
-                AVG(CASE WHEN score.month IN ('4','5','6') AND (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL) THEN CAST(score.total_score AS REAL) END) as q2,
-                AVG(CASE WHEN score.month IN ('7','8','9') AND (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL) THEN CAST(score.total_score AS REAL) END) as q3,
-                AVG(CASE WHEN score.month IN ('10','11','12') AND (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL) THEN CAST(score.total_score AS REAL) END) as q4,
-                AVG(CASE WHEN (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL) THEN CAST(score.total_score AS REAL) END) as avg_score
-            FROM supplier_database_table sup
-            INNER JOIN supplier_score_records_table score 
-                ON sup.supplier_id = score.supplier_id
-            WHERE score.year = '{}' 
-                AND (score.otif IS NOT NULL OR score.nil IS NOT NULL OR score.quality_pickup IS NOT NULL OR score.quality_package IS NOT NULL)
-            GROUP BY sup.supplier_id, sup.ssid, sup.vendor_name, sup.bu, sup.country, sup.supplier_po
-            HAVING avg_score < {} AND avg_score IS NOT NULL
-            ORDER BY avg_score ASC",
-            year_str, target
-        );
-        
-        println!("📋 Query SQL:\n{}", query);
-        
-        let mut stmt = conn.prepare(&query)
-            .map_err(|e| format!("Erro ao preparar query: {}", e))?;
-        
-        let suppliers = stmt.query_map([], |row| {
-            let supplier_id: String = row.get(0)?;
-            let ssid: Option<String> = row.get(1)?;
-            let vendor_name: String = row.get(2)?;
-            let bu: Option<String> = row.get(3)?;
-            let country: Option<String> = row.get(4)?;
-            let po: Option<String> = row.get(5)?;
-            let q1: Option<f64> = row.get(6)?;
-            let q2: Option<f64> = row.get(7)?;
-            let q3: Option<f64> = row.get(8)?;
-            let q4: Option<f64> = row.get(9)?;
-            let avg_score: f64 = row.get(10)?;
-            
-            println!("  📊 {} - {} | Média: {:.2} | Q1: {:?} | Q2: {:?} | Q3: {:?} | Q4: {:?}", 
-                     supplier_id, vendor_name, avg_score, q1, q2, q3, q4);
-            
-            Ok(RiskSupplier {
-                supplier_id,
-                ssid,
-                vendor_name,
-                bu,
-                country,
-                po,
-                q1,
-                q2,
-                q3,
-                q4,
-                avg_score,
-            })
-        })
-        .map_err(|e| format!("Erro ao executar query: {}", e))?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
-        
-        println!("✅ Encontrados {} fornecedores em risco (abaixo de {})", suppliers.len(), target);
-        
-        Ok(suppliers)
+        Self::insert_log(
+            conn,
+            &user_name,
+            "Update",
+            &user_wwid,
+            "Copied Scores Confirm",
+            Some(&supplier_id),
+            Some(&format!("{}/{}", month.trim(), year.trim())),
+            None,
+            Some(&confirmed.join(", ")),
+        )?;
+
+        Ok(confirmed.len())
     }
 
-    /// Registra log de geração em lote de notas cheias
+    /// Registra log de geração em lote de notas cheias (legado: generate_full_scores já registra o próprio log)
     pub fn log_bulk_generation(
         user_name: String,
         user_wwid: String,
@@ -3525,17 +7792,18 @@ impl DatabaseManager {
         println!("👤 Total de usuários: {}", user_count);
         
         let mut stmt = conn
-            .prepare(
+            .prepare(&format!(
                 "SELECT u.user_wwid, 
                         u.user_name, 
                         COUNT(l.wwid) as activity_count
                  FROM users_table u
-                 LEFT JOIN log_table l ON u.user_wwid = l.wwid
+                 LEFT JOIN log_table l ON u.user_wwid = l.wwid AND l.event NOT IN ({})
                  WHERE l.wwid IS NOT NULL AND l.wwid != 'Unknown' AND l.wwid != ''
                  GROUP BY u.user_wwid, u.user_name
                  ORDER BY activity_count DESC
-                 LIMIT ?1"
-            )
+                 LIMIT ?1",
+                AUTH_LOG_EVENTS
+            ))
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
         let users = stmt
@@ -3567,17 +7835,18 @@ impl DatabaseManager {
         let conn = conn_guard.as_ref().unwrap();
         
         let mut stmt = conn
-            .prepare(
+            .prepare(&format!(
                 "SELECT u.user_wwid, 
                         u.user_name, 
                         COUNT(l.wwid) as contribution_count,
                         MAX(l.date || ' ' || l.time) as last_input_date
                  FROM users_table u
-                 LEFT JOIN log_table l ON u.user_wwid = l.wwid
+                 LEFT JOIN log_table l ON u.user_wwid = l.wwid AND l.event NOT IN ({})
                  WHERE l.wwid IS NOT NULL AND l.wwid != 'Unknown' AND l.wwid != ''
                  GROUP BY u.user_wwid, u.user_name
-                 ORDER BY contribution_count DESC"
-            )
+                 ORDER BY contribution_count DESC",
+                AUTH_LOG_EVENTS
+            ))
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
         let contributors = stmt
@@ -3615,13 +7884,13 @@ impl DatabaseManager {
         ];
         
         let mut stmt = conn
-            .prepare(
+            .prepare(&format!(
                 "SELECT u.user_wwid, 
                         u.user_name, 
                         COUNT(l.wwid) as contribution_count,
                         MAX(l.date || ' ' || l.time) as last_input_date
                  FROM users_table u
-                 LEFT JOIN log_table l ON u.user_wwid = l.wwid
+                 LEFT JOIN log_table l ON u.user_wwid = l.wwid AND l.event NOT IN ({})
                                  WHERE l.wwid IS NOT NULL
                                      AND l.wwid != 'Unknown'
                                      AND l.wwid != ''
@@ -3632,8 +7901,9 @@ impl DatabaseManager {
                                      AND (l.date LIKE ?1 OR l.date LIKE ?2)
                  GROUP BY u.user_wwid, u.user_name
                  HAVING contribution_count > 0
-                 ORDER BY contribution_count DESC"
-            )
+                 ORDER BY contribution_count DESC",
+                AUTH_LOG_EVENTS
+            ))
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
         let contributors = stmt
@@ -3670,20 +7940,21 @@ impl DatabaseManager {
         ];
 
         let mut stmt = conn
-            .prepare(
+            .prepare(&format!(
                 "SELECT u.user_wwid,
                         u.user_name,
                         l.date,
                         COUNT(l.wwid) as contribution_count
                  FROM users_table u
-                 LEFT JOIN log_table l ON u.user_wwid = l.wwid
+                 LEFT JOIN log_table l ON u.user_wwid = l.wwid AND l.event NOT IN ({})
                  WHERE l.wwid IS NOT NULL
                    AND l.wwid != 'Unknown'
                    AND l.wwid != ''
                    AND (l.date LIKE ?1 OR l.date LIKE ?2)
                  GROUP BY u.user_wwid, u.user_name, l.date
-                 ORDER BY u.user_name, l.date"
-            )
+                 ORDER BY u.user_name, l.date",
+                AUTH_LOG_EVENTS
+            ))
             .map_err(|e| format!("Erro ao preparar query: {}", e))?;
 
         let rows = stmt
@@ -3721,9 +7992,6 @@ impl DatabaseManager {
         println!("📤 ========================================\n");
 
         let trimmed_criteria = criteria.trim().to_string();
-        let criteria_key = trimmed_criteria.to_lowercase();
-        let criteria_label = criteria_key.to_uppercase();
-        println!("📤 Critério normalizado: {}", criteria_key);
 
         // Validação: se include_score é true, mês e ano são obrigatórios
         if include_score {
@@ -3761,16 +8029,14 @@ impl DatabaseManager {
 
         let conn = conn_guard.as_ref().unwrap();
 
-        // Mapeia o nome do critério para o nome da coluna no banco
-        let score_column = match criteria_key.as_str() {
-            "otif" => "otif",
-            "nil" => "nil",
-            "pickup" => "quality_pickup",
-            "package" => "quality_package",
-            _ => return Err(format!("Critério inválido: {}", trimmed_criteria)),
-        };
+        // Resolve o critério configurado (chave ou nome exibido)
+        let definition = criteria::find_definition(conn, &trimmed_criteria)?;
+        if !definition.is_active {
+            return Err(format!("Critério inativo: {}", definition.display_name));
+        }
+        let criteria_key = definition.criterion_key.clone();
+        let criteria_label = definition.display_name.to_uppercase();
 
-        println!("📊 Coluna de score selecionada: {}", score_column);
 
         // Busca todos os fornecedores
         let suppliers_query = String::from(
@@ -3799,6 +8065,9 @@ impl DatabaseManager {
             supplier_po: String,
             score: Option<f64>,
             comment: Option<String>,
+            numerator: Option<f64>,
+            denominator: Option<f64>,
+            reason_codes: String,
         }
 
         let mut export_data: Vec<ExportRow> = Vec::new();
@@ -3821,45 +8090,47 @@ impl DatabaseManager {
             let mut score: Option<f64> = None;
             let mut record_id: Option<i32> = None;
             let mut comment: Option<String> = None;
+            let mut numerator: Option<f64> = None;
+            let mut denominator: Option<f64> = None;
+            let mut reason_codes = String::new();
 
             // Se deve incluir scores, busca da tabela de scores
-            if include_score && month.is_some() && year.is_some() {
-                let score_query = format!(
-                    "SELECT id, {}, comment FROM supplier_score_records_table 
-                     WHERE lower(trim(supplier_id)) = lower(trim(?1)) 
-                     AND month = ?2 
-                     AND year = ?3",
-                    score_column
-                );
-
-                println!("🔍 Query de busca: {}", score_query);
-                println!("🔍 Parâmetros: supplier_id='{}', month={}, year={}", 
-                         supplier_id, month.unwrap(), year.unwrap());
-
-                if let Ok(mut score_stmt) = conn.prepare(&score_query) {
+            if let (true, Some(score_month), Some(score_year)) = (include_score, month, year) {
+                let score_query = "SELECT r.id, v.value,
+                     COALESCE((SELECT c.body FROM score_comment_table c WHERE c.record_id = r.id AND c.criterion_key = ?4
+                               ORDER BY c.comment_id DESC LIMIT 1), r.comment),
+                     e.numerator, e.denominator,
+                     (SELECT group_concat(sr.reason_code, ', ') FROM score_reason_table sr
+                      WHERE sr.record_id = r.id AND sr.criterion_key = ?4)
+                     FROM supplier_score_records_table r
+                     LEFT JOIN supplier_score_values_table v ON v.record_id = r.id AND v.criterion_key = ?4
+                     LEFT JOIN supplier_score_evidence_table e ON e.record_id = r.id AND e.criterion_key = ?4
+                     WHERE lower(trim(r.supplier_id)) = lower(trim(?1)) 
+                     AND r.month = ?2 
+                     AND r.year = ?3";
+
+                if let Ok(mut score_stmt) = conn.prepare(score_query) {
                     match score_stmt.query_row(
-                        [&supplier_id, &month.unwrap().to_string(), &year.unwrap().to_string()],
+                        [&supplier_id, &score_month.to_string(), &score_year.to_string(), &criteria_key],
                         |row| {
                             let id = row.get::<_, i32>(0)?;
-                            
-                            // quality_pickup e quality_package são TEXT, outros são REAL
-                            let score_val = if score_column == "quality_pickup" || score_column == "quality_package" {
-                                row.get::<_, Option<String>>(1)?
-                                    .and_then(|s| s.parse::<f64>().ok())
-                            } else {
-                                row.get::<_, Option<f64>>(1)?
-                            };
+                            let score_val = row.get::<_, Option<f64>>(1)?;
                             
                             let comment_val = row.get::<_, Option<String>>(2)?;
                             
+                            let evidence_val = (row.get::<_, Option<f64>>(3)?, row.get::<_, Option<f64>>(4)?, row.get::<_, Option<String>>(5)?);
+                            
                             println!("   ✅ Encontrado: id={}, score={:?}, comment={:?}", id, score_val, comment_val);
-                            Ok((Some(id), score_val, comment_val))
+                            Ok((Some(id), score_val, comment_val, evidence_val))
                         }
                     ) {
                         Ok(result) => {
                             record_id = result.0;
                             score = result.1;
                             comment = result.2;
+                            numerator = result.3.0;
+                            denominator = result.3.1;
+                            reason_codes = result.3.2.unwrap_or_default();
                         }
                         Err(e) => {
                             println!("   ⚠️ Nenhum registro encontrado: {}", e);
@@ -3876,6 +8147,9 @@ impl DatabaseManager {
                 supplier_po,
                 score,
                 comment,
+                numerator,
+                denominator,
+                reason_codes,
             });
         }
 
@@ -3927,6 +8201,9 @@ impl DatabaseManager {
             worksheet.set_column_width(4, 15).map_err(|e| format!("Erro ao definir largura: {}", e))?;
             worksheet.set_column_width(5, 12).map_err(|e| format!("Erro ao definir largura: {}", e))?;
             worksheet.set_column_width(6, 50).map_err(|e| format!("Erro ao definir largura: {}", e))?;
+            worksheet.set_column_width(7, 14).map_err(|e| format!("Erro ao definir largura: {}", e))?;
+            worksheet.set_column_width(8, 14).map_err(|e| format!("Erro ao definir largura: {}", e))?;
+            worksheet.set_column_width(9, 20).map_err(|e| format!("Erro ao definir largura: {}", e))?;
         } else {
             worksheet.set_column_width(0, 15).map_err(|e| format!("Erro ao definir largura: {}", e))?;
             worksheet.set_column_width(1, 40).map_err(|e| format!("Erro ao definir largura: {}", e))?;
@@ -3952,6 +8229,12 @@ impl DatabaseManager {
                 .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
             worksheet.write_with_format(0, 6, "Comment", &header_format)
                 .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
+            worksheet.write_with_format(0, 7, "Numerator", &header_format)
+                .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
+            worksheet.write_with_format(0, 8, "Denominator", &header_format)
+                .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
+            worksheet.write_with_format(0, 9, "Reason Codes", &header_format)
+                .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
         } else {
             worksheet.write_with_format(0, 0, "Supplier ID", &header_format)
                 .map_err(|e| format!("Erro ao escrever cabeçalho: {}", e))?;
@@ -4014,6 +8297,19 @@ impl DatabaseManager {
                     worksheet.write_with_format(row_num, 6, "", &comment_format)
                         .map_err(|e| format!("Erro ao escrever dados: {}", e))?;
                 }
+
+                // Colunas H e I: evidência da nota (desbloqueadas)
+                for (col, value) in [(7u16, row.numerator), (8u16, row.denominator)] {
+                    match value {
+                        Some(v) => worksheet.write_with_format(row_num, col, v, &comment_format),
+                        None => worksheet.write_with_format(row_num, col, "", &comment_format),
+                    }
+                    .map_err(|e| format!("Erro ao escrever dados: {}", e))?;
+                }
+
+                // Coluna J: códigos de motivo do critério (separados por vírgula)
+                worksheet.write_with_format(row_num, 9, &row.reason_codes, &comment_format)
+                    .map_err(|e| format!("Erro ao escrever dados: {}", e))?;
             } else {
                 // Modo sem score (não usado mais, mas mantido por compatibilidade)
                 worksheet.write_with_format(row_num, 0, &row.supplier_id, &locked_format)
@@ -4217,7 +8513,7 @@ impl DatabaseManager {
             println!("   Data Export: {}", export_date);
 
             // Valida o critério
-            if criteria.to_lowercase() != expected_criteria.to_lowercase() {
+            if criteria::normalize_key(&criteria) != criteria::normalize_key(&expected_criteria) {
                 return Err(format!(
                     "Critério inválido. Esperado: {}, Encontrado: {}",
                     expected_criteria, criteria
@@ -4290,24 +8586,24 @@ impl DatabaseManager {
 
         let mut updated_count = 0;
         let mut error_count = 0;
+        let mut validation_errors: Vec<String> = Vec::new();
         let conn_guard = Self::get_connection()?;
         let conn = conn_guard.as_ref().ok_or_else(|| "Conexão não inicializada".to_string())?;
 
-        // Define qual coluna atualizar baseado no critério
-        let score_column = match criteria.to_lowercase().as_str() {
-            "otif" => "otif",
-            "nil" => "nil",
-            "pickup" => "quality_pickup",
-            "package" => "quality_package",
-            _ => return Err(format!("Critério inválido: {}", criteria)),
-        };
+        // Define qual critério atualizar
+        let definition = criteria::find_definition(conn, &criteria)?;
+        if !definition.is_active {
+            return Err(format!("Critério inativo: {}", definition.display_name));
+        }
+        let criterion_key = definition.criterion_key.clone();
+
+        if export_month > 0 {
+            periods::ensure_open(conn, export_month, export_year)?;
+        }
 
-        println!("📊 Atualizando coluna: {}", score_column);
 
-        // Busca os pesos dos critérios para calcular total_score
-        let criteria_weights = Self::get_criteria_weights(conn)?;
-        println!("⚖️ Pesos dos critérios: OTIF={}, NIL={}, Pickup={}, Package={}", 
-                 criteria_weights.0, criteria_weights.1, criteria_weights.2, criteria_weights.3);
+        // Pesos vigentes no período do arquivo, carregados uma vez para recalcular o total de cada linha
+        let criteria_weights = score_engine::load_weights(conn, export_month, export_year)?;
 
         let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
 
@@ -4340,6 +8636,14 @@ impl DatabaseManager {
             normalized.parse::<f64>().ok()
         }
 
+        fn split_codes(value: &str) -> Vec<String> {
+            value
+                .split([',', ';'])
+                .map(|c| c.trim().to_string())
+                .filter(|c| !c.is_empty())
+                .collect()
+        }
+
         // Itera sobre as linhas (pulando o cabeçalho na linha 0)
         for (row_idx, row) in range.rows().enumerate() {
             if row_idx == 0 {
@@ -4353,13 +8657,16 @@ impl DatabaseManager {
             }
 
             // Lê os dados da linha usando índices atualizados
-            let record_id_opt = row.get(0).and_then(|cell| cell_to_i32(cell));
-            let supplier_id_str = row.get(1).map(|cell| cell_to_string(cell)).unwrap_or_default();
-            let vendor_name_str = row.get(2).map(|cell| cell_to_string(cell)).unwrap_or_default();
-            let _bu_str = row.get(3).map(|cell| cell_to_string(cell)).unwrap_or_default();
-            let supplier_po_str = row.get(4).map(|cell| cell_to_string(cell)).unwrap_or_default();
-            let score_opt = row.get(5).and_then(|cell| cell_to_f64(cell));
-            let comment_str = row.get(6).map(|cell| cell_to_string(cell)).unwrap_or_default();
+            let record_id_opt = row.first().and_then(cell_to_i32);
+            let supplier_id_str = row.get(1).map(cell_to_string).unwrap_or_default();
+            let vendor_name_str = row.get(2).map(cell_to_string).unwrap_or_default();
+            let _bu_str = row.get(3).map(cell_to_string).unwrap_or_default();
+            let _supplier_po_str = row.get(4).map(cell_to_string).unwrap_or_default();
+            let score_opt = row.get(5).and_then(cell_to_f64);
+            let comment_str = row.get(6).map(cell_to_string).unwrap_or_default();
+            let numerator_opt = row.get(7).and_then(cell_to_f64);
+            let denominator_opt = row.get(8).and_then(cell_to_f64);
+            let reason_codes_str = row.get(9).map(cell_to_string).unwrap_or_default();
 
             // Valida supplier_id (necessário para busca se record_id faltar)
             if supplier_id_str.is_empty() {
@@ -4378,17 +8685,19 @@ impl DatabaseManager {
                 }
             };
 
-            // Valida range (0.0 a 10.0)
-            if score < 0.0 || score > 10.0 {
-                println!("⚠️ Score fora do range (0-10) na linha {}: {}", row_idx + 1, score);
-                error_count += 1;
-                continue;
-            }
-
             // Determina o record_id ou busca se necessário
             let mut record_id = record_id_opt.filter(|&id| id > 0);
             let mut is_new_record = false;
 
+            // O record_id do arquivo pode ser de outro mês: respeita o fechamento do período do registro
+            if let Some(id) = record_id {
+                if let Err(e) = periods::ensure_record_open(conn, id as i64) {
+                    validation_errors.push(format!("Linha {} ({}): {}", row_idx + 1, supplier_id_str, e));
+                    error_count += 1;
+                    continue;
+                }
+            }
+
             if record_id.is_none() {
                 println!("🔍 Buscando record_id para supplier {} em {}/{}", supplier_id_str, export_month, export_year);
                 let find_result = conn.query_row(
@@ -4409,77 +8718,71 @@ impl DatabaseManager {
                 }
             }
 
-            let (mut otif, mut nil, mut pickup, mut package) = if !is_new_record {
-                // Busca o registro atual para pegar todos os scores existentes
-                let current_record = conn.query_row(
-                    "SELECT otif, nil, quality_pickup, quality_package FROM supplier_score_records_table WHERE id = ?",
-                    params![record_id.unwrap()],
-                    |r| {
-                        Ok((
-                            r.get::<_, Option<f64>>(0)?,
-                            r.get::<_, Option<f64>>(1)?,
-                            r.get::<_, Option<String>>(2)?,
-                            r.get::<_, Option<String>>(3)?,
-                        ))
-                    }
-                );
+            // Só o critério importado é gravado; os demais critérios sem nota seguem a
+            // política de dados faltantes no cálculo do total (score_engine)
+            let raw_score = row.get(5).map(cell_to_string).unwrap_or_else(|| score.to_string());
+            let values_to_write: Vec<(String, String)> = vec![(criterion_key.clone(), raw_score)];
+            let mut existing_comment: Option<String> = None;
+
+            if !is_new_record {
+                existing_comment = conn
+                    .query_row(
+                        "SELECT comment FROM supplier_score_records_table WHERE id = ?1",
+                        [record_id.unwrap()],
+                        |r| r.get::<_, Option<String>>(0),
+                    )
+                    .ok()
+                    .flatten();
+            }
 
-                match current_record {
-                    Ok(data) => {
-                        println!("📊 Scores atuais do record {}: OTIF={:?}, NIL={:?}, Pickup={:?}, Package={:?}", 
-                                 record_id.unwrap(), data.0, data.1, data.2, data.3);
-                        data
-                    },
-                    Err(e) => {
-                        println!("⚠️ Record {:?} não encontrado no banco: {}", record_id, e);
-                        error_count += 1;
-                        continue;
-                    }
+            // A coluna Comment do formulário é o comentário do critério importado
+            let mut justification = match record_id {
+                Some(id) if !is_new_record => validation::Justification {
+                    comment: existing_comment.clone(),
+                    criterion_comments: comments::latest_by_criterion(conn, id as i64).unwrap_or_default(),
+                    reason_codes: reasons::read_codes_map(conn, id as i64).unwrap_or_default(),
+                },
+                _ => validation::Justification::default(),
+            };
+            if !comment_str.is_empty() {
+                justification.criterion_comments.insert(criterion_key.clone(), comment_str.clone());
+            }
+            // Coluna J: códigos de motivo separados por vírgula (vazia mantém os atuais)
+            let row_reasons = match reasons::check_codes(conn, &criterion_key, &split_codes(&reason_codes_str)) {
+                Ok(codes) => codes,
+                Err(e) => {
+                    validation_errors.push(format!("Linha {} ({}): {}: {}", row_idx + 1, supplier_id_str, validation::REASON_FIELD, e));
+                    error_count += 1;
+                    continue;
                 }
-            } else {
-                // Novos registros começam com 10.0 por padrão para outros campos (conforme regra de negócio observada no OTIF)
-                (Some(10.0), Some(10.0), Some("10.0".to_string()), Some("10.0".to_string()))
             };
-
-            // Atualiza APENAS o score do critério específico que está sendo importado
-            let mut filled_missing = is_new_record;
-            match criteria.to_lowercase().as_str() {
-                "otif" => {
-                    otif = Some(score);
-                    if !is_new_record && nil.is_none() && pickup.is_none() && package.is_none() {
-                        println!("⚙️ Nenhuma nota além de OTIF — preenchendo NIL/Pickup/Package com 10.0");
-                        nil = Some(10.0);
-                        pickup = Some("10.0".to_string());
-                        package = Some("10.0".to_string());
-                        filled_missing = true;
+            if !row_reasons.is_empty() {
+                justification.reason_codes.insert(criterion_key.clone(), row_reasons.clone());
+            }
+            let report = targets::load_supplier_scope(conn, &supplier_id_str).and_then(|scope| {
+                validation::validate_scores(conn, &scope, export_month, export_year, &values_to_write, &justification)
+            });
+            match report {
+                Ok(report) if report.valid => {}
+                Ok(report) => {
+                    for e in report.errors {
+                        validation_errors.push(format!("Linha {} ({}): {}: {}", row_idx + 1, supplier_id_str, e.field, e.message));
                     }
-                },
-                "nil" => nil = Some(score),
-                "pickup" => pickup = Some(score.to_string()),
-                "package" => package = Some(score.to_string()),
-                _ => {}
+                    error_count += 1;
+                    continue;
+                }
+                Err(e) => {
+                    validation_errors.push(format!("Linha {} ({}): {}", row_idx + 1, supplier_id_str, e));
+                    error_count += 1;
+                    continue;
+                }
             }
 
-            // Recalcula o total_score
-            let otif_val = otif.unwrap_or(0.0);
-            let nil_val = nil.unwrap_or(0.0);
-            let pickup_val = pickup.as_ref().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-            let package_val = package.as_ref().and_then(|s| s.parse::<f64>().ok()).unwrap_or(0.0);
-
-            let total_score = 
-                (otif_val * criteria_weights.0) +
-                (nil_val * criteria_weights.1) +
-                (pickup_val * criteria_weights.2) +
-                (package_val * criteria_weights.3);
-
-            let total_score_str = format!("{:.2}", total_score);
-
-            if is_new_record {
+            let target_id = if is_new_record {
                 // INSERT
                 let insert_query = "INSERT INTO supplier_score_records_table 
-                    (supplier_id, supplier_name, month, year, otif, nil, quality_pickup, quality_package, 
-                     total_score, comment, register_date, registered_by, change_date, changed_by)
-                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+                    (supplier_id, supplier_name, month, year, comment, register_date, registered_by, change_date, changed_by)
+                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)";
                 
                 let exec_result = conn.execute(
                     insert_query,
@@ -4488,12 +8791,7 @@ impl DatabaseManager {
                         vendor_name_str,
                         export_month.to_string(),
                         export_year.to_string(),
-                        otif,
-                        nil,
-                        pickup,
-                        package,
-                        total_score_str,
-                        comment_str,
+                        Option::<String>::None,
                         now,
                         "Import System",
                         now,
@@ -4502,51 +8800,60 @@ impl DatabaseManager {
                 );
 
                 match exec_result {
-                    Ok(_) => {
-                        println!("✅ Novo registro criado para supplier {}", supplier_id_str);
-                        updated_count += 1;
-                    },
+                    Ok(_) => conn.last_insert_rowid(),
                     Err(e) => {
                         println!("❌ Erro ao inserir novo registro: {}", e);
                         error_count += 1;
+                        continue;
                     }
                 }
             } else {
                 // UPDATE
-                let update_query = if filled_missing || !comment_str.is_empty() {
-                    format!("UPDATE supplier_score_records_table SET otif = ?, nil = ?, quality_pickup = ?, quality_package = ?, total_score = ?, comment = ?, change_date = ?, changed_by = ? WHERE id = ?")
-                } else {
-                    format!("UPDATE supplier_score_records_table SET {} = ?, total_score = ?, change_date = ?, changed_by = ? WHERE id = ?", score_column)
-                };
-
-                let exec_result = if filled_missing || !comment_str.is_empty() {
-                    conn.execute(
-                        &update_query,
-                        params![otif, nil, pickup, package, total_score_str, comment_str, now, "Import System", record_id.unwrap()]
-                    )
-                } else {
-                    if score_column == "quality_pickup" || score_column == "quality_package" {
-                        conn.execute(
-                            &update_query,
-                            params![score.to_string(), total_score_str, now, "Import System", record_id.unwrap()]
-                        )
-                    } else {
-                        conn.execute(
-                            &update_query,
-                            params![score, total_score_str, now, "Import System", record_id.unwrap()]
-                        )
-                    }
-                };
+                let exec_result = conn.execute(
+                    "UPDATE supplier_score_records_table SET change_date = ?, changed_by = ? WHERE id = ?",
+                    params![now, "Import System", record_id.unwrap()]
+                );
 
                 match exec_result {
-                    Ok(rows_affected) if rows_affected > 0 => {
-                        println!("✅ Record {} atualizado", record_id.unwrap());
-                        updated_count += 1;
-                    },
+                    Ok(rows_affected) if rows_affected > 0 => record_id.unwrap() as i64,
                     _ => {
                         println!("⚠️ Record {:?} não atualizado", record_id);
                         error_count += 1;
+                        continue;
+                    }
+                }
+            };
+
+            // Mantém rótulos e observação já gravados; o formulário só traz os números
+            let row_evidence = ScoreEvidence {
+                criterion_key: criterion_key.clone(),
+                numerator: numerator_opt,
+                denominator: denominator_opt,
+                ..evidence::read(conn, target_id).ok().and_then(|mut e| e.remove(&criterion_key)).unwrap_or_default()
+            };
+            let write_result = values_to_write
+                .iter()
+                .try_for_each(|(key, value)| {
+                    criteria::write_value(conn, target_id, key, value)?;
+                    carryover::clear(conn, target_id, key).map(|_| ())
+                })
+                .and_then(|_| if row_evidence.is_empty() { Ok(()) } else { evidence::write(conn, target_id, &row_evidence, "Import System") })
+                .and_then(|_| {
+                    // O formulário exporta o comentário geral quando o critério não tem um próprio: não duplica
+                    if comment_str.is_empty() || existing_comment.as_deref().map(str::trim) == Some(comment_str.as_str()) {
+                        return Ok(());
                     }
+                    comments::add_if_changed(conn, target_id, Some(&criterion_key), &comment_str, "Import System", None).map(|_| ())
+                })
+                .and_then(|_| if row_reasons.is_empty() { Ok(()) } else { reasons::write_codes(conn, target_id, &criterion_key, &row_reasons, "Import System") })
+                .and_then(|_| workflow::mark_edited(conn, target_id, "Import System", None))
+                .and_then(|_| score_engine::recalculate_record_with_weights(conn, target_id, &criteria_weights));
+
+            match write_result {
+                Ok(_) => updated_count += 1,
+                Err(e) => {
+                    validation_errors.push(format!("Linha {} ({}): {}", row_idx + 1, supplier_id_str, e));
+                    error_count += 1;
                 }
             }
         }
@@ -4556,43 +8863,17 @@ impl DatabaseManager {
         println!("   {} erros encontrados", error_count);
 
         if error_count > 0 {
-            Ok(format!("Processados: {} registros\nErros: {} registros", updated_count, error_count))
+            let mut message = format!("Processados: {} registros\nErros: {} registros", updated_count, error_count);
+            if !validation_errors.is_empty() {
+                message.push_str("\n\n");
+                message.push_str(&validation_errors.join("\n"));
+            }
+            Ok(message)
         } else {
             Ok(format!("✅ {} registros processados com sucesso!", updated_count))
         }
     }
 
-    // Função auxiliar para buscar os pesos dos critérios
-    fn get_criteria_weights(conn: &Connection) -> Result<(f64, f64, f64, f64), String> {
-        let mut stmt = conn
-            .prepare("SELECT criteria_id, criteria_category, value FROM criteria_table WHERE criteria_id <= 4 ORDER BY criteria_id")
-            .map_err(|e| format!("Erro ao buscar pesos: {}", e))?;
-
-        let mut weights = vec![0.0; 4];
-        let rows = stmt
-            .query_map([], |row| {
-                Ok((
-                    row.get::<_, i32>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                ))
-            })
-            .map_err(|e| format!("Erro ao processar pesos: {}", e))?;
-
-        for row_result in rows {
-            if let Ok((criteria_id, category, value_str)) = row_result {
-                let value = value_str.parse::<f64>().unwrap_or(0.0);
-                let idx = (criteria_id - 1) as usize;
-                if idx < 4 {
-                    weights[idx] = value;
-                    println!("  ⚖️ Critério {} ({}): peso = {}", criteria_id, category, value);
-                }
-            }
-        }
-
-        Ok((weights[0], weights[1], weights[2], weights[3]))
-    }
-
     /// Exporta todos os suppliers para Excel com validações
     pub fn export_suppliers() -> Result<Vec<u8>, String> {
         println!("\n📤 ========================================");
@@ -0,0 +1,603 @@
+// Linhas de entrega (PO) e cálculo do OTIF
+// As extrações do ERP são importadas em delivery_lines_table e ligadas ao fornecedor
+// pelo supplier_po. O OTIF mensal considera as linhas com data prometida no mês:
+// no prazo = recebida dentro da janela de tolerância; completa = quantidade recebida
+// dentro da tolerância de quantidade. A nota (0–10) é o percentual OTIF / 10 e o
+// resumo fica em otif_evidence_table como evidência do valor gravado.
+
+use chrono::NaiveDate;
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::tabular;
+
+/// Chave da configuração do cálculo de OTIF na app_settings_table
+pub const OTIF_CONFIG_SETTING: &str = "otif_config";
+
+/// Como tratar linhas entregues no prazo, mas com quantidade abaixo da tolerância
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PartialQuantityRule {
+    /// A linha não conta como OTIF
+    LineFails,
+    /// A linha conta proporcionalmente à quantidade recebida
+    Proportional,
+}
+
+/// Parâmetros do cálculo de OTIF (admin)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct OtifConfig {
+    /// Dias de antecedência aceitos antes da data prometida
+    pub early_tolerance_days: i64,
+    /// Dias de atraso aceitos depois da data prometida
+    pub late_tolerance_days: i64,
+    /// Percentual a menos aceito na quantidade (ex.: 2 = 98% da quantidade já é completa)
+    pub quantity_tolerance_pct: f64,
+    pub partial_quantity_rule: PartialQuantityRule,
+    /// Linhas sem recebimento com data prometida vencida contam como falha
+    pub count_open_past_due: bool,
+}
+
+impl Default for OtifConfig {
+    fn default() -> Self {
+        OtifConfig {
+            early_tolerance_days: 3,
+            late_tolerance_days: 0,
+            quantity_tolerance_pct: 0.0,
+            partial_quantity_rule: PartialQuantityRule::LineFails,
+            count_open_past_due: true,
+        }
+    }
+}
+
+/// Linha de entrega importada
+#[derive(Debug, Serialize, Clone)]
+pub struct DeliveryLine {
+    pub line_id: i64,
+    pub supplier_id: String,
+    pub supplier_po: String,
+    pub po_number: String,
+    pub po_line: String,
+    pub part_number: Option<String>,
+    pub promised_date: String,
+    pub receipt_date: Option<String>,
+    pub ordered_qty: f64,
+    pub received_qty: f64,
+    pub source_file: Option<String>,
+    pub imported_at: Option<String>,
+}
+
+/// Avaliação de uma linha no cálculo do OTIF
+#[derive(Debug, Serialize, Clone)]
+pub struct DeliveryLineResult {
+    pub line: DeliveryLine,
+    /// false = linha ainda em aberto e não vencida (fora do cálculo)
+    pub counted: bool,
+    pub on_time: bool,
+    pub in_full: bool,
+    /// 1 = OTIF, 0 = falha, fração = parcial proporcional
+    pub credit: f64,
+    pub reason: String,
+}
+
+/// OTIF de um fornecedor no mês, com as linhas que o justificam
+#[derive(Debug, Serialize, Clone)]
+pub struct OtifEvidence {
+    pub supplier_id: String,
+    pub month: i32,
+    pub year: i32,
+    pub total_lines: usize,
+    pub counted_lines: usize,
+    pub on_time_lines: usize,
+    pub in_full_lines: usize,
+    pub otif_pct: Option<f64>,
+    pub score: Option<f64>,
+    pub config: OtifConfig,
+    pub calculated_at: Option<String>,
+    pub calculated_by: Option<String>,
+    pub lines: Vec<DeliveryLineResult>,
+}
+
+/// Resultado da importação de um arquivo de linhas de entrega
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct DeliveryImportSummary {
+    pub imported: usize,
+    pub updated: usize,
+    pub unmatched: usize,
+    pub errors: Vec<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS delivery_lines_table (
+            line_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            supplier_id TEXT NOT NULL,
+            supplier_po TEXT NOT NULL,
+            po_number TEXT NOT NULL,
+            po_line TEXT NOT NULL DEFAULT '',
+            part_number TEXT,
+            promised_date TEXT NOT NULL,
+            receipt_date TEXT,
+            ordered_qty REAL NOT NULL,
+            received_qty REAL NOT NULL DEFAULT 0,
+            source_file TEXT,
+            imported_at TEXT,
+            imported_by TEXT,
+            UNIQUE (po_number, po_line, promised_date)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela delivery_lines_table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_delivery_lines_supplier_date ON delivery_lines_table (supplier_id, promised_date)",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar índice de delivery_lines_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS otif_evidence_table (
+            supplier_id TEXT NOT NULL,
+            month INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            total_lines INTEGER NOT NULL,
+            counted_lines INTEGER NOT NULL,
+            on_time_lines INTEGER NOT NULL,
+            in_full_lines INTEGER NOT NULL,
+            otif_pct REAL,
+            score REAL,
+            config TEXT NOT NULL,
+            calculated_at TEXT NOT NULL,
+            calculated_by TEXT NOT NULL,
+            PRIMARY KEY (supplier_id, month, year)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela otif_evidence_table: {}", e))?;
+    Ok(())
+}
+
+pub fn load_config(conn: &Connection) -> Result<OtifConfig, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [OTIF_CONFIG_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler configuração de OTIF: {}", e))?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("Configuração de OTIF inválida no banco: {}", e)),
+        None => Ok(OtifConfig::default()),
+    }
+}
+
+pub fn validate_config(config: &OtifConfig) -> Result<(), String> {
+    if config.early_tolerance_days < 0 || config.late_tolerance_days < 0 {
+        return Err("As tolerâncias de prazo não podem ser negativas".to_string());
+    }
+    if !(0.0..=100.0).contains(&config.quantity_tolerance_pct) {
+        return Err("A tolerância de quantidade deve estar entre 0 e 100%".to_string());
+    }
+    Ok(())
+}
+
+/// supplier_id pelo supplier_po (código do fornecedor no ERP)
+fn supplier_for_po(conn: &Connection, supplier_po: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT CAST(supplier_id AS TEXT) FROM supplier_database_table
+         WHERE trim(CAST(supplier_po AS TEXT)) = trim(?1)
+         LIMIT 1",
+        [supplier_po],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar fornecedor do PO {}: {}", supplier_po, e))
+}
+
+/// Importa uma extração de linhas de PO (CSV ou XLSX). Linhas já existentes
+/// (mesmo PO, item e data prometida) são atualizadas com o recebimento mais recente.
+pub fn import_lines(conn: &Connection, file_name: &str, content: &[u8], imported_by: &str) -> Result<DeliveryImportSummary, String> {
+    let rows = tabular::read_rows(file_name, content)?;
+    let header = rows.first().ok_or_else(|| "Arquivo vazio".to_string())?;
+
+    let column = |aliases: &[&str], label: &str| {
+        tabular::find_column(header, aliases).ok_or_else(|| format!("Coluna obrigatória não encontrada: {}", label))
+    };
+    let col_supplier_po = column(&["Supplier PO", "Vendor", "Vendor Number", "Fornecedor"], "Supplier PO")?;
+    let col_po_number = column(&["PO Number", "PO", "Purchase Order", "Pedido"], "PO Number")?;
+    let col_promised = column(&["Promised Date", "Due Date", "Data Prometida"], "Promised Date")?;
+    let col_ordered = column(&["Ordered Qty", "Order Qty", "Quantidade Pedida"], "Ordered Qty")?;
+    let col_po_line = tabular::find_column(header, &["PO Line", "Line", "Item"]);
+    let col_part = tabular::find_column(header, &["Part Number", "Part", "Material"]);
+    let col_receipt = tabular::find_column(header, &["Receipt Date", "Received Date", "GR Date", "Data Recebimento"]);
+    let col_received = tabular::find_column(header, &["Received Qty", "Receipt Qty", "GR Qty", "Quantidade Recebida"]);
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let mut summary = DeliveryImportSummary::default();
+
+    for (idx, row) in rows.iter().enumerate().skip(1) {
+        let line_no = idx + 1;
+        let cell = |col: usize| row.get(col).map(|v| v.trim().to_string()).unwrap_or_default();
+        let optional = |col: Option<usize>| col.map(&cell).filter(|v| !v.is_empty());
+
+        let supplier_po = cell(col_supplier_po);
+        let po_number = cell(col_po_number);
+        if supplier_po.is_empty() && po_number.is_empty() {
+            continue;
+        }
+        if supplier_po.is_empty() || po_number.is_empty() {
+            summary.errors.push(format!("Linha {}: Supplier PO e PO Number são obrigatórios", line_no));
+            continue;
+        }
+
+        let Some(promised) = tabular::parse_date(&cell(col_promised)) else {
+            summary.errors.push(format!("Linha {}: data prometida inválida '{}'", line_no, cell(col_promised)));
+            continue;
+        };
+        let Some(ordered_qty) = tabular::parse_number(&cell(col_ordered)).filter(|q| *q >= 0.0) else {
+            summary.errors.push(format!("Linha {}: quantidade pedida inválida '{}'", line_no, cell(col_ordered)));
+            continue;
+        };
+        let receipt = match optional(col_receipt) {
+            Some(text) => match tabular::parse_date(&text) {
+                Some(date) => Some(date),
+                None => {
+                    summary.errors.push(format!("Linha {}: data de recebimento inválida '{}'", line_no, text));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let received_qty = match optional(col_received) {
+            Some(text) => match tabular::parse_number(&text).filter(|q| *q >= 0.0) {
+                Some(qty) => qty,
+                None => {
+                    summary.errors.push(format!("Linha {}: quantidade recebida inválida '{}'", line_no, text));
+                    continue;
+                }
+            },
+            None => 0.0,
+        };
+
+        let Some(supplier_id) = supplier_for_po(conn, &supplier_po)? else {
+            summary.unmatched += 1;
+            summary.errors.push(format!("Linha {}: nenhum fornecedor com Supplier PO '{}'", line_no, supplier_po));
+            continue;
+        };
+
+        let po_line = optional(col_po_line).unwrap_or_default();
+        let promised_text = promised.format("%Y-%m-%d").to_string();
+        let exists = conn
+            .query_row(
+                "SELECT 1 FROM delivery_lines_table WHERE po_number = ?1 AND po_line = ?2 AND promised_date = ?3",
+                rusqlite::params![po_number, po_line, promised_text],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar linha de entrega: {}", e))?
+            .is_some();
+
+        conn.execute(
+            "INSERT INTO delivery_lines_table
+             (supplier_id, supplier_po, po_number, po_line, part_number, promised_date, receipt_date,
+              ordered_qty, received_qty, source_file, imported_at, imported_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)
+             ON CONFLICT(po_number, po_line, promised_date) DO UPDATE SET
+                supplier_id = excluded.supplier_id, supplier_po = excluded.supplier_po,
+                part_number = excluded.part_number, receipt_date = excluded.receipt_date,
+                ordered_qty = excluded.ordered_qty, received_qty = excluded.received_qty,
+                source_file = excluded.source_file, imported_at = excluded.imported_at,
+                imported_by = excluded.imported_by",
+            rusqlite::params![
+                supplier_id,
+                supplier_po,
+                po_number,
+                po_line,
+                optional(col_part),
+                promised_text,
+                receipt.map(|d| d.format("%Y-%m-%d").to_string()),
+                ordered_qty,
+                received_qty,
+                file_name,
+                now,
+                imported_by
+            ],
+        )
+        .map_err(|e| format!("Erro ao gravar linha {}: {}", line_no, e))?;
+
+        if exists {
+            summary.updated += 1;
+        } else {
+            summary.imported += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Linhas do fornecedor com data prometida no mês
+pub fn load_lines(conn: &Connection, supplier_id: &str, month: i32, year: i32) -> Result<Vec<DeliveryLine>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT line_id, supplier_id, supplier_po, po_number, po_line, part_number, promised_date, receipt_date,
+                    ordered_qty, received_qty, source_file, imported_at
+             FROM delivery_lines_table
+             WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND substr(promised_date, 1, 7) = ?2
+             ORDER BY promised_date, po_number, po_line",
+        )
+        .map_err(|e| format!("Erro ao buscar linhas de entrega: {}", e))?;
+    let lines = stmt
+        .query_map(rusqlite::params![supplier_id, crate::criteria::period_key(month, year)], |row| {
+            Ok(DeliveryLine {
+                line_id: row.get(0)?,
+                supplier_id: row.get(1)?,
+                supplier_po: row.get(2)?,
+                po_number: row.get(3)?,
+                po_line: row.get(4)?,
+                part_number: row.get(5)?,
+                promised_date: row.get(6)?,
+                receipt_date: row.get(7)?,
+                ordered_qty: row.get(8)?,
+                received_qty: row.get(9)?,
+                source_file: row.get(10)?,
+                imported_at: row.get(11)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar linhas de entrega: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar linhas de entrega: {}", e))?;
+    Ok(lines)
+}
+
+/// Fornecedores com linhas de entrega prometidas no mês
+pub fn suppliers_with_lines(conn: &Connection, month: i32, year: i32) -> Result<Vec<String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT DISTINCT supplier_id FROM delivery_lines_table
+             WHERE substr(promised_date, 1, 7) = ?1
+             ORDER BY supplier_id",
+        )
+        .map_err(|e| format!("Erro ao buscar fornecedores com entregas: {}", e))?;
+    let suppliers = stmt
+        .query_map([crate::criteria::period_key(month, year)], |row| row.get::<_, String>(0))
+        .map_err(|e| format!("Erro ao processar fornecedores com entregas: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar fornecedores com entregas: {}", e))?;
+    Ok(suppliers)
+}
+
+fn evaluate_line(line: DeliveryLine, config: &OtifConfig, today: NaiveDate) -> DeliveryLineResult {
+    let promised = tabular::parse_date(&line.promised_date);
+    let receipt = line.receipt_date.as_deref().and_then(tabular::parse_date);
+
+    let (Some(promised), Some(receipt)) = (promised, receipt) else {
+        let past_due = promised.map(|p| p < today).unwrap_or(false);
+        let counted = past_due && config.count_open_past_due;
+        return DeliveryLineResult {
+            line,
+            counted,
+            on_time: false,
+            in_full: false,
+            credit: 0.0,
+            reason: if counted { "Em aberto e vencida".to_string() } else { "Em aberto (fora do cálculo)".to_string() },
+        };
+    };
+
+    let days = (receipt - promised).num_days();
+    let on_time = days >= -config.early_tolerance_days && days <= config.late_tolerance_days;
+    let fill_ratio = if line.ordered_qty <= 0.0 { 1.0 } else { (line.received_qty / line.ordered_qty).min(1.0) };
+    let in_full = fill_ratio >= 1.0 - config.quantity_tolerance_pct / 100.0;
+
+    let credit = match (on_time, in_full, config.partial_quantity_rule) {
+        (true, true, _) => 1.0,
+        (true, false, PartialQuantityRule::Proportional) => fill_ratio,
+        _ => 0.0,
+    };
+
+    let timing = if on_time {
+        "no prazo".to_string()
+    } else if days < 0 {
+        format!("{} dia(s) adiantada", -days)
+    } else {
+        format!("{} dia(s) atrasada", days)
+    };
+    let quantity = if in_full {
+        "completa".to_string()
+    } else {
+        format!("parcial ({:.0}%)", fill_ratio * 100.0)
+    };
+
+    DeliveryLineResult {
+        line,
+        counted: true,
+        on_time,
+        in_full,
+        credit,
+        reason: format!("{}, {}", timing, quantity),
+    }
+}
+
+/// Calcula o OTIF do fornecedor no mês (sem gravar)
+pub fn calculate(conn: &Connection, supplier_id: &str, month: i32, year: i32, config: &OtifConfig) -> Result<OtifEvidence, String> {
+    let today = chrono::Local::now().date_naive();
+    let lines: Vec<DeliveryLineResult> = load_lines(conn, supplier_id, month, year)?
+        .into_iter()
+        .map(|line| evaluate_line(line, config, today))
+        .collect();
+
+    let counted: Vec<&DeliveryLineResult> = lines.iter().filter(|l| l.counted).collect();
+    let otif_pct = if counted.is_empty() {
+        None
+    } else {
+        let credit: f64 = counted.iter().map(|l| l.credit).sum();
+        Some((credit / counted.len() as f64 * 100.0 * 100.0).round() / 100.0)
+    };
+
+    Ok(OtifEvidence {
+        supplier_id: supplier_id.to_string(),
+        month,
+        year,
+        total_lines: lines.len(),
+        counted_lines: counted.len(),
+        on_time_lines: counted.iter().filter(|l| l.on_time).count(),
+        in_full_lines: counted.iter().filter(|l| l.in_full).count(),
+        otif_pct,
+        score: otif_pct.map(|pct| (pct / 10.0 * 100.0).round() / 100.0),
+        config: config.clone(),
+        calculated_at: None,
+        calculated_by: None,
+        lines,
+    })
+}
+
+/// Guarda o resumo do cálculo usado para gravar a nota
+pub fn save_evidence(conn: &Connection, evidence: &OtifEvidence, calculated_by: &str) -> Result<(), String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let config = serde_json::to_string(&evidence.config).map_err(|e| format!("Erro ao serializar configuração de OTIF: {}", e))?;
+    conn.execute(
+        "INSERT OR REPLACE INTO otif_evidence_table
+         (supplier_id, month, year, total_lines, counted_lines, on_time_lines, in_full_lines, otif_pct, score, config, calculated_at, calculated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        rusqlite::params![
+            evidence.supplier_id,
+            evidence.month,
+            evidence.year,
+            evidence.total_lines as i64,
+            evidence.counted_lines as i64,
+            evidence.on_time_lines as i64,
+            evidence.in_full_lines as i64,
+            evidence.otif_pct,
+            evidence.score,
+            config,
+            now,
+            calculated_by
+        ],
+    )
+    .map_err(|e| format!("Erro ao gravar evidência de OTIF: {}", e))?;
+    Ok(())
+}
+
+/// Evidência gravada do mês: refaz o detalhe das linhas com a configuração usada no cálculo
+pub fn load_evidence(conn: &Connection, supplier_id: &str, month: i32, year: i32) -> Result<Option<OtifEvidence>, String> {
+    let stored = conn
+        .query_row(
+            "SELECT config, calculated_at, calculated_by FROM otif_evidence_table
+             WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND month = ?2 AND year = ?3",
+            rusqlite::params![supplier_id, month, year],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar evidência de OTIF: {}", e))?;
+
+    let Some((config, calculated_at, calculated_by)) = stored else {
+        return Ok(None);
+    };
+    let config: OtifConfig = serde_json::from_str(&config).unwrap_or_default();
+    let mut evidence = calculate(conn, supplier_id, month, year, &config)?;
+    evidence.calculated_at = Some(calculated_at);
+    evidence.calculated_by = Some(calculated_by);
+    Ok(Some(evidence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(promised: &str, receipt: Option<&str>, ordered: f64, received: f64) -> DeliveryLine {
+        DeliveryLine {
+            line_id: 0,
+            supplier_id: "S1".to_string(),
+            supplier_po: "PO-S1".to_string(),
+            po_number: "4500".to_string(),
+            po_line: "10".to_string(),
+            part_number: None,
+            promised_date: promised.to_string(),
+            receipt_date: receipt.map(str::to_string),
+            ordered_qty: ordered,
+            received_qty: received,
+            source_file: None,
+            imported_at: None,
+        }
+    }
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 5, 20).unwrap()
+    }
+
+    #[test]
+    fn evaluate_line_applies_date_tolerances() {
+        let config = OtifConfig { late_tolerance_days: 1, ..OtifConfig::default() };
+        let early_ok = evaluate_line(line("2024-05-10", Some("2024-05-07"), 10.0, 10.0), &config, today());
+        assert!(early_ok.on_time && early_ok.credit == 1.0);
+        let too_early = evaluate_line(line("2024-05-10", Some("2024-05-06"), 10.0, 10.0), &config, today());
+        assert!(!too_early.on_time && too_early.credit == 0.0);
+        let late_ok = evaluate_line(line("2024-05-10", Some("2024-05-11"), 10.0, 10.0), &config, today());
+        assert!(late_ok.on_time);
+        let too_late = evaluate_line(line("2024-05-10", Some("2024-05-12"), 10.0, 10.0), &config, today());
+        assert!(!too_late.on_time);
+        assert_eq!(too_late.reason, "2 dia(s) atrasada, completa");
+    }
+
+    #[test]
+    fn evaluate_line_partial_quantity_rules() {
+        let partial = line("2024-05-10", Some("2024-05-10"), 10.0, 8.0);
+        let fails = evaluate_line(partial.clone(), &OtifConfig::default(), today());
+        assert!(fails.on_time && !fails.in_full);
+        assert_eq!(fails.credit, 0.0);
+
+        let proportional = OtifConfig { partial_quantity_rule: PartialQuantityRule::Proportional, ..OtifConfig::default() };
+        let result = evaluate_line(partial.clone(), &proportional, today());
+        assert!((result.credit - 0.8).abs() < 1e-9);
+
+        let tolerant = OtifConfig { quantity_tolerance_pct: 20.0, ..OtifConfig::default() };
+        let result = evaluate_line(partial, &tolerant, today());
+        assert!(result.in_full && result.credit == 1.0);
+    }
+
+    #[test]
+    fn evaluate_line_open_lines_count_only_when_past_due() {
+        let config = OtifConfig::default();
+        let past_due = evaluate_line(line("2024-05-10", None, 10.0, 0.0), &config, today());
+        assert!(past_due.counted && past_due.credit == 0.0);
+        let not_due = evaluate_line(line("2024-05-25", None, 10.0, 0.0), &config, today());
+        assert!(!not_due.counted);
+
+        let ignore_open = OtifConfig { count_open_past_due: false, ..OtifConfig::default() };
+        assert!(!evaluate_line(line("2024-05-10", None, 10.0, 0.0), &ignore_open, today()).counted);
+    }
+
+    #[test]
+    fn calculate_summarizes_lines_of_the_month() {
+        let conn = Connection::open_in_memory().unwrap();
+        create_tables(&conn).unwrap();
+        let lines = [
+            ("S1", "1", "2024-05-02", Some("2024-05-02"), 10.0, 10.0),
+            ("S1", "2", "2024-05-10", Some("2024-05-15"), 10.0, 10.0),
+            ("S1", "3", "2024-05-20", Some("2024-05-20"), 10.0, 5.0),
+            ("S1", "4", "2024-05-28", None, 10.0, 0.0),
+            ("S1", "5", "2024-06-01", Some("2024-06-01"), 10.0, 10.0),
+            ("S2", "6", "2024-05-02", Some("2024-05-02"), 10.0, 10.0),
+        ];
+        for (supplier_id, po_line, promised, receipt, ordered, received) in lines {
+            conn.execute(
+                "INSERT INTO delivery_lines_table (supplier_id, supplier_po, po_number, po_line, promised_date, receipt_date, ordered_qty, received_qty)
+                 VALUES (?1, ?1, '4500', ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![supplier_id, po_line, promised, receipt, ordered, received],
+            )
+            .unwrap();
+        }
+
+        let evidence = calculate(&conn, " s1 ", 5, 2024, &OtifConfig::default()).unwrap();
+        assert_eq!(evidence.total_lines, 4);
+        assert_eq!(evidence.counted_lines, 4);
+        assert_eq!(evidence.on_time_lines, 2);
+        assert_eq!(evidence.in_full_lines, 2);
+        assert_eq!(evidence.otif_pct, Some(25.0));
+        assert_eq!(evidence.score, Some(2.5));
+
+        let empty = calculate(&conn, "S3", 5, 2024, &OtifConfig::default()).unwrap();
+        assert_eq!((empty.total_lines, empty.otif_pct, empty.score), (0, None, None));
+    }
+}
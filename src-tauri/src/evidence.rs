@@ -0,0 +1,164 @@
+// Evidência numérica das notas (numerador / denominador por critério e registro)
+// Guarda como cada nota foi obtida: linhas no prazo x linhas totais (OTIF),
+// incidentes (NIL), cargas inspecionadas (pickup/package) etc. Todos os campos são
+// opcionais; a evidência não entra no cálculo do total.
+
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Evidência de um critério em um registro de nota
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ScoreEvidence {
+    #[serde(default)]
+    pub criterion_key: String,
+    #[serde(default)]
+    pub numerator: Option<f64>,
+    #[serde(default)]
+    pub denominator: Option<f64>,
+    /// Ex.: "linhas no prazo", "incidentes"
+    #[serde(default)]
+    pub numerator_label: Option<String>,
+    /// Ex.: "linhas totais", "cargas inspecionadas"
+    #[serde(default)]
+    pub denominator_label: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+impl ScoreEvidence {
+    /// Sem nenhum dado informado (gravar vazio remove a evidência)
+    pub fn is_empty(&self) -> bool {
+        self.numerator.is_none()
+            && self.denominator.is_none()
+            && self.note.as_deref().map(|n| n.trim().is_empty()).unwrap_or(true)
+    }
+
+    /// Texto curto para log e exportação, ex.: "45/60 (linhas no prazo/linhas totais)"
+    pub fn describe(&self) -> String {
+        let number = |v: Option<f64>| v.map(|v| format!("{}", v)).unwrap_or_else(|| "-".to_string());
+        let mut text = match self.denominator {
+            Some(_) => format!("{}/{}", number(self.numerator), number(self.denominator)),
+            None => number(self.numerator),
+        };
+        match (&self.numerator_label, &self.denominator_label) {
+            (Some(n), Some(d)) => text.push_str(&format!(" ({}/{})", n, d)),
+            (Some(n), None) => text.push_str(&format!(" ({})", n)),
+            (None, Some(d)) => text.push_str(&format!(" (/{})", d)),
+            (None, None) => {}
+        }
+        if let Some(note) = self.note.as_deref().filter(|n| !n.trim().is_empty()) {
+            text.push_str(&format!(" - {}", note.trim()));
+        }
+        text
+    }
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS supplier_score_evidence_table (
+            record_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            numerator REAL,
+            denominator REAL,
+            numerator_label TEXT,
+            denominator_label TEXT,
+            note TEXT,
+            updated_at TEXT,
+            updated_by TEXT,
+            PRIMARY KEY (record_id, criterion_key)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela supplier_score_evidence_table: {}", e))?;
+    Ok(())
+}
+
+/// Números não negativos; denominador maior que zero e não menor que o numerador
+pub fn validate(evidence: &ScoreEvidence) -> Result<(), String> {
+    for (label, value) in [("numerador", evidence.numerator), ("denominador", evidence.denominator)] {
+        if let Some(v) = value {
+            if !v.is_finite() || v < 0.0 {
+                return Err(format!("{}: {} inválido ({})", evidence.criterion_key, label, v));
+            }
+        }
+    }
+    if let Some(denominator) = evidence.denominator {
+        if denominator == 0.0 {
+            return Err(format!("{}: denominador deve ser maior que zero", evidence.criterion_key));
+        }
+        if evidence.numerator.map(|n| n > denominator).unwrap_or(false) {
+            return Err(format!("{}: numerador maior que o denominador", evidence.criterion_key));
+        }
+    }
+    Ok(())
+}
+
+/// Grava (ou remove, se vazia) a evidência do critério no registro
+pub fn write(conn: &Connection, record_id: i64, evidence: &ScoreEvidence, updated_by: &str) -> Result<(), String> {
+    validate(evidence)?;
+
+    if evidence.is_empty() {
+        conn.execute(
+            "DELETE FROM supplier_score_evidence_table WHERE record_id = ?1 AND criterion_key = ?2",
+            rusqlite::params![record_id, evidence.criterion_key],
+        )
+        .map_err(|e| format!("Erro ao remover evidência {}: {}", evidence.criterion_key, e))?;
+        return Ok(());
+    }
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO supplier_score_evidence_table
+         (record_id, criterion_key, numerator, denominator, numerator_label, denominator_label, note, updated_at, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+         ON CONFLICT(record_id, criterion_key) DO UPDATE SET
+            numerator = excluded.numerator, denominator = excluded.denominator,
+            numerator_label = excluded.numerator_label, denominator_label = excluded.denominator_label,
+            note = excluded.note, updated_at = excluded.updated_at, updated_by = excluded.updated_by",
+        rusqlite::params![
+            record_id,
+            evidence.criterion_key,
+            evidence.numerator,
+            evidence.denominator,
+            evidence.numerator_label,
+            evidence.denominator_label,
+            evidence.note,
+            now,
+            updated_by
+        ],
+    )
+    .map_err(|e| format!("Erro ao gravar evidência {}: {}", evidence.criterion_key, e))?;
+    Ok(())
+}
+
+/// Evidências do registro por critério
+pub fn read(conn: &Connection, record_id: i64) -> Result<BTreeMap<String, ScoreEvidence>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT criterion_key, numerator, denominator, numerator_label, denominator_label, note
+             FROM supplier_score_evidence_table WHERE record_id = ?1",
+        )
+        .map_err(|e| format!("Erro ao buscar evidências: {}", e))?;
+    let rows = stmt
+        .query_map([record_id], |row| {
+            Ok(ScoreEvidence {
+                criterion_key: row.get(0)?,
+                numerator: row.get(1)?,
+                denominator: row.get(2)?,
+                numerator_label: row.get(3)?,
+                denominator_label: row.get(4)?,
+                note: row.get(5)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar evidências: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar evidências: {}", e))?;
+    Ok(rows.into_iter().map(|e| (e.criterion_key.clone(), e)).collect())
+}
+
+pub fn delete_for_record(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM supplier_score_evidence_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao remover evidências do registro {}: {}", record_id, e))?;
+    Ok(())
+}
@@ -0,0 +1,394 @@
+// Registro de incidentes NIL e cálculo da nota NIL mensal
+// Cada incidente desconta pontos conforme a severidade; a nota do mês parte da nota
+// base e não fica abaixo do mínimo. Com muitos incidentes no mês a nota pode ir
+// direto para o mínimo. A lista de incidentes do mês é a evidência da nota.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::tabular;
+
+/// Chave das regras de cálculo do NIL na app_settings_table
+pub const NIL_RULES_SETTING: &str = "nil_rules";
+
+pub const SEVERITIES: [&str; 4] = ["low", "medium", "high", "critical"];
+
+/// Incidente NIL de um fornecedor
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NilIncident {
+    #[serde(default)]
+    pub incident_id: i64,
+    pub supplier_id: String,
+    /// AAAA-MM-DD
+    pub incident_date: String,
+    #[serde(default)]
+    pub part_number: Option<String>,
+    #[serde(default)]
+    pub quantity: Option<f64>,
+    /// low, medium, high ou critical
+    pub severity: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub updated_by: Option<String>,
+}
+
+/// Regras que transformam os incidentes do mês na nota NIL
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NilRules {
+    /// Nota de um mês sem incidentes
+    pub base_score: f64,
+    pub min_score: f64,
+    /// Pontos descontados por incidente de cada severidade
+    pub severity_points: BTreeMap<String, f64>,
+    /// A partir desta quantidade de incidentes no mês a nota vai para o mínimo
+    #[serde(default)]
+    pub incidents_for_min_score: Option<u32>,
+}
+
+impl Default for NilRules {
+    fn default() -> Self {
+        NilRules {
+            base_score: 10.0,
+            min_score: 0.0,
+            severity_points: [("low", 0.5), ("medium", 1.0), ("high", 2.0), ("critical", 5.0)]
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v))
+                .collect(),
+            incidents_for_min_score: None,
+        }
+    }
+}
+
+/// Nota NIL do mês e os incidentes que a justificam
+#[derive(Debug, Serialize, Clone)]
+pub struct NilEvidence {
+    pub supplier_id: String,
+    pub month: i32,
+    pub year: i32,
+    pub incident_count: usize,
+    pub by_severity: BTreeMap<String, usize>,
+    pub deduction: f64,
+    pub score: f64,
+    pub rules: NilRules,
+    pub incidents: Vec<NilIncident>,
+}
+
+/// Resultado da importação de incidentes
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct NilImportSummary {
+    pub imported: usize,
+    pub errors: Vec<String>,
+    /// (fornecedor, mês, ano) afetados, para recalcular a nota
+    #[serde(skip)]
+    pub affected: Vec<(String, i32, i32)>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS nil_incident_table (
+            incident_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            supplier_id TEXT NOT NULL,
+            incident_date TEXT NOT NULL,
+            part_number TEXT,
+            quantity REAL,
+            severity TEXT NOT NULL,
+            description TEXT,
+            created_at TEXT,
+            created_by TEXT,
+            updated_at TEXT,
+            updated_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela nil_incident_table: {}", e))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_nil_incident_supplier_date ON nil_incident_table (supplier_id, incident_date)",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar índice de nil_incident_table: {}", e))?;
+    Ok(())
+}
+
+pub fn load_rules(conn: &Connection) -> Result<NilRules, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [NIL_RULES_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler regras de NIL: {}", e))?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("Regras de NIL inválidas no banco: {}", e)),
+        None => Ok(NilRules::default()),
+    }
+}
+
+pub fn validate_rules(rules: &NilRules) -> Result<(), String> {
+    if rules.min_score > rules.base_score {
+        return Err("A nota mínima não pode ser maior que a nota base".to_string());
+    }
+    for severity in SEVERITIES {
+        match rules.severity_points.get(severity) {
+            Some(points) if *points >= 0.0 => {}
+            Some(_) => return Err(format!("Desconto negativo para a severidade {}", severity)),
+            None => return Err(format!("Informe o desconto da severidade {}", severity)),
+        }
+    }
+    Ok(())
+}
+
+/// Normaliza e valida o incidente; devolve (mês, ano) do incidente
+pub fn normalize(incident: &mut NilIncident) -> Result<(i32, i32), String> {
+    incident.supplier_id = incident.supplier_id.trim().to_string();
+    if incident.supplier_id.is_empty() {
+        return Err("Informe o fornecedor do incidente".to_string());
+    }
+
+    let date = tabular::parse_date(&incident.incident_date)
+        .ok_or_else(|| format!("Data do incidente inválida: {}", incident.incident_date))?;
+    incident.incident_date = date.format("%Y-%m-%d").to_string();
+
+    incident.severity = incident.severity.trim().to_lowercase();
+    if !SEVERITIES.contains(&incident.severity.as_str()) {
+        return Err(format!("Severidade inválida: {} (use low, medium, high ou critical)", incident.severity));
+    }
+    if incident.quantity.map(|q| q < 0.0 || !q.is_finite()).unwrap_or(false) {
+        return Err("A quantidade do incidente não pode ser negativa".to_string());
+    }
+
+    use chrono::Datelike;
+    Ok((date.month() as i32, date.year()))
+}
+
+fn row_to_incident(row: &rusqlite::Row) -> rusqlite::Result<NilIncident> {
+    Ok(NilIncident {
+        incident_id: row.get(0)?,
+        supplier_id: row.get(1)?,
+        incident_date: row.get(2)?,
+        part_number: row.get(3)?,
+        quantity: row.get(4)?,
+        severity: row.get(5)?,
+        description: row.get(6)?,
+        created_at: row.get(7)?,
+        created_by: row.get(8)?,
+        updated_at: row.get(9)?,
+        updated_by: row.get(10)?,
+    })
+}
+
+const INCIDENT_COLUMNS: &str = "incident_id, supplier_id, incident_date, part_number, quantity, severity, description,
+                                created_at, created_by, updated_at, updated_by";
+
+pub fn find(conn: &Connection, incident_id: i64) -> Result<Option<NilIncident>, String> {
+    conn.query_row(
+        &format!("SELECT {} FROM nil_incident_table WHERE incident_id = ?1", INCIDENT_COLUMNS),
+        [incident_id],
+        row_to_incident,
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar incidente {}: {}", incident_id, e))
+}
+
+/// Incidentes filtrados por fornecedor e/ou mês
+pub fn load(conn: &Connection, supplier_id: Option<&str>, period: Option<&str>) -> Result<Vec<NilIncident>, String> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT {} FROM nil_incident_table
+             WHERE (?1 IS NULL OR lower(trim(supplier_id)) = lower(trim(?1)))
+               AND (?2 IS NULL OR substr(incident_date, 1, 7) = ?2)
+             ORDER BY incident_date DESC, incident_id DESC",
+            INCIDENT_COLUMNS
+        ))
+        .map_err(|e| format!("Erro ao buscar incidentes: {}", e))?;
+    let incidents = stmt
+        .query_map(rusqlite::params![supplier_id, period], row_to_incident)
+        .map_err(|e| format!("Erro ao processar incidentes: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar incidentes: {}", e))?;
+    Ok(incidents)
+}
+
+/// Insere (incident_id = 0) ou atualiza o incidente; devolve o id
+pub fn save(conn: &Connection, incident: &NilIncident, user_name: &str) -> Result<i64, String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    if incident.incident_id > 0 {
+        let rows = conn
+            .execute(
+                "UPDATE nil_incident_table
+                 SET supplier_id = ?1, incident_date = ?2, part_number = ?3, quantity = ?4, severity = ?5,
+                     description = ?6, updated_at = ?7, updated_by = ?8
+                 WHERE incident_id = ?9",
+                rusqlite::params![
+                    incident.supplier_id,
+                    incident.incident_date,
+                    incident.part_number,
+                    incident.quantity,
+                    incident.severity,
+                    incident.description,
+                    now,
+                    user_name,
+                    incident.incident_id
+                ],
+            )
+            .map_err(|e| format!("Erro ao atualizar incidente: {}", e))?;
+        if rows == 0 {
+            return Err(format!("Incidente {} não encontrado", incident.incident_id));
+        }
+        return Ok(incident.incident_id);
+    }
+
+    conn.execute(
+        "INSERT INTO nil_incident_table
+         (supplier_id, incident_date, part_number, quantity, severity, description, created_at, created_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![
+            incident.supplier_id,
+            incident.incident_date,
+            incident.part_number,
+            incident.quantity,
+            incident.severity,
+            incident.description,
+            now,
+            user_name
+        ],
+    )
+    .map_err(|e| format!("Erro ao inserir incidente: {}", e))?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn delete(conn: &Connection, incident_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM nil_incident_table WHERE incident_id = ?1", [incident_id])
+        .map_err(|e| format!("Erro ao excluir incidente: {}", e))?;
+    Ok(())
+}
+
+/// Importa incidentes de uma planilha (ou CSV). O fornecedor pode vir pelo Supplier ID ou pelo Supplier PO.
+pub fn import(conn: &Connection, file_name: &str, content: &[u8], user_name: &str) -> Result<NilImportSummary, String> {
+    let rows = tabular::read_rows(file_name, content)?;
+    let header = rows.first().ok_or_else(|| "Arquivo vazio".to_string())?;
+
+    let col_supplier = tabular::find_column(header, &["Supplier ID", "Supplier", "Fornecedor"]);
+    let col_supplier_po = tabular::find_column(header, &["Supplier PO", "Vendor"]);
+    if col_supplier.is_none() && col_supplier_po.is_none() {
+        return Err("Coluna obrigatória não encontrada: Supplier ID ou Supplier PO".to_string());
+    }
+    let col_date = tabular::find_column(header, &["Date", "Incident Date", "Data"])
+        .ok_or_else(|| "Coluna obrigatória não encontrada: Date".to_string())?;
+    let col_severity = tabular::find_column(header, &["Severity", "Severidade"])
+        .ok_or_else(|| "Coluna obrigatória não encontrada: Severity".to_string())?;
+    let col_part = tabular::find_column(header, &["Part Number", "Part", "Material"]);
+    let col_quantity = tabular::find_column(header, &["Quantity", "Qty", "Quantidade"]);
+    let col_description = tabular::find_column(header, &["Description", "Descrição", "Descricao"]);
+
+    let mut summary = NilImportSummary::default();
+    for (idx, row) in rows.iter().enumerate().skip(1) {
+        let line_no = idx + 1;
+        let optional = |col: Option<usize>| col.and_then(|c| row.get(c)).map(|v| v.trim().to_string()).filter(|v| !v.is_empty());
+
+        let supplier_id = match (optional(col_supplier), optional(col_supplier_po)) {
+            (Some(id), _) => Some(id),
+            (None, Some(po)) => conn
+                .query_row(
+                    "SELECT CAST(supplier_id AS TEXT) FROM supplier_database_table WHERE trim(CAST(supplier_po AS TEXT)) = trim(?1) LIMIT 1",
+                    [&po],
+                    |r| r.get::<_, String>(0),
+                )
+                .optional()
+                .map_err(|e| format!("Erro ao buscar fornecedor do PO {}: {}", po, e))?,
+            (None, None) => None,
+        };
+        let Some(supplier_id) = supplier_id else {
+            if row.iter().all(|c| c.trim().is_empty()) {
+                continue;
+            }
+            summary.errors.push(format!("Linha {}: fornecedor não encontrado", line_no));
+            continue;
+        };
+
+        let quantity = match optional(col_quantity) {
+            Some(text) => match tabular::parse_number(&text) {
+                Some(q) => Some(q),
+                None => {
+                    summary.errors.push(format!("Linha {}: quantidade inválida '{}'", line_no, text));
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let mut incident = NilIncident {
+            incident_id: 0,
+            supplier_id,
+            incident_date: optional(Some(col_date)).unwrap_or_default(),
+            part_number: optional(col_part),
+            quantity,
+            severity: optional(Some(col_severity)).unwrap_or_default(),
+            description: optional(col_description),
+            created_at: None,
+            created_by: None,
+            updated_at: None,
+            updated_by: None,
+        };
+        let (month, year) = match normalize(&mut incident) {
+            Ok(period) => period,
+            Err(e) => {
+                summary.errors.push(format!("Linha {}: {}", line_no, e));
+                continue;
+            }
+        };
+
+        save(conn, &incident, user_name)?;
+        summary.imported += 1;
+        let key = (incident.supplier_id.clone(), month, year);
+        if !summary.affected.contains(&key) {
+            summary.affected.push(key);
+        }
+    }
+
+    Ok(summary)
+}
+
+/// Aplica as regras aos incidentes do fornecedor no mês
+pub fn evaluate(conn: &Connection, supplier_id: &str, month: i32, year: i32, rules: &NilRules) -> Result<NilEvidence, String> {
+    let incidents = load(conn, Some(supplier_id), Some(&crate::criteria::period_key(month, year)))?;
+
+    let mut by_severity: BTreeMap<String, usize> = BTreeMap::new();
+    let mut deduction = 0.0;
+    for incident in &incidents {
+        *by_severity.entry(incident.severity.clone()).or_default() += 1;
+        deduction += rules.severity_points.get(&incident.severity).copied().unwrap_or(0.0);
+    }
+
+    let reached_limit = rules
+        .incidents_for_min_score
+        .map(|limit| limit > 0 && incidents.len() >= limit as usize)
+        .unwrap_or(false);
+    let score = if reached_limit {
+        rules.min_score
+    } else {
+        (rules.base_score - deduction).max(rules.min_score)
+    };
+
+    Ok(NilEvidence {
+        supplier_id: supplier_id.to_string(),
+        month,
+        year,
+        incident_count: incidents.len(),
+        by_severity,
+        deduction,
+        score: (score * 100.0).round() / 100.0,
+        rules: rules.clone(),
+        incidents,
+    })
+}
@@ -0,0 +1,553 @@
+// Checklists de inspeção (pickup / package) e a nota mensal derivada delas
+// Cada checklist alimenta um critério. Uma inspeção responde os itens do checklist
+// para um embarque; a nota da inspeção (0–10) é a média ponderada dos itens
+// respondidos (aprovado = 1, reprovado = 0, graduado = nota / nota máxima).
+// A nota mensal do critério é a média das inspeções do fornecedor no mês.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::tabular;
+
+pub const ANSWER_PASS_FAIL: &str = "pass_fail";
+pub const ANSWER_GRADED: &str = "graded";
+
+fn default_active() -> bool {
+    true
+}
+
+/// Item de checklist
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ChecklistItem {
+    #[serde(default)]
+    pub item_id: i64,
+    pub description: String,
+    pub weight: f64,
+    /// pass_fail ou graded
+    pub answer_type: String,
+    /// Nota máxima dos itens graduados
+    #[serde(default)]
+    pub max_grade: Option<f64>,
+    #[serde(default)]
+    pub sort_order: i32,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+/// Checklist de inspeção ligado a um critério
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InspectionChecklist {
+    #[serde(default)]
+    pub checklist_id: i64,
+    pub name: String,
+    pub criterion_key: String,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+    #[serde(default)]
+    pub items: Vec<ChecklistItem>,
+}
+
+/// Resposta de um item em uma inspeção (sem passed/grade = não se aplica)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct InspectionAnswer {
+    pub item_id: i64,
+    #[serde(default)]
+    pub passed: Option<bool>,
+    #[serde(default)]
+    pub grade: Option<f64>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// Inspeção de um embarque
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Inspection {
+    #[serde(default)]
+    pub inspection_id: i64,
+    pub checklist_id: i64,
+    pub supplier_id: String,
+    #[serde(default)]
+    pub shipment_ref: Option<String>,
+    /// AAAA-MM-DD
+    pub inspection_date: String,
+    #[serde(default)]
+    pub inspector: Option<String>,
+    #[serde(default)]
+    pub comment: Option<String>,
+    #[serde(default)]
+    pub answers: Vec<InspectionAnswer>,
+    /// Calculada ao gravar
+    #[serde(default)]
+    pub score: Option<f64>,
+    #[serde(default)]
+    pub criterion_key: Option<String>,
+    #[serde(default)]
+    pub created_at: Option<String>,
+    #[serde(default)]
+    pub created_by: Option<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inspection_checklist_table (
+            checklist_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            criterion_key TEXT NOT NULL,
+            is_active INTEGER NOT NULL DEFAULT 1,
+            updated_at TEXT,
+            updated_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela inspection_checklist_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inspection_checklist_item_table (
+            item_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            checklist_id INTEGER NOT NULL,
+            description TEXT NOT NULL,
+            weight REAL NOT NULL,
+            answer_type TEXT NOT NULL,
+            max_grade REAL,
+            sort_order INTEGER NOT NULL DEFAULT 0,
+            is_active INTEGER NOT NULL DEFAULT 1
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela inspection_checklist_item_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inspection_table (
+            inspection_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            checklist_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            supplier_id TEXT NOT NULL,
+            shipment_ref TEXT,
+            inspection_date TEXT NOT NULL,
+            inspector TEXT,
+            comment TEXT,
+            score REAL,
+            created_at TEXT,
+            created_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela inspection_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS inspection_answer_table (
+            inspection_id INTEGER NOT NULL,
+            item_id INTEGER NOT NULL,
+            passed INTEGER,
+            grade REAL,
+            note TEXT,
+            PRIMARY KEY (inspection_id, item_id)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela inspection_answer_table: {}", e))?;
+    Ok(())
+}
+
+fn load_items(conn: &Connection, checklist_id: i64, include_inactive: bool) -> Result<Vec<ChecklistItem>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT item_id, description, weight, answer_type, max_grade, sort_order, is_active
+             FROM inspection_checklist_item_table
+             WHERE checklist_id = ?1 AND (?2 OR is_active = 1)
+             ORDER BY sort_order, item_id",
+        )
+        .map_err(|e| format!("Erro ao buscar itens do checklist: {}", e))?;
+    let items = stmt
+        .query_map(rusqlite::params![checklist_id, include_inactive], |row| {
+            Ok(ChecklistItem {
+                item_id: row.get(0)?,
+                description: row.get(1)?,
+                weight: row.get(2)?,
+                answer_type: row.get(3)?,
+                max_grade: row.get(4)?,
+                sort_order: row.get(5)?,
+                is_active: row.get::<_, i32>(6)? != 0,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar itens do checklist: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar itens do checklist: {}", e))?;
+    Ok(items)
+}
+
+pub fn load_checklists(conn: &Connection, include_inactive: bool) -> Result<Vec<InspectionChecklist>, String> {
+    let headers = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT checklist_id, name, criterion_key, is_active FROM inspection_checklist_table
+                 WHERE ?1 OR is_active = 1
+                 ORDER BY criterion_key, name",
+            )
+            .map_err(|e| format!("Erro ao buscar checklists: {}", e))?;
+        let rows = stmt
+            .query_map([include_inactive], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?, row.get::<_, i32>(3)? != 0))
+            })
+            .map_err(|e| format!("Erro ao processar checklists: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar checklists: {}", e))?;
+        rows
+    };
+
+    headers
+        .into_iter()
+        .map(|(checklist_id, name, criterion_key, is_active)| {
+            Ok(InspectionChecklist {
+                checklist_id,
+                name,
+                criterion_key,
+                is_active,
+                items: load_items(conn, checklist_id, include_inactive)?,
+            })
+        })
+        .collect()
+}
+
+pub fn find_checklist(conn: &Connection, checklist_id: i64) -> Result<InspectionChecklist, String> {
+    let (name, criterion_key, is_active) = conn
+        .query_row(
+            "SELECT name, criterion_key, is_active FROM inspection_checklist_table WHERE checklist_id = ?1",
+            [checklist_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i32>(2)? != 0)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar checklist: {}", e))?
+        .ok_or_else(|| format!("Checklist {} não encontrado", checklist_id))?;
+
+    Ok(InspectionChecklist {
+        checklist_id,
+        name,
+        criterion_key,
+        is_active,
+        items: load_items(conn, checklist_id, true)?,
+    })
+}
+
+/// Cria ou atualiza o checklist e seus itens. Itens que saíram da lista são desativados
+/// (as respostas antigas continuam apontando para eles). Devolve o id do checklist.
+pub fn save_checklist(conn: &Connection, checklist: &InspectionChecklist, updated_by: &str) -> Result<i64, String> {
+    if checklist.name.trim().is_empty() {
+        return Err("Informe o nome do checklist".to_string());
+    }
+    if checklist.items.iter().filter(|i| i.is_active).count() == 0 {
+        return Err("O checklist precisa de pelo menos um item ativo".to_string());
+    }
+    for item in &checklist.items {
+        if item.description.trim().is_empty() {
+            return Err("Todos os itens precisam de descrição".to_string());
+        }
+        if !item.weight.is_finite() || item.weight <= 0.0 {
+            return Err(format!("Peso inválido no item '{}'", item.description));
+        }
+        match item.answer_type.as_str() {
+            ANSWER_PASS_FAIL => {}
+            ANSWER_GRADED if item.max_grade.map(|m| m > 0.0).unwrap_or(false) => {}
+            ANSWER_GRADED => return Err(format!("Informe a nota máxima do item graduado '{}'", item.description)),
+            other => return Err(format!("Tipo de resposta inválido: {} (use pass_fail ou graded)", other)),
+        }
+    }
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let checklist_id = if checklist.checklist_id > 0 {
+        let rows = conn
+            .execute(
+                "UPDATE inspection_checklist_table SET name = ?1, criterion_key = ?2, is_active = ?3, updated_at = ?4, updated_by = ?5
+                 WHERE checklist_id = ?6",
+                rusqlite::params![checklist.name.trim(), checklist.criterion_key, checklist.is_active, now, updated_by, checklist.checklist_id],
+            )
+            .map_err(|e| format!("Erro ao atualizar checklist: {}", e))?;
+        if rows == 0 {
+            return Err(format!("Checklist {} não encontrado", checklist.checklist_id));
+        }
+        checklist.checklist_id
+    } else {
+        conn.execute(
+            "INSERT INTO inspection_checklist_table (name, criterion_key, is_active, updated_at, updated_by) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![checklist.name.trim(), checklist.criterion_key, checklist.is_active, now, updated_by],
+        )
+        .map_err(|e| format!("Erro ao inserir checklist: {}", e))?;
+        conn.last_insert_rowid()
+    };
+
+    let mut kept_ids = Vec::new();
+    for item in &checklist.items {
+        let item_id = if item.item_id > 0 {
+            conn.execute(
+                "UPDATE inspection_checklist_item_table
+                 SET description = ?1, weight = ?2, answer_type = ?3, max_grade = ?4, sort_order = ?5, is_active = ?6
+                 WHERE item_id = ?7 AND checklist_id = ?8",
+                rusqlite::params![
+                    item.description.trim(),
+                    item.weight,
+                    item.answer_type,
+                    item.max_grade,
+                    item.sort_order,
+                    item.is_active,
+                    item.item_id,
+                    checklist_id
+                ],
+            )
+            .map_err(|e| format!("Erro ao atualizar item do checklist: {}", e))?;
+            item.item_id
+        } else {
+            conn.execute(
+                "INSERT INTO inspection_checklist_item_table (checklist_id, description, weight, answer_type, max_grade, sort_order, is_active)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                rusqlite::params![checklist_id, item.description.trim(), item.weight, item.answer_type, item.max_grade, item.sort_order, item.is_active],
+            )
+            .map_err(|e| format!("Erro ao inserir item do checklist: {}", e))?;
+            conn.last_insert_rowid()
+        };
+        kept_ids.push(item_id);
+    }
+
+    for existing in load_items(conn, checklist_id, true)? {
+        if !kept_ids.contains(&existing.item_id) {
+            conn.execute("UPDATE inspection_checklist_item_table SET is_active = 0 WHERE item_id = ?1", [existing.item_id])
+                .map_err(|e| format!("Erro ao desativar item do checklist: {}", e))?;
+        }
+    }
+
+    Ok(checklist_id)
+}
+
+/// Nota (0–10) de uma inspeção pelas respostas; None se nenhum item foi respondido
+pub fn score_answers(checklist: &InspectionChecklist, answers: &[InspectionAnswer]) -> Result<Option<f64>, String> {
+    let mut weighted = 0.0;
+    let mut weight_sum = 0.0;
+
+    for answer in answers {
+        let item = checklist
+            .items
+            .iter()
+            .find(|i| i.item_id == answer.item_id)
+            .ok_or_else(|| format!("Item {} não pertence ao checklist {}", answer.item_id, checklist.name))?;
+
+        let item_score = match item.answer_type.as_str() {
+            ANSWER_GRADED => match answer.grade {
+                Some(grade) => {
+                    let max = item.max_grade.unwrap_or(1.0);
+                    if !(0.0..=max).contains(&grade) {
+                        return Err(format!("Nota do item '{}' deve estar entre 0 e {}", item.description, max));
+                    }
+                    Some(grade / max)
+                }
+                None => None,
+            },
+            _ => answer.passed.map(|p| if p { 1.0 } else { 0.0 }),
+        };
+
+        if let Some(item_score) = item_score {
+            weighted += item_score * item.weight;
+            weight_sum += item.weight;
+        }
+    }
+
+    if weight_sum <= 0.0 {
+        return Ok(None);
+    }
+    Ok(Some((weighted / weight_sum * 10.0 * 100.0).round() / 100.0))
+}
+
+/// Valida a inspeção contra o checklist, calcula a nota e devolve (mês, ano)
+pub fn prepare(conn: &Connection, inspection: &mut Inspection) -> Result<(i32, i32), String> {
+    inspection.supplier_id = inspection.supplier_id.trim().to_string();
+    if inspection.supplier_id.is_empty() {
+        return Err("Informe o fornecedor da inspeção".to_string());
+    }
+    let date = tabular::parse_date(&inspection.inspection_date)
+        .ok_or_else(|| format!("Data da inspeção inválida: {}", inspection.inspection_date))?;
+    inspection.inspection_date = date.format("%Y-%m-%d").to_string();
+
+    let checklist = find_checklist(conn, inspection.checklist_id)?;
+    if !checklist.is_active && inspection.inspection_id == 0 {
+        return Err(format!("Checklist inativo: {}", checklist.name));
+    }
+    inspection.score = score_answers(&checklist, &inspection.answers)?;
+    if inspection.score.is_none() {
+        return Err("Responda pelo menos um item do checklist".to_string());
+    }
+    inspection.criterion_key = Some(checklist.criterion_key);
+
+    use chrono::Datelike;
+    Ok((date.month() as i32, date.year()))
+}
+
+/// Grava a inspeção já preparada (insere ou substitui); devolve o id
+pub fn save_inspection(conn: &Connection, inspection: &Inspection, user_name: &str) -> Result<i64, String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let criterion_key = inspection.criterion_key.clone().unwrap_or_default();
+
+    let inspection_id = if inspection.inspection_id > 0 {
+        let rows = conn
+            .execute(
+                "UPDATE inspection_table
+                 SET checklist_id = ?1, criterion_key = ?2, supplier_id = ?3, shipment_ref = ?4, inspection_date = ?5,
+                     inspector = ?6, comment = ?7, score = ?8
+                 WHERE inspection_id = ?9",
+                rusqlite::params![
+                    inspection.checklist_id,
+                    criterion_key,
+                    inspection.supplier_id,
+                    inspection.shipment_ref,
+                    inspection.inspection_date,
+                    inspection.inspector,
+                    inspection.comment,
+                    inspection.score,
+                    inspection.inspection_id
+                ],
+            )
+            .map_err(|e| format!("Erro ao atualizar inspeção: {}", e))?;
+        if rows == 0 {
+            return Err(format!("Inspeção {} não encontrada", inspection.inspection_id));
+        }
+        conn.execute("DELETE FROM inspection_answer_table WHERE inspection_id = ?1", [inspection.inspection_id])
+            .map_err(|e| format!("Erro ao substituir respostas: {}", e))?;
+        inspection.inspection_id
+    } else {
+        conn.execute(
+            "INSERT INTO inspection_table
+             (checklist_id, criterion_key, supplier_id, shipment_ref, inspection_date, inspector, comment, score, created_at, created_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            rusqlite::params![
+                inspection.checklist_id,
+                criterion_key,
+                inspection.supplier_id,
+                inspection.shipment_ref,
+                inspection.inspection_date,
+                inspection.inspector,
+                inspection.comment,
+                inspection.score,
+                now,
+                user_name
+            ],
+        )
+        .map_err(|e| format!("Erro ao inserir inspeção: {}", e))?;
+        conn.last_insert_rowid()
+    };
+
+    for answer in &inspection.answers {
+        conn.execute(
+            "INSERT INTO inspection_answer_table (inspection_id, item_id, passed, grade, note) VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![inspection_id, answer.item_id, answer.passed, answer.grade, answer.note],
+        )
+        .map_err(|e| format!("Erro ao gravar resposta: {}", e))?;
+    }
+
+    Ok(inspection_id)
+}
+
+pub fn delete_inspection(conn: &Connection, inspection_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM inspection_answer_table WHERE inspection_id = ?1", [inspection_id])
+        .map_err(|e| format!("Erro ao excluir respostas: {}", e))?;
+    conn.execute("DELETE FROM inspection_table WHERE inspection_id = ?1", [inspection_id])
+        .map_err(|e| format!("Erro ao excluir inspeção: {}", e))?;
+    Ok(())
+}
+
+fn load_answers(conn: &Connection, inspection_id: i64) -> Result<Vec<InspectionAnswer>, String> {
+    let mut stmt = conn
+        .prepare_cached("SELECT item_id, passed, grade, note FROM inspection_answer_table WHERE inspection_id = ?1 ORDER BY item_id")
+        .map_err(|e| format!("Erro ao buscar respostas: {}", e))?;
+    let answers = stmt
+        .query_map([inspection_id], |row| {
+            Ok(InspectionAnswer {
+                item_id: row.get(0)?,
+                passed: row.get::<_, Option<i32>>(1)?.map(|p| p != 0),
+                grade: row.get(2)?,
+                note: row.get(3)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar respostas: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar respostas: {}", e))?;
+    Ok(answers)
+}
+
+/// Histórico de inspeções (filtros opcionais: fornecedor, critério, período AAAA-MM inicial/final)
+pub fn load_history(
+    conn: &Connection,
+    supplier_id: Option<&str>,
+    criterion_key: Option<&str>,
+    from_period: Option<&str>,
+    to_period: Option<&str>,
+) -> Result<Vec<Inspection>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT inspection_id, checklist_id, supplier_id, shipment_ref, inspection_date, inspector, comment, score,
+                    criterion_key, created_at, created_by
+             FROM inspection_table
+             WHERE (?1 IS NULL OR lower(trim(supplier_id)) = lower(trim(?1)))
+               AND (?2 IS NULL OR criterion_key = ?2)
+               AND (?3 IS NULL OR substr(inspection_date, 1, 7) >= ?3)
+               AND (?4 IS NULL OR substr(inspection_date, 1, 7) <= ?4)
+             ORDER BY inspection_date DESC, inspection_id DESC",
+        )
+        .map_err(|e| format!("Erro ao buscar inspeções: {}", e))?;
+    let inspections = stmt
+        .query_map(rusqlite::params![supplier_id, criterion_key, from_period, to_period], |row| {
+            Ok(Inspection {
+                inspection_id: row.get(0)?,
+                checklist_id: row.get(1)?,
+                supplier_id: row.get(2)?,
+                shipment_ref: row.get(3)?,
+                inspection_date: row.get(4)?,
+                inspector: row.get(5)?,
+                comment: row.get(6)?,
+                score: row.get(7)?,
+                criterion_key: row.get(8)?,
+                created_at: row.get(9)?,
+                created_by: row.get(10)?,
+                answers: Vec::new(),
+            })
+        })
+        .map_err(|e| format!("Erro ao processar inspeções: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar inspeções: {}", e))?;
+
+    inspections
+        .into_iter()
+        .map(|mut inspection| {
+            inspection.answers = load_answers(conn, inspection.inspection_id)?;
+            Ok(inspection)
+        })
+        .collect()
+}
+
+pub fn find_inspection(conn: &Connection, inspection_id: i64) -> Result<Option<Inspection>, String> {
+    let found = conn
+        .query_row(
+            "SELECT supplier_id, criterion_key, inspection_date FROM inspection_table WHERE inspection_id = ?1",
+            [inspection_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar inspeção {}: {}", inspection_id, e))?;
+
+    let Some((supplier_id, criterion_key, date)) = found else {
+        return Ok(None);
+    };
+    let period = date.get(..7).map(str::to_string);
+    Ok(load_history(conn, Some(&supplier_id), Some(&criterion_key), period.as_deref(), period.as_deref())?
+        .into_iter()
+        .find(|i| i.inspection_id == inspection_id))
+}
+
+/// Média das inspeções do fornecedor no mês para o critério (None = sem inspeções)
+pub fn monthly_score(conn: &Connection, supplier_id: &str, criterion_key: &str, month: i32, year: i32) -> Result<(Option<f64>, usize), String> {
+    conn.query_row(
+        "SELECT AVG(score), COUNT(*) FROM inspection_table
+         WHERE lower(trim(supplier_id)) = lower(trim(?1)) AND criterion_key = ?2
+           AND substr(inspection_date, 1, 7) = ?3 AND score IS NOT NULL",
+        rusqlite::params![supplier_id, criterion_key, crate::criteria::period_key(month, year)],
+        |row| Ok((row.get::<_, Option<f64>>(0)?, row.get::<_, i64>(1)? as usize)),
+    )
+    .map(|(avg, count)| (avg.map(|a| (a * 100.0).round() / 100.0), count))
+    .map_err(|e| format!("Erro ao calcular nota mensal das inspeções: {}", e))
+}
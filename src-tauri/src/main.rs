@@ -1,9 +1,44 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod auth_provider;
+mod carryover;
+mod comments;
+mod criteria;
+mod cycles;
 mod db_manager;
-
-use db_manager::{DatabaseManager, LoginResponse, SupplierScore, ScoreRecord, Criteria, Supplier, SupplierUpdate, ListItemThreeFields, ListItemSingleField, SupplierResponsibles, RiskSupplier};
+mod deliveries;
+mod evidence;
+mod incidents;
+mod inspections;
+mod pending;
+mod periods;
+mod reasons;
+mod score_engine;
+mod tabular;
+mod targets;
+mod tiering;
+mod validation;
+mod workflow;
+
+use db_manager::{DatabaseManager, LoginResponse, SupplierScore, ScoreRecord, Criteria, Supplier, SupplierUpdate, ListItemThreeFields, ListItemSingleField, SupplierResponsibles, RiskSupplier, PasswordPolicy, OnlineUser, CriteriaPermissions, PeriodRange, RecalculationPreview, SupplierFilter, OverwritePolicy, FullScoreSummary};
+use auth_provider::AuthConfig;
+use comments::ScoreComment;
+use criteria::{CriteriaVersion, CriterionDefinition};
+use cycles::{CycleStatus, EvaluationCycle};
+use evidence::ScoreEvidence;
+use deliveries::{DeliveryImportSummary, DeliveryLine, OtifConfig, OtifEvidence};
+use incidents::{NilEvidence, NilImportSummary, NilIncident, NilRules};
+use inspections::{Inspection, InspectionChecklist};
+use pending::{DeadlineConfig, EvaluatorAssignment, PendingQueueFilter, PendingQueuePage};
+use periods::{PeriodStatus, PeriodStatusEvent};
+use reasons::{ReasonCode, ReasonParetoEntry};
+use score_engine::MissingValuePolicy;
+use targets::{ResolvedTargets, TargetOverride};
+use tiering::{SupplierTier, TierEvent, TieringConfig, TieringSummary};
+use validation::{ValidationReport, ValidationRule};
+use workflow::WorkflowEvent;
+use std::collections::{BTreeMap, HashMap};
 
 /// Comando Tauri para validar login
 #[tauri::command]
@@ -11,6 +46,66 @@ fn validate_login(username: String, password: String) -> Result<LoginResponse, S
     DatabaseManager::validate_login(username, password)
 }
 
+/// Comando Tauri para registrar logout do usuário
+#[tauri::command]
+fn logout_user(user_id: i32) -> Result<(), String> {
+    DatabaseManager::logout_user(user_id)
+}
+
+/// Comando Tauri para listar contas com falhas de login ou bloqueadas (admin)
+#[tauri::command]
+fn get_login_lockouts(admin_wwid: String) -> Result<Vec<serde_json::Value>, String> {
+    DatabaseManager::get_login_lockouts(admin_wwid)
+}
+
+/// Comando Tauri para desbloquear a conta de um usuário (admin)
+#[tauri::command]
+fn unlock_user_account(user_id: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::unlock_user_account(user_id, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para o próprio usuário trocar a senha (inclusive senha expirada)
+#[tauri::command]
+fn change_password(wwid: String, current_password: String, new_password: String) -> Result<(), String> {
+    DatabaseManager::change_password(wwid, current_password, new_password)
+}
+
+/// Comando Tauri para obter a configuração de autenticação (local/LDAP)
+#[tauri::command]
+fn get_auth_config() -> Result<AuthConfig, String> {
+    DatabaseManager::get_auth_config()
+}
+
+/// Comando Tauri para atualizar a configuração de autenticação (admin)
+#[tauri::command]
+fn update_auth_config(config: AuthConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_auth_config(config, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para testar uma configuração LDAP com credenciais de um usuário (admin)
+#[tauri::command]
+fn test_ldap_connection(config: AuthConfig, wwid: String, password: String, admin_wwid: String) -> Result<serde_json::Value, String> {
+    DatabaseManager::test_ldap_connection(config, wwid, password, admin_wwid)
+}
+
+/// Comando Tauri para definir a origem de autenticação de um usuário (contingência local)
+#[tauri::command]
+fn set_user_auth_source(user_id: i32, auth_source: Option<String>, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::set_user_auth_source(user_id, auth_source, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para obter a política de senhas
+#[tauri::command]
+fn get_password_policy() -> Result<PasswordPolicy, String> {
+    DatabaseManager::get_password_policy()
+}
+
+/// Comando Tauri para atualizar a política de senhas (admin)
+#[tauri::command]
+fn update_password_policy(policy: PasswordPolicy, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_password_policy(policy, admin_name, admin_wwid)
+}
+
 /// Comando Tauri para listar usuários (debug)
 #[tauri::command]
 fn list_users() -> Result<Vec<(String, String)>, String> {
@@ -31,8 +126,8 @@ fn get_total_evaluations() -> Result<i64, String> {
 
 /// Comando Tauri para obter média de score
 #[tauri::command]
-fn get_average_score() -> Result<f64, String> {
-    DatabaseManager::get_average_score()
+fn get_average_score(approved_only: Option<bool>) -> Result<f64, String> {
+    DatabaseManager::get_average_score(approved_only.unwrap_or(false))
 }
 
 /// Comando Tauri para obter total de usuários
@@ -61,8 +156,8 @@ fn search_suppliers_data(query: String) -> Result<Vec<Supplier>, String> {
 
 /// Comando Tauri para obter todos os fornecedores por status
 #[tauri::command]
-fn get_all_suppliers_by_status(include_inactive: bool) -> Result<Vec<Supplier>, String> {
-    DatabaseManager::get_all_suppliers_by_status(include_inactive)
+fn get_all_suppliers_by_status(include_inactive: bool, tier: Option<String>) -> Result<Vec<Supplier>, String> {
+    DatabaseManager::get_all_suppliers_by_status(include_inactive, tier)
 }
 
 /// Comando Tauri para obter dados de um fornecedor
@@ -189,6 +284,30 @@ fn update_user_status(user_id: i32, status: String) -> Result<(), String> {
     DatabaseManager::update_user_status(user_id, status)
 }
 
+/// Comando Tauri para aprovar um cadastro com papel e permissões de critério
+#[tauri::command]
+fn approve_user(
+    user_id: i32,
+    role: String,
+    criteria_permissions: CriteriaPermissions,
+    approver_name: String,
+    approver_wwid: String,
+) -> Result<(), String> {
+    DatabaseManager::approve_user(user_id, role, criteria_permissions, approver_name, approver_wwid)
+}
+
+/// Comando Tauri para rejeitar um cadastro com motivo
+#[tauri::command]
+fn reject_user(user_id: i32, reason: String, approver_name: String, approver_wwid: String) -> Result<(), String> {
+    DatabaseManager::reject_user(user_id, reason, approver_name, approver_wwid)
+}
+
+/// Comando Tauri para buscar o histórico de aprovação de um usuário
+#[tauri::command]
+fn get_user_approval_history(user_id: i32) -> Result<Vec<serde_json::Value>, String> {
+    DatabaseManager::get_user_approval_history(user_id)
+}
+
 /// Comando Tauri para buscar scores de fornecedores
 #[tauri::command]
 fn get_supplier_scores(supplier_ids: Vec<String>, month: i32, year: i32) -> Result<Vec<SupplierScore>, String> {
@@ -212,6 +331,10 @@ fn save_supplier_score(
     nil_score: Option<String>,
     pickup_score: Option<String>,
     package_score: Option<String>,
+    criteria_scores: Option<HashMap<String, String>>,
+    criteria_evidence: Option<HashMap<String, ScoreEvidence>>,
+    criteria_comments: Option<HashMap<String, String>>,
+    criteria_reasons: Option<HashMap<String, Vec<String>>>,
     total_score: Option<String>,
     comments: Option<String>,
     user_name: String,
@@ -226,6 +349,10 @@ fn save_supplier_score(
         nil_score,
         pickup_score,
         package_score,
+        criteria_scores,
+        criteria_evidence,
+        criteria_comments,
+        criteria_reasons,
         total_score,
         comments,
         user_name,
@@ -247,8 +374,379 @@ fn get_target() -> Result<f64, String> {
 
 /// Comando Tauri para atualizar critérios de avaliação
 #[tauri::command]
-fn update_criteria(criteria: Vec<Criteria>) -> Result<(), String> {
-    DatabaseManager::update_criteria(criteria)
+fn update_criteria(
+    criteria: Vec<Criteria>,
+    effective_from: Option<String>,
+    user_name: Option<String>,
+    user_wwid: Option<String>,
+) -> Result<(), String> {
+    DatabaseManager::update_criteria(criteria, effective_from, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar a configuração do cálculo de OTIF
+#[tauri::command]
+fn get_otif_config() -> Result<OtifConfig, String> {
+    DatabaseManager::get_otif_config()
+}
+
+/// Comando Tauri para atualizar a configuração do cálculo de OTIF (admin)
+#[tauri::command]
+fn update_otif_config(config: OtifConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_otif_config(config, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para importar linhas de entrega (CSV/XLSX)
+#[tauri::command]
+fn import_delivery_lines(file_content: Vec<u8>, file_name: String, user_name: String, user_wwid: String) -> Result<DeliveryImportSummary, String> {
+    DatabaseManager::import_delivery_lines(file_content, file_name, user_name, user_wwid)
+}
+
+/// Comando Tauri para listar as linhas de entrega de um fornecedor no mês
+#[tauri::command]
+fn get_delivery_lines(supplier_id: String, month: i32, year: i32) -> Result<Vec<DeliveryLine>, String> {
+    DatabaseManager::get_delivery_lines(supplier_id, month, year)
+}
+
+/// Comando Tauri para prévia do OTIF calculado das entregas
+#[tauri::command]
+fn calculate_otif(supplier_id: String, month: i32, year: i32) -> Result<OtifEvidence, String> {
+    DatabaseManager::calculate_otif(supplier_id, month, year)
+}
+
+/// Comando Tauri para gravar o OTIF calculado das entregas
+#[tauri::command]
+fn apply_otif_scores(month: i32, year: i32, supplier_ids: Option<Vec<String>>, user_name: String, user_wwid: String) -> Result<Vec<OtifEvidence>, String> {
+    DatabaseManager::apply_otif_scores(month, year, supplier_ids, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar a evidência do OTIF gravado
+#[tauri::command]
+fn get_otif_evidence(supplier_id: String, month: i32, year: i32) -> Result<Option<OtifEvidence>, String> {
+    DatabaseManager::get_otif_evidence(supplier_id, month, year)
+}
+
+/// Comando Tauri para buscar as regras de cálculo do NIL
+#[tauri::command]
+fn get_nil_rules() -> Result<NilRules, String> {
+    DatabaseManager::get_nil_rules()
+}
+
+/// Comando Tauri para atualizar as regras de cálculo do NIL (admin)
+#[tauri::command]
+fn update_nil_rules(rules: NilRules, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_nil_rules(rules, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para listar incidentes NIL
+#[tauri::command]
+fn get_nil_incidents(supplier_id: Option<String>, month: Option<i32>, year: Option<i32>) -> Result<Vec<NilIncident>, String> {
+    DatabaseManager::get_nil_incidents(supplier_id, month, year)
+}
+
+/// Comando Tauri para criar ou atualizar um incidente NIL
+#[tauri::command]
+fn save_nil_incident(incident: NilIncident, user_name: String, user_wwid: String) -> Result<i64, String> {
+    DatabaseManager::save_nil_incident(incident, user_name, user_wwid)
+}
+
+/// Comando Tauri para excluir um incidente NIL
+#[tauri::command]
+fn delete_nil_incident(incident_id: i64, user_name: String, user_wwid: String) -> Result<(), String> {
+    DatabaseManager::delete_nil_incident(incident_id, user_name, user_wwid)
+}
+
+/// Comando Tauri para importar incidentes NIL de Excel/CSV
+#[tauri::command]
+fn import_nil_incidents(file_content: Vec<u8>, file_name: String, user_name: String, user_wwid: String) -> Result<NilImportSummary, String> {
+    DatabaseManager::import_nil_incidents(file_content, file_name, user_name, user_wwid)
+}
+
+/// Comando Tauri para recalcular as notas NIL do mês pelos incidentes
+#[tauri::command]
+fn apply_nil_scores(month: i32, year: i32, supplier_ids: Option<Vec<String>>, user_name: String, user_wwid: String) -> Result<Vec<NilEvidence>, String> {
+    DatabaseManager::apply_nil_scores(month, year, supplier_ids, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar a nota NIL calculada com os incidentes do mês
+#[tauri::command]
+fn get_nil_evidence(supplier_id: String, month: i32, year: i32) -> Result<NilEvidence, String> {
+    DatabaseManager::get_nil_evidence(supplier_id, month, year)
+}
+
+/// Comando Tauri para listar os checklists de inspeção
+#[tauri::command]
+fn get_inspection_checklists(include_inactive: Option<bool>) -> Result<Vec<InspectionChecklist>, String> {
+    DatabaseManager::get_inspection_checklists(include_inactive.unwrap_or(false))
+}
+
+/// Comando Tauri para criar ou atualizar um checklist de inspeção (admin)
+#[tauri::command]
+fn save_inspection_checklist(checklist: InspectionChecklist, admin_name: String, admin_wwid: String) -> Result<i64, String> {
+    DatabaseManager::save_inspection_checklist(checklist, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para registrar ou corrigir uma inspeção de embarque
+#[tauri::command]
+fn record_inspection(inspection: Inspection, user_name: String, user_wwid: String) -> Result<Inspection, String> {
+    DatabaseManager::record_inspection(inspection, user_name, user_wwid)
+}
+
+/// Comando Tauri para excluir uma inspeção
+#[tauri::command]
+fn delete_inspection(inspection_id: i64, user_name: String, user_wwid: String) -> Result<(), String> {
+    DatabaseManager::delete_inspection(inspection_id, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar o histórico de inspeções
+#[tauri::command]
+fn get_inspection_history(
+    supplier_id: Option<String>,
+    criterion_key: Option<String>,
+    start_month: Option<i32>,
+    start_year: Option<i32>,
+    end_month: Option<i32>,
+    end_year: Option<i32>,
+) -> Result<Vec<Inspection>, String> {
+    DatabaseManager::get_inspection_history(supplier_id, criterion_key, start_month, start_year, end_month, end_year)
+}
+
+/// Comando Tauri para recalcular as notas de pickup/package pelas inspeções do mês
+#[tauri::command]
+fn apply_inspection_scores(month: i32, year: i32, supplier_ids: Option<Vec<String>>, user_name: String, user_wwid: String) -> Result<usize, String> {
+    DatabaseManager::apply_inspection_scores(month, year, supplier_ids, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar a política de dados faltantes
+#[tauri::command]
+fn get_missing_value_policy() -> Result<MissingValuePolicy, String> {
+    DatabaseManager::get_missing_value_policy()
+}
+
+/// Comando Tauri para trocar a política de dados faltantes (admin)
+#[tauri::command]
+fn set_missing_value_policy(policy: MissingValuePolicy, admin_name: String, admin_wwid: String) -> Result<usize, String> {
+    DatabaseManager::set_missing_value_policy(policy, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para listar as regras de validação das notas
+#[tauri::command]
+fn get_validation_rules() -> Result<Vec<ValidationRule>, String> {
+    DatabaseManager::get_validation_rules()
+}
+
+/// Comando Tauri para salvar a regra de validação de um critério (admin)
+#[tauri::command]
+fn save_validation_rule(rule: ValidationRule, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::save_validation_rule(rule, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para validar notas por campo sem gravar
+#[tauri::command]
+fn validate_scores(
+    supplier_id: String,
+    month: i32,
+    year: i32,
+    scores: HashMap<String, String>,
+    comment: Option<String>,
+    criteria_comments: Option<HashMap<String, String>>,
+    criteria_reasons: Option<HashMap<String, Vec<String>>>,
+) -> Result<ValidationReport, String> {
+    DatabaseManager::validate_scores(supplier_id, month, year, scores, comment, criteria_comments, criteria_reasons)
+}
+
+/// Comando Tauri para acrescentar um comentário à thread de um registro de nota
+#[tauri::command]
+fn add_score_comment(
+    record_id: i64,
+    criterion_key: Option<String>,
+    body: String,
+    user_name: String,
+    user_wwid: String,
+) -> Result<ScoreComment, String> {
+    DatabaseManager::add_score_comment(record_id, criterion_key, body, user_name, user_wwid)
+}
+
+/// Comando Tauri para buscar a thread de comentários de um registro
+#[tauri::command]
+fn get_score_comments(record_id: i64) -> Result<Vec<ScoreComment>, String> {
+    DatabaseManager::get_score_comments(record_id)
+}
+
+/// Comando Tauri para buscar os comentários de um fornecedor no ano (email do scorecard)
+#[tauri::command]
+fn get_supplier_comments(supplier_id: String, year: i32, month: Option<i32>) -> Result<Vec<ScoreComment>, String> {
+    DatabaseManager::get_supplier_comments(supplier_id, year, month)
+}
+
+/// Comando Tauri para enviar notas para revisão
+#[tauri::command]
+fn submit_scores(record_ids: Vec<i64>, comment: Option<String>, user_name: String, user_wwid: String) -> Result<usize, String> {
+    DatabaseManager::submit_scores(record_ids, comment, user_name, user_wwid)
+}
+
+/// Comando Tauri para aprovar notas enviadas
+#[tauri::command]
+fn approve_scores(record_ids: Vec<i64>, comment: Option<String>, user_name: String, user_wwid: String) -> Result<usize, String> {
+    DatabaseManager::approve_scores(record_ids, comment, user_name, user_wwid)
+}
+
+/// Comando Tauri para rejeitar notas enviadas com comentário
+#[tauri::command]
+fn reject_scores(record_ids: Vec<i64>, comment: String, user_name: String, user_wwid: String) -> Result<usize, String> {
+    DatabaseManager::reject_scores(record_ids, comment, user_name, user_wwid)
+}
+
+/// Comando Tauri para listar o histórico do fluxo de revisão de um registro
+#[tauri::command]
+fn get_score_workflow_history(record_id: i64) -> Result<Vec<WorkflowEvent>, String> {
+    DatabaseManager::get_score_workflow_history(record_id)
+}
+
+/// Comando Tauri para listar registros de um mês por estado do fluxo
+#[tauri::command]
+fn get_scores_by_workflow_status(status: String, month: i32, year: i32) -> Result<Vec<serde_json::Value>, String> {
+    DatabaseManager::get_scores_by_workflow_status(status, month, year)
+}
+
+/// Comando Tauri para listar a situação (aberto/fechado/reaberto) dos meses
+#[tauri::command]
+fn get_period_statuses(year: Option<i32>) -> Result<Vec<PeriodStatus>, String> {
+    DatabaseManager::get_period_statuses(year)
+}
+
+/// Comando Tauri para listar fechamentos e reaberturas de um mês
+#[tauri::command]
+fn get_period_history(month: i32, year: i32) -> Result<Vec<PeriodStatusEvent>, String> {
+    DatabaseManager::get_period_history(month, year)
+}
+
+/// Comando Tauri para fechar um mês (admin)
+#[tauri::command]
+fn close_period(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::close_period(month, year, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para reabrir um mês fechado com motivo (admin)
+#[tauri::command]
+fn reopen_period(month: i32, year: i32, reason: String, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::reopen_period(month, year, reason, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para gerar notas cheias de um mês em uma única transação
+#[tauri::command]
+fn generate_full_scores(
+    month: i32,
+    year: i32,
+    criteria: Option<Vec<String>>,
+    supplier_filter: Option<SupplierFilter>,
+    overwrite_policy: OverwritePolicy,
+    user_name: String,
+    user_wwid: String,
+) -> Result<FullScoreSummary, String> {
+    DatabaseManager::generate_full_scores(month, year, criteria, supplier_filter, overwrite_policy, user_name, user_wwid)
+}
+
+/// Comando Tauri para copiar as notas de um mês para outro (ficam marcadas até a confirmação)
+#[tauri::command]
+fn copy_scores_from_period(
+    source_month: i32,
+    source_year: i32,
+    target_month: i32,
+    target_year: i32,
+    criteria: Option<Vec<String>>,
+    supplier_filter: Option<SupplierFilter>,
+    user_name: String,
+    user_wwid: String,
+) -> Result<FullScoreSummary, String> {
+    DatabaseManager::copy_scores_from_period(source_month, source_year, target_month, target_year, criteria, supplier_filter, user_name, user_wwid)
+}
+
+/// Comando Tauri para confirmar notas copiadas de outro mês
+#[tauri::command]
+fn confirm_carried_over_scores(record_id: i64, criteria: Option<Vec<String>>, user_name: String, user_wwid: String) -> Result<usize, String> {
+    DatabaseManager::confirm_carried_over_scores(record_id, criteria, user_name, user_wwid)
+}
+
+/// Comando Tauri para obter os parâmetros da segmentação de fornecedores
+#[tauri::command]
+fn get_tiering_config() -> Result<TieringConfig, String> {
+    DatabaseManager::get_tiering_config()
+}
+
+/// Comando Tauri para atualizar os parâmetros da segmentação (admin)
+#[tauri::command]
+fn update_tiering_config(config: TieringConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_tiering_config(config, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para recalcular os tiers de um mês (admin)
+#[tauri::command]
+fn calculate_supplier_tiers(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<TieringSummary, String> {
+    DatabaseManager::calculate_supplier_tiers(month, year, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para o histórico mensal de tiers de um fornecedor
+#[tauri::command]
+fn get_supplier_tier_history(supplier_id: String) -> Result<Vec<SupplierTier>, String> {
+    DatabaseManager::get_supplier_tier_history(supplier_id)
+}
+
+/// Comando Tauri para listar mudanças de tier
+#[tauri::command]
+fn get_tier_events(supplier_id: Option<String>, start_month: Option<i32>, start_year: Option<i32>) -> Result<Vec<TierEvent>, String> {
+    DatabaseManager::get_tier_events(supplier_id, start_month, start_year)
+}
+
+/// Comando Tauri para prévia do recálculo dos totais de um intervalo de períodos
+#[tauri::command]
+fn preview_recalculation(period_range: PeriodRange) -> Result<RecalculationPreview, String> {
+    DatabaseManager::preview_recalculation(period_range)
+}
+
+/// Comando Tauri para aplicar o recálculo dos totais de um intervalo de períodos
+#[tauri::command]
+fn apply_recalculation(period_range: PeriodRange, user_name: String, user_wwid: String) -> Result<RecalculationPreview, String> {
+    DatabaseManager::apply_recalculation(period_range, user_name, user_wwid)
+}
+
+/// Comando Tauri para listar o histórico de versões de pesos e target
+#[tauri::command]
+fn get_criteria_history(criterion_key: Option<String>) -> Result<Vec<CriteriaVersion>, String> {
+    DatabaseManager::get_criteria_history(criterion_key)
+}
+
+/// Comando Tauri para listar os critérios de avaliação configurados
+#[tauri::command]
+fn get_criteria_definitions(include_inactive: bool) -> Result<Vec<CriterionDefinition>, String> {
+    DatabaseManager::get_criteria_definitions(include_inactive)
+}
+
+/// Comando Tauri para criar ou atualizar um critério de avaliação
+#[tauri::command]
+fn save_criterion_definition(
+    definition: CriterionDefinition,
+    effective_from: Option<String>,
+    admin_name: String,
+    admin_wwid: String,
+) -> Result<CriterionDefinition, String> {
+    DatabaseManager::save_criterion_definition(definition, effective_from, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para buscar as permissões de um usuário por critério
+#[tauri::command]
+fn get_user_criteria_permissions(user_id: i32) -> Result<BTreeMap<String, bool>, String> {
+    DatabaseManager::get_user_criteria_permissions(user_id)
+}
+
+/// Comando Tauri para atualizar as permissões de um usuário por critério
+#[tauri::command]
+fn update_user_criteria_permissions(
+    user_id: i32,
+    permissions: HashMap<String, bool>,
+    admin_name: String,
+    admin_wwid: String,
+) -> Result<(), String> {
+    DatabaseManager::update_user_criteria_permissions(user_id, permissions, admin_name, admin_wwid)
 }
 
 /// Comando Tauri para buscar lista de planners
@@ -287,10 +785,41 @@ fn get_categories() -> Result<Vec<String>, String> {
     DatabaseManager::get_categories()
 }
 
+/// Comando Tauri para listar targets sobrescritos por categoria, BU ou fornecedor
+#[tauri::command]
+fn get_target_overrides(scope: Option<String>) -> Result<Vec<TargetOverride>, String> {
+    DatabaseManager::get_target_overrides(scope)
+}
+
+/// Comando Tauri para definir o target de uma categoria, BU ou fornecedor
+#[tauri::command]
+fn save_target_override(
+    scope: String,
+    scope_value: String,
+    criterion_key: String,
+    target: f64,
+    admin_name: String,
+    admin_wwid: String,
+) -> Result<(), String> {
+    DatabaseManager::save_target_override(scope, scope_value, criterion_key, target, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para remover um target sobrescrito
+#[tauri::command]
+fn delete_target_override(override_id: i64, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::delete_target_override(override_id, admin_name, admin_wwid)
+}
+
+/// Comando Tauri para buscar os targets efetivos de um fornecedor
+#[tauri::command]
+fn get_resolved_targets(supplier_id: String, month: Option<i32>, year: Option<i32>) -> Result<ResolvedTargets, String> {
+    DatabaseManager::get_resolved_targets(supplier_id, month, year)
+}
+
 /// Comando Tauri para buscar fornecedores em risco
 #[tauri::command]
-fn get_suppliers_at_risk(year: i32, target: f64) -> Result<Vec<RiskSupplier>, String> {
-    DatabaseManager::get_suppliers_at_risk(year, target)
+fn get_suppliers_at_risk(year: i32, target: Option<f64>, approved_only: Option<bool>) -> Result<Vec<RiskSupplier>, String> {
+    DatabaseManager::get_suppliers_at_risk(year, target, approved_only.unwrap_or(false))
 }
 
 // ========== LISTS MANAGEMENT COMMANDS ==========
@@ -439,6 +968,45 @@ fn delete_category_item(name: String) -> Result<(), String> {
     DatabaseManager::delete_list_item_single_field("categories_table", "category", &name)
 }
 
+/// Comando para obter os códigos de motivo
+#[tauri::command]
+fn get_reason_code_list(include_inactive: Option<bool>) -> Result<Vec<ReasonCode>, String> {
+    DatabaseManager::get_reason_codes(include_inactive.unwrap_or(false))
+}
+
+/// Comando para adicionar código de motivo
+#[tauri::command]
+fn add_reason_code_item(item: ReasonCode) -> Result<(), String> {
+    DatabaseManager::add_reason_code(item)
+}
+
+/// Comando para atualizar código de motivo
+#[tauri::command]
+fn update_reason_code_item(old_code: String, item: ReasonCode) -> Result<(), String> {
+    DatabaseManager::update_reason_code(&old_code, item)
+}
+
+/// Comando para deletar código de motivo
+#[tauri::command]
+fn delete_reason_code_item(code: String) -> Result<(), String> {
+    DatabaseManager::delete_reason_code(&code)
+}
+
+/// Comando para a análise de Pareto dos códigos de motivo
+#[tauri::command]
+fn get_reason_pareto(
+    criterion_key: Option<String>,
+    bu: Option<String>,
+    category: Option<String>,
+    supplier_id: Option<String>,
+    start_month: Option<i32>,
+    start_year: Option<i32>,
+    end_month: Option<i32>,
+    end_year: Option<i32>,
+) -> Result<Vec<ReasonParetoEntry>, String> {
+    DatabaseManager::get_reason_pareto(criterion_key, bu, category, supplier_id, start_month, start_year, end_month, end_year)
+}
+
 /// Comando para obter contagem de usuários online
 #[tauri::command]
 fn get_online_users_count() -> Result<i32, String> {
@@ -451,10 +1019,16 @@ fn set_user_online_status(user_id: i32, is_online: bool) -> Result<(), String> {
     DatabaseManager::set_user_online_status(user_id, is_online)
 }
 
-/// Comando Tauri para resetar todos os usuários para offline
+/// Comando para listar usuários online e a tela em que estão
 #[tauri::command]
-fn reset_all_users_offline() -> Result<(), String> {
-    DatabaseManager::reset_all_users_offline()
+fn get_online_users() -> Result<Vec<OnlineUser>, String> {
+    DatabaseManager::get_online_users()
+}
+
+/// Comando para o heartbeat de presença do usuário
+#[tauri::command]
+fn record_heartbeat(user_id: i32, screen: Option<String>) -> Result<(), String> {
+    DatabaseManager::record_heartbeat(user_id, screen)
 }
 
 /// Comando para buscar scores pendentes de avaliação
@@ -463,15 +1037,84 @@ fn get_pending_scores(user_id: i32) -> Result<Vec<serde_json::Value>, String> {
     DatabaseManager::get_pending_scores(user_id)
 }
 
+/// Comando para a fila de pendências paginada (avaliador, prazo e atraso por critério)
+#[tauri::command]
+fn get_pending_queue(filter: PendingQueueFilter) -> Result<PendingQueuePage, String> {
+    DatabaseManager::get_pending_queue(filter)
+}
+
+/// Comando para listar as regras de atribuição de avaliadores
+#[tauri::command]
+fn get_evaluator_assignments() -> Result<Vec<EvaluatorAssignment>, String> {
+    DatabaseManager::get_evaluator_assignments()
+}
+
+/// Comando para criar ou atualizar uma regra de atribuição (admin)
+#[tauri::command]
+fn save_evaluator_assignment(assignment: EvaluatorAssignment, admin_name: String, admin_wwid: String) -> Result<i64, String> {
+    DatabaseManager::save_evaluator_assignment(assignment, admin_name, admin_wwid)
+}
+
+/// Comando para remover uma regra de atribuição (admin)
+#[tauri::command]
+fn delete_evaluator_assignment(assignment_id: i64, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::delete_evaluator_assignment(assignment_id, admin_name, admin_wwid)
+}
+
+/// Comando para obter o prazo de avaliação
+#[tauri::command]
+fn get_pending_deadline_config() -> Result<DeadlineConfig, String> {
+    DatabaseManager::get_pending_deadline_config()
+}
+
+/// Comando para atualizar o prazo de avaliação (admin)
+#[tauri::command]
+fn update_pending_deadline_config(config: DeadlineConfig, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::update_pending_deadline_config(config, admin_name, admin_wwid)
+}
+
+/// Comando para listar os ciclos de avaliação cadastrados
+#[tauri::command]
+fn get_evaluation_cycles(year: Option<i32>) -> Result<Vec<EvaluationCycle>, String> {
+    DatabaseManager::get_evaluation_cycles(year)
+}
+
+/// Comando para obter o ciclo de avaliação de um mês
+#[tauri::command]
+fn get_evaluation_cycle(month: i32, year: i32) -> Result<EvaluationCycle, String> {
+    DatabaseManager::get_evaluation_cycle(month, year)
+}
+
+/// Comando para cadastrar ou alterar um ciclo de avaliação (admin)
+#[tauri::command]
+fn save_evaluation_cycle(cycle: EvaluationCycle, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::save_evaluation_cycle(cycle, admin_name, admin_wwid)
+}
+
+/// Comando para remover um ciclo de avaliação (admin)
+#[tauri::command]
+fn delete_evaluation_cycle(month: i32, year: i32, admin_name: String, admin_wwid: String) -> Result<(), String> {
+    DatabaseManager::delete_evaluation_cycle(month, year, admin_name, admin_wwid)
+}
+
+/// Comando para o painel de preenchimento do ciclo
+#[tauri::command]
+fn get_cycle_status(month: i32, year: i32) -> Result<CycleStatus, String> {
+    DatabaseManager::get_cycle_status(month, year)
+}
+
 /// Comando para salvar score individual
 #[tauri::command]
 fn save_individual_score(
     record_id: i32,
     score_type: String,
     score_value: String,
+    score_evidence: Option<ScoreEvidence>,
+    score_comment: Option<String>,
+    reason_codes: Option<Vec<String>>,
     user_name: String,
 ) -> Result<String, String> {
-    DatabaseManager::save_individual_score(record_id, score_type, score_value, user_name)
+    DatabaseManager::save_individual_score(record_id, score_type, score_value, score_evidence, score_comment, reason_codes, user_name)
 }
 
 /// Comando de debug para consultar registro
@@ -571,22 +1214,23 @@ fn main() {
     println!("\n🚀 Iniciando Score App...");
     
     match DatabaseManager::initialize() {
-        Ok(_) => {
-            println!("✅ Banco de dados inicializado com sucesso!");
-            
-            // Reseta todos os usuários para offline ao iniciar
-            match DatabaseManager::reset_all_users_offline() {
-                Ok(_) => println!("✅ Status de usuários resetado para offline"),
-                Err(e) => println!("⚠️ Erro ao resetar status dos usuários: {}\n", e),
-            }
-            println!();
-        },
+        Ok(_) => println!("✅ Banco de dados inicializado com sucesso!\n"),
         Err(e) => println!("⚠️ Erro ao inicializar banco: {}\n", e),
     }
 
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             validate_login, 
+            logout_user,
+            get_login_lockouts,
+            unlock_user_account,
+            change_password,
+            get_password_policy,
+            update_password_policy,
+            get_auth_config,
+            update_auth_config,
+            test_ldap_connection,
+            set_user_auth_source,
             list_users,
             get_total_suppliers,
             get_total_evaluations,
@@ -612,12 +1256,68 @@ fn main() {
             count_pending_users,
             get_pending_users,
             update_user_status,
+            approve_user,
+            reject_user,
+            get_user_approval_history,
             get_supplier_scores,
             get_supplier_score_records,
             save_supplier_score,
             get_criteria,
             get_target,
             update_criteria,
+            get_criteria_history,
+            generate_full_scores,
+            copy_scores_from_period,
+            confirm_carried_over_scores,
+            get_tiering_config,
+            update_tiering_config,
+            calculate_supplier_tiers,
+            get_supplier_tier_history,
+            get_tier_events,
+            get_otif_config,
+            update_otif_config,
+            import_delivery_lines,
+            get_delivery_lines,
+            calculate_otif,
+            apply_otif_scores,
+            get_otif_evidence,
+            get_nil_rules,
+            update_nil_rules,
+            get_nil_incidents,
+            save_nil_incident,
+            delete_nil_incident,
+            import_nil_incidents,
+            apply_nil_scores,
+            get_nil_evidence,
+            get_inspection_checklists,
+            save_inspection_checklist,
+            record_inspection,
+            delete_inspection,
+            get_inspection_history,
+            apply_inspection_scores,
+            get_missing_value_policy,
+            set_missing_value_policy,
+            get_validation_rules,
+            save_validation_rule,
+            validate_scores,
+            add_score_comment,
+            get_score_comments,
+            get_supplier_comments,
+            submit_scores,
+            approve_scores,
+            reject_scores,
+            get_score_workflow_history,
+            get_scores_by_workflow_status,
+            get_period_statuses,
+            get_period_history,
+            close_period,
+            reopen_period,
+            preview_recalculation,
+            apply_recalculation,
+            get_criteria_definitions,
+            save_criterion_definition,
+            get_user_criteria_permissions,
+            update_user_criteria_permissions,
             get_planners,
             get_continuity_options,
             get_sourcing_options,
@@ -625,6 +1325,10 @@ fn main() {
             get_business_units,
             get_categories,
             get_suppliers_at_risk,
+            get_target_overrides,
+            save_target_override,
+            delete_target_override,
+            get_resolved_targets,
             // Logs
             get_all_logs,
             get_logs_by_user,
@@ -659,10 +1363,27 @@ fn main() {
             add_category_item,
             update_category_item,
             delete_category_item,
+            get_reason_code_list,
+            add_reason_code_item,
+            update_reason_code_item,
+            delete_reason_code_item,
+            get_reason_pareto,
             get_online_users_count,
             set_user_online_status,
-            reset_all_users_offline,
+            get_online_users,
+            record_heartbeat,
             get_pending_scores,
+            get_pending_queue,
+            get_evaluator_assignments,
+            save_evaluator_assignment,
+            delete_evaluator_assignment,
+            get_pending_deadline_config,
+            update_pending_deadline_config,
+            get_evaluation_cycles,
+            get_evaluation_cycle,
+            save_evaluation_cycle,
+            delete_evaluation_cycle,
+            get_cycle_status,
             save_individual_score,
             debug_get_record,
             send_email_via_outlook,
@@ -0,0 +1,567 @@
+// Fila de pendências de avaliação
+// Cada item é um critério ativo sem nota (ou com nota copiada de outro mês ainda não
+// confirmada) em um registro já iniciado (ao menos uma nota lançada), fora de mês
+// fechado e não dispensado em pending_scores_override.
+// O avaliador vem das regras de atribuição (fornecedor x critério, com curinga para
+// "todos"), que apontam para um alias fixo ou para o responsável do cadastro do
+// fornecedor (sqie, planner, continuity, sourcing). O prazo é o do ciclo de
+// avaliação do mês ou, sem ciclo cadastrado, o fim do mês avaliado mais N dias
+// (corridos ou úteis), configurável pelo admin.
+
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::carryover;
+use crate::criteria::{self, CriterionDefinition};
+use crate::cycles;
+use crate::periods;
+use crate::targets::{SupplierScope, TargetResolver};
+
+/// Chave da configuração de prazo na app_settings_table
+pub const DEADLINE_SETTING: &str = "pending_deadline_config";
+
+/// Campos do cadastro do fornecedor que podem indicar o avaliador
+pub const RESPONSIBLE_FIELDS: [&str; 4] = ["sqie", "planner", "continuity", "sourcing"];
+
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Prazo de avaliação contado a partir do último dia do mês avaliado
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DeadlineConfig {
+    pub days_after_month_end: i64,
+    /// Conta apenas dias úteis (segunda a sexta)
+    pub business_days: bool,
+}
+
+impl Default for DeadlineConfig {
+    fn default() -> Self {
+        DeadlineConfig { days_after_month_end: 5, business_days: true }
+    }
+}
+
+/// Regra de atribuição (supplier_id / criterion_key vazios = todos).
+/// Informe `responsible_field` OU `evaluator`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvaluatorAssignment {
+    #[serde(default)]
+    pub assignment_id: i64,
+    #[serde(default)]
+    pub supplier_id: Option<String>,
+    #[serde(default)]
+    pub criterion_key: Option<String>,
+    /// sqie / planner / continuity / sourcing: usa o alias do cadastro do fornecedor
+    #[serde(default)]
+    pub responsible_field: Option<String>,
+    /// Alias fixo do avaliador
+    #[serde(default)]
+    pub evaluator: Option<String>,
+}
+
+/// Filtros da fila (todos opcionais; página começa em 1)
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct PendingQueueFilter {
+    /// Restringe aos critérios que o usuário pode editar
+    #[serde(default)]
+    pub user_id: Option<i64>,
+    #[serde(default)]
+    pub supplier_id: Option<String>,
+    #[serde(default)]
+    pub criterion_key: Option<String>,
+    #[serde(default)]
+    pub assignee: Option<String>,
+    #[serde(default)]
+    pub bu: Option<String>,
+    #[serde(default)]
+    pub category: Option<String>,
+    #[serde(default)]
+    pub month: Option<i32>,
+    #[serde(default)]
+    pub year: Option<i32>,
+    #[serde(default)]
+    pub overdue_only: bool,
+    #[serde(default)]
+    pub unassigned_only: bool,
+    /// Apenas notas copiadas aguardando confirmação
+    #[serde(default)]
+    pub carried_over_only: bool,
+    #[serde(default)]
+    pub page: Option<usize>,
+    #[serde(default)]
+    pub page_size: Option<usize>,
+}
+
+/// Item da fila: um critério pendente de um registro
+#[derive(Debug, Serialize, Clone)]
+pub struct PendingItem {
+    pub record_id: i64,
+    pub supplier_id: String,
+    pub supplier_name: String,
+    pub bu: Option<String>,
+    pub category: Option<String>,
+    pub month: i32,
+    pub year: i32,
+    pub criterion_key: String,
+    pub criterion_name: String,
+    pub target: f64,
+    /// Nota copiada de outro mês aguardando confirmação (o valor já está gravado)
+    pub carried_over: bool,
+    pub assignee: Option<String>,
+    /// Origem do avaliador: sqie / planner / continuity / sourcing / fixed (vazio = sem atribuição)
+    pub assignment_source: Option<String>,
+    pub due_date: String,
+    pub overdue: bool,
+    pub days_overdue: i64,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PendingQueuePage {
+    pub items: Vec<PendingItem>,
+    pub total: usize,
+    pub overdue_count: usize,
+    pub unassigned_count: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub total_pages: usize,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS evaluator_assignment_table (
+            assignment_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            supplier_id TEXT,
+            criterion_key TEXT,
+            responsible_field TEXT,
+            evaluator TEXT,
+            updated_at TEXT,
+            updated_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela evaluator_assignment_table: {}", e))?;
+
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_evaluator_assignment_scope
+         ON evaluator_assignment_table (COALESCE(supplier_id, ''), COALESCE(criterion_key, ''))",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar índice de atribuições: {}", e))?;
+    Ok(())
+}
+
+pub fn load_config(conn: &Connection) -> Result<DeadlineConfig, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [DEADLINE_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler configuração de prazo: {}", e))?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("Configuração de prazo inválida no banco: {}", e)),
+        None => Ok(DeadlineConfig::default()),
+    }
+}
+
+pub fn validate_config(config: &DeadlineConfig) -> Result<(), String> {
+    if !(0..=60).contains(&config.days_after_month_end) {
+        return Err("O prazo deve estar entre 0 e 60 dias após o fim do mês".to_string());
+    }
+    Ok(())
+}
+
+/// Último dia do mês
+pub fn month_end(month: i32, year: i32) -> Option<NaiveDate> {
+    let (next_month, next_year) = if month == 12 { (1, year + 1) } else { (month + 1, year) };
+    NaiveDate::from_ymd_opt(next_year, next_month as u32, 1)?.pred_opt()
+}
+
+/// Data limite padrão da avaliação do mês (o ciclo cadastrado, se houver, tem prioridade)
+pub fn due_date(month: i32, year: i32, config: &DeadlineConfig) -> Option<NaiveDate> {
+    let mut date = month_end(month, year)?;
+
+    if !config.business_days {
+        return Some(date + Duration::days(config.days_after_month_end));
+    }
+    let mut remaining = config.days_after_month_end;
+    while remaining > 0 {
+        date = date.succ_opt()?;
+        if !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) {
+            remaining -= 1;
+        }
+    }
+    Some(date)
+}
+
+fn normalize(value: Option<String>) -> Option<String> {
+    value.map(|v| v.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+pub fn validate_assignment(conn: &Connection, assignment: &mut EvaluatorAssignment) -> Result<(), String> {
+    assignment.supplier_id = normalize(assignment.supplier_id.take());
+    assignment.evaluator = normalize(assignment.evaluator.take());
+    assignment.responsible_field = normalize(assignment.responsible_field.take()).map(|f| f.to_lowercase());
+    assignment.criterion_key = match normalize(assignment.criterion_key.take()) {
+        Some(key) => Some(criteria::find_definition(conn, &key)?.criterion_key),
+        None => None,
+    };
+
+    match (&assignment.responsible_field, &assignment.evaluator) {
+        (Some(field), None) if RESPONSIBLE_FIELDS.contains(&field.as_str()) => Ok(()),
+        (Some(field), None) => Err(format!("Responsável inválido: {} (use {})", field, RESPONSIBLE_FIELDS.join(", "))),
+        (None, Some(_)) => Ok(()),
+        (Some(_), Some(_)) => Err("Informe o responsável do cadastro OU um avaliador fixo, não ambos".to_string()),
+        (None, None) => Err("Informe o responsável do cadastro ou um avaliador fixo".to_string()),
+    }
+}
+
+pub fn load_assignments(conn: &Connection) -> Result<Vec<EvaluatorAssignment>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT assignment_id, supplier_id, criterion_key, responsible_field, evaluator
+             FROM evaluator_assignment_table
+             ORDER BY supplier_id IS NOT NULL, supplier_id, criterion_key IS NOT NULL, criterion_key",
+        )
+        .map_err(|e| format!("Erro ao buscar atribuições: {}", e))?;
+    let assignments = stmt
+        .query_map([], |row| {
+            Ok(EvaluatorAssignment {
+                assignment_id: row.get(0)?,
+                supplier_id: row.get(1)?,
+                criterion_key: row.get(2)?,
+                responsible_field: row.get(3)?,
+                evaluator: row.get(4)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar atribuições: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar atribuições: {}", e))?;
+    Ok(assignments)
+}
+
+/// Insere (assignment_id 0) ou atualiza a regra; devolve o id
+pub fn save_assignment(conn: &Connection, assignment: &EvaluatorAssignment, updated_by: &str) -> Result<i64, String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    let duplicate = |e: rusqlite::Error| match e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == rusqlite::ErrorCode::ConstraintViolation => format!(
+            "Já existe atribuição para fornecedor '{}' e critério '{}'",
+            assignment.supplier_id.as_deref().unwrap_or("todos"),
+            assignment.criterion_key.as_deref().unwrap_or("todos")
+        ),
+        e => format!("Erro ao gravar atribuição: {}", e),
+    };
+
+    if assignment.assignment_id > 0 {
+        let updated = conn
+            .execute(
+                "UPDATE evaluator_assignment_table
+                 SET supplier_id = ?1, criterion_key = ?2, responsible_field = ?3, evaluator = ?4,
+                     updated_at = ?5, updated_by = ?6
+                 WHERE assignment_id = ?7",
+                rusqlite::params![
+                    assignment.supplier_id,
+                    assignment.criterion_key,
+                    assignment.responsible_field,
+                    assignment.evaluator,
+                    now,
+                    updated_by,
+                    assignment.assignment_id
+                ],
+            )
+            .map_err(duplicate)?;
+        if updated == 0 {
+            return Err(format!("Atribuição {} não encontrada", assignment.assignment_id));
+        }
+        return Ok(assignment.assignment_id);
+    }
+
+    conn.execute(
+        "INSERT INTO evaluator_assignment_table
+         (supplier_id, criterion_key, responsible_field, evaluator, updated_at, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            assignment.supplier_id,
+            assignment.criterion_key,
+            assignment.responsible_field,
+            assignment.evaluator,
+            now,
+            updated_by
+        ],
+    )
+    .map_err(duplicate)?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn find_assignment(conn: &Connection, assignment_id: i64) -> Result<EvaluatorAssignment, String> {
+    load_assignments(conn)?
+        .into_iter()
+        .find(|a| a.assignment_id == assignment_id)
+        .ok_or_else(|| format!("Atribuição {} não encontrada", assignment_id))
+}
+
+pub fn delete_assignment(conn: &Connection, assignment_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM evaluator_assignment_table WHERE assignment_id = ?1", [assignment_id])
+        .map_err(|e| format!("Erro ao remover atribuição {}: {}", assignment_id, e))?;
+    Ok(())
+}
+
+/// Responsáveis do cadastro do fornecedor (alias por campo)
+#[derive(Debug, Clone, Default)]
+pub struct SupplierResponsibleAliases {
+    pub sqie: Option<String>,
+    pub planner: Option<String>,
+    pub continuity: Option<String>,
+    pub sourcing: Option<String>,
+}
+
+impl SupplierResponsibleAliases {
+    fn get(&self, field: &str) -> Option<&String> {
+        match field {
+            "sqie" => self.sqie.as_ref(),
+            "planner" => self.planner.as_ref(),
+            "continuity" => self.continuity.as_ref(),
+            "sourcing" => self.sourcing.as_ref(),
+            _ => None,
+        }
+    }
+}
+
+/// Resolve o avaliador com as regras carregadas uma vez.
+/// Prioridade: fornecedor + critério, fornecedor, critério, regra geral.
+pub struct AssignmentResolver {
+    rules: HashMap<(String, String), EvaluatorAssignment>,
+}
+
+impl AssignmentResolver {
+    pub fn new(conn: &Connection) -> Result<Self, String> {
+        let rules = load_assignments(conn)?
+            .into_iter()
+            .map(|a| {
+                let key = (
+                    a.supplier_id.as_deref().unwrap_or("").trim().to_lowercase(),
+                    a.criterion_key.clone().unwrap_or_default(),
+                );
+                (key, a)
+            })
+            .collect();
+        Ok(AssignmentResolver { rules })
+    }
+
+    /// (avaliador, origem) do critério para o fornecedor
+    pub fn resolve(
+        &self,
+        supplier_id: &str,
+        criterion_key: &str,
+        responsibles: &SupplierResponsibleAliases,
+    ) -> (Option<String>, Option<String>) {
+        let supplier = supplier_id.trim().to_lowercase();
+        let candidates = [
+            (supplier.as_str(), criterion_key),
+            (supplier.as_str(), ""),
+            ("", criterion_key),
+            ("", ""),
+        ];
+
+        for (supplier_key, criterion) in candidates {
+            let Some(rule) = self.rules.get(&(supplier_key.to_string(), criterion.to_string())) else {
+                continue;
+            };
+            return match (&rule.evaluator, &rule.responsible_field) {
+                (Some(evaluator), _) => (Some(evaluator.clone()), Some("fixed".to_string())),
+                (None, Some(field)) => (
+                    responsibles.get(field).map(|a| a.trim().to_string()).filter(|a| !a.is_empty()),
+                    Some(field.clone()),
+                ),
+                (None, None) => (None, None),
+            };
+        }
+        (None, None)
+    }
+}
+
+fn matches_text(filter: &Option<String>, value: Option<&str>) -> bool {
+    match filter.as_deref().map(str::trim).filter(|f| !f.is_empty()) {
+        Some(f) => value.map(|v| v.trim().eq_ignore_ascii_case(f)).unwrap_or(false),
+        None => true,
+    }
+}
+
+/// Fila completa filtrada e ordenada por prazo, fornecedor e ordem do critério
+pub fn load_queue(conn: &Connection, filter: &PendingQueueFilter, today: NaiveDate) -> Result<Vec<PendingItem>, String> {
+    let mut definitions: Vec<CriterionDefinition> = criteria::load_definitions(conn, false)?;
+    if let Some(user_id) = filter.user_id {
+        let permissions = criteria::load_user_permissions(conn, user_id)?;
+        definitions.retain(|d| permissions.get(&d.criterion_key).copied().unwrap_or(false));
+    }
+    if let Some(key) = filter.criterion_key.as_deref().filter(|k| !k.trim().is_empty()) {
+        let wanted = criteria::find_definition(conn, key)?.criterion_key;
+        definitions.retain(|d| d.criterion_key == wanted);
+    }
+    if definitions.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT r.id, r.supplier_id, COALESCE(r.supplier_name, s.vendor_name, ''), r.month, r.year,
+                    s.bu, s.supplier_category, s.sqie, s.planner, s.continuity, s.sourcing
+             FROM supplier_score_records_table r
+             LEFT JOIN supplier_database_table s ON s.supplier_id = r.supplier_id
+             WHERE EXISTS (
+                SELECT 1 FROM supplier_score_values_table v
+                JOIN criteria_definition_table d ON d.criterion_key = v.criterion_key AND d.is_active = 1
+                WHERE v.record_id = r.id)
+               AND (?1 IS NULL OR lower(trim(r.supplier_id)) = lower(trim(?1)))
+               AND (?2 IS NULL OR CAST(r.month AS INTEGER) = ?2)
+               AND (?3 IS NULL OR CAST(r.year AS INTEGER) = ?3)",
+        )
+        .map_err(|e| format!("Erro ao preparar fila de pendências: {}", e))?;
+    let candidates = stmt
+        .query_map(
+            rusqlite::params![
+                filter.supplier_id.as_deref().filter(|s| !s.trim().is_empty()),
+                filter.month,
+                filter.year
+            ],
+            |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?.trim().parse::<i32>().unwrap_or(0),
+                    row.get::<_, String>(4)?.trim().parse::<i32>().unwrap_or(0),
+                    row.get::<_, Option<String>>(5)?,
+                    row.get::<_, Option<String>>(6)?,
+                    SupplierResponsibleAliases {
+                        sqie: row.get(7)?,
+                        planner: row.get(8)?,
+                        continuity: row.get(9)?,
+                        sourcing: row.get(10)?,
+                    },
+                ))
+            },
+        )
+        .map_err(|e| format!("Erro ao executar fila de pendências: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao processar registro pendente: {}", e))?;
+
+    let mut dismissed_stmt = conn
+        .prepare("SELECT score_type FROM pending_scores_override WHERE record_id = ?1")
+        .map_err(|e| format!("Erro ao preparar query de pendências: {}", e))?;
+
+    let config = load_config(conn)?;
+    let assignments = AssignmentResolver::new(conn)?;
+    let mut resolver = TargetResolver::new(conn)?;
+    let mut closed_periods: HashMap<(i32, i32), bool> = HashMap::new();
+    let mut deadlines: HashMap<(i32, i32), Option<NaiveDate>> = HashMap::new();
+    let mut items = Vec::new();
+
+    for (record_id, supplier_id, supplier_name, month, year, bu, category, responsibles) in candidates {
+        if !matches_text(&filter.bu, bu.as_deref()) || !matches_text(&filter.category, category.as_deref()) {
+            continue;
+        }
+        let closed = match closed_periods.get(&(month, year)) {
+            Some(closed) => *closed,
+            None => {
+                let closed = periods::status_for(conn, month, year)? == periods::STATUS_CLOSED;
+                closed_periods.insert((month, year), closed);
+                closed
+            }
+        };
+        if closed {
+            continue;
+        }
+
+        let values = criteria::read_values(conn, record_id)?;
+        let carried = carryover::read(conn, record_id)?;
+        let dismissed: Vec<String> = dismissed_stmt
+            .query_map([record_id], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Erro ao buscar pendências: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar pendências: {}", e))?;
+
+        let due = match deadlines.get(&(month, year)) {
+            Some(due) => *due,
+            None => {
+                let due = cycles::deadline_for(conn, month, year, &config)?;
+                deadlines.insert((month, year), due);
+                due
+            }
+        };
+        let days_overdue = due.map(|d| (today - d).num_days().max(0)).unwrap_or(0);
+        let scope = SupplierScope { supplier_id: supplier_id.clone(), bu: bu.clone(), category: category.clone() };
+        let period = criteria::period_key(month, year);
+
+        for definition in &definitions {
+            let carried_over = carried.contains_key(&definition.criterion_key);
+            if (values.contains_key(&definition.criterion_key) && !carried_over) || dismissed.contains(&definition.criterion_key) {
+                continue;
+            }
+            if filter.carried_over_only && !carried_over {
+                continue;
+            }
+            let (assignee, assignment_source) = assignments.resolve(&supplier_id, &definition.criterion_key, &responsibles);
+            if filter.unassigned_only && assignee.is_some() {
+                continue;
+            }
+            if !matches_text(&filter.assignee, assignee.as_deref()) {
+                continue;
+            }
+            if filter.overdue_only && days_overdue == 0 {
+                continue;
+            }
+
+            items.push(PendingItem {
+                record_id,
+                supplier_id: supplier_id.clone(),
+                supplier_name: supplier_name.clone(),
+                bu: bu.clone(),
+                category: category.clone(),
+                month,
+                year,
+                criterion_key: definition.criterion_key.clone(),
+                criterion_name: definition.display_name.clone(),
+                target: resolver.resolve(conn, &scope, &definition.criterion_key, &period)?.target,
+                carried_over,
+                assignee,
+                assignment_source,
+                due_date: due.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default(),
+                overdue: days_overdue > 0,
+                days_overdue,
+            });
+        }
+    }
+
+    let order: HashMap<&str, i32> = definitions.iter().map(|d| (d.criterion_key.as_str(), d.sort_order)).collect();
+    items.sort_by(|a, b| {
+        a.due_date
+            .cmp(&b.due_date)
+            .then_with(|| a.supplier_name.to_lowercase().cmp(&b.supplier_name.to_lowercase()))
+            .then_with(|| order.get(a.criterion_key.as_str()).cmp(&order.get(b.criterion_key.as_str())))
+    });
+    Ok(items)
+}
+
+/// Recorta a página pedida e calcula os totais da fila
+pub fn paginate(items: Vec<PendingItem>, page: Option<usize>, page_size: Option<usize>) -> PendingQueuePage {
+    let page_size = page_size.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let total = items.len();
+    let total_pages = total.div_ceil(page_size).max(1);
+    let page = page.unwrap_or(1).clamp(1, total_pages);
+    let overdue_count = items.iter().filter(|i| i.overdue).count();
+    let unassigned_count = items.iter().filter(|i| i.assignee.is_none()).count();
+
+    PendingQueuePage {
+        items: items.into_iter().skip((page - 1) * page_size).take(page_size).collect(),
+        total,
+        overdue_count,
+        unassigned_count,
+        page,
+        page_size,
+        total_pages,
+    }
+}
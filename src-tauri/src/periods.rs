@@ -0,0 +1,189 @@
+// Fechamento de períodos mensais
+// Um mês sem linha na tabela está aberto. Fechar trava todas as gravações de notas
+// do mês; reabrir (com motivo) libera de novo e fica registrado no histórico.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+use crate::criteria;
+
+pub const STATUS_OPEN: &str = "open";
+pub const STATUS_CLOSED: &str = "closed";
+pub const STATUS_REOPENED: &str = "reopened";
+
+/// Situação de um mês
+#[derive(Debug, Serialize, Clone)]
+pub struct PeriodStatus {
+    pub period: String,
+    pub month: i32,
+    pub year: i32,
+    pub status: String,
+    pub reason: Option<String>,
+    pub changed_at: Option<String>,
+    pub changed_by: Option<String>,
+}
+
+/// Mudança de situação de um mês (fechamento ou reabertura)
+#[derive(Debug, Serialize, Clone)]
+pub struct PeriodStatusEvent {
+    pub event_id: i64,
+    pub period: String,
+    pub old_status: String,
+    pub new_status: String,
+    pub reason: Option<String>,
+    pub changed_at: String,
+    pub changed_by: String,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS period_status_table (
+            period TEXT PRIMARY KEY,
+            month INTEGER NOT NULL,
+            year INTEGER NOT NULL,
+            status TEXT NOT NULL,
+            reason TEXT,
+            changed_at TEXT,
+            changed_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela period_status_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS period_status_history_table (
+            event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            period TEXT NOT NULL,
+            old_status TEXT NOT NULL,
+            new_status TEXT NOT NULL,
+            reason TEXT,
+            changed_at TEXT NOT NULL,
+            changed_by TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela period_status_history_table: {}", e))?;
+    Ok(())
+}
+
+pub fn status_for(conn: &Connection, month: i32, year: i32) -> Result<String, String> {
+    let status = conn
+        .query_row(
+            "SELECT status FROM period_status_table WHERE period = ?1",
+            [criteria::period_key(month, year)],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar situação do período: {}", e))?;
+    Ok(status.unwrap_or_else(|| STATUS_OPEN.to_string()))
+}
+
+/// Erro se o mês estiver fechado (usado por todas as gravações de notas)
+pub fn ensure_open(conn: &Connection, month: i32, year: i32) -> Result<(), String> {
+    if status_for(conn, month, year)? == STATUS_CLOSED {
+        return Err(format!("Período {:02}/{} está fechado para alterações", month, year));
+    }
+    Ok(())
+}
+
+/// Igual a `ensure_open`, usando o mês/ano do registro
+pub fn ensure_record_open(conn: &Connection, record_id: i64) -> Result<(), String> {
+    let period = conn
+        .query_row(
+            "SELECT month, year FROM supplier_score_records_table WHERE id = ?1",
+            [record_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar período do registro {}: {}", record_id, e))?;
+
+    match period {
+        Some((month, year)) => ensure_open(conn, month.trim().parse().unwrap_or(0), year.trim().parse().unwrap_or(0)),
+        None => Ok(()),
+    }
+}
+
+/// Muda a situação do mês e registra o evento no histórico; devolve a situação anterior
+pub fn set_status(conn: &Connection, month: i32, year: i32, status: &str, reason: Option<&str>, changed_by: &str) -> Result<String, String> {
+    if !(1..=12).contains(&month) {
+        return Err(format!("Mês inválido: {}", month));
+    }
+
+    let period = criteria::period_key(month, year);
+    let old_status = status_for(conn, month, year)?;
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "INSERT INTO period_status_table (period, month, year, status, reason, changed_at, changed_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+         ON CONFLICT(period) DO UPDATE SET
+            status = excluded.status, reason = excluded.reason,
+            changed_at = excluded.changed_at, changed_by = excluded.changed_by",
+        rusqlite::params![period, month, year, status, reason, now, changed_by],
+    )
+    .map_err(|e| format!("Erro ao atualizar situação do período: {}", e))?;
+
+    conn.execute(
+        "INSERT INTO period_status_history_table (period, old_status, new_status, reason, changed_at, changed_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![period, old_status, status, reason, now, changed_by],
+    )
+    .map_err(|e| format!("Erro ao registrar histórico do período: {}", e))?;
+
+    Ok(old_status)
+}
+
+/// Meses com situação registrada (opcionalmente de um ano)
+pub fn load_statuses(conn: &Connection, year: Option<i32>) -> Result<Vec<PeriodStatus>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT period, month, year, status, reason, changed_at, changed_by
+             FROM period_status_table
+             WHERE ?1 IS NULL OR year = ?1
+             ORDER BY period",
+        )
+        .map_err(|e| format!("Erro ao buscar períodos: {}", e))?;
+    let statuses = stmt
+        .query_map([year], |row| {
+            Ok(PeriodStatus {
+                period: row.get(0)?,
+                month: row.get(1)?,
+                year: row.get(2)?,
+                status: row.get(3)?,
+                reason: row.get(4)?,
+                changed_at: row.get(5)?,
+                changed_by: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar períodos: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar períodos: {}", e))?;
+    Ok(statuses)
+}
+
+pub fn load_history(conn: &Connection, month: i32, year: i32) -> Result<Vec<PeriodStatusEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_id, period, old_status, new_status, reason, changed_at, changed_by
+             FROM period_status_history_table
+             WHERE period = ?1
+             ORDER BY event_id DESC",
+        )
+        .map_err(|e| format!("Erro ao buscar histórico do período: {}", e))?;
+    let events = stmt
+        .query_map([criteria::period_key(month, year)], |row| {
+            Ok(PeriodStatusEvent {
+                event_id: row.get(0)?,
+                period: row.get(1)?,
+                old_status: row.get(2)?,
+                new_status: row.get(3)?,
+                reason: row.get(4)?,
+                changed_at: row.get(5)?,
+                changed_by: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar histórico do período: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar histórico do período: {}", e))?;
+    Ok(events)
+}
@@ -0,0 +1,301 @@
+// Códigos de motivo para notas baixas
+// Taxonomia administrada como as demais listas (get/add/update/delete por código).
+// Cada registro de nota pode ter um ou mais códigos por critério; a regra de
+// validação pode exigir código quando a nota fica abaixo do target. A análise de
+// Pareto conta os códigos por critério, BU, categoria, fornecedor e período.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+fn default_active() -> bool {
+    true
+}
+
+/// Código de motivo (criterion_key vazio = vale para todos os critérios)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReasonCode {
+    pub code: String,
+    pub description: String,
+    #[serde(default)]
+    pub criterion_key: Option<String>,
+    #[serde(default = "default_active")]
+    pub is_active: bool,
+}
+
+/// Linha da análise de Pareto
+#[derive(Debug, Serialize, Clone)]
+pub struct ReasonParetoEntry {
+    pub code: String,
+    pub description: String,
+    pub count: usize,
+    pub percentage: f64,
+    pub cumulative_percentage: f64,
+}
+
+/// Filtros da análise de Pareto (todos opcionais; períodos AAAA-MM inclusivos)
+#[derive(Debug, Clone, Default)]
+pub struct ReasonParetoFilter {
+    pub criterion_key: Option<String>,
+    pub bu: Option<String>,
+    pub category: Option<String>,
+    pub supplier_id: Option<String>,
+    pub from_period: Option<String>,
+    pub to_period: Option<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS reason_code_table (
+            code TEXT PRIMARY KEY,
+            description TEXT NOT NULL,
+            criterion_key TEXT,
+            is_active INTEGER NOT NULL DEFAULT 1
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela reason_code_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS score_reason_table (
+            record_id INTEGER NOT NULL,
+            criterion_key TEXT NOT NULL,
+            reason_code TEXT NOT NULL,
+            assigned_at TEXT,
+            assigned_by TEXT,
+            PRIMARY KEY (record_id, criterion_key, reason_code)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela score_reason_table: {}", e))?;
+    Ok(())
+}
+
+pub fn normalize_code(code: &str) -> String {
+    code.trim().to_uppercase()
+}
+
+pub fn load_codes(conn: &Connection, include_inactive: bool) -> Result<Vec<ReasonCode>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT code, description, criterion_key, is_active FROM reason_code_table
+             WHERE ?1 OR is_active = 1
+             ORDER BY COALESCE(criterion_key, ''), code",
+        )
+        .map_err(|e| format!("Erro ao preparar query: {}", e))?;
+    let codes = stmt
+        .query_map([include_inactive], |row| {
+            Ok(ReasonCode {
+                code: row.get(0)?,
+                description: row.get(1)?,
+                criterion_key: row.get(2)?,
+                is_active: row.get::<_, i32>(3)? != 0,
+            })
+        })
+        .map_err(|e| format!("Erro ao executar query: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar resultados: {}", e))?;
+    Ok(codes)
+}
+
+fn find_code(conn: &Connection, code: &str) -> Result<Option<ReasonCode>, String> {
+    conn.query_row(
+        "SELECT code, description, criterion_key, is_active FROM reason_code_table WHERE code = ?1",
+        [normalize_code(code)],
+        |row| {
+            Ok(ReasonCode {
+                code: row.get(0)?,
+                description: row.get(1)?,
+                criterion_key: row.get(2)?,
+                is_active: row.get::<_, i32>(3)? != 0,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar código de motivo: {}", e))
+}
+
+fn check_item(item: &ReasonCode) -> Result<(), String> {
+    if normalize_code(&item.code).is_empty() {
+        return Err("Informe o código".to_string());
+    }
+    if item.description.trim().is_empty() {
+        return Err("Informe a descrição do código".to_string());
+    }
+    Ok(())
+}
+
+pub fn add_code(conn: &Connection, item: &ReasonCode) -> Result<(), String> {
+    check_item(item)?;
+    conn.execute(
+        "INSERT INTO reason_code_table (code, description, criterion_key, is_active) VALUES (?1, ?2, ?3, ?4)",
+        rusqlite::params![normalize_code(&item.code), item.description.trim(), item.criterion_key, item.is_active],
+    )
+    .map_err(|e| format!("Erro ao inserir item: {}", e))?;
+    Ok(())
+}
+
+/// Atualiza o código; renomear também atualiza os registros que já usam o código antigo
+pub fn update_code(conn: &Connection, old_code: &str, item: &ReasonCode) -> Result<(), String> {
+    check_item(item)?;
+    let old_code = normalize_code(old_code);
+    let new_code = normalize_code(&item.code);
+    let rows = conn
+        .execute(
+            "UPDATE reason_code_table SET code = ?1, description = ?2, criterion_key = ?3, is_active = ?4 WHERE code = ?5",
+            rusqlite::params![new_code, item.description.trim(), item.criterion_key, item.is_active, old_code],
+        )
+        .map_err(|e| format!("Erro ao atualizar item: {}", e))?;
+    if rows == 0 {
+        return Err(format!("Código {} não encontrado", old_code));
+    }
+    if new_code != old_code {
+        conn.execute(
+            "UPDATE score_reason_table SET reason_code = ?1 WHERE reason_code = ?2",
+            rusqlite::params![new_code, old_code],
+        )
+        .map_err(|e| format!("Erro ao atualizar registros do código: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Exclui o código; códigos já usados em notas só podem ser desativados
+pub fn delete_code(conn: &Connection, code: &str) -> Result<(), String> {
+    let code = normalize_code(code);
+    let in_use: i64 = conn
+        .query_row("SELECT COUNT(*) FROM score_reason_table WHERE reason_code = ?1", [&code], |row| row.get(0))
+        .map_err(|e| format!("Erro ao verificar uso do código: {}", e))?;
+    if in_use > 0 {
+        return Err(format!("Código {} usado em {} notas; desative-o em vez de excluir", code, in_use));
+    }
+    conn.execute("DELETE FROM reason_code_table WHERE code = ?1", [&code])
+        .map_err(|e| format!("Erro ao deletar item: {}", e))?;
+    Ok(())
+}
+
+/// Confere se os códigos existem, estão ativos e valem para o critério; devolve normalizados e sem repetição
+pub fn check_codes(conn: &Connection, criterion_key: &str, codes: &[String]) -> Result<Vec<String>, String> {
+    let mut checked: Vec<String> = Vec::new();
+    for code in codes {
+        let normalized = normalize_code(code);
+        if normalized.is_empty() || checked.contains(&normalized) {
+            continue;
+        }
+        let reason = find_code(conn, &normalized)?.ok_or_else(|| format!("código de motivo inexistente: {}", normalized))?;
+        if !reason.is_active {
+            return Err(format!("código de motivo inativo: {}", normalized));
+        }
+        if let Some(key) = reason.criterion_key.as_deref().filter(|k| !k.is_empty()) {
+            if key != criterion_key {
+                return Err(format!("código {} não se aplica a {}", normalized, criterion_key));
+            }
+        }
+        checked.push(normalized);
+    }
+    Ok(checked)
+}
+
+/// Substitui os códigos do critério no registro (lista vazia remove)
+pub fn write_codes(conn: &Connection, record_id: i64, criterion_key: &str, codes: &[String], assigned_by: &str) -> Result<(), String> {
+    conn.execute(
+        "DELETE FROM score_reason_table WHERE record_id = ?1 AND criterion_key = ?2",
+        rusqlite::params![record_id, criterion_key],
+    )
+    .map_err(|e| format!("Erro ao substituir códigos de motivo: {}", e))?;
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    for code in codes {
+        conn.execute(
+            "INSERT OR IGNORE INTO score_reason_table (record_id, criterion_key, reason_code, assigned_at, assigned_by)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![record_id, criterion_key, normalize_code(code), now, assigned_by],
+        )
+        .map_err(|e| format!("Erro ao gravar código de motivo: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Códigos do registro por critério
+pub fn read_codes(conn: &Connection, record_id: i64) -> Result<BTreeMap<String, Vec<String>>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT criterion_key, reason_code FROM score_reason_table WHERE record_id = ?1 ORDER BY criterion_key, reason_code",
+        )
+        .map_err(|e| format!("Erro ao buscar códigos de motivo: {}", e))?;
+    let rows = stmt
+        .query_map([record_id], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Erro ao processar códigos de motivo: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar códigos de motivo: {}", e))?;
+
+    let mut codes: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for (criterion_key, code) in rows {
+        codes.entry(criterion_key).or_default().push(code);
+    }
+    Ok(codes)
+}
+
+/// Mesmo que `read_codes`, no formato usado pela validação
+pub fn read_codes_map(conn: &Connection, record_id: i64) -> Result<HashMap<String, Vec<String>>, String> {
+    Ok(read_codes(conn, record_id)?.into_iter().collect())
+}
+
+pub fn delete_for_record(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM score_reason_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao remover códigos de motivo do registro {}: {}", record_id, e))?;
+    Ok(())
+}
+
+/// Contagem por código (decrescente) com percentuais simples e acumulados
+pub fn pareto(conn: &Connection, filter: &ReasonParetoFilter) -> Result<Vec<ReasonParetoEntry>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT sr.reason_code, COALESCE(rc.description, sr.reason_code), COUNT(*) AS total
+             FROM score_reason_table sr
+             JOIN supplier_score_records_table r ON r.id = sr.record_id
+             LEFT JOIN reason_code_table rc ON rc.code = sr.reason_code
+             LEFT JOIN supplier_database_table s
+                    ON lower(trim(CAST(s.supplier_id AS TEXT))) = lower(trim(r.supplier_id))
+             WHERE (?1 IS NULL OR sr.criterion_key = ?1)
+               AND (?2 IS NULL OR s.bu = ?2)
+               AND (?3 IS NULL OR s.supplier_category = ?3)
+               AND (?4 IS NULL OR lower(trim(r.supplier_id)) = lower(trim(?4)))
+               AND (?5 IS NULL OR printf('%04d-%02d', CAST(r.year AS INTEGER), CAST(r.month AS INTEGER)) >= ?5)
+               AND (?6 IS NULL OR printf('%04d-%02d', CAST(r.year AS INTEGER), CAST(r.month AS INTEGER)) <= ?6)
+             GROUP BY sr.reason_code
+             ORDER BY total DESC, sr.reason_code",
+        )
+        .map_err(|e| format!("Erro ao preparar Pareto: {}", e))?;
+    let rows = stmt
+        .query_map(
+            rusqlite::params![
+                filter.criterion_key,
+                filter.bu,
+                filter.category,
+                filter.supplier_id,
+                filter.from_period,
+                filter.to_period
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, i64>(2)? as usize)),
+        )
+        .map_err(|e| format!("Erro ao calcular Pareto: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar Pareto: {}", e))?;
+
+    let total: usize = rows.iter().map(|(_, _, count)| count).sum();
+    let mut cumulative = 0usize;
+    Ok(rows
+        .into_iter()
+        .map(|(code, description, count)| {
+            cumulative += count;
+            let pct = |n: usize| if total == 0 { 0.0 } else { (n as f64 / total as f64 * 10000.0).round() / 100.0 };
+            ReasonParetoEntry {
+                code,
+                description,
+                count,
+                percentage: pct(count),
+                cumulative_percentage: pct(cumulative),
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,362 @@
+// Motor único de cálculo do total_score
+// Único ponto do backend que grava supplier_score_records_table.total_score:
+// salvamento manual, edição por critério e importação passam por aqui.
+// O total considera os critérios ativos no mês/ano de cada registro, com o peso
+// vigente nesse período (criteria_version_table).
+// Critérios sem nota seguem a política escolhida pelo admin (app_settings_table).
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::criteria;
+use crate::validation;
+
+/// Política aplicada aos critérios sem nota em um registro
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MissingValuePolicy {
+    /// Ignora os critérios sem nota e redistribui o peso entre os presentes
+    #[default]
+    Renormalize,
+    /// Critério sem nota conta como zero
+    Zero,
+    /// Usa a última nota anterior do fornecedor no critério (sem nota anterior, é ignorado)
+    CarryForward,
+    /// Critério sem nota conta como o máximo da regra de validação
+    DefaultMax,
+}
+
+/// Chave da política de dados faltantes na app_settings_table
+pub const MISSING_VALUE_POLICY_SETTING: &str = "missing_value_policy";
+
+/// Política configurada (Renormalize se ainda não foi escolhida)
+pub fn load_policy(conn: &Connection) -> Result<MissingValuePolicy, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [MISSING_VALUE_POLICY_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler política de dados faltantes: {}", e))?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json)
+            .map_err(|e| format!("Política de dados faltantes inválida no banco: {}", e)),
+        None => Ok(MissingValuePolicy::default()),
+    }
+}
+
+/// Peso de um critério ativo, lido de criteria_definition_table
+#[derive(Debug, Clone)]
+pub struct CriterionWeight {
+    pub criterion_key: String,
+    pub weight: f64,
+}
+
+/// Converte uma nota em texto (aceita vírgula decimal); vazio ou inválido = sem nota
+pub fn parse_score_text(value: &str) -> Option<f64> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    trimmed.replace(',', ".").parse::<f64>().ok().filter(|v| v.is_finite())
+}
+
+/// Lê os pesos vigentes no período para os critérios ativos nesse período (um critério
+/// desativado depois continua valendo no histórico); critérios sem versão no período
+/// ficam de fora. Sem pesos configurados, todos valem o mesmo.
+pub fn load_weights(conn: &Connection, month: i32, year: i32) -> Result<Vec<CriterionWeight>, String> {
+    let period = criteria::period_key(month, year);
+    let mut weights = Vec::new();
+    for definition in criteria::load_definitions(conn, true)? {
+        if !criteria::active_for_period(conn, &definition.criterion_key, &period)? {
+            continue;
+        }
+        if let Some(weight) = criteria::weight_for_period(conn, &definition.criterion_key, &period)? {
+            weights.push(CriterionWeight { criterion_key: definition.criterion_key, weight: weight.max(0.0) });
+        }
+    }
+
+    if weights.iter().map(|w| w.weight).sum::<f64>() <= 0.0 {
+        for w in weights.iter_mut() {
+            w.weight = 1.0;
+        }
+    }
+
+    Ok(weights)
+}
+
+/// Nota usada no lugar de um critério sem nota, conforme a política (None = ignorar o critério)
+fn substitute_value(conn: &Connection, record_id: i64, criterion_key: &str, policy: MissingValuePolicy) -> Result<Option<f64>, String> {
+    match policy {
+        MissingValuePolicy::Renormalize => Ok(None),
+        MissingValuePolicy::Zero => Ok(Some(0.0)),
+        MissingValuePolicy::DefaultMax => Ok(Some(validation::load_rule(conn, criterion_key)?.max_value)),
+        MissingValuePolicy::CarryForward => conn
+            .query_row(
+                "SELECT v.value
+                 FROM supplier_score_values_table v
+                 JOIN supplier_score_records_table r ON r.id = v.record_id
+                 JOIN supplier_score_records_table cur ON cur.id = ?1
+                 WHERE v.criterion_key = ?2
+                   AND lower(trim(r.supplier_id)) = lower(trim(cur.supplier_id))
+                   AND CAST(r.year AS INTEGER) * 100 + CAST(r.month AS INTEGER)
+                       < CAST(cur.year AS INTEGER) * 100 + CAST(cur.month AS INTEGER)
+                 ORDER BY CAST(r.year AS INTEGER) * 100 + CAST(r.month AS INTEGER) DESC
+                 LIMIT 1",
+                rusqlite::params![record_id, criterion_key],
+                |row| row.get::<_, f64>(0),
+            )
+            .optional()
+            .map_err(|e| format!("Erro ao buscar nota anterior de {}: {}", criterion_key, e)),
+    }
+}
+
+/// Notas do registro com os critérios faltantes preenchidos conforme a política.
+/// Um registro sem nenhuma nota continua vazio (não é avaliação incompleta).
+pub fn effective_values(conn: &Connection, record_id: i64, weights: &[CriterionWeight], policy: MissingValuePolicy) -> Result<HashMap<String, f64>, String> {
+    let mut values = criteria::read_values(conn, record_id)?;
+    if weights.iter().all(|w| !values.contains_key(&w.criterion_key)) {
+        return Ok(values);
+    }
+
+    for w in weights {
+        if !values.contains_key(&w.criterion_key) {
+            if let Some(value) = substitute_value(conn, record_id, &w.criterion_key, policy)? {
+                values.insert(w.criterion_key.clone(), value);
+            }
+        }
+    }
+    Ok(values)
+}
+
+/// Calcula o total ponderado das notas presentes (já preenchidas pela política).
+/// Os pesos são normalizados pela soma, então tanto frações (0.25) quanto
+/// percentuais (25) geram o mesmo resultado.
+/// Retorna None quando nenhum critério ativo tem nota.
+pub fn compute_total(weights: &[CriterionWeight], values: &HashMap<String, f64>) -> Option<f64> {
+    if weights.iter().all(|w| !values.contains_key(&w.criterion_key)) {
+        return None;
+    }
+
+    let mut weighted_sum = 0.0;
+    let mut weight_sum = 0.0;
+
+    for w in weights {
+        if let Some(v) = values.get(&w.criterion_key) {
+            weighted_sum += v * w.weight;
+            weight_sum += w.weight;
+        }
+    }
+
+    if weight_sum <= 0.0 {
+        return None;
+    }
+
+    Some(weighted_sum / weight_sum)
+}
+
+/// Formato gravado na coluna total_score (TEXT)
+pub fn format_total(total: Option<f64>) -> Option<String> {
+    total.map(|t| format!("{:.2}", t))
+}
+
+/// Recalcula e grava o total_score de um registro com os pesos vigentes no seu mês/ano
+pub fn recalculate_record(conn: &Connection, record_id: i64) -> Result<Option<String>, String> {
+    let (month, year) = conn
+        .query_row(
+            "SELECT month, year FROM supplier_score_records_table WHERE id = ?1",
+            [record_id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar registro {}: {}", record_id, e))?
+        .ok_or_else(|| format!("Registro {} não encontrado para recalcular o total", record_id))?;
+
+    let weights = load_weights(conn, month.trim().parse().unwrap_or(0), year.trim().parse().unwrap_or(0))?;
+    recalculate_record_with_weights(conn, record_id, &weights)
+}
+
+/// Total que o registro teria com os pesos informados e a política vigente, sem gravar (prévia de recálculo)
+pub fn compute_record_total(conn: &Connection, record_id: i64, weights: &[CriterionWeight]) -> Result<Option<String>, String> {
+    let values = effective_values(conn, record_id, weights, load_policy(conn)?)?;
+    Ok(format_total(compute_total(weights, &values)))
+}
+
+/// Igual a `recalculate_record`, reaproveitando pesos já carregados para o período do registro (importações em lote)
+pub fn recalculate_record_with_weights(conn: &Connection, record_id: i64, weights: &[CriterionWeight]) -> Result<Option<String>, String> {
+    let exists = conn
+        .query_row("SELECT 1 FROM supplier_score_records_table WHERE id = ?1", [record_id], |_| Ok(()))
+        .optional()
+        .map_err(|e| format!("Erro ao buscar registro {}: {}", record_id, e))?;
+    if exists.is_none() {
+        return Err(format!("Registro {} não encontrado para recalcular o total", record_id));
+    }
+
+    let total = compute_record_total(conn, record_id, weights)?;
+    write_total(conn, record_id, total.as_deref())?;
+    Ok(total)
+}
+
+/// Grava um total já calculado pelo motor (recálculo em lote aplicando a prévia)
+pub fn write_total(conn: &Connection, record_id: i64, total: Option<&str>) -> Result<(), String> {
+    conn.execute(
+        "UPDATE supplier_score_records_table SET total_score = ?1 WHERE id = ?2",
+        rusqlite::params![total, record_id],
+    )
+    .map_err(|e| format!("Erro ao atualizar total_score do registro {}: {}", record_id, e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn weight(key: &str, weight: f64) -> CriterionWeight {
+        CriterionWeight { criterion_key: key.to_string(), weight }
+    }
+
+    fn values(pairs: &[(&str, f64)]) -> HashMap<String, f64> {
+        pairs.iter().map(|(k, v)| (k.to_string(), *v)).collect()
+    }
+
+    #[test]
+    fn compute_total_weights_present_criteria() {
+        let weights = [weight("otif", 0.5), weight("nil", 0.25), weight("pickup", 0.25)];
+        let total = compute_total(&weights, &values(&[("otif", 10.0), ("nil", 8.0), ("pickup", 6.0)]));
+        assert_eq!(total, Some(8.5));
+    }
+
+    #[test]
+    fn compute_total_fractions_and_percentages_match() {
+        let scores = values(&[("otif", 9.0), ("nil", 7.0)]);
+        let fractions = compute_total(&[weight("otif", 0.75), weight("nil", 0.25)], &scores);
+        let percentages = compute_total(&[weight("otif", 75.0), weight("nil", 25.0)], &scores);
+        assert_eq!(fractions, percentages);
+        assert_eq!(fractions, Some(8.5));
+    }
+
+    #[test]
+    fn compute_total_renormalizes_missing_criteria() {
+        let weights = [weight("otif", 0.5), weight("nil", 0.5)];
+        assert_eq!(compute_total(&weights, &values(&[("otif", 7.0)])), Some(7.0));
+    }
+
+    #[test]
+    fn compute_total_ignores_inactive_criteria() {
+        let weights = [weight("otif", 1.0)];
+        assert_eq!(compute_total(&weights, &values(&[("otif", 9.0), ("legacy", 0.0)])), Some(9.0));
+    }
+
+    #[test]
+    fn compute_total_without_scores_is_none() {
+        let weights = [weight("otif", 0.5), weight("nil", 0.5)];
+        assert_eq!(compute_total(&weights, &HashMap::new()), None);
+        assert_eq!(compute_total(&[weight("otif", 0.0)], &values(&[("otif", 9.0)])), None);
+    }
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE supplier_score_records_table (id INTEGER PRIMARY KEY, supplier_id TEXT, month TEXT, year TEXT);
+             CREATE TABLE supplier_score_values_table (
+                record_id INTEGER NOT NULL, criterion_key TEXT NOT NULL, value REAL NOT NULL,
+                PRIMARY KEY (record_id, criterion_key)
+             );",
+        )
+        .unwrap();
+        validation::create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn insert_record(conn: &Connection, id: i64, supplier_id: &str, month: i32, year: i32, scores: &[(&str, f64)]) {
+        conn.execute(
+            "INSERT INTO supplier_score_records_table (id, supplier_id, month, year) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![id, supplier_id, month.to_string(), year.to_string()],
+        )
+        .unwrap();
+        for (key, value) in scores {
+            conn.execute(
+                "INSERT INTO supplier_score_values_table (record_id, criterion_key, value) VALUES (?1, ?2, ?3)",
+                rusqlite::params![id, key, value],
+            )
+            .unwrap();
+        }
+    }
+
+    fn weights() -> Vec<CriterionWeight> {
+        vec![weight("otif", 0.5), weight("nil", 0.5)]
+    }
+
+    #[test]
+    fn effective_values_renormalize_leaves_missing_out() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 5, 2024, &[("otif", 8.0)]);
+        let values = effective_values(&conn, 1, &weights(), MissingValuePolicy::Renormalize).unwrap();
+        assert_eq!(values.get("nil"), None);
+        assert_eq!(compute_total(&weights(), &values), Some(8.0));
+    }
+
+    #[test]
+    fn effective_values_zero_counts_missing_as_zero() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 5, 2024, &[("otif", 8.0)]);
+        let values = effective_values(&conn, 1, &weights(), MissingValuePolicy::Zero).unwrap();
+        assert_eq!(values.get("nil"), Some(&0.0));
+        assert_eq!(compute_total(&weights(), &values), Some(4.0));
+    }
+
+    #[test]
+    fn effective_values_default_max_uses_rule_maximum() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 5, 2024, &[("otif", 8.0)]);
+        let rule = validation::ValidationRule {
+            criterion_key: "nil".to_string(),
+            min_value: 0.0,
+            max_value: 5.0,
+            decimal_places: 2,
+            comment_required_below_target: false,
+            reason_required_below_target: false,
+        };
+        validation::save_rule(&conn, &rule, "test").unwrap();
+        let values = effective_values(&conn, 1, &weights(), MissingValuePolicy::DefaultMax).unwrap();
+        assert_eq!(values.get("nil"), Some(&5.0));
+    }
+
+    #[test]
+    fn effective_values_carry_forward_uses_latest_previous_month_of_same_supplier() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 11, 2023, &[("nil", 6.0)]);
+        insert_record(&conn, 2, "S1", 2, 2024, &[("nil", 7.0)]);
+        insert_record(&conn, 3, "S1", 6, 2024, &[("nil", 1.0)]);
+        insert_record(&conn, 4, "S2", 4, 2024, &[("nil", 2.0)]);
+        insert_record(&conn, 5, " s1 ", 5, 2024, &[("otif", 9.0)]);
+        let values = effective_values(&conn, 5, &weights(), MissingValuePolicy::CarryForward).unwrap();
+        assert_eq!(values.get("nil"), Some(&7.0));
+    }
+
+    #[test]
+    fn effective_values_carry_forward_without_history_leaves_missing_out() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 5, 2024, &[("otif", 9.0)]);
+        let values = effective_values(&conn, 1, &weights(), MissingValuePolicy::CarryForward).unwrap();
+        assert_eq!(values.get("nil"), None);
+    }
+
+    #[test]
+    fn effective_values_keeps_empty_record_empty() {
+        let conn = test_conn();
+        insert_record(&conn, 1, "S1", 5, 2024, &[]);
+        let values = effective_values(&conn, 1, &weights(), MissingValuePolicy::Zero).unwrap();
+        assert!(values.is_empty());
+        assert_eq!(compute_total(&weights(), &values), None);
+    }
+
+    #[test]
+    fn format_total_uses_two_decimals() {
+        assert_eq!(format_total(Some(8.456)), Some("8.46".to_string()));
+        assert_eq!(format_total(None), None);
+    }
+}
@@ -0,0 +1,126 @@
+// Leitura de extrações tabulares (CSV ou XLSX) vindas de outros sistemas
+// Devolve as linhas como texto; cabeçalhos são localizados por nome (com apelidos),
+// então a ordem das colunas do arquivo não importa.
+
+use calamine::{open_workbook_from_rs, Data, Reader, Xlsx};
+use chrono::NaiveDate;
+use std::io::Cursor;
+
+/// Lê a primeira aba do XLSX ou o CSV (separador ';' ou ',' detectado pelo cabeçalho)
+pub fn read_rows(file_name: &str, content: &[u8]) -> Result<Vec<Vec<String>>, String> {
+    if file_name.trim().to_lowercase().ends_with(".csv") {
+        return Ok(read_csv(content));
+    }
+
+    let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(content.to_vec()))
+        .map_err(|e| format!("Erro ao abrir Excel: {}", e))?;
+    let sheet_name = workbook
+        .sheet_names()
+        .first()
+        .cloned()
+        .ok_or_else(|| "Nenhuma aba encontrada no arquivo".to_string())?;
+    let range = workbook
+        .worksheet_range(&sheet_name)
+        .map_err(|e| format!("Erro ao ler planilha: {}", e))?;
+
+    Ok(range.rows().map(|row| row.iter().map(cell_text).collect()).collect())
+}
+
+/// Texto da célula; datas do Excel viram AAAA-MM-DD e números inteiros perdem o ".0"
+pub fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::Float(v) if v.fract() == 0.0 && v.abs() < 1e15 => format!("{}", *v as i64),
+        Data::DateTime(dt) => serial_to_date(dt.as_f64())
+            .map(|d| d.format("%Y-%m-%d").to_string())
+            .unwrap_or_default(),
+        other => other.to_string().trim().to_string(),
+    }
+}
+
+fn read_csv(content: &[u8]) -> Vec<Vec<String>> {
+    let text = String::from_utf8_lossy(content);
+    let text = text.trim_start_matches('\u{feff}');
+    let first_line = text.lines().next().unwrap_or_default();
+    let delimiter = if first_line.matches(';').count() > first_line.matches(',').count() { ';' } else { ',' };
+
+    text.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| split_csv_line(line, delimiter))
+        .collect()
+}
+
+/// Divide uma linha CSV respeitando aspas ("a;b" fica em um campo, "" é aspas literal)
+fn split_csv_line(line: &str, delimiter: char) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                current.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            c if c == delimiter && !in_quotes => fields.push(std::mem::take(&mut current).trim().to_string()),
+            c => current.push(c),
+        }
+    }
+    fields.push(current.trim().to_string());
+    fields
+}
+
+fn normalize_header(value: &str) -> String {
+    value
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Índice da primeira coluna cujo cabeçalho bate com um dos apelidos
+pub fn find_column(header: &[String], aliases: &[&str]) -> Option<usize> {
+    let aliases: Vec<String> = aliases.iter().map(|a| normalize_header(a)).collect();
+    header.iter().position(|h| aliases.contains(&normalize_header(h)))
+}
+
+/// Data do Excel (número de série, base 1899-12-30)
+fn serial_to_date(serial: f64) -> Option<NaiveDate> {
+    if !(1.0..=2_958_465.0).contains(&serial) {
+        return None;
+    }
+    NaiveDate::from_ymd_opt(1899, 12, 30)?.checked_add_signed(chrono::Duration::days(serial.trunc() as i64))
+}
+
+/// Aceita AAAA-MM-DD, DD/MM/AAAA, DD.MM.AAAA (com ou sem hora) e número de série do Excel
+pub fn parse_date(value: &str) -> Option<NaiveDate> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let date_part = trimmed.split(['T', ' ']).next().unwrap_or(trimmed);
+    for format in ["%Y-%m-%d", "%d/%m/%Y", "%d.%m.%Y", "%Y/%m/%d"] {
+        if let Ok(date) = NaiveDate::parse_from_str(date_part, format) {
+            return Some(date);
+        }
+    }
+    trimmed.parse::<f64>().ok().and_then(serial_to_date)
+}
+
+/// Número com vírgula ou ponto decimal; vazio = None
+pub fn parse_number(value: &str) -> Option<f64> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    // "1.234,5" (milhar com ponto) e "1234.5"
+    let normalized = if trimmed.contains(',') {
+        trimmed.replace('.', "").replace(',', ".")
+    } else {
+        trimmed.to_string()
+    };
+    normalized.parse::<f64>().ok().filter(|v| v.is_finite())
+}
@@ -0,0 +1,307 @@
+// Targets por fornecedor, BU e categoria
+// Um target pode ser sobrescrito por categoria, BU ou fornecedor; o que não for
+// sobrescrito herda o valor global vigente (criteria_version_table).
+// Ordem de resolução: fornecedor > BU > categoria > global.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::criteria::{self, TOTAL_TARGET_KEY};
+
+pub const SCOPE_SUPPLIER: &str = "supplier";
+pub const SCOPE_BU: &str = "bu";
+pub const SCOPE_CATEGORY: &str = "category";
+pub const SCOPE_GLOBAL: &str = "global";
+
+/// Target usado quando nada foi configurado (mesmo padrão de get_target)
+pub const DEFAULT_TARGET: f64 = 8.7;
+
+/// Target sobrescrito para uma categoria, BU ou fornecedor
+#[derive(Debug, Serialize, Clone)]
+pub struct TargetOverride {
+    pub override_id: i64,
+    pub scope: String,
+    pub scope_value: String,
+    pub criterion_key: String,
+    pub target: f64,
+    pub updated_at: Option<String>,
+    pub updated_by: Option<String>,
+}
+
+/// Target efetivo de um critério e de onde ele veio (supplier, bu, category ou global)
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedTarget {
+    pub target: f64,
+    pub source: String,
+}
+
+/// Targets efetivos de um fornecedor em um período
+#[derive(Debug, Serialize, Clone)]
+pub struct ResolvedTargets {
+    pub supplier_id: String,
+    pub period: String,
+    pub total: ResolvedTarget,
+    pub criteria: BTreeMap<String, ResolvedTarget>,
+}
+
+/// Atributos do fornecedor usados na herança
+#[derive(Debug, Clone, Default)]
+pub struct SupplierScope {
+    pub supplier_id: String,
+    pub bu: Option<String>,
+    pub category: Option<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS criteria_target_override_table (
+            override_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            scope TEXT NOT NULL,
+            scope_value TEXT NOT NULL,
+            criterion_key TEXT NOT NULL,
+            target REAL NOT NULL,
+            updated_at TEXT,
+            updated_by TEXT,
+            UNIQUE (scope, scope_value, criterion_key)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela criteria_target_override_table: {}", e))?;
+    Ok(())
+}
+
+pub fn validate_scope(scope: &str) -> Result<(), String> {
+    if [SCOPE_SUPPLIER, SCOPE_BU, SCOPE_CATEGORY].contains(&scope) {
+        Ok(())
+    } else {
+        Err(format!("Escopo de target inválido: {} (use supplier, bu ou category)", scope))
+    }
+}
+
+/// Normaliza o valor do escopo para comparação (BU/categoria sem diferenciar maiúsculas)
+fn scope_lookup_key(value: &str) -> String {
+    value.trim().to_lowercase()
+}
+
+/// Busca BU e categoria do fornecedor
+pub fn load_supplier_scope(conn: &Connection, supplier_id: &str) -> Result<SupplierScope, String> {
+    let scope = conn
+        .query_row(
+            "SELECT bu, supplier_category FROM supplier_database_table
+             WHERE lower(trim(CAST(supplier_id AS TEXT))) = lower(trim(?1))",
+            [supplier_id],
+            |row| Ok((row.get::<_, Option<String>>(0)?, row.get::<_, Option<String>>(1)?)),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar fornecedor {}: {}", supplier_id, e))?;
+
+    let (bu, category) = scope.unwrap_or((None, None));
+    Ok(SupplierScope { supplier_id: supplier_id.to_string(), bu, category })
+}
+
+pub fn load_overrides(conn: &Connection, scope: Option<&str>) -> Result<Vec<TargetOverride>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT override_id, scope, scope_value, criterion_key, target, updated_at, updated_by
+             FROM criteria_target_override_table
+             WHERE ?1 IS NULL OR scope = ?1
+             ORDER BY scope, scope_value, criterion_key",
+        )
+        .map_err(|e| format!("Erro ao buscar targets: {}", e))?;
+    let overrides = stmt
+        .query_map([scope], |row| {
+            Ok(TargetOverride {
+                override_id: row.get(0)?,
+                scope: row.get(1)?,
+                scope_value: row.get(2)?,
+                criterion_key: row.get(3)?,
+                target: row.get(4)?,
+                updated_at: row.get(5)?,
+                updated_by: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar targets: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar targets: {}", e))?;
+    Ok(overrides)
+}
+
+/// Resolve targets com os overrides carregados uma vez (listas e relatórios com muitos fornecedores)
+pub struct TargetResolver {
+    overrides: HashMap<(String, String, String), f64>,
+    globals: HashMap<(String, String), Option<f64>>,
+}
+
+impl TargetResolver {
+    pub fn new(conn: &Connection) -> Result<Self, String> {
+        let overrides = load_overrides(conn, None)?
+            .into_iter()
+            .map(|o| ((o.scope, scope_lookup_key(&o.scope_value), o.criterion_key), o.target))
+            .collect();
+        Ok(TargetResolver { overrides, globals: HashMap::new() })
+    }
+
+    /// Target global vigente no período só do próprio critério (None = sem target próprio)
+    fn global(&mut self, conn: &Connection, criterion_key: &str, period: &str) -> Result<Option<f64>, String> {
+        let cache_key = (criterion_key.to_string(), period.to_string());
+        if let Some(target) = self.globals.get(&cache_key) {
+            return Ok(*target);
+        }
+
+        let target = criteria::target_for_period(conn, criterion_key, period)?;
+        self.globals.insert(cache_key, target);
+        Ok(target)
+    }
+
+    pub fn resolve(&mut self, conn: &Connection, supplier: &SupplierScope, criterion_key: &str, period: &str) -> Result<ResolvedTarget, String> {
+        let candidates = [
+            (SCOPE_SUPPLIER, Some(supplier.supplier_id.as_str())),
+            (SCOPE_BU, supplier.bu.as_deref()),
+            (SCOPE_CATEGORY, supplier.category.as_deref()),
+        ];
+
+        for (scope, value) in candidates {
+            let Some(value) = value.filter(|v| !v.trim().is_empty()) else {
+                continue;
+            };
+            let key = (scope.to_string(), scope_lookup_key(value), criterion_key.to_string());
+            if let Some(target) = self.overrides.get(&key) {
+                return Ok(ResolvedTarget { target: *target, source: scope.to_string() });
+            }
+        }
+
+        match self.global(conn, criterion_key, period)? {
+            Some(target) => Ok(ResolvedTarget { target, source: SCOPE_GLOBAL.to_string() }),
+            // Sem target próprio em nenhum escopo: herda o target geral com os overrides do fornecedor/BU/categoria
+            None if criterion_key != TOTAL_TARGET_KEY => self.resolve(conn, supplier, TOTAL_TARGET_KEY, period),
+            None => Ok(ResolvedTarget { target: DEFAULT_TARGET, source: SCOPE_GLOBAL.to_string() }),
+        }
+    }
+
+    /// Target geral e de cada critério ativo para o fornecedor
+    pub fn resolve_all(&mut self, conn: &Connection, supplier: &SupplierScope, period: &str) -> Result<ResolvedTargets, String> {
+        let total = self.resolve(conn, supplier, TOTAL_TARGET_KEY, period)?;
+        let mut by_criterion = BTreeMap::new();
+        for definition in criteria::load_definitions(conn, false)? {
+            let resolved = self.resolve(conn, supplier, &definition.criterion_key, period)?;
+            by_criterion.insert(definition.criterion_key, resolved);
+        }
+
+        Ok(ResolvedTargets {
+            supplier_id: supplier.supplier_id.clone(),
+            period: period.to_string(),
+            total,
+            criteria: by_criterion,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_conn() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE criteria_version_table (
+                version_id INTEGER PRIMARY KEY AUTOINCREMENT,
+                criterion_key TEXT NOT NULL,
+                weight REAL,
+                target REAL,
+                is_active INTEGER,
+                effective_from TEXT NOT NULL,
+                created_at TEXT,
+                created_by TEXT,
+                UNIQUE (criterion_key, effective_from)
+            );",
+        )
+        .unwrap();
+        create_tables(&conn).unwrap();
+        conn
+    }
+
+    fn add_override(conn: &Connection, scope: &str, scope_value: &str, criterion_key: &str, target: f64) {
+        conn.execute(
+            "INSERT INTO criteria_target_override_table (scope, scope_value, criterion_key, target) VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![scope, scope_value, criterion_key, target],
+        )
+        .unwrap();
+    }
+
+    fn supplier() -> SupplierScope {
+        SupplierScope {
+            supplier_id: "S1".to_string(),
+            bu: Some("Fluid".to_string()),
+            category: Some("Castings".to_string()),
+        }
+    }
+
+    #[test]
+    fn resolve_prefers_supplier_then_bu_then_category() {
+        let conn = test_conn();
+        add_override(&conn, SCOPE_CATEGORY, "castings", "otif", 7.0);
+        add_override(&conn, SCOPE_BU, "FLUID", "otif", 8.0);
+        add_override(&conn, SCOPE_SUPPLIER, "S1", "otif", 9.0);
+        add_override(&conn, SCOPE_BU, "fluid", "nil", 6.5);
+        add_override(&conn, SCOPE_CATEGORY, "Castings", "ppm", 5.5);
+
+        let mut resolver = TargetResolver::new(&conn).unwrap();
+        let otif = resolver.resolve(&conn, &supplier(), "otif", "2024-05").unwrap();
+        assert_eq!((otif.target, otif.source.as_str()), (9.0, SCOPE_SUPPLIER));
+        let nil = resolver.resolve(&conn, &supplier(), "nil", "2024-05").unwrap();
+        assert_eq!((nil.target, nil.source.as_str()), (6.5, SCOPE_BU));
+        let ppm = resolver.resolve(&conn, &supplier(), "ppm", "2024-05").unwrap();
+        assert_eq!((ppm.target, ppm.source.as_str()), (5.5, SCOPE_CATEGORY));
+    }
+
+    #[test]
+    fn resolve_uses_global_target_in_effect_for_the_period() {
+        let conn = test_conn();
+        criteria::record_version(&conn, "otif", None, Some(8.0), "2024-01", "test").unwrap();
+        criteria::record_version(&conn, "otif", None, Some(9.0), "2024-07", "test").unwrap();
+
+        let mut resolver = TargetResolver::new(&conn).unwrap();
+        let before = resolver.resolve(&conn, &supplier(), "otif", "2024-05").unwrap();
+        assert_eq!((before.target, before.source.as_str()), (8.0, SCOPE_GLOBAL));
+        let after = resolver.resolve(&conn, &supplier(), "otif", "2024-08").unwrap();
+        assert_eq!(after.target, 9.0);
+    }
+
+    #[test]
+    fn resolve_without_own_target_inherits_total_target_overrides() {
+        let conn = test_conn();
+        criteria::record_version(&conn, TOTAL_TARGET_KEY, None, Some(8.5), "2024-01", "test").unwrap();
+        add_override(&conn, SCOPE_BU, "Fluid", TOTAL_TARGET_KEY, 9.2);
+
+        let mut resolver = TargetResolver::new(&conn).unwrap();
+        let inherited = resolver.resolve(&conn, &supplier(), "otif", "2024-05").unwrap();
+        assert_eq!((inherited.target, inherited.source.as_str()), (9.2, SCOPE_BU));
+
+        let other = SupplierScope { supplier_id: "S2".to_string(), ..Default::default() };
+        let global = resolver.resolve(&conn, &other, "otif", "2024-05").unwrap();
+        assert_eq!((global.target, global.source.as_str()), (8.5, SCOPE_GLOBAL));
+    }
+
+    #[test]
+    fn resolve_falls_back_to_default_target() {
+        let conn = test_conn();
+        let mut resolver = TargetResolver::new(&conn).unwrap();
+        let resolved = resolver.resolve(&conn, &SupplierScope::default(), "otif", "2024-05").unwrap();
+        assert_eq!((resolved.target, resolved.source.as_str()), (DEFAULT_TARGET, SCOPE_GLOBAL));
+    }
+
+    #[test]
+    fn resolve_ignores_blank_bu_and_category() {
+        let conn = test_conn();
+        add_override(&conn, SCOPE_BU, "", "otif", 1.0);
+        let scope = SupplierScope {
+            supplier_id: "S1".to_string(),
+            bu: Some("  ".to_string()),
+            category: None,
+        };
+        let mut resolver = TargetResolver::new(&conn).unwrap();
+        let resolved = resolver.resolve(&conn, &scope, "otif", "2024-05").unwrap();
+        assert_eq!(resolved.target, DEFAULT_TARGET);
+    }
+}
@@ -0,0 +1,526 @@
+// Segmentação de fornecedores (tiers A/B/C) pelo desempenho recente
+// Para cada fornecedor ativo usa a nota total dos últimos 12 meses até o mês
+// classificado: médias móveis de 3, 6 e 12 meses e volatilidade (desvio-padrão
+// da janela configurada). Tier A e B exigem todos os limites informados na regra;
+// o restante é C. Com poucos meses avaliados o fornecedor fica sem tier.
+// O resultado de cada mês fica no histórico e toda mudança de tier vira evento.
+// O cálculo roda ao fechar o mês e pode ser refeito pelo admin.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::criteria;
+use crate::workflow;
+
+/// Chave da configuração de segmentação na app_settings_table
+pub const TIERING_CONFIG_SETTING: &str = "tiering_config";
+
+pub const TIER_A: &str = "A";
+pub const TIER_B: &str = "B";
+pub const TIER_C: &str = "C";
+
+/// Limites de uma regra de tier (vazio = não exigido)
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TierThreshold {
+    #[serde(default)]
+    pub min_avg_3m: Option<f64>,
+    #[serde(default)]
+    pub min_avg_6m: Option<f64>,
+    #[serde(default)]
+    pub min_avg_12m: Option<f64>,
+    #[serde(default)]
+    pub max_volatility: Option<f64>,
+}
+
+/// Parâmetros da segmentação (admin)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TieringConfig {
+    pub tier_a: TierThreshold,
+    pub tier_b: TierThreshold,
+    /// Meses com nota nos últimos 12 exigidos para classificar
+    pub min_months_scored: usize,
+    /// Janela (3, 6 ou 12 meses) do desvio-padrão
+    pub volatility_window_months: i32,
+    /// Considera apenas registros aprovados no fluxo de revisão
+    pub approved_only: bool,
+}
+
+impl Default for TieringConfig {
+    fn default() -> Self {
+        TieringConfig {
+            tier_a: TierThreshold {
+                min_avg_3m: Some(9.0),
+                min_avg_6m: None,
+                min_avg_12m: Some(8.7),
+                max_volatility: Some(0.5),
+            },
+            tier_b: TierThreshold {
+                min_avg_3m: None,
+                min_avg_6m: Some(8.0),
+                min_avg_12m: None,
+                max_volatility: None,
+            },
+            min_months_scored: 3,
+            volatility_window_months: 6,
+            approved_only: false,
+        }
+    }
+}
+
+/// Tier de um fornecedor em um mês, com os indicadores usados
+#[derive(Debug, Serialize, Clone)]
+pub struct SupplierTier {
+    pub supplier_id: String,
+    pub period: String,
+    pub tier: String,
+    pub avg_3m: Option<f64>,
+    pub avg_6m: Option<f64>,
+    pub avg_12m: Option<f64>,
+    pub volatility: Option<f64>,
+    pub months_scored: usize,
+    pub calculated_at: String,
+}
+
+/// Mudança de tier (old_tier vazio = primeira classificação)
+#[derive(Debug, Serialize, Clone)]
+pub struct TierEvent {
+    pub event_id: i64,
+    pub supplier_id: String,
+    pub period: String,
+    pub old_tier: Option<String>,
+    pub new_tier: String,
+    pub changed_at: String,
+    pub changed_by: String,
+}
+
+/// Resultado da classificação de um mês
+#[derive(Debug, Serialize, Clone)]
+pub struct TieringSummary {
+    pub period: String,
+    pub classified: usize,
+    pub unrated: usize,
+    pub counts: BTreeMap<String, usize>,
+    pub changes: Vec<TierEvent>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS supplier_tier_history_table (
+            supplier_id TEXT NOT NULL,
+            period TEXT NOT NULL,
+            tier TEXT NOT NULL,
+            avg_3m REAL,
+            avg_6m REAL,
+            avg_12m REAL,
+            volatility REAL,
+            months_scored INTEGER NOT NULL,
+            calculated_at TEXT NOT NULL,
+            calculated_by TEXT NOT NULL,
+            PRIMARY KEY (supplier_id, period)
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela supplier_tier_history_table: {}", e))?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS supplier_tier_event_table (
+            event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            supplier_id TEXT NOT NULL,
+            period TEXT NOT NULL,
+            old_tier TEXT,
+            new_tier TEXT NOT NULL,
+            changed_at TEXT NOT NULL,
+            changed_by TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela supplier_tier_event_table: {}", e))?;
+    Ok(())
+}
+
+pub fn load_config(conn: &Connection) -> Result<TieringConfig, String> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT setting_value FROM app_settings_table WHERE setting_key = ?1",
+            [TIERING_CONFIG_SETTING],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao ler configuração de segmentação: {}", e))?;
+
+    match value {
+        Some(json) => serde_json::from_str(&json).map_err(|e| format!("Configuração de segmentação inválida no banco: {}", e)),
+        None => Ok(TieringConfig::default()),
+    }
+}
+
+pub fn validate_config(config: &TieringConfig) -> Result<(), String> {
+    if ![3, 6, 12].contains(&config.volatility_window_months) {
+        return Err("A janela de volatilidade deve ser de 3, 6 ou 12 meses".to_string());
+    }
+    if !(1..=12).contains(&config.min_months_scored) {
+        return Err("O mínimo de meses avaliados deve estar entre 1 e 12".to_string());
+    }
+    for (tier, threshold) in [(TIER_A, &config.tier_a), (TIER_B, &config.tier_b)] {
+        for average in [threshold.min_avg_3m, threshold.min_avg_6m, threshold.min_avg_12m].into_iter().flatten() {
+            if !(0.0..=10.0).contains(&average) {
+                return Err(format!("Tier {}: média mínima deve estar entre 0 e 10", tier));
+            }
+        }
+        if threshold.max_volatility.map(|v| v < 0.0).unwrap_or(false) {
+            return Err(format!("Tier {}: volatilidade máxima não pode ser negativa", tier));
+        }
+    }
+    Ok(())
+}
+
+pub fn validate_tier(tier: &str) -> Result<String, String> {
+    let tier = tier.trim().to_uppercase();
+    if [TIER_A, TIER_B, TIER_C].contains(&tier.as_str()) {
+        Ok(tier)
+    } else {
+        Err(format!("Tier inválido: {} (use A, B ou C)", tier))
+    }
+}
+
+/// Médias e volatilidade a partir das notas (meses atrás, nota); 0 = mês classificado
+struct Metrics {
+    avg_3m: Option<f64>,
+    avg_6m: Option<f64>,
+    avg_12m: Option<f64>,
+    volatility: Option<f64>,
+    months_scored: usize,
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
+
+fn metrics(scores: &[(i32, f64)], volatility_window: i32) -> Metrics {
+    let window = |months: i32| -> Vec<f64> { scores.iter().filter(|(back, _)| *back < months).map(|(_, s)| *s).collect() };
+    let average = |values: &[f64]| (!values.is_empty()).then(|| round2(values.iter().sum::<f64>() / values.len() as f64));
+
+    let volatility_scores = window(volatility_window);
+    let volatility = average(&volatility_scores).map(|mean| {
+        let variance = volatility_scores.iter().map(|s| (s - mean).powi(2)).sum::<f64>() / volatility_scores.len() as f64;
+        round2(variance.sqrt())
+    });
+
+    Metrics {
+        avg_3m: average(&window(3)),
+        avg_6m: average(&window(6)),
+        avg_12m: average(&window(12)),
+        volatility,
+        months_scored: window(12).len(),
+    }
+}
+
+fn meets(threshold: &TierThreshold, metrics: &Metrics) -> bool {
+    let at_least = |min: Option<f64>, value: Option<f64>| match min {
+        Some(min) => value.map(|v| v >= min).unwrap_or(false),
+        None => true,
+    };
+    let at_most = match threshold.max_volatility {
+        Some(max) => metrics.volatility.map(|v| v <= max).unwrap_or(false),
+        None => true,
+    };
+    at_least(threshold.min_avg_3m, metrics.avg_3m)
+        && at_least(threshold.min_avg_6m, metrics.avg_6m)
+        && at_least(threshold.min_avg_12m, metrics.avg_12m)
+        && at_most
+}
+
+fn classify(config: &TieringConfig, metrics: &Metrics) -> Option<&'static str> {
+    if metrics.months_scored < config.min_months_scored {
+        None
+    } else if meets(&config.tier_a, metrics) {
+        Some(TIER_A)
+    } else if meets(&config.tier_b, metrics) {
+        Some(TIER_B)
+    } else {
+        Some(TIER_C)
+    }
+}
+
+/// Notas totais dos 12 meses até o mês informado, por fornecedor (id em minúsculas)
+fn load_scores(conn: &Connection, month: i32, year: i32, approved_only: bool) -> Result<HashMap<String, Vec<(i32, f64)>>, String> {
+    let end = year * 12 + (month - 1);
+    let mut stmt = conn
+        .prepare(
+            "SELECT lower(trim(r.supplier_id)), CAST(r.year AS INTEGER) * 12 + CAST(r.month AS INTEGER) - 1,
+                    CAST(r.total_score AS REAL)
+             FROM supplier_score_records_table r
+             WHERE CAST(r.year AS INTEGER) * 12 + CAST(r.month AS INTEGER) - 1 BETWEEN ?1 AND ?2
+               AND trim(COALESCE(r.total_score, '')) != ''
+               AND EXISTS (SELECT 1 FROM supplier_score_values_table v WHERE v.record_id = r.id)
+               AND (?3 = 0 OR r.workflow_status = ?4)",
+        )
+        .map_err(|e| format!("Erro ao buscar notas para segmentação: {}", e))?;
+    let rows = stmt
+        .query_map(rusqlite::params![end - 11, end, approved_only, workflow::STATUS_APPROVED], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i32>(1)?, row.get::<_, f64>(2)?))
+        })
+        .map_err(|e| format!("Erro ao processar notas para segmentação: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar notas para segmentação: {}", e))?;
+
+    let mut scores: HashMap<String, Vec<(i32, f64)>> = HashMap::new();
+    for (supplier_id, index, total) in rows {
+        scores.entry(supplier_id).or_default().push((end - index, total));
+    }
+    Ok(scores)
+}
+
+/// Tier vigente antes do mês (o já gravado no mês ou o do último mês anterior)
+fn previous_tier(conn: &Connection, supplier_id: &str, period: &str) -> Result<Option<String>, String> {
+    conn.query_row(
+        "SELECT tier FROM supplier_tier_history_table
+         WHERE supplier_id = ?1 AND period <= ?2
+         ORDER BY period DESC LIMIT 1",
+        rusqlite::params![supplier_id, period],
+        |row| row.get(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar tier anterior de {}: {}", supplier_id, e))
+}
+
+/// Classifica os fornecedores ativos no mês, grava o histórico e registra as mudanças
+pub fn calculate(conn: &Connection, month: i32, year: i32, calculated_by: &str) -> Result<TieringSummary, String> {
+    if !(1..=12).contains(&month) {
+        return Err(format!("Mês inválido: {}", month));
+    }
+    let config = load_config(conn)?;
+    let period = criteria::period_key(month, year);
+    let scores = load_scores(conn, month, year, config.approved_only)?;
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let supplier_ids: Vec<String> = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT DISTINCT trim(CAST(supplier_id AS TEXT)) FROM supplier_database_table
+                 WHERE LOWER(COALESCE(supplier_status, '')) = 'active'
+                   AND trim(COALESCE(CAST(supplier_id AS TEXT), '')) != ''",
+            )
+            .map_err(|e| format!("Erro ao buscar fornecedores ativos: {}", e))?;
+        let ids = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(|e| format!("Erro ao processar fornecedores ativos: {}", e))?
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|e| format!("Erro ao coletar fornecedores ativos: {}", e))?;
+        ids
+    };
+
+    let mut summary = TieringSummary {
+        period: period.clone(),
+        classified: 0,
+        unrated: 0,
+        counts: BTreeMap::new(),
+        changes: Vec::new(),
+    };
+
+    for supplier_id in supplier_ids {
+        let supplier_scores = scores.get(&supplier_id.to_lowercase()).map(Vec::as_slice).unwrap_or(&[]);
+        let metrics = metrics(supplier_scores, config.volatility_window_months);
+        let Some(tier) = classify(&config, &metrics) else {
+            // Recálculo: sem dados suficientes, remove a classificação anterior do mês
+            conn.execute(
+                "DELETE FROM supplier_tier_history_table WHERE supplier_id = ?1 AND period = ?2",
+                rusqlite::params![supplier_id, period],
+            )
+            .map_err(|e| format!("Erro ao limpar tier de {}: {}", supplier_id, e))?;
+            summary.unrated += 1;
+            continue;
+        };
+
+        let old_tier = previous_tier(conn, &supplier_id, &period)?;
+        conn.execute(
+            "INSERT INTO supplier_tier_history_table
+             (supplier_id, period, tier, avg_3m, avg_6m, avg_12m, volatility, months_scored, calculated_at, calculated_by)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+             ON CONFLICT(supplier_id, period) DO UPDATE SET
+                tier = excluded.tier, avg_3m = excluded.avg_3m, avg_6m = excluded.avg_6m, avg_12m = excluded.avg_12m,
+                volatility = excluded.volatility, months_scored = excluded.months_scored,
+                calculated_at = excluded.calculated_at, calculated_by = excluded.calculated_by",
+            rusqlite::params![
+                supplier_id,
+                period,
+                tier,
+                metrics.avg_3m,
+                metrics.avg_6m,
+                metrics.avg_12m,
+                metrics.volatility,
+                metrics.months_scored as i64,
+                now,
+                calculated_by
+            ],
+        )
+        .map_err(|e| format!("Erro ao gravar tier de {}: {}", supplier_id, e))?;
+
+        if old_tier.as_deref() != Some(tier) {
+            conn.execute(
+                "INSERT INTO supplier_tier_event_table (supplier_id, period, old_tier, new_tier, changed_at, changed_by)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                rusqlite::params![supplier_id, period, old_tier, tier, now, calculated_by],
+            )
+            .map_err(|e| format!("Erro ao registrar mudança de tier de {}: {}", supplier_id, e))?;
+            summary.changes.push(TierEvent {
+                event_id: conn.last_insert_rowid(),
+                supplier_id: supplier_id.clone(),
+                period: period.clone(),
+                old_tier,
+                new_tier: tier.to_string(),
+                changed_at: now.clone(),
+                changed_by: calculated_by.to_string(),
+            });
+        }
+        summary.classified += 1;
+        *summary.counts.entry(tier.to_string()).or_default() += 1;
+    }
+
+    Ok(summary)
+}
+
+fn row_to_tier(row: &rusqlite::Row) -> rusqlite::Result<SupplierTier> {
+    Ok(SupplierTier {
+        supplier_id: row.get(0)?,
+        period: row.get(1)?,
+        tier: row.get(2)?,
+        avg_3m: row.get(3)?,
+        avg_6m: row.get(4)?,
+        avg_12m: row.get(5)?,
+        volatility: row.get(6)?,
+        months_scored: row.get::<_, i64>(7)? as usize,
+        calculated_at: row.get(8)?,
+    })
+}
+
+/// Histórico mensal de tiers do fornecedor (mais recente primeiro)
+pub fn load_history(conn: &Connection, supplier_id: &str) -> Result<Vec<SupplierTier>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT supplier_id, period, tier, avg_3m, avg_6m, avg_12m, volatility, months_scored, calculated_at
+             FROM supplier_tier_history_table
+             WHERE lower(trim(supplier_id)) = lower(trim(?1))
+             ORDER BY period DESC",
+        )
+        .map_err(|e| format!("Erro ao buscar histórico de tiers: {}", e))?;
+    let history = stmt
+        .query_map([supplier_id], row_to_tier)
+        .map_err(|e| format!("Erro ao processar histórico de tiers: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar histórico de tiers: {}", e))?;
+    Ok(history)
+}
+
+/// Tier mais recente de cada fornecedor (id em minúsculas)
+pub fn current_tiers(conn: &Connection) -> Result<HashMap<String, String>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT lower(trim(h.supplier_id)), h.tier
+             FROM supplier_tier_history_table h
+             WHERE h.period = (SELECT MAX(period) FROM supplier_tier_history_table l WHERE l.supplier_id = h.supplier_id)",
+        )
+        .map_err(|e| format!("Erro ao buscar tiers atuais: {}", e))?;
+    let tiers = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))
+        .map_err(|e| format!("Erro ao processar tiers atuais: {}", e))?
+        .collect::<Result<HashMap<_, _>, _>>()
+        .map_err(|e| format!("Erro ao coletar tiers atuais: {}", e))?;
+    Ok(tiers)
+}
+
+/// Mudanças de tier (filtros opcionais de fornecedor e período AAAA-MM inicial)
+pub fn load_events(conn: &Connection, supplier_id: Option<&str>, from_period: Option<&str>) -> Result<Vec<TierEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_id, supplier_id, period, old_tier, new_tier, changed_at, changed_by
+             FROM supplier_tier_event_table
+             WHERE (?1 IS NULL OR lower(trim(supplier_id)) = lower(trim(?1)))
+               AND (?2 IS NULL OR period >= ?2)
+             ORDER BY event_id DESC",
+        )
+        .map_err(|e| format!("Erro ao buscar mudanças de tier: {}", e))?;
+    let events = stmt
+        .query_map(rusqlite::params![supplier_id, from_period], |row| {
+            Ok(TierEvent {
+                event_id: row.get(0)?,
+                supplier_id: row.get(1)?,
+                period: row.get(2)?,
+                old_tier: row.get(3)?,
+                new_tier: row.get(4)?,
+                changed_at: row.get(5)?,
+                changed_by: row.get(6)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar mudanças de tier: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar mudanças de tier: {}", e))?;
+    Ok(events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Notas do mês classificado para trás (índice 0 = mês classificado)
+    fn monthly(scores: &[f64]) -> Vec<(i32, f64)> {
+        scores.iter().enumerate().map(|(back, score)| (back as i32, *score)).collect()
+    }
+
+    #[test]
+    fn metrics_uses_moving_windows() {
+        let m = metrics(&monthly(&[9.0, 9.0, 9.0, 8.0, 8.0, 8.0, 7.0]), 6);
+        assert_eq!(m.avg_3m, Some(9.0));
+        assert_eq!(m.avg_6m, Some(8.5));
+        assert_eq!(m.avg_12m, Some(8.29));
+        assert_eq!(m.volatility, Some(0.5));
+        assert_eq!(m.months_scored, 7);
+    }
+
+    #[test]
+    fn metrics_skips_months_without_score() {
+        let m = metrics(&[(0, 9.0), (4, 7.0), (11, 8.0), (12, 1.0)], 3);
+        assert_eq!(m.avg_3m, Some(9.0));
+        assert_eq!(m.avg_6m, Some(8.0));
+        assert_eq!(m.avg_12m, Some(8.0));
+        assert_eq!(m.volatility, Some(0.0));
+        assert_eq!(m.months_scored, 3);
+
+        let none = metrics(&[(5, 9.0)], 3);
+        assert_eq!((none.avg_3m, none.volatility), (None, None));
+    }
+
+    #[test]
+    fn classify_with_default_rules() {
+        let config = TieringConfig::default();
+        let steady = metrics(&monthly(&[9.2; 12]), 6);
+        assert_eq!(classify(&config, &steady), Some(TIER_A));
+
+        // Média alta, mas volátil demais para A
+        let volatile = metrics(&monthly(&[10.0, 9.0, 10.0, 8.0, 10.0, 8.0]), 6);
+        assert_eq!(classify(&config, &volatile), Some(TIER_B));
+
+        let low = metrics(&monthly(&[7.0; 12]), 6);
+        assert_eq!(classify(&config, &low), Some(TIER_C));
+    }
+
+    #[test]
+    fn classify_requires_minimum_months() {
+        let config = TieringConfig::default();
+        assert_eq!(classify(&config, &metrics(&monthly(&[9.5, 9.5]), 6)), None);
+        assert_eq!(classify(&config, &metrics(&[], 6)), None);
+    }
+
+    #[test]
+    fn classify_required_limit_without_data_fails_the_tier() {
+        let config = TieringConfig {
+            tier_b: TierThreshold { min_avg_3m: Some(5.0), ..TierThreshold::default() },
+            min_months_scored: 1,
+            ..TieringConfig::default()
+        };
+        // Só meses antigos: sem média de 3 meses, não atende B
+        let old_only = metrics(&[(5, 9.5), (6, 9.5)], 6);
+        assert_eq!(classify(&config, &old_only), Some(TIER_C));
+    }
+}
@@ -0,0 +1,333 @@
+// Regras de validação das notas
+// Cada critério tem mínimo, máximo, casas decimais e a opção de exigir comentário
+// e/ou código de motivo quando a nota fica abaixo do target efetivo do fornecedor. As mesmas regras são
+// aplicadas por todas as gravações (tela, edição individual, importação, lote) e os
+// problemas são devolvidos por campo.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::criteria;
+use crate::score_engine::parse_score_text;
+use crate::targets::{SupplierScope, TargetResolver};
+
+pub const DEFAULT_MIN: f64 = 0.0;
+pub const DEFAULT_MAX: f64 = 10.0;
+pub const DEFAULT_DECIMALS: i32 = 2;
+
+/// Campo usado para erros do comentário
+pub const COMMENT_FIELD: &str = "comment";
+
+/// Campo usado para erros do código de motivo
+pub const REASON_FIELD: &str = "reason_codes";
+
+/// Regra de validação de um critério
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ValidationRule {
+    #[serde(default)]
+    pub criterion_key: String,
+    pub min_value: f64,
+    pub max_value: f64,
+    pub decimal_places: i32,
+    #[serde(default)]
+    pub comment_required_below_target: bool,
+    #[serde(default)]
+    pub reason_required_below_target: bool,
+}
+
+impl ValidationRule {
+    fn default_for(criterion_key: &str) -> Self {
+        ValidationRule {
+            criterion_key: criterion_key.to_string(),
+            min_value: DEFAULT_MIN,
+            max_value: DEFAULT_MAX,
+            decimal_places: DEFAULT_DECIMALS,
+            comment_required_below_target: false,
+            reason_required_below_target: false,
+        }
+    }
+}
+
+/// Justificativas enviadas com as notas (ou já gravadas no registro)
+#[derive(Debug, Clone, Default)]
+pub struct Justification {
+    /// Comentário geral do registro
+    pub comment: Option<String>,
+    /// Último comentário por critério
+    pub criterion_comments: HashMap<String, String>,
+    /// Códigos de motivo por critério
+    pub reason_codes: HashMap<String, Vec<String>>,
+}
+
+impl Justification {
+    fn has_comment(&self, criterion_key: &str) -> bool {
+        self.comment.as_deref().map(|c| !c.trim().is_empty()).unwrap_or(false)
+            || self.criterion_comments.get(criterion_key).map(|c| !c.trim().is_empty()).unwrap_or(false)
+    }
+
+    fn has_reason(&self, criterion_key: &str) -> bool {
+        self.reason_codes
+            .get(criterion_key)
+            .map(|codes| codes.iter().any(|c| !c.trim().is_empty()))
+            .unwrap_or(false)
+    }
+}
+
+/// Problema encontrado em um campo
+#[derive(Debug, Serialize, Clone)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// Resultado da validação de um conjunto de notas
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ValidationReport {
+    pub valid: bool,
+    pub errors: Vec<FieldError>,
+}
+
+impl ValidationReport {
+    /// Converte em erro (uma linha por campo) para as gravações
+    pub fn into_result(self) -> Result<(), String> {
+        if self.errors.is_empty() {
+            return Ok(());
+        }
+        Err(self
+            .errors
+            .iter()
+            .map(|e| format!("{}: {}", e.field, e.message))
+            .collect::<Vec<_>>()
+            .join("\n"))
+    }
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS criteria_validation_table (
+            criterion_key TEXT PRIMARY KEY,
+            min_value REAL NOT NULL,
+            max_value REAL NOT NULL,
+            decimal_places INTEGER NOT NULL,
+            comment_required_below_target INTEGER NOT NULL DEFAULT 0,
+            updated_at TEXT,
+            updated_by TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela criteria_validation_table: {}", e))?;
+
+    let has_reason_column: i32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('criteria_validation_table') WHERE lower(name) = 'reason_required_below_target'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Erro ao verificar colunas de criteria_validation_table: {}", e))?;
+    if has_reason_column == 0 {
+        conn.execute(
+            "ALTER TABLE criteria_validation_table ADD COLUMN reason_required_below_target INTEGER NOT NULL DEFAULT 0",
+            [],
+        )
+        .map_err(|e| format!("Erro ao adicionar coluna reason_required_below_target: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Regra do critério (padrão 0–10 com 2 casas quando não configurada)
+pub fn load_rule(conn: &Connection, criterion_key: &str) -> Result<ValidationRule, String> {
+    let rule = conn
+        .query_row(
+            "SELECT criterion_key, min_value, max_value, decimal_places, comment_required_below_target,
+                    reason_required_below_target
+             FROM criteria_validation_table WHERE criterion_key = ?1",
+            [criterion_key],
+            |row| {
+                Ok(ValidationRule {
+                    criterion_key: row.get(0)?,
+                    min_value: row.get(1)?,
+                    max_value: row.get(2)?,
+                    decimal_places: row.get(3)?,
+                    comment_required_below_target: row.get::<_, i32>(4)? != 0,
+                    reason_required_below_target: row.get::<_, i32>(5)? != 0,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| format!("Erro ao buscar regra de validação: {}", e))?;
+    Ok(rule.unwrap_or_else(|| ValidationRule::default_for(criterion_key)))
+}
+
+/// Regras de todos os critérios ativos
+pub fn load_rules(conn: &Connection) -> Result<Vec<ValidationRule>, String> {
+    criteria::load_definitions(conn, false)?
+        .iter()
+        .map(|d| load_rule(conn, &d.criterion_key))
+        .collect()
+}
+
+pub fn save_rule(conn: &Connection, rule: &ValidationRule, updated_by: &str) -> Result<(), String> {
+    if rule.min_value > rule.max_value {
+        return Err("O mínimo não pode ser maior que o máximo".to_string());
+    }
+    if !(0..=4).contains(&rule.decimal_places) {
+        return Err("Casas decimais devem estar entre 0 e 4".to_string());
+    }
+
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+    conn.execute(
+        "INSERT INTO criteria_validation_table
+         (criterion_key, min_value, max_value, decimal_places, comment_required_below_target,
+          reason_required_below_target, updated_at, updated_by)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+         ON CONFLICT(criterion_key) DO UPDATE SET
+            min_value = excluded.min_value, max_value = excluded.max_value,
+            decimal_places = excluded.decimal_places,
+            comment_required_below_target = excluded.comment_required_below_target,
+            reason_required_below_target = excluded.reason_required_below_target,
+            updated_at = excluded.updated_at, updated_by = excluded.updated_by",
+        rusqlite::params![
+            rule.criterion_key,
+            rule.min_value,
+            rule.max_value,
+            rule.decimal_places,
+            rule.comment_required_below_target,
+            rule.reason_required_below_target,
+            now,
+            updated_by
+        ],
+    )
+    .map_err(|e| format!("Erro ao salvar regra de validação: {}", e))?;
+    Ok(())
+}
+
+/// Valida um valor isolado; vazio é válido (apaga a nota)
+pub fn check_value(rule: &ValidationRule, raw_value: &str) -> Result<Option<f64>, String> {
+    let trimmed = raw_value.trim();
+    if trimmed.is_empty() {
+        return Ok(None);
+    }
+
+    let value = parse_score_text(trimmed).ok_or_else(|| format!("valor não numérico: '{}'", trimmed))?;
+    if value < rule.min_value || value > rule.max_value {
+        return Err(format!("{} fora do intervalo {}–{}", value, rule.min_value, rule.max_value));
+    }
+
+    let decimals = trimmed
+        .replace(',', ".")
+        .split_once('.')
+        .map(|(_, frac)| frac.trim_end_matches('0').len())
+        .unwrap_or(0);
+    if decimals as i32 > rule.decimal_places {
+        return Err(format!("no máximo {} casas decimais", rule.decimal_places));
+    }
+
+    Ok(Some(value))
+}
+
+/// Valida as notas de um fornecedor/mês, incluindo comentário e código de motivo obrigatórios
+/// abaixo do target (o comentário pode ser o geral ou o do próprio critério)
+pub fn validate_scores(
+    conn: &Connection,
+    supplier: &SupplierScope,
+    month: i32,
+    year: i32,
+    values: &[(String, String)],
+    justification: &Justification,
+) -> Result<ValidationReport, String> {
+    let period = criteria::period_key(month, year);
+    let mut resolver: Option<TargetResolver> = None;
+    let mut errors = Vec::new();
+
+    for (criterion_key, raw_value) in values {
+        let rule = load_rule(conn, criterion_key)?;
+        let value = match check_value(&rule, raw_value) {
+            Ok(value) => value,
+            Err(message) => {
+                errors.push(FieldError { field: criterion_key.clone(), message });
+                continue;
+            }
+        };
+
+        let missing_comment = rule.comment_required_below_target && !justification.has_comment(criterion_key);
+        let missing_reason = rule.reason_required_below_target && !justification.has_reason(criterion_key);
+        let Some(value) = value.filter(|_| missing_comment || missing_reason) else {
+            continue;
+        };
+
+        if resolver.is_none() {
+            resolver = Some(TargetResolver::new(conn)?);
+        }
+        let Some(targets) = resolver.as_mut() else {
+            continue;
+        };
+        let target = targets.resolve(conn, supplier, criterion_key, &period)?.target;
+        if value >= target {
+            continue;
+        }
+        if missing_comment {
+            errors.push(FieldError {
+                field: COMMENT_FIELD.to_string(),
+                message: format!("comentário obrigatório: {} abaixo do target ({} < {})", criterion_key, value, target),
+            });
+        }
+        if missing_reason {
+            errors.push(FieldError {
+                field: REASON_FIELD.to_string(),
+                message: format!("código de motivo obrigatório: {} abaixo do target ({} < {})", criterion_key, value, target),
+            });
+        }
+    }
+
+    Ok(ValidationReport { valid: errors.is_empty(), errors })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_value_accepts_blank_as_cleared() {
+        let rule = ValidationRule::default_for("otif");
+        assert_eq!(check_value(&rule, ""), Ok(None));
+        assert_eq!(check_value(&rule, "   "), Ok(None));
+    }
+
+    #[test]
+    fn check_value_parses_comma_and_dot_decimals() {
+        let rule = ValidationRule::default_for("otif");
+        assert_eq!(check_value(&rule, "8,5"), Ok(Some(8.5)));
+        assert_eq!(check_value(&rule, " 9.25 "), Ok(Some(9.25)));
+        assert_eq!(check_value(&rule, "10"), Ok(Some(10.0)));
+    }
+
+    #[test]
+    fn check_value_rejects_non_numeric() {
+        let rule = ValidationRule::default_for("otif");
+        assert!(check_value(&rule, "abc").is_err());
+        assert!(check_value(&rule, "NaN").is_err());
+    }
+
+    #[test]
+    fn check_value_enforces_range() {
+        let rule = ValidationRule { min_value: 1.0, max_value: 5.0, ..ValidationRule::default_for("nil") };
+        assert_eq!(check_value(&rule, "1"), Ok(Some(1.0)));
+        assert_eq!(check_value(&rule, "5"), Ok(Some(5.0)));
+        assert!(check_value(&rule, "0.99").is_err());
+        assert!(check_value(&rule, "5.01").is_err());
+        assert!(check_value(&ValidationRule::default_for("nil"), "-1").is_err());
+    }
+
+    #[test]
+    fn check_value_limits_decimal_places_ignoring_trailing_zeros() {
+        let rule = ValidationRule { decimal_places: 1, ..ValidationRule::default_for("ppm") };
+        assert_eq!(check_value(&rule, "7.5"), Ok(Some(7.5)));
+        assert_eq!(check_value(&rule, "7,500"), Ok(Some(7.5)));
+        assert!(check_value(&rule, "7.55").is_err());
+
+        let integers = ValidationRule { decimal_places: 0, ..ValidationRule::default_for("ppm") };
+        assert_eq!(check_value(&integers, "7.0"), Ok(Some(7.0)));
+        assert!(check_value(&integers, "7.5").is_err());
+    }
+}
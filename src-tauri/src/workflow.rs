@@ -0,0 +1,228 @@
+// Fluxo de revisão das notas (rascunho -> enviado -> aprovado/rejeitado)
+// Cada registro fornecedor/mês tem um estado; toda alteração de nota (inclusive do total
+// por recálculo) devolve o registro para rascunho, e quem aprova não pode ser quem enviou
+// nem quem alterou as notas desde a última decisão.
+
+use rusqlite::{Connection, OptionalExtension};
+use serde::Serialize;
+
+pub const STATUS_DRAFT: &str = "draft";
+pub const STATUS_SUBMITTED: &str = "submitted";
+pub const STATUS_APPROVED: &str = "approved";
+pub const STATUS_REJECTED: &str = "rejected";
+
+pub const ACTION_SUBMIT: &str = "submit";
+pub const ACTION_APPROVE: &str = "approve";
+pub const ACTION_REJECT: &str = "reject";
+pub const ACTION_EDIT: &str = "edit";
+
+/// Mudança de estado de um registro
+#[derive(Debug, Serialize, Clone)]
+pub struct WorkflowEvent {
+    pub event_id: i64,
+    pub record_id: i64,
+    pub action: String,
+    pub old_status: String,
+    pub new_status: String,
+    pub comment: Option<String>,
+    pub changed_at: String,
+    pub changed_by: String,
+    pub changed_by_wwid: Option<String>,
+}
+
+pub fn create_tables(conn: &Connection) -> Result<(), String> {
+    let has_column: i32 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM pragma_table_info('supplier_score_records_table') WHERE lower(name) = 'workflow_status'",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| format!("Erro ao inspecionar supplier_score_records_table: {}", e))?;
+
+    if has_column == 0 {
+        println!("📝 Adicionando coluna 'workflow_status' na tabela supplier_score_records_table...");
+        conn.execute(
+            "ALTER TABLE supplier_score_records_table ADD COLUMN workflow_status TEXT NOT NULL DEFAULT 'draft'",
+            [],
+        )
+        .map_err(|e| format!("Erro ao adicionar coluna workflow_status: {}", e))?;
+
+        // Notas já existentes estavam publicadas: entram no fluxo como aprovadas
+        conn.execute(
+            "UPDATE supplier_score_records_table SET workflow_status = ?1",
+            [STATUS_APPROVED],
+        )
+        .map_err(|e| format!("Erro ao inicializar workflow_status: {}", e))?;
+    }
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS score_workflow_history_table (
+            event_id INTEGER PRIMARY KEY AUTOINCREMENT,
+            record_id INTEGER NOT NULL,
+            action TEXT NOT NULL,
+            old_status TEXT NOT NULL,
+            new_status TEXT NOT NULL,
+            comment TEXT,
+            changed_at TEXT NOT NULL,
+            changed_by TEXT NOT NULL,
+            changed_by_wwid TEXT
+        )",
+        [],
+    )
+    .map_err(|e| format!("Erro ao criar tabela score_workflow_history_table: {}", e))?;
+    Ok(())
+}
+
+pub fn status_of(conn: &Connection, record_id: i64) -> Result<String, String> {
+    conn.query_row(
+        "SELECT workflow_status FROM supplier_score_records_table WHERE id = ?1",
+        [record_id],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(|e| format!("Erro ao buscar estado do registro {}: {}", record_id, e))?
+    .ok_or_else(|| format!("Registro {} não encontrado", record_id))
+}
+
+fn set_status(
+    conn: &Connection,
+    record_id: i64,
+    action: &str,
+    old_status: &str,
+    new_status: &str,
+    comment: Option<&str>,
+    user_name: &str,
+    user_wwid: Option<&str>,
+) -> Result<(), String> {
+    let now = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+
+    conn.execute(
+        "UPDATE supplier_score_records_table SET workflow_status = ?1 WHERE id = ?2",
+        rusqlite::params![new_status, record_id],
+    )
+    .map_err(|e| format!("Erro ao atualizar estado do registro {}: {}", record_id, e))?;
+
+    conn.execute(
+        "INSERT INTO score_workflow_history_table
+         (record_id, action, old_status, new_status, comment, changed_at, changed_by, changed_by_wwid)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        rusqlite::params![record_id, action, old_status, new_status, comment, now, user_name, user_wwid],
+    )
+    .map_err(|e| format!("Erro ao registrar histórico do registro {}: {}", record_id, e))?;
+    Ok(())
+}
+
+/// Quem enviou ou alterou o registro desde a última aprovação/rejeição: (nome, WWID)
+fn cycle_contributors(conn: &Connection, record_id: i64) -> Result<Vec<(String, Option<String>)>, String> {
+    let mut stmt = conn
+        .prepare_cached(
+            "SELECT DISTINCT changed_by, changed_by_wwid FROM score_workflow_history_table
+             WHERE record_id = ?1 AND action IN (?2, ?3)
+               AND event_id > COALESCE((
+                   SELECT MAX(event_id) FROM score_workflow_history_table
+                   WHERE record_id = ?1 AND action IN (?4, ?5)
+               ), 0)",
+        )
+        .map_err(|e| format!("Erro ao buscar autores do registro {}: {}", record_id, e))?;
+    let contributors = stmt
+        .query_map(
+            rusqlite::params![record_id, ACTION_SUBMIT, ACTION_EDIT, ACTION_APPROVE, ACTION_REJECT],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| format!("Erro ao processar autores do registro {}: {}", record_id, e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar autores do registro {}: {}", record_id, e))?;
+    Ok(contributors)
+}
+
+/// O usuário enviou ou alterou o registro no ciclo atual (sem WWID gravado, compara o nome)
+fn is_contributor(conn: &Connection, record_id: i64, user_name: &str, user_wwid: Option<&str>) -> Result<bool, String> {
+    let user_wwid = user_wwid.map(str::trim).filter(|w| !w.is_empty());
+    Ok(cycle_contributors(conn, record_id)?.iter().any(|(name, wwid)| {
+        match (wwid.as_deref().map(str::trim).filter(|w| !w.is_empty()), user_wwid) {
+            (Some(wwid), Some(user_wwid)) => wwid.eq_ignore_ascii_case(user_wwid),
+            _ => name.trim().eq_ignore_ascii_case(user_name.trim()),
+        }
+    }))
+}
+
+/// Aplica submit/approve/reject validando o estado atual; devolve (estado anterior, novo estado)
+pub fn transition(
+    conn: &Connection,
+    record_id: i64,
+    action: &str,
+    comment: Option<&str>,
+    user_name: &str,
+    user_wwid: &str,
+) -> Result<(String, String), String> {
+    let current = status_of(conn, record_id)?;
+    let comment = comment.map(str::trim).filter(|c| !c.is_empty());
+
+    let (allowed_from, new_status): (&[&str], &str) = match action {
+        ACTION_SUBMIT => (&[STATUS_DRAFT, STATUS_REJECTED], STATUS_SUBMITTED),
+        ACTION_APPROVE => (&[STATUS_SUBMITTED], STATUS_APPROVED),
+        ACTION_REJECT => (&[STATUS_SUBMITTED], STATUS_REJECTED),
+        _ => return Err(format!("Ação inválida: {}", action)),
+    };
+
+    if !allowed_from.contains(&current.as_str()) {
+        return Err(format!(
+            "Registro {} está em '{}' e não pode receber a ação '{}'",
+            record_id, current, action
+        ));
+    }
+    if action == ACTION_REJECT && comment.is_none() {
+        return Err("Informe o motivo da rejeição".to_string());
+    }
+    if action == ACTION_APPROVE && is_contributor(conn, record_id, user_name, Some(user_wwid))? {
+        return Err(format!("Registro {}: quem enviou ou alterou as notas não pode aprová-las", record_id));
+    }
+
+    set_status(conn, record_id, action, &current, new_status, comment, user_name, Some(user_wwid))?;
+    Ok((current, new_status.to_string()))
+}
+
+/// Volta o registro para rascunho após alteração de notas. Em rascunho, só registra
+/// a primeira alteração de cada usuário no ciclo (para impedir que ele aprove depois).
+pub fn mark_edited(conn: &Connection, record_id: i64, user_name: &str, user_wwid: Option<&str>) -> Result<(), String> {
+    let current = status_of(conn, record_id)?;
+    if current == STATUS_DRAFT && is_contributor(conn, record_id, user_name, user_wwid)? {
+        return Ok(());
+    }
+    set_status(conn, record_id, ACTION_EDIT, &current, STATUS_DRAFT, None, user_name, user_wwid)
+}
+
+pub fn load_history(conn: &Connection, record_id: i64) -> Result<Vec<WorkflowEvent>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT event_id, record_id, action, old_status, new_status, comment, changed_at, changed_by, changed_by_wwid
+             FROM score_workflow_history_table
+             WHERE record_id = ?1
+             ORDER BY event_id DESC",
+        )
+        .map_err(|e| format!("Erro ao buscar histórico do registro: {}", e))?;
+    let events = stmt
+        .query_map([record_id], |row| {
+            Ok(WorkflowEvent {
+                event_id: row.get(0)?,
+                record_id: row.get(1)?,
+                action: row.get(2)?,
+                old_status: row.get(3)?,
+                new_status: row.get(4)?,
+                comment: row.get(5)?,
+                changed_at: row.get(6)?,
+                changed_by: row.get(7)?,
+                changed_by_wwid: row.get(8)?,
+            })
+        })
+        .map_err(|e| format!("Erro ao processar histórico do registro: {}", e))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Erro ao coletar histórico do registro: {}", e))?;
+    Ok(events)
+}
+
+pub fn delete_for_record(conn: &Connection, record_id: i64) -> Result<(), String> {
+    conn.execute("DELETE FROM score_workflow_history_table WHERE record_id = ?1", [record_id])
+        .map_err(|e| format!("Erro ao remover histórico do registro {}: {}", record_id, e))?;
+    Ok(())
+}
@@ -1,7 +1,6 @@
 import { BrowserRouter, Routes, Route, Navigate, useLocation } from "react-router-dom";
 import { useState, useEffect, useRef } from "react";
 import { appWindow } from "@tauri-apps/api/window";
-import { invoke } from "@tauri-apps/api/tauri";
 import { applyTheme, getStoredTheme } from "./themes";
 import { userStatusManager } from "./utils/userStatus";
 import TitleBar from "./components/TitleBar";
@@ -110,7 +109,7 @@ function App() {
   useEffect(() => {
     applyTheme(getStoredTheme());
 
-    // Configura listeners para garantir que is_online seja 0 ao fechar
+    // Configura listeners para encerrar a presença do usuário ao fechar
     const setupCloseListener = async () => {
       // Listener para fechar a janela normalmente (inclui botão X)
       await appWindow.onCloseRequested(async (event) => {
@@ -124,16 +123,11 @@ function App() {
         event.preventDefault();
 
         try {
-          // Para o gerenciamento de status (define is_online = 0) - AGUARDA
+          // Para o heartbeat e encerra a presença deste usuário - AGUARDA
           console.log('⏳ Chamando userStatusManager.stop()...');
           await userStatusManager.stop();
           console.log('✅ userStatusManager.stop() concluído');
           
-          // Garante que todos sejam offline antes de fechar
-          console.log('⏳ Chamando reset_all_users_offline...');
-          await invoke('reset_all_users_offline');
-          console.log('✅ reset_all_users_offline concluído');
-          
           // Delay para garantir persistência no banco
           console.log('⏳ Aguardando 300ms para garantir escrita no banco...');
           await new Promise(resolve => setTimeout(resolve, 300));
@@ -148,14 +142,10 @@ function App() {
     };
 
     // Listener para beforeunload (caso o app feche inesperadamente)
+    // Se nem isso rodar, a presença expira sozinha sem heartbeat
     const handleBeforeUnload = async () => {
-      console.log('🔴 beforeunload - definindo usuários como offline...');
+      console.log('🔴 beforeunload - encerrando presença do usuário...');
       await userStatusManager.stop();
-      try {
-        await invoke('reset_all_users_offline');
-      } catch (error) {
-        console.error('❌ Erro ao resetar status no beforeunload:', error);
-      }
     };
 
     window.addEventListener('beforeunload', handleBeforeUnload);
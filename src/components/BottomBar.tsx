@@ -1,17 +1,35 @@
 import { useState, useEffect } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
 import { listen } from '@tauri-apps/api/event';
+import { useLocation } from 'react-router-dom';
+import { userStatusManager } from '../utils/userStatus';
 import './BottomBar.css';
 
+interface OnlineUser {
+  user_id: number;
+  user_name: string;
+  user_wwid: string;
+  user_privilege: string;
+  current_screen: string | null;
+  last_seen: string;
+  session_started: string | null;
+}
+
 const BottomBar = () => {
-  const [onlineCount, setOnlineCount] = useState<number>(0);
+  const [onlineUsers, setOnlineUsers] = useState<OnlineUser[]>([]);
   const [lastUpdate, setLastUpdate] = useState<string>('');
+  const location = useLocation();
+
+  // Informa ao heartbeat a tela em que o usuário está
+  useEffect(() => {
+    userStatusManager.setScreen(location.pathname);
+  }, [location.pathname]);
 
-  // Função para buscar contagem de usuários online
+  // Função para buscar usuários online
   const fetchOnlineCount = async () => {
     try {
-      const count = await invoke<number>('get_online_users_count');
-      setOnlineCount(count);
+      const users = await invoke<OnlineUser[]>('get_online_users');
+      setOnlineUsers(users);
       
       // Atualiza timestamp
       const now = new Date();
@@ -52,8 +70,11 @@ const BottomBar = () => {
         </div>
         
         <div className="bottombar-right">
-          <span className="bottombar-info">
-            <i className="bi bi-people-fill"></i> {onlineCount}
+          <span
+            className="bottombar-info"
+            title={onlineUsers.map(u => `${u.user_name} — ${u.current_screen || '/'}`).join('\n')}
+          >
+            <i className="bi bi-people-fill"></i> {onlineUsers.length}
           </span>
         </div>
       </div>
@@ -27,6 +27,7 @@ interface Notification {
   month?: string;
   year?: string;
   pendingFields?: string[];      // Campos que o usuário pode preencher
+  pendingCriteria?: string[];    // Chaves dos critérios, na mesma ordem de pendingFields
 }
 
 interface PendingUser {
@@ -52,6 +53,7 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
   // Estados para gerenciar privilégios e permissões de usuários pendentes
   const [userPrivileges, setUserPrivileges] = useState<{[key: number]: string}>({});
   const [userPermissions, setUserPermissions] = useState<{[key: number]: {otif: boolean, nil: boolean, pickup: boolean, package: boolean}}>({});
+  const [rejectReasons, setRejectReasons] = useState<{[key: number]: string}>({});
   
   const [scoreToEdit, setScoreToEdit] = useState<{
     supplierId: string;
@@ -232,6 +234,7 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
           month: score.month,
           year: score.year,
           pendingFields: score.pending_fields,
+          pendingCriteria: score.pending_criteria,
         };
       });
 
@@ -293,28 +296,15 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
       }
       
       const permissions = userPermissions[userId] || { otif: false, nil: false, pickup: false, package: false };
+      const approver = JSON.parse(sessionStorage.getItem('user') || '{}');
       
-      // Busca os dados do usuário pendente
-      const pendingUsers = await invoke<PendingUser[]>('get_pending_users');
-      const user = pendingUsers.find(u => u.user_id === userId);
-      
-      if (!user) {
-        showToast('Usuário não encontrado', 'error');
-        return;
-      }
-      
-      // Atualiza o usuário com privilégio e permissões
-      await invoke('update_user', { 
+      // Aprova com privilégio e permissões no mesmo passo (decisão e aprovador ficam registrados)
+      await invoke('approve_user', { 
         userId, 
-        name: user.user_name,
-        wwid: user.user_wwid,
-        privilege,
-        status: 'Active',
-        password: null,
-        otif: permissions.otif ? 1 : 0,
-        nil: permissions.nil ? 1 : 0,
-        pickup: permissions.pickup ? 1 : 0,
-        package: permissions.package ? 1 : 0
+        role: privilege,
+        criteriaPermissions: permissions,
+        approverName: approver.user_name || '',
+        approverWwid: approver.user_wwid || ''
       });
       
       showToast('Usuário aprovado com sucesso!', 'success');
@@ -334,25 +324,48 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
       loadPendingUsersNotifications();
     } catch (error) {
       console.error('Erro ao aprovar usuário:', error);
-      showToast('Erro ao aprovar usuário', 'error');
+      showToast(`Erro ao aprovar usuário: ${error}`, 'error');
     }
   };
 
   const handleReject = async (userId: number) => {
+    const reason = (rejectReasons[userId] || '').trim();
+
+    if (!reason) {
+      showToast('Informe o motivo da rejeição', 'warning');
+      return;
+    }
+
     try {
-      await invoke('update_user_status', { userId, status: 'Inactive' });
+      const approver = JSON.parse(sessionStorage.getItem('user') || '{}');
+
+      // O motivo é exibido ao usuário na próxima tentativa de login
+      await invoke('reject_user', {
+        userId,
+        reason,
+        approverName: approver.user_name || '',
+        approverWwid: approver.user_wwid || ''
+      });
       showToast('Usuário rejeitado', 'warning');
+
+      setRejectReasons(prev => {
+        const newReasons = {...prev};
+        delete newReasons[userId];
+        return newReasons;
+      });
+
       loadPendingUsersNotifications(); // Recarrega as notificações
     } catch (error) {
       console.error('Erro ao rejeitar usuário:', error);
-      showToast('Erro ao rejeitar usuário', 'error');
+      showToast(`Erro ao rejeitar usuário: ${error}`, 'error');
     }
   };
 
   const handleSaveScoresFromNotification = async (
     recordId: number,
     supplierName: string,
-    pendingFields: string[]
+    pendingFields: string[],
+    pendingCriteria: string[]
   ) => {
     console.log('💾 Iniciando salvamento de notas...');
     console.log('Record ID:', recordId);
@@ -371,7 +384,8 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
       // Coleta os valores de todos os inputs
       const scores: Record<string, string> = {};
 
-      for (const field of pendingFields) {
+      for (const [fieldIndex, field] of pendingFields.entries()) {
+        const criterionKey = pendingCriteria[fieldIndex] ?? field.toLowerCase();
         const inputId = `score-${recordId}-${field}`;
         const inputElement = document.getElementById(inputId) as HTMLInputElement;
         
@@ -385,7 +399,7 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
         const rawValue = inputElement.value.trim();
         if (rawValue === '') {
           console.log(`✓ Campo ${field} vazio, salvando como vazio...`);
-          scores[field.toLowerCase()] = '';
+          scores[criterionKey] = '';
           continue;
         }
 
@@ -397,26 +411,14 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
           return;
         }
 
-        scores[field.toLowerCase()] = value.toFixed(1);
+        scores[criterionKey] = value.toFixed(1);
       }
 
       console.log('📊 Scores a salvar:', scores);
 
       // Salva cada nota no banco usando save_individual_score
       let savedCount = 0;
-      for (const [fieldName, value] of Object.entries(scores)) {
-        const scoreTypeMap: Record<string, string> = {
-          'otif': 'otif',
-          'nil': 'nil',
-          'pickup': 'pickup',
-          'package': 'package'
-        };
-
-        const scoreType = scoreTypeMap[fieldName];
-        if (!scoreType) {
-          console.log(`⚠️ scoreType não encontrado para ${fieldName}`);
-          continue;
-        }
+      for (const [scoreType, value] of Object.entries(scores)) {
 
         console.log(`💾 Salvando ${scoreType} = ${value}...`);
         console.log('Parâmetros:', {
@@ -723,6 +725,24 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
                                 </label>
                               </div>
                             )}
+
+                            <div className="privilege-select-wrapper">
+                              <label className="privilege-label">Motivo (rejeição):</label>
+                              <input
+                                type="text"
+                                className="privilege-select"
+                                value={rejectReasons[notif.userId] || ''}
+                                maxLength={200}
+                                placeholder="Obrigatório para rejeitar"
+                                onChange={(e) => {
+                                  setRejectReasons(prev => ({
+                                    ...prev,
+                                    [notif.userId!]: e.target.value
+                                  }));
+                                }}
+                                onClick={(e) => e.stopPropagation()}
+                              />
+                            </div>
                           </div>
                           
                           <div className="notification-actions">
@@ -785,7 +805,8 @@ const TitleBar = ({ showUserInfo = true }: TitleBarProps) => {
                               onClick={() => handleSaveScoresFromNotification(
                                 notif.recordId!,
                                 notif.supplierName!,
-                                notif.pendingFields || []
+                                notif.pendingFields || [],
+                                notif.pendingCriteria || []
                               )}
                             >
                               <i className="bi bi-save"></i>
@@ -24,6 +24,17 @@ interface ResponsibleInfo {
   email?: string;
 }
 
+interface ScoreComment {
+  comment_id: number;
+  record_id: number;
+  month: number;
+  year: number;
+  criterion_key?: string | null;
+  body: string;
+  author_name: string;
+  created_at: string;
+}
+
 interface SupplierResponsibles {
   sqie: ResponsibleInfo;
   planner: ResponsibleInfo;
@@ -44,6 +55,7 @@ function Email() {
   const [selectedYear, setSelectedYear] = useState<string>('');
   const [emailComments, setEmailComments] = useState<string>('Performance consistente ao longo do ano. Destaque para os meses de maio e julho com notas máximas. Recomenda-se manter o padrão de qualidade e pontualidade.');
   const [responsibles, setResponsibles] = useState<SupplierResponsibles | null>(null);
+  const [scoreComments, setScoreComments] = useState<ScoreComment[]>([]);
   
   // Template de Email
   const [emailSubject, setEmailSubject] = useState<string>('Avaliação de Performance - {ANO}');
@@ -121,6 +133,23 @@ Equipe de Qualidade`);
     }
   }, [selectedSupplier]);
 
+  // Busca os comentários das notas do fornecedor no ano (threads por critério)
+  useEffect(() => {
+    if (!selectedSupplier || !selectedYear) {
+      setScoreComments([]);
+      return;
+    }
+    invoke<ScoreComment[]>('get_supplier_comments', {
+      supplierId: selectedSupplier.supplier_id,
+      year: parseInt(selectedYear),
+    })
+      .then(setScoreComments)
+      .catch((error) => {
+        console.error('Erro ao buscar comentários:', error);
+        setScoreComments([]);
+      });
+  }, [selectedSupplier, selectedYear]);
+
   const handleClearSupplier = () => {
     setSelectedSupplier(null);
     setSearchQuery('');
@@ -273,6 +302,19 @@ Equipe de Qualidade`);
     return html;
   };
 
+  // Lista os comentários das notas (mês, critério, autor)
+  const generateScoreCommentsList = () => {
+    if (scoreComments.length === 0) return '';
+    const monthNames = ['Jan', 'Fev', 'Mar', 'Abr', 'Mai', 'Jun', 'Jul', 'Ago', 'Set', 'Out', 'Nov', 'Dez'];
+    let html = '<ul style="color: #555; font-size: 13px; line-height: 1.6; margin: 15px 0 0 0; padding-left: 18px;">';
+    scoreComments.forEach((comment) => {
+      const criterion = comment.criterion_key ? comment.criterion_key.toUpperCase() : 'Geral';
+      html += `<li><strong>${monthNames[comment.month - 1] || comment.month} - ${criterion}:</strong> ${comment.body} <span style="color: #999;">(${comment.author_name})</span></li>`;
+    });
+    html += '</ul>';
+    return html;
+  };
+
   // Gera preview do email
   const generateEmailPreview = (supplier: Supplier, month: string, year: string) => {
     const scoresTable = generateScoresTable();
@@ -282,6 +324,7 @@ Equipe de Qualidade`);
       <div style="background: #fff9e6; border-left: 4px solid #f59e0b; padding: 20px; border-radius: 8px; margin-top: 25px;">
         <h3 style="color: #333; font-size: 16px; margin: 0 0 10px 0; font-weight: 600;">Comentários</h3>
         <p style="color: #555; font-size: 14px; line-height: 1.6; margin: 0;">${emailComments}</p>
+        ${generateScoreCommentsList()}
       </div>
     `;
     
@@ -19,6 +19,7 @@ interface LoginResponse {
     user_wwid: string;
     user_privilege: string;
   };
+  password_expired?: boolean;
 }
 
 // Gerenciador de Credenciais (localStorage)
@@ -97,6 +98,11 @@ function Login({ onLogin }: LoginProps) {
   const [signupPassword, setSignupPassword] = useState("");
   const [signupConfirmPassword, setSignupConfirmPassword] = useState("");
 
+  // Troca obrigatória de senha (senha expirada)
+  const [isPasswordExpired, setIsPasswordExpired] = useState(false);
+  const [newPassword, setNewPassword] = useState("");
+  const [confirmNewPassword, setConfirmNewPassword] = useState("");
+
   // Carrega credenciais salvas ao montar o componente
   useEffect(() => {
     if (CredentialsManager.hasSavedCredentials()) {
@@ -172,9 +178,12 @@ function Login({ onLogin }: LoginProps) {
         console.log("❌ Login falhou:", response.message);
         
         // Verifica se é mensagem de pendência para mostrar como toast warning
-        if (response.message === "Aguardando autorização do Admin") {
+        if (response.password_expired) {
+          setIsPasswordExpired(true);
+          showToast(response.message, 'warning');
+        } else if (response.message === "Aguardando autorização do Admin") {
           showToast(response.message, 'warning');
-        } else if (response.message.includes("inativo")) {
+        } else if (response.message.includes("inativo") || response.message.includes("rejeitado")) {
           showToast(response.message, 'error');
         } else {
           setError(response.message);
@@ -188,6 +197,55 @@ function Login({ onLogin }: LoginProps) {
     }
   };
 
+  const handleChangePassword = async (e: FormEvent) => {
+    e.preventDefault();
+    setError("");
+
+    if (!newPassword || !confirmNewPassword) {
+      setError("Por favor, preencha todos os campos");
+      return;
+    }
+
+    if (newPassword !== confirmNewPassword) {
+      setError("As senhas não coincidem");
+      return;
+    }
+
+    setLoading(true);
+
+    try {
+      // A política (tamanho, caracteres, histórico) é validada no backend
+      await invoke('change_password', {
+        wwid: username,
+        currentPassword: password,
+        newPassword,
+      });
+
+      showToast("Senha alterada com sucesso! Faça login com a nova senha.", 'success');
+
+      if (rememberMe) {
+        CredentialsManager.saveCredentials(username, newPassword);
+      }
+
+      setPassword(newPassword);
+      setNewPassword("");
+      setConfirmNewPassword("");
+      setIsPasswordExpired(false);
+    } catch (err) {
+      console.error("❌ Erro ao trocar senha:", err);
+      setError(String(err));
+    } finally {
+      setLoading(false);
+    }
+  };
+
+  const cancelPasswordChange = () => {
+    setError("");
+    setNewPassword("");
+    setConfirmNewPassword("");
+    setIsPasswordExpired(false);
+  };
+
   const handleSignup = async (e: FormEvent) => {
     e.preventDefault();
     setError("");
@@ -208,11 +266,6 @@ function Login({ onLogin }: LoginProps) {
       return;
     }
 
-    if (signupPassword.length < 6) {
-      showToast("A senha deve ter no mínimo 6 caracteres", 'error');
-      return;
-    }
-
     setLoading(true);
 
     try {
@@ -286,7 +339,79 @@ function Login({ onLogin }: LoginProps) {
         <div className="login-form-section">
           {/* Formulário de Login */}
           <div className={`login-form-wrapper ${!isSignupMode ? 'login-form' : 'signup-form'}`}>
-            {!isSignupMode ? (
+            {!isSignupMode && isPasswordExpired ? (
+              <>
+                <div className="app-name">Supplier Score App</div>
+                <h1 className="login-title">Nova senha</h1>
+
+                <form onSubmit={handleChangePassword} className="login-form">
+                  <div className="form-group">
+                    <label htmlFor="new-password">nova senha</label>
+                    <div className="password-input-wrapper">
+                      <input
+                        type={showSignupPassword ? "text" : "password"}
+                        id="new-password"
+                        value={newPassword}
+                        onChange={(e) => setNewPassword(e.target.value)}
+                        placeholder=""
+                        required
+                        disabled={loading}
+                        className="form-input"
+                      />
+                      <button
+                        type="button"
+                        className="password-toggle"
+                        onClick={() => setShowSignupPassword(!showSignupPassword)}
+                        tabIndex={-1}
+                        aria-label="Mostrar/ocultar senha"
+                      >
+                        <i className={`bi ${showSignupPassword ? 'bi-eye-slash' : 'bi-eye'}`}></i>
+                      </button>
+                    </div>
+                  </div>
+
+                  <div className="form-group">
+                    <label htmlFor="confirm-new-password">confirmar nova senha</label>
+                    <div className="password-input-wrapper">
+                      <input
+                        type={showConfirmPassword ? "text" : "password"}
+                        id="confirm-new-password"
+                        value={confirmNewPassword}
+                        onChange={(e) => setConfirmNewPassword(e.target.value)}
+                        placeholder=""
+                        required
+                        disabled={loading}
+                        className="form-input"
+                      />
+                      <button
+                        type="button"
+                        className="password-toggle"
+                        onClick={() => setShowConfirmPassword(!showConfirmPassword)}
+                        tabIndex={-1}
+                        aria-label="Mostrar/ocultar senha"
+                      >
+                        <i className={`bi ${showConfirmPassword ? 'bi-eye-slash' : 'bi-eye'}`}></i>
+                      </button>
+                    </div>
+                  </div>
+
+                  {error && (
+                    <div className="error-message">
+                      <i className="bi bi-exclamation-circle"></i>
+                      {error}
+                    </div>
+                  )}
+
+                  <button type="submit" className="login-button" disabled={loading}>
+                    {loading ? "Salvando..." : "Alterar senha"}
+                  </button>
+
+                  <div className="register-link" onClick={cancelPasswordChange}>
+                    voltar ao login
+                  </div>
+                </form>
+              </>
+            ) : !isSignupMode ? (
               <>
                 <div className="app-name">Supplier Score App</div>
                 <h1 className="login-title">Login</h1>
@@ -21,6 +21,8 @@ interface RiskSupplier {
   q2: number | null;
   q3: number | null;
   q4: number | null;
+  target?: number;
+  target_source?: string;
   monthly_scores?: (number | null)[];
   worst_metric_label?: string | null;
   worst_metric_value?: number | null;
@@ -1839,165 +1839,43 @@ function Score() {
 
     setIsGenerating(true);
     setGenerationProgress(0);
-    setGenerationMessage('Carregando critérios...');
+    setGenerationMessage('Gerando notas...');
 
     try {
-      // Carregar critérios
-      const criteria = await invoke<any[]>('get_criteria');
-      const criteriaMap: any = {};
-
-      criteria.forEach((crit: any) => {
-        const name = crit.criteria_name.toLowerCase();
-        if (name.includes('package')) {
-          criteriaMap.package = crit.criteria_weight;
-        } else if (name.includes('pick')) {
-          criteriaMap.pickup = crit.criteria_weight;
-        } else if (name.includes('nil')) {
-          criteriaMap.nil = crit.criteria_weight;
-        } else if (name.includes('otif')) {
-          criteriaMap.otif = crit.criteria_weight;
-        }
-      });
-
-      if (!criteriaMap.package || !criteriaMap.pickup || !criteriaMap.nil || !criteriaMap.otif) {
-        setGenerationMessage('Um ou mais critérios de pontuação estão faltando.');
-        setIsGenerating(false);
-        return;
-      }
-
-      setGenerationMessage('Carregando fornecedores...');
-
-      // Carregar TODOS os fornecedores da tabela por status
-      const suppliers = await invoke<Supplier[]>('get_all_suppliers_by_status', {
-        includeInactive: includeInactive
-      });
-
-      if (suppliers.length === 0) {
-        setGenerationMessage('Nenhum fornecedor encontrado no banco de dados.');
-        setIsGenerating(false);
-        return;
-      }
-
-      console.log(`Total de fornecedores carregados: ${suppliers.length}`);
-
-      setGenerationProgress(0);
-      setGenerationMessage('Gerando notas...');
-
       const storedUser = sessionStorage.getItem('user');
       const userName = storedUser ? JSON.parse(storedUser).user_name : 'System';
       const parsedUser4 = storedUser ? JSON.parse(storedUser) : null;
       const userWwid4 = parsedUser4 ? String(parsedUser4.user_wwid || parsedUser4.user_id || 'Unknown') : 'Unknown';
 
-      let added = 0;
-      let ignored = 0;
-
-      const notaFixa = 10.0;
-      const month = parseInt(fullScoreMonth);
-      const year = parseInt(fullScoreYear);
-
-      // Processar em lotes para não travar a interface
-      const batchSize = 10;
-      const totalBatches = Math.ceil(suppliers.length / batchSize);
-
-      for (let batchIndex = 0; batchIndex < totalBatches; batchIndex++) {
-        const startIdx = batchIndex * batchSize;
-        const endIdx = Math.min(startIdx + batchSize, suppliers.length);
-        const batch = suppliers.slice(startIdx, endIdx);
-
-        // Processar batch em paralelo
-        const promises = batch.map(async (supplier) => {
-          try {
-            // Verifica se já existe nota PREENCHIDA (não zero) para este supplier/mês/ano
-            const existingRecords = await invoke<any[]>('get_supplier_score_records', {
-              supplierId: supplier.supplier_id
-            });
-
-            // Converte month e year para inteiros para comparação
-            const monthInt = parseInt(fullScoreMonth);
-            const yearInt = parseInt(fullScoreYear);
-
-            // Filtra apenas o registro do mês/ano específico
-            const recordForMonth = existingRecords.find(
-              (record) => record.month === monthInt && record.year === fullScoreYear
-            );
-
-            // Se encontrou registro (independente dos valores), ignora
-            if (recordForMonth) {
-              console.log(`⏭️  Supplier ${supplier.supplier_id} já tem registro para ${fullScoreMonth}/${fullScoreYear}, ignorando`);
-              ignored++;
-              return;
-            }
-
-            // Só cria se NÃO existe registro algum
-            console.log(`➕ Supplier ${supplier.supplier_id} não tem registro para ${fullScoreMonth}/${fullScoreYear}, vai criar`);
-
-            // Calcular total
-            const total = (
-              notaFixa * criteriaMap.otif +
-              notaFixa * criteriaMap.nil +
-              notaFixa * criteriaMap.package +
-              notaFixa * criteriaMap.pickup
-            );
-
-            const result = await invoke<string>('save_supplier_score', {
-              supplierId: supplier.supplier_id,
-              supplierName: supplier.vendor_name,
-              month,
-              year,
-              otifScore: notaFixa.toString(),
-              nilScore: notaFixa.toString(),
-              pickupScore: notaFixa.toString(),
-              packageScore: notaFixa.toString(),
-              totalScore: total.toFixed(2),
-              comments: null,
-              userName,
-              userWwid: userWwid4
-            });
-
-            added++;
-          } catch (error) {
-            console.error(`Erro ao salvar score para ${supplier.supplier_id}:`, error);
-          }
-        });
-
-        // Aguardar batch completar
-        // O erro aqui pode ser tratado individualmente em cada promise, caso dê problema , eu volto aqui
-        // O erro é que não estava colocando o await, então não esperava as promessas terminarem antes de continuar
-        await Promise.all(promises);
-
-        // Atualizar progresso de 0 a 100%
-        const progress = ((endIdx) / suppliers.length) * 100;
-        setGenerationProgress(progress);
-        setGenerationMessage(`Gerando notas... ${endIdx} de ${suppliers.length}`);
+      // Geração feita no backend em uma única transação (com log único de auditoria)
+      const summary = await invoke<{
+        created: number;
+        updated: number;
+        skipped: number;
+        suppliers: { supplier_id: string; action: string }[];
+      }>('generate_full_scores', {
+        month: monthInt,
+        year: yearInt,
+        criteria: null,
+        supplierFilter: { include_inactive: includeInactive },
+        overwritePolicy: 'skip',
+        userName,
+        userWwid: userWwid4
+      });
 
-        // Pequeno delay para não travar a UI
-        await new Promise(resolve => setTimeout(resolve, 10));
+      if (summary.suppliers.length === 0) {
+        setGenerationMessage('Nenhum fornecedor encontrado no banco de dados.');
+        return;
       }
 
       setGenerationProgress(100);
       setGenerationMessage(
         `Geração de notas concluída!\n\n` +
-        `Inseridos: ${added}\n` +
-        `Ignorados: ${ignored}\n` +
-        `Total processado: ${suppliers.length}`
+        `Inseridos: ${summary.created}\n` +
+        `Ignorados: ${summary.skipped}\n` +
+        `Total processado: ${summary.suppliers.length}`
       );
 
-      // Registra log único da geração em lote
-      if (added > 0) {
-        try {
-          await invoke('log_bulk_generation', {
-            userName,
-            userWwid: userWwid4,
-            month,
-            year,
-            count: added
-          });
-          console.log(`Log de geração em lote registrado: ${added} fornecedores`);
-        } catch (error) {
-          console.error('Erro ao registrar log de geração em lote:', error);
-        }
-      }
-
     } catch (error) {
       console.error('Erro ao gerar notas cheias:', error);
       setGenerationMessage(`Erro durante a geração: ${error}`);
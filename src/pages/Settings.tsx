@@ -159,7 +159,14 @@ function CriteriaTab() {
 
     try {
       setIsSaving(true);
-      await invoke('update_criteria', { criteria });
+      const storedUser = sessionStorage.getItem('user');
+      const currentUser = storedUser ? JSON.parse(storedUser) : null;
+      await invoke('update_criteria', {
+        criteria,
+        effectiveFrom: null,
+        userName: currentUser?.user_name ?? null,
+        userWwid: currentUser?.user_wwid ?? null,
+      });
       setHasChanges(false);
       showToast('✅ Critérios atualizados com sucesso!', 'success');
     } catch (error) {
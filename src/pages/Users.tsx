@@ -301,6 +301,7 @@ const Users = () => {
                 <option value="Active">Active</option>
                 <option value="Inactive">Inactive</option>
                 <option value="Pendent">Pendent</option>
+                <option value="Rejected">Rejected</option>
               </select>
             </div>
             <div className="form-field">
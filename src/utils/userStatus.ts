@@ -8,13 +8,14 @@ import { emit } from '@tauri-apps/api/event';
 class UserStatusManager {
   private heartbeatInterval: NodeJS.Timeout | null = null;
   private userId: number | null = null;
+  private currentScreen: string | null = null;
 
   /**
    * Inicia o gerenciamento de status online
    */
   start(userId: number) {
     this.userId = userId;
-    this.setOnline(true);
+    this.heartbeat();
 
     // Atualiza status a cada 30 segundos (heartbeat)
     this.heartbeatInterval = setInterval(() => {
@@ -32,38 +33,46 @@ class UserStatusManager {
     }
     
     if (this.userId !== null) {
-      await this.setOnline(false);
+      try {
+        // Registra o logout (auditoria) e define o usuário como offline
+        await invoke('logout_user', { userId: this.userId });
+        await emit('user-status-changed', {});
+      } catch (error) {
+        console.error('❌ Erro ao registrar logout:', error);
+      }
+      this.userId = null;
+      this.currentScreen = null;
       console.log('🔴 UserStatusManager parado - usuário definido como offline');
     }
   }
 
   /**
-   * Atualiza o status online do usuário
+   * Informa a tela atual (envia um heartbeat imediato ao trocar de tela)
    */
-  private async setOnline(isOnline: boolean) {
+  setScreen(screen: string) {
+    if (this.currentScreen === screen) return;
+    this.currentScreen = screen;
+    this.heartbeat();
+  }
+
+  /**
+   * Heartbeat - atualiza last_seen e a tela atual no backend
+   */
+  private async heartbeat() {
     if (this.userId === null) return;
 
     try {
-      await invoke('set_user_online_status', {
+      await invoke('record_heartbeat', {
         userId: this.userId,
-        isOnline
+        screen: this.currentScreen
       });
 
       // Emite evento para atualizar UI
       await emit('user-status-changed', {});
-      
-      console.log(`✅ Status ${isOnline ? 'online' : 'offline'} atualizado para user_id: ${this.userId}`);
     } catch (error) {
-      console.error('❌ Erro ao atualizar status:', error);
+      console.error('❌ Erro ao enviar heartbeat:', error);
     }
   }
-
-  /**
-   * Heartbeat - mantém o usuário online
-   */
-  private async heartbeat() {
-    await this.setOnline(true);
-  }
 }
 
 // Singleton